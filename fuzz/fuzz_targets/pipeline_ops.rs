@@ -1,14 +1,20 @@
 #![no_main]
 
 //! Fuzz target for image pipeline operations.
-//! Tests resize, crop, rotate, flip, brightness, contrast operations.
+//! Tests resize, crop, extend, rotate, flip, brightness, contrast operations.
 
 use arbitrary::{Arbitrary, Unstructured};
 use image::{DynamicImage, RgbaImage};
 use lazy_image::engine::apply_ops;
-use lazy_image::ops::{ColorSpace, Operation, ResizeFit};
+use lazy_image::ops::{
+    BlendMode, Channel, Color, ColorSpace, CubeLut, ExtendMode, IccProfile, Levels, Operation,
+    Overlay, OverlayPlacement, ResizeFit, ResizeKernel, ResizePosition, RotateInterpolation,
+    Sharpen,
+};
+use lazy_image::CropRegion;
 use libfuzzer_sys::fuzz_target;
 use std::borrow::Cow;
+use std::sync::Arc;
 
 #[derive(Arbitrary, Debug)]
 struct OperationSeed {
@@ -36,19 +42,115 @@ fn build_image(data: &[u8]) -> DynamicImage {
     DynamicImage::ImageRgba8(rgba)
 }
 
+/// Whole image for negative `d`, otherwise one rectangle that may reach past
+/// the image edges.
+fn fuzz_regions(seed: &OperationSeed) -> Vec<CropRegion> {
+    if seed.d < 0 {
+        return Vec::new();
+    }
+    vec![CropRegion {
+        left: seed.b.rem_euclid(96) as u32,
+        top: seed.c.rem_euclid(96) as u32,
+        width: (seed.d % 96) as u32 + 1,
+        height: ((seed.d >> 8) % 96) as u32 + 1,
+    }]
+}
+
+/// `size`^3 LUT (2 to 5 points) whose entries, including some outside 0-1,
+/// come from the seed bytes.
+fn fuzz_lut(seed: &OperationSeed) -> CubeLut {
+    let size = seed.a.rem_euclid(4) as usize + 2;
+    let bytes = [
+        seed.b.to_le_bytes(),
+        seed.c.to_le_bytes(),
+        seed.d.to_le_bytes(),
+    ]
+    .concat();
+    let table = (0..size * size * size)
+        .map(|i| std::array::from_fn(|c| bytes[(i * 3 + c) % bytes.len()] as f32 / 200.0 - 0.1))
+        .collect();
+    CubeLut {
+        size,
+        domain_min: [0.0; 3],
+        domain_max: [1.0; 3],
+        table,
+    }
+}
+
+/// PNG overlay (1 to 16 pixels square) filled from the seed bytes, placed
+/// anywhere within 32 pixels of the image.
+fn fuzz_overlay(seed: &OperationSeed) -> Overlay {
+    let side = seed.a.rem_euclid(16) as u32 + 1;
+    let bytes = seed.b.to_le_bytes();
+    let overlay = RgbaImage::from_fn(side, side, |x, y| {
+        image::Rgba(std::array::from_fn(|c| {
+            bytes[(x + y + c as u32) as usize % 4]
+        }))
+    });
+    let mut input = Vec::new();
+    let _ = DynamicImage::ImageRgba8(overlay).write_to(
+        &mut std::io::Cursor::new(&mut input),
+        image::ImageFormat::Png,
+    );
+    Overlay {
+        input: Arc::new(input),
+        placement: if seed.c < 0 {
+            OverlayPlacement::Gravity(ResizePosition::SouthEast)
+        } else {
+            OverlayPlacement::Offset {
+                left: seed.c % 64 - 32,
+                top: (seed.c >> 8) % 64 - 32,
+            }
+        },
+        blend: match seed.d.rem_euclid(3) {
+            0 => BlendMode::Over,
+            1 => BlendMode::Multiply,
+            _ => BlendMode::Screen,
+        },
+        opacity: (seed.d >> 8).rem_euclid(101) as f64 / 100.0,
+        tile: (seed.d >> 16) & 1 == 1,
+    }
+}
+
 fn seeds_to_ops(seeds: Vec<OperationSeed>) -> Vec<Operation> {
     seeds
         .into_iter()
         .take(16)
-        .map(|seed| match seed.kind % 8 {
+        .map(|seed| match seed.kind % 30 {
             0 => Operation::Resize {
                 width: Some(seed.a.clamp(1, 4096) as u32),
                 height: Some(seed.b.clamp(1, 4096) as u32),
-                fit: match seed.c.rem_euclid(3) {
+                fit: match seed.c.rem_euclid(5) {
                     0 => ResizeFit::Inside,
                     1 => ResizeFit::Cover,
+                    2 => ResizeFit::Contain {
+                        background: Color::rgba(seed.a as u8, seed.b as u8, 0, seed.c as u8),
+                    },
+                    3 => ResizeFit::Outside,
                     _ => ResizeFit::Fill,
                 },
+                position: match seed.d.rem_euclid(5) {
+                    0 => ResizePosition::Center,
+                    1 => ResizePosition::NorthWest,
+                    2 => ResizePosition::Entropy,
+                    3 => ResizePosition::Attention,
+                    _ => ResizePosition::Focal {
+                        x: (seed.d.rem_euclid(101) as f64) / 100.0,
+                        y: (seed.a.rem_euclid(101) as f64) / 100.0,
+                    },
+                },
+                without_enlargement: seed.d & 1 == 1,
+                without_reduction: seed.d & 2 == 2,
+                kernel: match seed.b.rem_euclid(6) {
+                    0 => ResizeKernel::Nearest,
+                    1 => ResizeKernel::Box,
+                    2 => ResizeKernel::Bilinear,
+                    3 => ResizeKernel::CatmullRom,
+                    4 => ResizeKernel::Mitchell,
+                    _ => ResizeKernel::Lanczos3,
+                },
+                linear: seed.d & 4 == 4,
+                sharpen: seed.d & 8 == 8,
             },
             1 => Operation::Crop {
                 x: seed.a.max(0) as u32,
@@ -65,6 +167,129 @@ fn seeds_to_ops(seeds: Vec<OperationSeed>) -> Vec<Operation> {
             6 => Operation::Contrast {
                 value: seed.b.clamp(-200, 200),
             },
+            7 => Operation::Extend {
+                top: seed.a.rem_euclid(64) as u32,
+                right: seed.b.rem_euclid(64) as u32,
+                bottom: seed.c.rem_euclid(64) as u32,
+                left: seed.d.rem_euclid(64) as u32,
+                background: Color::rgba(seed.a as u8, seed.b as u8, seed.c as u8, seed.d as u8),
+                mode: match seed.kind / 30 % 3 {
+                    0 => ExtendMode::Background,
+                    1 => ExtendMode::Copy,
+                    _ => ExtendMode::Mirror,
+                },
+            },
+            8 => Operation::Trim {
+                threshold: seed.a as u8,
+                background: (seed.b & 1 == 1)
+                    .then(|| Color::rgba(seed.b as u8, seed.c as u8, seed.d as u8, 255)),
+            },
+            9 => Operation::RotateArbitrary {
+                degrees: seed.a as f64 / 16.0,
+                background: Color::rgba(seed.b as u8, seed.c as u8, seed.d as u8, seed.b as u8),
+                interpolation: if seed.c & 1 == 1 {
+                    RotateInterpolation::Bicubic
+                } else {
+                    RotateInterpolation::Bilinear
+                },
+            },
+            10 => Operation::Flatten {
+                background: Color::rgba(seed.a as u8, seed.b as u8, seed.c as u8, seed.d as u8),
+            },
+            11 => Operation::EnsureAlpha {
+                alpha: seed.a.rem_euclid(101) as f64 / 100.0,
+            },
+            12 => Operation::RemoveAlpha,
+            13 => Operation::ExtractChannel {
+                channel: match seed.b.rem_euclid(4) {
+                    0 => Channel::Red,
+                    1 => Channel::Green,
+                    2 => Channel::Blue,
+                    _ => Channel::Alpha,
+                },
+            },
+            14 => Operation::Modulate {
+                brightness: seed.a.rem_euclid(301) as f64 / 100.0,
+                saturation: seed.b.rem_euclid(301) as f64 / 100.0,
+                hue: seed.c.rem_euclid(360) as f64,
+                lightness: seed.d.rem_euclid(201) as f64 - 100.0,
+            },
+            15 => Operation::Gamma {
+                gamma: (seed.a.rem_euclid(100) + 1) as f64 / 10.0,
+            },
+            16 => Operation::Negate,
+            17 => Operation::Threshold {
+                value: seed.a as u8,
+                grayscale: seed.b & 1 == 1,
+            },
+            18 => Operation::Levels {
+                levels: Levels {
+                    in_black: seed.a.rem_euclid(256) as f64,
+                    in_white: seed.b.rem_euclid(256) as f64,
+                    gamma: (seed.c.rem_euclid(100) + 1) as f64 / 10.0,
+                    out_black: seed.d.rem_euclid(256) as f64,
+                    out_white: 255.0 - seed.a.rem_euclid(256) as f64,
+                },
+            },
+            19 => Operation::Normalize {
+                lower: seed.a.rem_euclid(50) as f64,
+                upper: 50.0 + seed.b.rem_euclid(51) as f64,
+            },
+            20 => Operation::Clahe {
+                width: seed.a.rem_euclid(64) as u32 + 1,
+                height: seed.b.rem_euclid(64) as u32 + 1,
+                max_slope: seed.c.rem_euclid(101) as u32,
+            },
+            21 => Operation::Sharpen {
+                sharpen: Sharpen {
+                    sigma: 0.3 + seed.a.rem_euclid(98) as f64 / 10.0,
+                    amount: seed.b.rem_euclid(101) as f64 / 10.0,
+                    threshold: seed.c as u8,
+                },
+            },
+            22 => Operation::Blur {
+                sigma: 0.3 + seed.a.rem_euclid(200) as f64 / 10.0,
+                regions: fuzz_regions(&seed),
+            },
+            23 => Operation::Pixelate {
+                block_size: seed.a.rem_euclid(64) as u32 + 1,
+                regions: fuzz_regions(&seed),
+            },
+            24 => Operation::Recomb {
+                matrix: std::array::from_fn(|i| {
+                    std::array::from_fn(|j| {
+                        let seed = [seed.a, seed.b, seed.c, seed.d][(i + j) % 4];
+                        (seed >> (j * 8)) as i8 as f64 / 64.0
+                    })
+                }),
+            },
+            25 => Operation::Lut3d {
+                lut: Arc::new(fuzz_lut(&seed)),
+            },
+            26 => Operation::Composite {
+                layers: vec![fuzz_overlay(&seed)],
+            },
+            27 => Operation::Affine {
+                matrix: [
+                    [seed.a as f64 / 64.0, seed.b as f64 / 64.0],
+                    [seed.c as f64 / 64.0, seed.d as f64 / 64.0],
+                ],
+                translate: [seed.b as f64 / 8.0, seed.c as f64 / 8.0],
+                background: Color::rgba(seed.a as u8, seed.b as u8, seed.c as u8, seed.d as u8),
+                interpolation: if seed.d & 1 == 1 {
+                    RotateInterpolation::Bicubic
+                } else {
+                    RotateInterpolation::Bilinear
+                },
+            },
+            28 => Operation::IccTransform {
+                source: (seed.a & 1 == 1).then_some(IccProfile::DisplayP3),
+                target: if seed.b & 1 == 1 {
+                    IccProfile::DisplayP3
+                } else {
+                    IccProfile::Srgb
+                },
+            },
             _ => Operation::ColorSpace {
                 target: ColorSpace::Srgb,
             },
@@ -33,12 +33,74 @@ export declare class ImageEngine {
    * - "inside" (default): maintain aspect ratio and fit within the box
    * - "cover": maintain aspect ratio and crop to fill the box
    * - "fill": ignore aspect ratio and force exact dimensions
+   * - "contain": fit within the box, then pad to its exact size with
+   *   `options.background` (transparent by default)
+   * - "outside": maintain aspect ratio and cover the box without cropping
+   *
+   * `position` picks the region kept by "cover": a compass gravity
+   * ("centre", "north", "northeast", ... "northwest"), a fractional
+   * focal point `{ x, y }` in the 0.0-1.0 range, or a content-aware
+   * strategy ("entropy", "attention") whose chosen window is reported
+   * as `metrics.smartCrop`. With "contain" it places the image on the canvas.
+   *
+   * `options.withoutEnlargement` / `options.withoutReduction` clamp the
+   * scale to the dimensions the resize receives at execution time.
+   * `options.kernel` selects the resampling filter: "nearest", "box",
+   * "bilinear", "catmullrom", "mitchell" or "lanczos3" (default).
+   * `options.linear` resamples in linear light, which keeps fine
+   * high-contrast detail from darkening when downscaling.
+   * `options.sharpen: "auto"` applies a light unsharp mask after a
+   * downscale, stronger the more the image was reduced.
    */
-  resize(width?: number | undefined | null, height?: number | undefined | null, fit?: string | undefined | null): ImageEngine
+  resize(width?: number | undefined | null, height?: number | undefined | null, fit?: string | undefined | null, position?: string | FocalPoint | undefined | null, options?: ResizeOptions | undefined | null): ImageEngine
   /** Crop a region from the image. */
   crop(x: number, y: number, width: number, height: number): ImageEngine
-  /** Rotate by degrees (90, 180, 270 only) */
-  rotate(degrees: number): ImageEngine
+  /**
+   * Grow the canvas by `top` / `right` / `bottom` / `left` pixels without cropping.
+   * `extendWith` fills the new border: "background" (default) paints
+   * `background` (transparent by default), "copy" repeats the edge pixels
+   * and "mirror" reflects the image.
+   */
+  extend(options: ExtendOptions): ImageEngine
+  /**
+   * Crop away uniform borders. Pixels within `threshold` of `background`
+   * (the top-left pixel by default) are treated as border. The kept region
+   * is reported as `metrics.trim`; a uniform image is left unchanged.
+   */
+  trim(options?: TrimOptions | undefined | null): ImageEngine
+  /**
+   * Rotate clockwise by `degrees`. Multiples of 90 are lossless; any other angle
+   * expands the canvas to fit and fills the corners with `options.background`
+   * (opaque black by default).
+   */
+  rotate(degrees: number, options?: RotateOptions | undefined | null): ImageEngine
+  /**
+   * Warp by the 2x2 matrix `[[a, b], [c, d]]`, which maps source (x, y)
+   * to (ax + by, cx + dy). The canvas is sized to the transformed bounds
+   * before anything runs, shifted by `translate`, and uncovered areas are
+   * filled with `background` (opaque black by default).
+   */
+  affine(matrix: Array<Array<number>>, options?: AffineOptions | undefined | null): ImageEngine
+  /**
+   * Composite onto `options.background` (default white) and drop the alpha channel.
+   * Images without alpha are left unchanged.
+   */
+  flatten(options?: FlattenOptions | undefined | null): ImageEngine
+  /**
+   * Add an alpha channel with opacity `alpha` (0.0-1.0, default 1.0).
+   * Images that already have alpha are left unchanged.
+   */
+  ensureAlpha(alpha?: number | undefined | null): ImageEngine
+  /** Drop the alpha channel without compositing (see `flatten`). */
+  removeAlpha(): ImageEngine
+  /** Keep a single channel ('r', 'g', 'b' or 'alpha') as a greyscale image. */
+  extractChannel(channel: 'r' | 'g' | 'b' | 'alpha' | 'red' | 'green' | 'blue'): ImageEngine
+  /**
+   * Use an encoded image (reduced to greyscale) as the alpha channel.
+   * The mask is decoded when the pipeline runs and must match the size
+   * of the image at that point.
+   */
+  joinChannel(mask: Buffer): ImageEngine
   /** Flip horizontally */
   flipH(): ImageEngine
   /** Flip vertically */
@@ -51,6 +113,19 @@ export declare class ImageEngine {
    * `false` = ignore EXIF Orientation
    */
   autoOrient(enabled: boolean): ImageEngine
+  /**
+   * Colour transparent pixels are flattened onto when the output format
+   * has no alpha channel (JPEG). Default: white.
+   */
+  flattenBackground(background: string): ImageEngine
+  /**
+   * Bits per sample written by the encoder: 8, 10, 12 or 16.
+   * PNG stores 8 or 16 (10 and 12 round up to 16), AVIF stores 8, 10 or 12
+   * (16 is capped at 12), JPEG and WebP are always 8-bit.
+   * By default 16-bit sources stay 16-bit in PNG and everything else is 8-bit.
+   * 16-bit pixels written at 8 bits are dithered to avoid banding.
+   */
+  bitDepth(bits: 8 | 10 | 12 | 16): ImageEngine
   /**
    * Preserve metadata in output.
    * - ICC profile: Preserved when `icc: true` (default when options provided)
@@ -76,10 +151,93 @@ export declare class ImageEngine {
   brightness(value: number): ImageEngine
   /** Adjust contrast (-100 to 100) */
   contrast(value: number): ImageEngine
+  /**
+   * Adjust in CIE LCh: `brightness` and `saturation` are multipliers
+   * (default 1), `hue` rotates by degrees and `lightness` is added to L
+   * (-100 to 100).
+   */
+  modulate(options?: ModulateOptions | undefined | null): ImageEngine
+  /**
+   * Apply the power curve `out = in^(1 / gamma)` (0.1 to 10). Values
+   * above 1 brighten mid-tones; 1 is a no-op.
+   */
+  gamma(gamma: number): ImageEngine
+  /** Invert the colour channels; alpha is kept. */
+  negate(): ImageEngine
+  /**
+   * Binarize at `value` (0 to 255, default 128): samples at or above it
+   * become white, the rest black. Converts to greyscale first unless
+   * `options.grayscale` is false, in which case each colour channel is
+   * thresholded on its own.
+   */
+  threshold(value?: number | undefined | null, options?: ThresholdOptions | undefined | null): ImageEngine
+  /**
+   * Stretch `inBlack..inWhite` to `outBlack..outWhite` (0 to 255) with a
+   * mid-tone `gamma` (0.1 to 10, default 1). Reversed output bounds invert.
+   */
+  levels(options?: LevelsOptions | undefined | null): ImageEngine
+  /**
+   * Stretch luminance so the `lower` and `upper` percentiles (0 to 100,
+   * default 1 and 99) of its histogram become black and white.
+   */
+  normalize(options?: NormalizeOptions | undefined | null): ImageEngine
+  /**
+   * Contrast-limited adaptive histogram equalization over `width` x
+   * `height` pixel tiles. `maxSlope` (0 to 100, default 3) caps the
+   * contrast gain; 0 disables the limit.
+   */
+  clahe(options: ClaheOptions): ImageEngine
+  /**
+   * Unsharp mask: colour samples move away from their Gaussian blur
+   * (`sigma` 0.3 to 10 pixels, default 1) by `amount` (0 to 10, default 1)
+   * times the difference, skipping differences below `threshold`
+   * (0 to 255, default 0). Alpha is kept.
+   */
+  sharpen(options?: SharpenOptions | undefined | null): ImageEngine
+  /**
+   * Multiply each pixel by a colour matrix given as rows: 3x3 acts on RGB
+   * (alpha kept), 4x4 on RGBA (alpha reads as opaque when the image has
+   * none). Greyscale input becomes RGB.
+   */
+  recomb(matrix: Array<Array<number>>): ImageEngine
+  /**
+   * Map colours through an Adobe `.cube` 3D LUT (trilinear
+   * interpolation; alpha kept). The file is parsed and validated here,
+   * so malformed LUTs throw `E220` and oversized ones `E221`.
+   */
+  applyLut(cube: Buffer): ImageEngine
+  /**
+   * Gaussian blur with `sigma` (0.3 to 100 pixels) of every channel. With
+   * `regions`, only those rectangles (clipped to the image) are blurred;
+   * their edges still sample the surrounding pixels.
+   */
+  blur(sigma: number, regions?: Array<Region> | undefined | null): ImageEngine
+  /**
+   * Replace `blockSize` x `blockSize` squares (1 to 4096 pixels) with
+   * their mean colour. With `regions`, only those rectangles (clipped to
+   * the image) are pixelated, on a grid starting at each region's corner.
+   */
+  pixelate(blockSize: number, regions?: Array<Region> | undefined | null): ImageEngine
+  /**
+   * Blend overlay images onto the image, in order, at this point of the
+   * pipeline (queue it after resize/crop to place it on the final frame).
+   * Each overlay is decoded when the pipeline runs, under the same
+   * firewall limits as the source.
+   */
+  composite(layers: Array<CompositeLayer>): ImageEngine
+  /**
+   * Convert colours from the input's embedded ICC profile (sRGB when
+   * untagged) to `profile`: `'srgb'`, `'p3'`, `'rec2020'` or the bytes of
+   * an RGB ICC profile. The target profile is embedded in the output, except
+   * sRGB when metadata is stripped. AVIF and PNG output also signal sRGB,
+   * Display P3 and BT.2020 with CICP code points. Unusable profile bytes
+   * throw `E210`.
+   */
+  toColorProfile(profile: 'srgb' | 'p3' | 'display-p3' | 'rec2020' | Buffer): ImageEngine
   /**
    * Normalize pixel format to RGB/RGBA without performing any color space transformation.
    * This does not apply ICC profile conversion; it only guarantees the pixel layout is RGB/RGBA.
-   * Use a dedicated color management library for true color space conversions.
+   * Use `toColorProfile()` for true color space conversions.
    */
   normalizePixelFormat(): ImageEngine
   /**
@@ -151,6 +309,13 @@ export declare class ImageEngine {
    * Returns the profile size in bytes, or null if no profile exists.
    */
   hasIccProfile(): number | null
+  /**
+   * Read camera make/model, lens, capture date, orientation, exposure,
+   * copyright, XMP packet, IPTC keywords, ICC description and density
+   * from the source headers WITHOUT decoding pixels.
+   * GPS coordinates are only included with `{ gps: true }`.
+   */
+  metadata(options?: MetadataOptions | undefined | null): EmbeddedMetadata
   /**
    * Process multiple images in parallel with the same operations.
    *
@@ -196,11 +361,233 @@ export interface BatchResult {
   errorCategory?: ErrorCategory
 }
 
+/**
+ * Crop window chosen by a content-aware cover resize (`position: 'entropy' | 'attention'`)
+ * or kept by `trim()`. Coordinates are relative to the image the operation received
+ * (for cover, the scaled image the window was cut from).
+ */
+export interface CropRegion {
+  left: number
+  top: number
+  width: number
+  height: number
+}
+
+/** Fractional focal point used by `resize(..., 'cover', position)` */
+export interface FocalPoint {
+  /** Horizontal position (0.0 = left edge, 1.0 = right edge) */
+  x: number
+  /** Vertical position (0.0 = top edge, 1.0 = bottom edge) */
+  y: number
+}
+
+/** Options for `modulate()` */
+export interface ModulateOptions {
+  /** Lightness multiplier (0 to 10, default 1) */
+  brightness?: number
+  /** Chroma multiplier (0 to 10, default 1; 0 = greyscale) */
+  saturation?: number
+  /** Hue rotation in degrees (default 0) */
+  hue?: number
+  /** Added to lightness L (-100 to 100, default 0) */
+  lightness?: number
+}
+
+/** Options for `threshold()` */
+export interface ThresholdOptions {
+  /** Convert to greyscale before thresholding (default true) */
+  grayscale?: boolean
+}
+
+/** Options for `levels()` */
+export interface LevelsOptions {
+  /** Input value mapped to `outBlack` (0-255, default 0) */
+  inBlack?: number
+  /** Input value mapped to `outWhite` (0-255, default 255) */
+  inWhite?: number
+  /** Mid-tone curve `in^(1 / gamma)` (0.1-10, default 1) */
+  gamma?: number
+  /** Output for `inBlack` and below (0-255, default 0) */
+  outBlack?: number
+  /** Output for `inWhite` and above (0-255, default 255) */
+  outWhite?: number
+}
+
+/** Options for `normalize()` */
+export interface NormalizeOptions {
+  /** Percentile mapped to black (0-100, default 1) */
+  lower?: number
+  /** Percentile mapped to white (0-100, default 99) */
+  upper?: number
+}
+
+/** Options for `clahe()` */
+export interface ClaheOptions {
+  /** Tile width in pixels */
+  width: number
+  /** Tile height in pixels */
+  height: number
+  /** Contrast gain cap (0-100, default 3; 0 = unlimited) */
+  maxSlope?: number
+}
+
+/** Options for `sharpen()` */
+export interface SharpenOptions {
+  /** Gaussian radius in pixels (0.3-10, default 1) */
+  sigma?: number
+  /** Strength of the mask (0-10, default 1) */
+  amount?: number
+  /** Smallest difference sharpened (0-255, default 0) */
+  threshold?: number
+}
+
+/** Rectangle limiting `blur()` / `pixelate()` */
+export interface Region {
+  left: number
+  top: number
+  width: number
+  height: number
+}
+
+/** One overlay for `composite()` */
+export interface CompositeLayer {
+  /** Encoded image bytes or a file path */
+  input: Buffer | string
+  /** Compass anchor (default "centre"); not combined with `left` / `top` */
+  gravity?: string
+  /** Pixel offset from the left edge (may be negative); requires `top` */
+  left?: number
+  /** Pixel offset from the top edge (may be negative); requires `left` */
+  top?: number
+  /** "over" (default), "multiply" or "screen" */
+  blend?: string
+  /** Multiplier on the overlay's alpha (0-1, default 1) */
+  opacity?: number
+  /** Repeat the overlay over the whole image (default false) */
+  tile?: boolean
+}
+
+/** Options for `flatten()` */
+export interface FlattenOptions {
+  /** Colour composited under transparent pixels (default white) */
+  background?: string
+}
+
+/** Options for `affine()` */
+export interface AffineOptions {
+  /** Output shift `[tx, ty]` in pixels (default `[0, 0]`) */
+  translate?: Array<number>
+  /** "bilinear" (default) or "bicubic" */
+  interpolator?: 'bilinear' | 'bicubic'
+  /** Fill for areas the warped image does not cover (default opaque black) */
+  background?: string
+}
+
+/** Options for `rotate()` */
+export interface RotateOptions {
+  /** Fill for the corners uncovered by non-right-angle rotations (default opaque black) */
+  background?: string
+  /** "bilinear" (default) or "bicubic" */
+  interpolation?: 'bilinear' | 'bicubic'
+}
+
+/** Options for `trim()` */
+export interface TrimOptions {
+  /** Allowed per-channel difference from the background, 0-255 (default 10) */
+  threshold?: number
+  /** Border colour (default: the top-left pixel) */
+  background?: string
+}
+
+/** Options for `extend()` */
+export interface ExtendOptions {
+  /** Pixels added above the image (default 0) */
+  top?: number
+  /** Pixels added right of the image (default 0) */
+  right?: number
+  /** Pixels added below the image (default 0) */
+  bottom?: number
+  /** Pixels added left of the image (default 0) */
+  left?: number
+  /** Fill colour for "background" mode (default transparent) */
+  background?: string
+  /** "background" (default), "copy" or "mirror" */
+  extendWith?: 'background' | 'copy' | 'mirror'
+}
+
+/** Extra options for `resize()` */
+export interface ResizeOptions {
+  /** Padding colour for "contain" (`#rgb`, `#rrggbb`, `#rrggbbaa`, `transparent`, ...) */
+  background?: string
+  /** Keep the current size instead of scaling up */
+  withoutEnlargement?: boolean
+  /** Keep the current size instead of scaling down */
+  withoutReduction?: boolean
+  /** Resampling kernel (default "lanczos3") */
+  kernel?: ResizeKernel
+  /** Resample in linear light (gamma-correct) instead of sRGB */
+  linear?: boolean
+  /** "auto" sharpens after downscaling, scaled to the reduction ratio */
+  sharpen?: 'auto' | false
+}
+
+export type ResizeKernel = 'nearest' | 'box' | 'bilinear' | 'catmullrom' | 'mitchell' | 'lanczos3'
+
 export interface Dimensions {
   width: number
   height: number
 }
 
+/** Options for `metadata()` */
+export interface MetadataOptions {
+  /** Include GPS coordinates (default false) */
+  gps?: boolean
+}
+
+/** GPS position returned by `metadata({ gps: true })` */
+export interface GpsPosition {
+  /** Decimal degrees, negative south of the equator */
+  latitude: number
+  /** Decimal degrees, negative west of Greenwich */
+  longitude: number
+  /** Metres above sea level (negative below) */
+  altitude?: number
+}
+
+/** Metadata returned by `metadata()`; absent fields are undefined */
+export interface EmbeddedMetadata {
+  /** Camera manufacturer (EXIF Make) */
+  make?: string
+  /** Camera model (EXIF Model) */
+  model?: string
+  /** Lens model (EXIF LensModel, or LensMake) */
+  lens?: string
+  /** Capture time as ISO 8601, with the EXIF offset when recorded */
+  captureDate?: string
+  /** EXIF Orientation (1-8) */
+  orientation?: number
+  /** Exposure time in seconds */
+  exposureTime?: number
+  /** Aperture f-number */
+  fNumber?: number
+  /** ISO sensitivity */
+  iso?: number
+  /** Focal length in millimetres */
+  focalLength?: number
+  /** Only present with `{ gps: true }` */
+  gps?: GpsPosition
+  /** EXIF Copyright */
+  copyright?: string
+  /** Raw XMP packet */
+  xmp?: string
+  /** IPTC keywords (empty when none) */
+  iptcKeywords: Array<string>
+  /** Description of the embedded ICC profile */
+  iccDescription?: string
+  /** Pixels per inch */
+  density?: number
+}
+
 /**
  * Error taxonomy for proper error handling in JavaScript
  *
@@ -247,6 +634,8 @@ export declare const enum ErrorCode {
   InvalidResizeDimensions = 203,
   InvalidResizeFit = 204,
   UnsupportedColorSpace = 210,
+  InvalidLut = 220,
+  LutSizeExceedsLimit = 221,
   ResizeFailed = 299,
   EncodeFailed = 300,
   FileWriteFailed = 301,
@@ -379,6 +768,10 @@ export interface ProcessingMetrics {
   metadataStripped: boolean
   /** Non-fatal policy rejections (e.g., strict policy forcing metadata strip) */
   policyViolations: Array<string>
+  /** Crop window picked by the last content-aware cover resize, if any */
+  smartCrop?: CropRegion
+  /** Region kept by the last trim(), relative to the image it received */
+  trim?: CropRegion
   /** Time taken to decode the image (milliseconds) - legacy alias of decode_ms */
   decodeTime: number
   /** Time taken to apply all operations (milliseconds) - legacy alias of ops_ms */
@@ -426,11 +819,72 @@ export declare function createStreamingPipeline(options: {
   format?: string
   quality?: number
   ops?: Array<{
-    op: 'resize' | 'rotate' | 'flipH' | 'flipV' | 'grayscale' | 'autoOrient'
+    op:
+      | 'resize'
+      | 'rotate'
+      | 'affine'
+      | 'flatten'
+      | 'ensureAlpha'
+      | 'removeAlpha'
+      | 'extractChannel'
+      | 'joinChannel'
+      | 'flipH'
+      | 'flipV'
+      | 'grayscale'
+      | 'modulate'
+      | 'gamma'
+      | 'negate'
+      | 'threshold'
+      | 'levels'
+      | 'normalize'
+      | 'clahe'
+      | 'sharpen'
+      | 'recomb'
+      | 'applyLut'
+      | 'blur'
+      | 'pixelate'
+      | 'composite'
+      | 'toColorProfile'
+      | 'autoOrient'
     width?: number
     height?: number
     fit?: string
+    position?: string | FocalPoint
+    background?: string
+    withoutEnlargement?: boolean
+    withoutReduction?: boolean
+    kernel?: ResizeKernel
+    linear?: boolean
+    sharpen?: 'auto' | false
     degrees?: number
+    interpolation?: 'bilinear' | 'bicubic'
+    translate?: Array<number>
+    interpolator?: 'bilinear' | 'bicubic'
+    alpha?: number
+    channel?: 'r' | 'g' | 'b' | 'alpha' | 'red' | 'green' | 'blue'
+    mask?: Buffer
+    brightness?: number
+    saturation?: number
+    hue?: number
+    lightness?: number
+    gamma?: number
+    threshold?: number
+    grayscale?: boolean
+    inBlack?: number
+    inWhite?: number
+    outBlack?: number
+    outWhite?: number
+    lower?: number
+    upper?: number
+    maxSlope?: number
+    sigma?: number
+    amount?: number
+    blockSize?: number
+    regions?: Array<Region>
+    matrix?: Array<Array<number>>
+    cube?: Buffer
+    layers?: Array<CompositeLayer>
+    profile?: 'srgb' | 'p3' | 'display-p3' | 'rec2020' | Buffer
     enabled?: boolean
   }>
   /**
@@ -645,6 +645,7 @@ function getErrorCategory(err) {
       case ErrorCode.InvalidArgument:
       case ErrorCode.InvalidPreset:
       case ErrorCode.InvalidFirewallPolicy:
+      case ErrorCode.InvalidLut:
       case ErrorCode.SourceConsumed:
         return ErrorCategory.UserError
 
@@ -664,6 +665,7 @@ function getErrorCategory(err) {
       case ErrorCode.MmapFailed:
       case ErrorCode.FileWriteFailed:
       case ErrorCode.FirewallViolation:
+      case ErrorCode.LutSizeExceedsLimit:
         return ErrorCategory.ResourceLimit
 
       // InternalBug: library bugs
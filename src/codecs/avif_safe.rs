@@ -436,17 +436,26 @@ impl Default for SafeAvifRwData {
 
 /// Helper function to create and configure an avifRGBImage structure.
 /// This encapsulates the unsafe operations needed to set up RGB image data.
+/// `depth` is the sample size of `pixels`: 8 (RGBA8) or 16 (native-endian RGBA16).
 pub fn create_rgb_image(
     image: &mut SafeAvifImage,
     pixels: *const u8,
     width: u32,
     height: u32,
+    depth: u32,
 ) -> Result<avifRGBImage, LazyImageError> {
     // Ensure dimensions are non-zero and within global bounds.
     SafeAvifImage::validate_dimensions(width, height)?;
 
-    // rowBytes = width * 4 (RGBA8). Validate against overflow and libavif expectations (u32).
-    let row_bytes_u32: u32 = width.checked_mul(4).ok_or_else(|| {
+    if depth != 8 && depth != 16 {
+        return Err(LazyImageError::encode_failed(
+            "avif",
+            format!("unsupported RGB sample depth {depth}"),
+        ));
+    }
+
+    // rowBytes = width * 4 samples. Validate against overflow and libavif expectations (u32).
+    let row_bytes_u32: u32 = width.checked_mul(4 * depth / 8).ok_or_else(|| {
         LazyImageError::encode_failed("avif", "row bytes overflow for RGBA image")
     })?;
 
@@ -474,7 +483,7 @@ pub fn create_rgb_image(
     unsafe {
         avifRGBImageSetDefaults(&mut rgb, image.as_mut_ptr());
         rgb.format = AVIF_RGB_FORMAT_RGBA;
-        rgb.depth = 8;
+        rgb.depth = depth;
         rgb.pixels = pixels as *mut u8;
         rgb.rowBytes = row_bytes_u32;
     }
@@ -543,8 +552,8 @@ mod tests {
     fn create_rgb_image_rejects_pixel_overflow() {
         // MAX_DIMENSION^2 exceeds MAX_PIXELS, should fail validation.
         let mut img = SafeAvifImage::new(1, 1, 8, AVIF_PIXEL_FORMAT_YUV420).unwrap();
-        let err =
-            create_rgb_image(&mut img, std::ptr::null(), MAX_DIMENSION, MAX_DIMENSION).unwrap_err();
+        let err = create_rgb_image(&mut img, std::ptr::null(), MAX_DIMENSION, MAX_DIMENSION, 8)
+            .unwrap_err();
         assert!(err.to_string().contains("Pixel count"));
     }
 
@@ -552,9 +561,15 @@ mod tests {
     fn create_rgb_image_sets_row_bytes() {
         let mut img = SafeAvifImage::new(4, 2, 8, AVIF_PIXEL_FORMAT_YUV420).unwrap();
         let pixels: [u8; 32] = [0; 32];
-        let rgb = create_rgb_image(&mut img, pixels.as_ptr(), 4, 2).unwrap();
+        let rgb = create_rgb_image(&mut img, pixels.as_ptr(), 4, 2, 8).unwrap();
         assert_eq!(rgb.rowBytes, 16);
         assert_eq!(rgb.format, AVIF_RGB_FORMAT_RGBA);
+
+        let mut img = SafeAvifImage::new(4, 2, 10, AVIF_PIXEL_FORMAT_YUV420).unwrap();
+        let pixels: [u16; 32] = [0; 32];
+        let rgb = create_rgb_image(&mut img, pixels.as_ptr().cast(), 4, 2, 16).unwrap();
+        assert_eq!(rgb.rowBytes, 32);
+        assert_eq!(rgb.depth, 16);
     }
 
     #[test]
@@ -34,16 +34,28 @@ pub const FUZZ_MAX_PIXELS: u64 = 1_000_000; // ~4MB RGBA; keeps fuzz run under 2
 
 // Import decomposed modules
 mod api;
+mod channels;
 mod common;
+mod composite;
 mod decoder;
+mod depth;
 mod encoder;
+mod filter;
 mod firewall;
+mod grade;
+mod icc;
 mod io;
+mod linear;
 mod memory;
+mod metadata;
 mod pipeline;
 mod pool;
+mod rotate;
+mod smartcrop;
 mod stress;
 mod tasks;
+mod tone;
+mod trim;
 
 // Re-export commonly used types and functions
 pub use api::ImageEngine;
@@ -52,14 +64,14 @@ pub use decoder::{
     ensure_dimensions_safe,
 };
 pub use encoder::{
-    embed_icc_jpeg, embed_icc_png, embed_icc_webp, encode_avif, encode_jpeg, encode_png,
-    encode_webp, QualitySettings,
+    embed_icc_jpeg, embed_icc_png, embed_icc_webp, encode_avif, encode_avif_with_settings,
+    encode_jpeg, encode_png, encode_png_with_gamut, encode_webp, QualitySettings,
 };
 pub use firewall::FirewallConfig;
 pub use io::{extract_icc_profile, extract_icc_profile_lossy, Source};
 pub use pipeline::{
-    apply_ops, calc_resize_dimensions, fast_resize, fast_resize_internal, fast_resize_owned,
-    optimize_ops, ResizeError,
+    apply_ops, calc_outside_resize_dimensions, calc_resize_dimensions, fast_resize,
+    fast_resize_internal, fast_resize_owned, optimize_ops, Gamut, ResizeError,
 };
 
 // Re-export pool constants for tasks.rs
@@ -68,7 +80,7 @@ pub use pool::{get_pool, MAX_CONCURRENCY};
 
 // Re-export types from api.rs and tasks.rs
 #[cfg(feature = "napi")]
-pub use api::{Dimensions, PresetResult};
+pub use api::{Dimensions, EmbeddedMetadata, PresetResult};
 #[cfg(feature = "napi")]
 pub use tasks::BatchResult;
 
@@ -92,7 +104,9 @@ mod tests {
     use crate::engine::firewall::FirewallConfig;
     use crate::engine::tasks::EncodeTask;
     use crate::error::LazyImageError;
-    use crate::ops::{Operation, OutputFormat, ResizeFit};
+    use crate::ops::{
+        Color, Operation, OutputDepth, OutputFormat, ResizeFit, ResizeKernel, ResizePosition,
+    };
     use image::{DynamicImage, GenericImageView, RgbImage, RgbaImage};
     use std::borrow::Cow;
     use std::sync::Arc;
@@ -831,6 +845,12 @@ mod tests {
                 width: Some(50),
                 height: Some(50),
                 fit: ResizeFit::Inside,
+                position: ResizePosition::Center,
+                without_enlargement: false,
+                without_reduction: false,
+                kernel: ResizeKernel::Lanczos3,
+                linear: false,
+                sharpen: false,
             }];
             let result = apply_ops(Cow::Owned(img), &ops).unwrap();
             assert_eq!(result.dimensions(), (50, 50));
@@ -843,6 +863,12 @@ mod tests {
                 width: Some(50),
                 height: None,
                 fit: ResizeFit::Inside,
+                position: ResizePosition::Center,
+                without_enlargement: false,
+                without_reduction: false,
+                kernel: ResizeKernel::Lanczos3,
+                linear: false,
+                sharpen: false,
             }];
             let result = apply_ops(Cow::Owned(img), &ops).unwrap();
             assert_eq!(result.dimensions(), (50, 25));
@@ -855,6 +881,12 @@ mod tests {
                 width: None,
                 height: Some(25),
                 fit: ResizeFit::Inside,
+                position: ResizePosition::Center,
+                without_enlargement: false,
+                without_reduction: false,
+                kernel: ResizeKernel::Lanczos3,
+                linear: false,
+                sharpen: false,
             }];
             let result = apply_ops(Cow::Owned(img), &ops).unwrap();
             assert_eq!(result.dimensions(), (50, 25));
@@ -1024,6 +1056,12 @@ mod tests {
                     width: Some(100),
                     height: None,
                     fit: ResizeFit::Inside,
+                    position: ResizePosition::Center,
+                    without_enlargement: false,
+                    without_reduction: false,
+                    kernel: ResizeKernel::Lanczos3,
+                    linear: false,
+                    sharpen: false,
                 },
                 Operation::Rotate { degrees: 90 },
                 Operation::Grayscale,
@@ -1053,11 +1091,23 @@ mod tests {
                     width: Some(800),
                     height: None,
                     fit: ResizeFit::Inside,
+                    position: ResizePosition::Center,
+                    without_enlargement: false,
+                    without_reduction: false,
+                    kernel: ResizeKernel::Lanczos3,
+                    linear: false,
+                    sharpen: false,
                 },
                 Operation::Resize {
                     width: Some(400),
                     height: None,
                     fit: ResizeFit::Inside,
+                    position: ResizePosition::Center,
+                    without_enlargement: false,
+                    without_reduction: false,
+                    kernel: ResizeKernel::Lanczos3,
+                    linear: false,
+                    sharpen: false,
                 },
             ];
             let optimized = optimize_ops(&ops);
@@ -1066,6 +1116,7 @@ mod tests {
                 width,
                 height: _,
                 fit,
+                ..
             } = &optimized[0]
             {
                 assert_eq!(*width, Some(400));
@@ -1082,12 +1133,24 @@ mod tests {
                     width: Some(800),
                     height: None,
                     fit: ResizeFit::Inside,
+                    position: ResizePosition::Center,
+                    without_enlargement: false,
+                    without_reduction: false,
+                    kernel: ResizeKernel::Lanczos3,
+                    linear: false,
+                    sharpen: false,
                 },
                 Operation::Grayscale,
                 Operation::Resize {
                     width: Some(400),
                     height: None,
                     fit: ResizeFit::Inside,
+                    position: ResizePosition::Center,
+                    without_enlargement: false,
+                    without_reduction: false,
+                    kernel: ResizeKernel::Lanczos3,
+                    linear: false,
+                    sharpen: false,
                 },
             ];
             let optimized = optimize_ops(&ops);
@@ -1100,6 +1163,12 @@ mod tests {
                 width: Some(100),
                 height: None,
                 fit: ResizeFit::Inside,
+                position: ResizePosition::Center,
+                without_enlargement: false,
+                without_reduction: false,
+                kernel: ResizeKernel::Lanczos3,
+                linear: false,
+                sharpen: false,
             }];
             let optimized = optimize_ops(&ops);
             assert_eq!(optimized.len(), 1);
@@ -1119,16 +1188,34 @@ mod tests {
                     width: Some(1000),
                     height: None,
                     fit: ResizeFit::Inside,
+                    position: ResizePosition::Center,
+                    without_enlargement: false,
+                    without_reduction: false,
+                    kernel: ResizeKernel::Lanczos3,
+                    linear: false,
+                    sharpen: false,
                 },
                 Operation::Resize {
                     width: Some(800),
                     height: None,
                     fit: ResizeFit::Inside,
+                    position: ResizePosition::Center,
+                    without_enlargement: false,
+                    without_reduction: false,
+                    kernel: ResizeKernel::Lanczos3,
+                    linear: false,
+                    sharpen: false,
                 },
                 Operation::Resize {
                     width: Some(400),
                     height: None,
                     fit: ResizeFit::Inside,
+                    position: ResizePosition::Center,
+                    without_enlargement: false,
+                    without_reduction: false,
+                    kernel: ResizeKernel::Lanczos3,
+                    linear: false,
+                    sharpen: false,
                 },
             ];
             let optimized = optimize_ops(&ops);
@@ -1137,6 +1224,7 @@ mod tests {
                 width,
                 height: _,
                 fit,
+                ..
             } = &optimized[0]
             {
                 assert_eq!(*width, Some(400));
@@ -1151,16 +1239,31 @@ mod tests {
                     width: Some(800),
                     height: None,
                     fit: ResizeFit::Inside,
+                    position: ResizePosition::Center,
+                    without_enlargement: false,
+                    without_reduction: false,
+                    kernel: ResizeKernel::Lanczos3,
+                    linear: false,
+                    sharpen: false,
                 },
                 Operation::Resize {
                     width: Some(400),
                     height: Some(300),
                     fit: ResizeFit::Inside,
+                    position: ResizePosition::Center,
+                    without_enlargement: false,
+                    without_reduction: false,
+                    kernel: ResizeKernel::Lanczos3,
+                    linear: false,
+                    sharpen: false,
                 },
             ];
             let optimized = optimize_ops(&ops);
             assert_eq!(optimized.len(), 1);
-            if let Operation::Resize { width, height, fit } = &optimized[0] {
+            if let Operation::Resize {
+                width, height, fit, ..
+            } = &optimized[0]
+            {
                 assert_eq!(*width, Some(400));
                 assert_eq!(*height, Some(300));
                 assert_eq!(*fit, ResizeFit::Inside);
@@ -1382,6 +1485,8 @@ mod tests {
                 keep_exif: false,
                 strip_gps: true,
                 firewall: FirewallConfig::disabled(),
+                flatten_background: Color::WHITE,
+                output_depth: OutputDepth::Auto,
                 #[cfg(feature = "napi")]
                 last_error: None,
             };
@@ -1408,6 +1513,8 @@ mod tests {
                 keep_exif: false,
                 strip_gps: true,
                 firewall: FirewallConfig::disabled(),
+                flatten_background: Color::WHITE,
+                output_depth: OutputDepth::Auto,
                 #[cfg(feature = "napi")]
                 last_error: None,
             };
@@ -1417,6 +1524,182 @@ mod tests {
             assert_eq!(decoded_img.dimensions(), img.dimensions());
         }
 
+        #[test]
+        fn test_jpeg_output_flattens_alpha_onto_background() {
+            let img = DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(
+                16,
+                16,
+                image::Rgba([0, 0, 0, 0]),
+            ));
+            let task = EncodeTask {
+                source: None,
+                decoded: Some(Arc::new(img)),
+                ops: vec![],
+                format: OutputFormat::Jpeg {
+                    quality: 90,
+                    fast_mode: false,
+                },
+                icc_profile: None,
+                icc_present: false,
+                exif_data: None,
+                auto_orient: true,
+                keep_icc: false,
+                keep_exif: false,
+                strip_gps: true,
+                firewall: FirewallConfig::disabled(),
+                flatten_background: Color::rgba(255, 0, 0, 255),
+                output_depth: OutputDepth::Auto,
+                #[cfg(feature = "napi")]
+                last_error: None,
+            };
+            let jpeg = task.process_and_encode(None).unwrap();
+            let decoded = image::load_from_memory(&jpeg).unwrap().to_rgb8();
+            let pixel = decoded.get_pixel(8, 8).0;
+            assert!(
+                pixel[0] > 240 && pixel[1] < 20 && pixel[2] < 20,
+                "transparent pixels should become the flatten background, got {pixel:?}"
+            );
+        }
+
+        #[test]
+        fn test_png_output_depth_follows_pipeline_and_request() {
+            let ramp = DynamicImage::ImageRgb16(image::ImageBuffer::from_fn(32, 4, |x, _| {
+                image::Rgb([20000 + x as u16, 0, 65535])
+            }));
+            let encode = |depth: OutputDepth, img: &DynamicImage| {
+                let task = EncodeTask {
+                    source: None,
+                    decoded: Some(Arc::new(img.clone())),
+                    ops: vec![Operation::Resize {
+                        width: Some(16),
+                        height: None,
+                        fit: ResizeFit::Inside,
+                        position: ResizePosition::Center,
+                        without_enlargement: false,
+                        without_reduction: false,
+                        kernel: ResizeKernel::Lanczos3,
+                        linear: false,
+                        sharpen: false,
+                    }],
+                    format: OutputFormat::Png,
+                    icc_profile: None,
+                    icc_present: false,
+                    exif_data: None,
+                    auto_orient: true,
+                    keep_icc: false,
+                    keep_exif: false,
+                    strip_gps: true,
+                    firewall: FirewallConfig::disabled(),
+                    flatten_background: Color::WHITE,
+                    output_depth: depth,
+                    #[cfg(feature = "napi")]
+                    last_error: None,
+                };
+                let png = task.process_and_encode(None).unwrap();
+                image::load_from_memory(&png).unwrap()
+            };
+
+            let kept = encode(OutputDepth::Auto, &ramp);
+            assert_eq!(kept.color(), image::ColorType::Rgb16);
+            assert!(kept.to_rgb16().pixels().any(|p| p[0] % 257 != 0));
+
+            let reduced = encode(OutputDepth::Eight, &ramp);
+            assert_eq!(reduced.color(), image::ColorType::Rgb8);
+
+            let widened = encode(OutputDepth::Sixteen, &create_test_image(32, 4));
+            assert_eq!(widened.color(), image::ColorType::Rgb16);
+        }
+
+        #[test]
+        fn test_sixteen_bit_png_source_keeps_depth() {
+            let ramp = DynamicImage::ImageRgba16(image::ImageBuffer::from_fn(32, 4, |x, y| {
+                image::Rgba([20000 + x as u16, 300 * y as u16, 65535, 40000])
+            }));
+            let mut png16 = Vec::new();
+            ramp.write_to(
+                &mut std::io::Cursor::new(&mut png16),
+                image::ImageFormat::Png,
+            )
+            .unwrap();
+
+            let (decoded, cmyk) = crate::engine::decoder::decode_source(&png16).unwrap();
+            assert!(!cmyk);
+            assert_eq!(decoded.color(), image::ColorType::Rgba16);
+            assert_eq!(decoded.to_rgba16().as_raw(), ramp.to_rgba16().as_raw());
+
+            use crate::engine::io::Source;
+            let task = EncodeTask {
+                source: Some(Source::Memory(Arc::new(png16))),
+                decoded: None,
+                ops: vec![],
+                format: OutputFormat::Png,
+                icc_profile: None,
+                icc_present: false,
+                exif_data: None,
+                auto_orient: true,
+                keep_icc: false,
+                keep_exif: false,
+                strip_gps: true,
+                firewall: FirewallConfig::disabled(),
+                flatten_background: Color::WHITE,
+                output_depth: OutputDepth::Auto,
+                #[cfg(feature = "napi")]
+                last_error: None,
+            };
+            let png = task.process_and_encode(None).unwrap();
+            assert_eq!(png[24], 16, "IHDR bit depth");
+            let out = image::load_from_memory(&png).unwrap();
+            assert_eq!(out.color(), image::ColorType::Rgba16);
+            assert!(out.to_rgba16().pixels().any(|p| p[0] % 257 != 0));
+        }
+
+        #[test]
+        fn test_png_signals_tracked_gamut() {
+            let p3 = Arc::new(moxcms::ColorProfile::new_display_p3().encode().unwrap());
+            let encode = |keep_icc: bool, ops: Vec<Operation>| {
+                let task = EncodeTask {
+                    source: None,
+                    decoded: Some(Arc::new(create_test_image(8, 8))),
+                    ops,
+                    format: OutputFormat::Png,
+                    icc_profile: Some(Arc::clone(&p3)),
+                    icc_present: true,
+                    exif_data: None,
+                    auto_orient: true,
+                    keep_icc,
+                    keep_exif: false,
+                    strip_gps: true,
+                    firewall: FirewallConfig::disabled(),
+                    flatten_background: Color::WHITE,
+                    output_depth: OutputDepth::Auto,
+                    #[cfg(feature = "napi")]
+                    last_error: None,
+                };
+                let png = task.process_and_encode(None).unwrap();
+                let png = img_parts::png::Png::from_bytes(png.into()).unwrap();
+                png.chunks().iter().map(|c| c.kind()).collect::<Vec<_>>()
+            };
+
+            // Kept P3 profile: cICP next to the ICC profile.
+            let kept = encode(true, vec![]);
+            assert!(kept.contains(b"cICP") && kept.contains(b"iCCP"));
+
+            // Stripped profile: converted to sRGB and marked with sRGB + gAMA.
+            let stripped = encode(false, vec![]);
+            assert!(stripped.contains(b"sRGB") && stripped.contains(b"gAMA"));
+            assert!(!stripped.contains(b"cICP") && !stripped.contains(b"iCCP"));
+
+            // The last conversion decides.
+            let rec2020 = encode(
+                false,
+                vec![Operation::IccTransform {
+                    source: None,
+                    target: crate::ops::IccProfile::Bt2020,
+                }],
+            );
+            assert!(rec2020.contains(b"cICP") && rec2020.contains(b"iCCP"));
+        }
+
         #[test]
         fn test_decode_no_source() {
             let task = EncodeTask {
@@ -1432,6 +1715,8 @@ mod tests {
                 keep_exif: false,
                 strip_gps: true,
                 firewall: FirewallConfig::disabled(),
+                flatten_background: Color::WHITE,
+                output_depth: OutputDepth::Auto,
                 #[cfg(feature = "napi")]
                 last_error: None,
             };
@@ -1463,6 +1748,8 @@ mod tests {
                 keep_exif: false,
                 strip_gps: true,
                 firewall,
+                flatten_background: Color::WHITE,
+                output_depth: OutputDepth::Auto,
                 #[cfg(feature = "napi")]
                 last_error: None,
             };
@@ -1490,6 +1777,8 @@ mod tests {
                 keep_exif: false,
                 strip_gps: true,
                 firewall,
+                flatten_background: Color::WHITE,
+                output_depth: OutputDepth::Auto,
                 #[cfg(feature = "napi")]
                 last_error: None,
             };
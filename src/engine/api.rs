@@ -15,11 +15,17 @@ use crate::engine::io::{extract_exif_raw, extract_icc_profile_lossy, Source};
 use crate::engine::tasks::{
     BatchResult, BatchTask, EncodeTask, EncodeWithMetricsTask, WriteFileTask,
 };
+#[cfg(feature = "napi")]
+use crate::engine::{decoder::detect_format, metadata};
 use crate::error::LazyImageError;
 #[cfg(feature = "napi")]
-use crate::ops::{Operation, OutputFormat, PresetConfig, ResizeFit};
+use crate::ops::{
+    BlendMode, Channel, Color, ExtendMode, IccProfile, Levels, Operation, OutputDepth,
+    OutputFormat, Overlay, OverlayPlacement, PresetConfig, ResizeFit, ResizeKernel, ResizePosition,
+    RotateInterpolation, Sharpen,
+};
 #[cfg(not(feature = "napi"))]
-use crate::ops::{Operation, PresetConfig};
+use crate::ops::{Color, Operation, OutputDepth, PresetConfig};
 #[cfg(feature = "napi")]
 use image::ImageReader;
 use image::{DynamicImage, GenericImageView};
@@ -59,6 +65,7 @@ fn napi_err(env: &Env, err: LazyImageError) -> napi::Error {
 #[cfg(feature = "napi")]
 mod validation {
     use super::*;
+    use crate::CropRegion;
     use std::path::Path;
 
     #[derive(Debug, PartialEq, Eq)]
@@ -150,6 +157,111 @@ mod validation {
         Ok((width, height))
     }
 
+    pub fn sanitize_resize_position(
+        position: Option<Either<String, FocalPoint>>,
+    ) -> std::result::Result<ResizePosition, LazyImageError> {
+        match position {
+            None => Ok(ResizePosition::default()),
+            Some(Either::A(name)) => ResizePosition::from_str(&name)
+                .map_err(|reason| LazyImageError::invalid_argument("position", name, reason)),
+            Some(Either::B(point)) => ResizePosition::focal(point.x, point.y).map_err(|reason| {
+                LazyImageError::invalid_argument(
+                    "position",
+                    format!("{{ x: {}, y: {} }}", point.x, point.y),
+                    reason,
+                )
+            }),
+        }
+    }
+
+    pub fn sanitize_float(
+        name: &'static str,
+        value: Option<f64>,
+        default: f64,
+        range: std::ops::RangeInclusive<f64>,
+    ) -> std::result::Result<f64, LazyImageError> {
+        let value = value.unwrap_or(default);
+        if range.contains(&value) {
+            Ok(value)
+        } else {
+            Err(LazyImageError::invalid_argument(
+                name,
+                number_label(value),
+                format!(
+                    "expected a number between {} and {}",
+                    range.start(),
+                    range.end()
+                ),
+            ))
+        }
+    }
+
+    pub fn sanitize_integer(
+        name: &'static str,
+        value: f64,
+        range: std::ops::RangeInclusive<i64>,
+    ) -> std::result::Result<i64, LazyImageError> {
+        let int = ensure_finite_integer(name, value)?;
+        if range.contains(&int) {
+            Ok(int)
+        } else {
+            Err(LazyImageError::invalid_argument(
+                name,
+                int.to_string(),
+                format!("must be between {} and {}", range.start(), range.end()),
+            ))
+        }
+    }
+
+    pub fn sanitize_color(
+        name: &'static str,
+        value: Option<String>,
+        default: Color,
+    ) -> std::result::Result<Color, LazyImageError> {
+        match value {
+            None => Ok(default),
+            Some(text) => Color::from_str(&text)
+                .map_err(|reason| LazyImageError::invalid_argument(name, text, reason)),
+        }
+    }
+
+    pub fn sanitize_extend_edge(
+        name: &'static str,
+        value: Option<f64>,
+    ) -> std::result::Result<u32, LazyImageError> {
+        let Some(value) = value else {
+            return Ok(0);
+        };
+        let int = ensure_finite_integer(name, value)?;
+        if int < 0 || int > crate::engine::MAX_DIMENSION as i64 {
+            return Err(LazyImageError::invalid_argument(
+                name,
+                int.to_string(),
+                format!("must be between 0 and {}", crate::engine::MAX_DIMENSION),
+            ));
+        }
+        Ok(int as u32)
+    }
+
+    /// Threshold (0-255) for `trim()` (default 10, so JPEG noise in a flat
+    /// border is ignored) and `threshold()` (default 128).
+    pub fn sanitize_threshold(
+        value: Option<f64>,
+        default: u8,
+    ) -> std::result::Result<u8, LazyImageError> {
+        let Some(value) = value else {
+            return Ok(default);
+        };
+        let int = ensure_finite_integer("threshold", value)?;
+        u8::try_from(int).map_err(|_| {
+            LazyImageError::invalid_argument(
+                "threshold",
+                int.to_string(),
+                "must be between 0 and 255",
+            )
+        })
+    }
+
     pub fn sanitize_crop(
         x: f64,
         y: f64,
@@ -196,6 +308,197 @@ mod validation {
         Ok((x_u32, y_u32, width_u32, height_u32))
     }
 
+    /// 3x3 (RGB) or 4x4 (RGBA) colour matrix of finite values, returned as
+    /// 4x4 with an identity alpha row and column for 3x3 input.
+    pub fn sanitize_matrix(
+        rows: Vec<Vec<f64>>,
+    ) -> std::result::Result<[[f64; 4]; 4], LazyImageError> {
+        let n = rows.len();
+        if !matches!(n, 3 | 4) || rows.iter().any(|row| row.len() != n) {
+            let shape = rows
+                .iter()
+                .map(|row| row.len().to_string())
+                .collect::<Vec<_>>();
+            return Err(LazyImageError::invalid_argument(
+                "matrix",
+                format!("[{}]", shape.join(", ")),
+                "expected a 3x3 or 4x4 array of rows",
+            ));
+        }
+        let mut matrix = [
+            [0.0, 0.0, 0.0, 0.0],
+            [0.0; 4],
+            [0.0; 4],
+            [0.0, 0.0, 0.0, 1.0],
+        ];
+        for (i, row) in rows.iter().enumerate() {
+            for (j, &value) in row.iter().enumerate() {
+                if !value.is_finite() {
+                    return Err(LazyImageError::invalid_argument(
+                        "matrix",
+                        number_label(value),
+                        "must contain finite numbers",
+                    ));
+                }
+                matrix[i][j] = value;
+            }
+        }
+        Ok(matrix)
+    }
+
+    /// `affine()` arguments: a 2x2 array of rows that must be invertible,
+    /// plus an optional `[tx, ty]` shift bounded like extend/offset values.
+    pub fn sanitize_affine(
+        rows: Vec<Vec<f64>>,
+        translate: Option<Vec<f64>>,
+    ) -> std::result::Result<([[f64; 2]; 2], [f64; 2]), LazyImageError> {
+        if rows.len() != 2 || rows.iter().any(|row| row.len() != 2) {
+            let shape = rows
+                .iter()
+                .map(|row| row.len().to_string())
+                .collect::<Vec<_>>();
+            return Err(LazyImageError::invalid_argument(
+                "matrix",
+                format!("[{}]", shape.join(", ")),
+                "expected a 2x2 array of rows",
+            ));
+        }
+        let [a, b, c, d] = [rows[0][0], rows[0][1], rows[1][0], rows[1][1]];
+        if let Some(value) = [a, b, c, d].into_iter().find(|v| !v.is_finite()) {
+            return Err(LazyImageError::invalid_argument(
+                "matrix",
+                number_label(value),
+                "must contain finite numbers",
+            ));
+        }
+        let matrix = [[a, b], [c, d]];
+        if crate::engine::rotate::invert(&matrix).is_none() {
+            return Err(LazyImageError::invalid_argument(
+                "matrix",
+                format!("[[{a}, {b}], [{c}, {d}]]"),
+                "matrix must be invertible (non-zero determinant)",
+            ));
+        }
+
+        let max = crate::engine::MAX_DIMENSION as f64;
+        let translate = match translate.as_deref() {
+            None => [0.0, 0.0],
+            Some(&[tx, ty]) => [
+                sanitize_float("translate", Some(tx), 0.0, -max..=max)?,
+                sanitize_float("translate", Some(ty), 0.0, -max..=max)?,
+            ],
+            Some(other) => {
+                return Err(LazyImageError::invalid_argument(
+                    "translate",
+                    format!("{} values", other.len()),
+                    "expected [tx, ty]",
+                ));
+            }
+        };
+        Ok((matrix, translate))
+    }
+
+    /// One `composite()` layer: the input is read now (paths from disk) and
+    /// must look like an encoded image; it is decoded when the pipeline runs.
+    /// Placement is a compass `gravity` or both `left` and `top`, not both.
+    pub fn sanitize_overlay(layer: CompositeLayer) -> std::result::Result<Overlay, LazyImageError> {
+        let input = match layer.input {
+            Either::A(buffer) => buffer.to_vec(),
+            Either::B(path) if path.trim().is_empty() => {
+                return Err(LazyImageError::invalid_argument(
+                    "input",
+                    "<empty>",
+                    "path must not be empty",
+                ));
+            }
+            Either::B(path) => std::fs::read(&path).map_err(|e| {
+                if e.kind() == std::io::ErrorKind::NotFound {
+                    LazyImageError::file_not_found(path.clone())
+                } else {
+                    LazyImageError::file_read_failed(path.clone(), e)
+                }
+            })?,
+        };
+        if crate::engine::decoder::detect_format(&input).is_none() {
+            return Err(LazyImageError::invalid_argument(
+                "input",
+                format!("{} bytes", input.len()),
+                "overlay must be an encoded image",
+            ));
+        }
+
+        let max = crate::engine::MAX_DIMENSION as i64;
+        let placement = match (layer.gravity, layer.left, layer.top) {
+            (None, None, None) => OverlayPlacement::Gravity(ResizePosition::Center),
+            (Some(name), None, None) => match ResizePosition::from_str(&name) {
+                Ok(position) if !position.is_content_aware() => OverlayPlacement::Gravity(position),
+                _ => {
+                    return Err(LazyImageError::invalid_argument(
+                        "gravity",
+                        name,
+                        "expected centre, north, northeast, east, southeast, south, \
+                         southwest, west, or northwest",
+                    ));
+                }
+            },
+            (None, Some(left), Some(top)) => OverlayPlacement::Offset {
+                left: sanitize_integer("left", left, -max..=max)? as i32,
+                top: sanitize_integer("top", top, -max..=max)? as i32,
+            },
+            (gravity, left, top) => {
+                return Err(LazyImageError::invalid_argument(
+                    "placement",
+                    format!(
+                        "gravity={}, left={}, top={}",
+                        gravity.as_deref().unwrap_or("-"),
+                        left.map_or("-".to_string(), number_label),
+                        top.map_or("-".to_string(), number_label)
+                    ),
+                    "use either gravity or both left and top",
+                ));
+            }
+        };
+        let blend = match layer.blend {
+            None => BlendMode::default(),
+            Some(name) => BlendMode::from_str(&name)
+                .map_err(|reason| LazyImageError::invalid_argument("blend", name, reason))?,
+        };
+        Ok(Overlay {
+            input: Arc::new(input),
+            placement,
+            blend,
+            opacity: sanitize_float("opacity", layer.opacity, 1.0, 0.0..=1.0)?,
+            tile: layer.tile.unwrap_or(false),
+        })
+    }
+
+    /// Rectangle limiting `blur()` / `pixelate()`. It may reach past the
+    /// image (it is clipped when applied) but must not be empty.
+    pub fn sanitize_region(region: &Region) -> std::result::Result<CropRegion, LazyImageError> {
+        let max = crate::engine::MAX_DIMENSION as i64;
+        let field = |name, value, min| sanitize_integer(name, value, min..=max).map(|v| v as u32);
+        Ok(CropRegion {
+            left: field("left", region.left, 0)?,
+            top: field("top", region.top, 0)?,
+            width: field("width", region.width, 1)?,
+            height: field("height", region.height, 1)?,
+        })
+    }
+
+    pub fn sanitize_bit_depth(value: f64) -> std::result::Result<OutputDepth, LazyImageError> {
+        let int = ensure_finite_integer("bitDepth", value)?;
+        u32::try_from(int)
+            .ok()
+            .and_then(OutputDepth::from_bits)
+            .ok_or_else(|| {
+                LazyImageError::invalid_argument(
+                    "bitDepth",
+                    int.to_string(),
+                    "must be 8, 10, 12 or 16",
+                )
+            })
+    }
+
     pub fn sanitize_quality(
         quality: Option<f64>,
     ) -> std::result::Result<Option<u8>, LazyImageError> {
@@ -351,6 +654,10 @@ pub struct ImageEngine {
     /// Whether we already emitted a warning about XMP being unsupported.
     pub(crate) xmp_warning_emitted: bool,
     pub(crate) firewall: FirewallConfig,
+    /// Colour alpha is flattened onto for formats without alpha (default: white)
+    pub(crate) flatten_background: Color,
+    /// Output bit depth requested with `bitDepth()` (default: follow the pipeline)
+    pub(crate) output_depth: OutputDepth,
 }
 
 #[cfg(feature = "napi")]
@@ -388,6 +695,8 @@ impl ImageEngine {
             strip_gps: true, // Strip GPS by default for privacy (exceeds Sharp)
             xmp_warning_emitted: false,
             firewall: FirewallConfig::disabled(),
+            flatten_background: Color::WHITE,
+            output_depth: OutputDepth::Auto,
         }
     }
 
@@ -460,6 +769,8 @@ impl ImageEngine {
             strip_gps: true, // Strip GPS by default for privacy (exceeds Sharp)
             xmp_warning_emitted: false,
             firewall: FirewallConfig::disabled(),
+            flatten_background: Color::WHITE,
+            output_depth: OutputDepth::Auto,
         })
     }
 
@@ -479,6 +790,8 @@ impl ImageEngine {
             strip_gps: self.strip_gps,
             xmp_warning_emitted: self.xmp_warning_emitted,
             firewall: self.firewall.clone(),
+            flatten_background: self.flatten_background,
+            output_depth: self.output_depth,
         })
     }
 
@@ -491,7 +804,26 @@ impl ImageEngine {
     /// - "inside" (default): maintain aspect ratio and fit within the box
     /// - "cover": maintain aspect ratio and crop to fill the box
     /// - "fill": ignore aspect ratio and force exact dimensions
+    /// - "contain": fit within the box, then pad to its exact size with
+    ///   `options.background` (transparent by default)
+    /// - "outside": maintain aspect ratio and cover the box without cropping
+    ///
+    /// `position` picks the region kept by "cover": a compass gravity
+    /// ("centre", "north", "northeast", ... "northwest"), a fractional
+    /// focal point `{ x, y }` in the 0.0-1.0 range, or a content-aware
+    /// strategy ("entropy", "attention") whose chosen window is reported
+    /// as `metrics.smartCrop`. With "contain" it places the image on the canvas.
+    ///
+    /// `options.withoutEnlargement` / `options.withoutReduction` clamp the
+    /// scale to the dimensions the resize receives at execution time.
+    /// `options.kernel` selects the resampling filter: "nearest", "box",
+    /// "bilinear", "catmullrom", "mitchell" or "lanczos3" (default).
+    /// `options.linear` resamples in linear light, which keeps fine
+    /// high-contrast detail from darkening when downscaling.
+    /// `options.sharpen: "auto"` applies a light unsharp mask after a
+    /// downscale, stronger the more the image was reduced.
     #[napi]
+    #[allow(clippy::too_many_arguments)]
     pub fn resize(
         &mut self,
         env: Env,
@@ -499,8 +831,10 @@ impl ImageEngine {
         width: Option<f64>,
         height: Option<f64>,
         fit: Option<String>,
+        position: Option<Either<String, FocalPoint>>,
+        options: Option<ResizeOptions>,
     ) -> Result<Reference<ImageEngine>> {
-        let fit_mode = if let Some(value) = fit {
+        let mut fit_mode = if let Some(value) = fit {
             ResizeFit::from_str(&value)
                 .map_err(|_| napi_err(&env, LazyImageError::invalid_resize_fit(value)))?
         } else {
@@ -509,11 +843,47 @@ impl ImageEngine {
 
         let (width, height) =
             validation::sanitize_resize_dimensions(width, height).map_err(|e| napi_err(&env, e))?;
+        let position =
+            validation::sanitize_resize_position(position).map_err(|e| napi_err(&env, e))?;
+        let options = options.unwrap_or_default();
+        let background =
+            validation::sanitize_color("background", options.background, Color::TRANSPARENT)
+                .map_err(|e| napi_err(&env, e))?;
+        if let ResizeFit::Contain { background: fill } = &mut fit_mode {
+            *fill = background;
+        }
+        let kernel = match options.kernel {
+            Some(value) => ResizeKernel::from_str(&value).map_err(|reason| {
+                napi_err(
+                    &env,
+                    LazyImageError::invalid_argument("kernel", value, reason),
+                )
+            })?,
+            None => ResizeKernel::default(),
+        };
+        let invalid_sharpen = |value: String| {
+            napi_err(
+                &env,
+                LazyImageError::invalid_argument("sharpen", value, "expected 'auto' or false"),
+            )
+        };
+        let sharpen = match options.sharpen {
+            None | Some(Either::B(false)) => false,
+            Some(Either::A(value)) if value.trim().eq_ignore_ascii_case("auto") => true,
+            Some(Either::A(value)) => return Err(invalid_sharpen(value)),
+            Some(Either::B(true)) => return Err(invalid_sharpen("true".to_string())),
+        };
 
         self.ops.push(Operation::Resize {
             width,
             height,
             fit: fit_mode,
+            position,
+            without_enlargement: options.without_enlargement.unwrap_or(false),
+            without_reduction: options.without_reduction.unwrap_or(false),
+            kernel,
+            linear: options.linear.unwrap_or(false),
+            sharpen,
         });
         Ok(this)
     }
@@ -540,24 +910,251 @@ impl ImageEngine {
         Ok(this)
     }
 
-    /// Rotate by degrees (90, 180, 270 only)
+    /// Grow the canvas by `top` / `right` / `bottom` / `left` pixels without cropping.
+    /// `extendWith` fills the new border: "background" (default) paints
+    /// `background` (transparent by default), "copy" repeats the edge pixels
+    /// and "mirror" reflects the image.
+    #[napi]
+    pub fn extend(
+        &mut self,
+        env: Env,
+        this: Reference<ImageEngine>,
+        options: ExtendOptions,
+    ) -> Result<Reference<ImageEngine>> {
+        let top =
+            validation::sanitize_extend_edge("top", options.top).map_err(|e| napi_err(&env, e))?;
+        let right = validation::sanitize_extend_edge("right", options.right)
+            .map_err(|e| napi_err(&env, e))?;
+        let bottom = validation::sanitize_extend_edge("bottom", options.bottom)
+            .map_err(|e| napi_err(&env, e))?;
+        let left = validation::sanitize_extend_edge("left", options.left)
+            .map_err(|e| napi_err(&env, e))?;
+        let background =
+            validation::sanitize_color("background", options.background, Color::TRANSPARENT)
+                .map_err(|e| napi_err(&env, e))?;
+        let mode = match options.extend_with {
+            Some(value) => ExtendMode::from_str(&value).map_err(|reason| {
+                napi_err(
+                    &env,
+                    LazyImageError::invalid_argument("extendWith", value, reason),
+                )
+            })?,
+            None => ExtendMode::default(),
+        };
+
+        self.ops.push(Operation::Extend {
+            top,
+            right,
+            bottom,
+            left,
+            background,
+            mode,
+        });
+        Ok(this)
+    }
+
+    /// Crop away uniform borders. Pixels within `threshold` (0-255 per channel,
+    /// default 10) of `background` — the top-left pixel when omitted — count as
+    /// border. The kept region is reported as `metrics.trim`; a uniform image
+    /// is left unchanged.
+    #[napi]
+    pub fn trim(
+        &mut self,
+        env: Env,
+        this: Reference<ImageEngine>,
+        options: Option<TrimOptions>,
+    ) -> Result<Reference<ImageEngine>> {
+        let options = options.unwrap_or_default();
+        let threshold =
+            validation::sanitize_threshold(options.threshold, 10).map_err(|e| napi_err(&env, e))?;
+        let background = match options.background {
+            Some(text) => Some(
+                validation::sanitize_color("background", Some(text), Color::WHITE)
+                    .map_err(|e| napi_err(&env, e))?,
+            ),
+            None => None,
+        };
+
+        self.ops.push(Operation::Trim {
+            threshold,
+            background,
+        });
+        Ok(this)
+    }
+
+    /// Composite onto `background` (default white) and drop the alpha
+    /// channel. Images without alpha are left unchanged.
+    #[napi]
+    pub fn flatten(
+        &mut self,
+        env: Env,
+        this: Reference<ImageEngine>,
+        options: Option<FlattenOptions>,
+    ) -> Result<Reference<ImageEngine>> {
+        let options = options.unwrap_or_default();
+        let background = validation::sanitize_color("background", options.background, Color::WHITE)
+            .map_err(|e| napi_err(&env, e))?;
+        self.ops.push(Operation::Flatten { background });
+        Ok(this)
+    }
+
+    /// Add an alpha channel with opacity `alpha` (0.0-1.0, default 1.0).
+    /// Images that already have alpha are left unchanged.
+    #[napi(js_name = "ensureAlpha")]
+    pub fn ensure_alpha(
+        &mut self,
+        env: Env,
+        this: Reference<ImageEngine>,
+        alpha: Option<f64>,
+    ) -> Result<Reference<ImageEngine>> {
+        let alpha = alpha.unwrap_or(1.0);
+        if !(0.0..=1.0).contains(&alpha) {
+            return Err(napi_err(
+                &env,
+                LazyImageError::invalid_argument(
+                    "alpha",
+                    alpha.to_string(),
+                    "expected a number between 0 and 1",
+                ),
+            ));
+        }
+        self.ops.push(Operation::EnsureAlpha { alpha });
+        Ok(this)
+    }
+
+    /// Drop the alpha channel without compositing (see `flatten`).
+    #[napi(js_name = "removeAlpha")]
+    pub fn remove_alpha(&mut self, this: Reference<ImageEngine>) -> Reference<ImageEngine> {
+        self.ops.push(Operation::RemoveAlpha);
+        this
+    }
+
+    /// Keep a single channel ('r', 'g', 'b' or 'alpha') as a greyscale image.
+    #[napi(js_name = "extractChannel")]
+    pub fn extract_channel(
+        &mut self,
+        env: Env,
+        this: Reference<ImageEngine>,
+        channel: String,
+    ) -> Result<Reference<ImageEngine>> {
+        let parsed = Channel::from_str(&channel).map_err(|reason| {
+            napi_err(
+                &env,
+                LazyImageError::invalid_argument("channel", channel.clone(), reason),
+            )
+        })?;
+        self.ops.push(Operation::ExtractChannel { channel: parsed });
+        Ok(this)
+    }
+
+    /// Use an encoded image (reduced to greyscale) as the alpha channel.
+    /// The mask is decoded when the pipeline runs and must match the size
+    /// of the image at that point.
+    #[napi(js_name = "joinChannel")]
+    pub fn join_channel(
+        &mut self,
+        env: Env,
+        this: Reference<ImageEngine>,
+        mask: Buffer,
+    ) -> Result<Reference<ImageEngine>> {
+        if super::decoder::detect_format(&mask).is_none() {
+            return Err(napi_err(
+                &env,
+                LazyImageError::invalid_argument(
+                    "mask",
+                    format!("{} bytes", mask.len()),
+                    "mask must be an encoded image",
+                ),
+            ));
+        }
+        self.ops.push(Operation::JoinChannel {
+            mask: Arc::new(mask.to_vec()),
+        });
+        Ok(this)
+    }
+
+    /// Rotate clockwise by `degrees`. Right angles are lossless; any other
+    /// angle expands the canvas and fills the corners with `background`
+    /// (opaque black by default).
     #[napi]
     pub fn rotate(
         &mut self,
         env: Env,
         this: Reference<ImageEngine>,
-        degrees: i32,
+        degrees: f64,
+        options: Option<RotateOptions>,
     ) -> Result<Reference<ImageEngine>> {
-        match degrees {
-            0 | 90 | 180 | 270 | -90 | -180 | -270 => {
-                self.ops.push(Operation::Rotate { degrees });
-                Ok(this)
-            }
-            other => Err(napi_err(
+        if !degrees.is_finite() {
+            return Err(napi_err(
                 &env,
-                LazyImageError::invalid_rotation_angle(other),
-            )),
+                LazyImageError::invalid_argument(
+                    "degrees",
+                    degrees.to_string(),
+                    "rotation angle must be a finite number",
+                ),
+            ));
+        }
+        let options = options.unwrap_or_default();
+        let background = validation::sanitize_color("background", options.background, Color::BLACK)
+            .map_err(|e| napi_err(&env, e))?;
+        let interpolation = match options.interpolation {
+            Some(value) => RotateInterpolation::from_str(&value).map_err(|reason| {
+                napi_err(
+                    &env,
+                    LazyImageError::invalid_argument("interpolation", value, reason),
+                )
+            })?,
+            None => RotateInterpolation::default(),
+        };
+
+        let normalized = degrees.rem_euclid(360.0);
+        if normalized % 90.0 == 0.0 {
+            self.ops.push(Operation::Rotate {
+                degrees: normalized as i32,
+            });
+        } else {
+            self.ops.push(Operation::RotateArbitrary {
+                degrees: normalized,
+                background,
+                interpolation,
+            });
         }
+        Ok(this)
+    }
+
+    /// Warp by the 2x2 matrix `[[a, b], [c, d]]`, which maps source (x, y)
+    /// to (ax + by, cx + dy). The canvas is sized to the transformed bounds
+    /// before anything runs, shifted by `translate`, and uncovered areas are
+    /// filled with `background` (opaque black by default).
+    #[napi]
+    pub fn affine(
+        &mut self,
+        env: Env,
+        this: Reference<ImageEngine>,
+        matrix: Vec<Vec<f64>>,
+        options: Option<AffineOptions>,
+    ) -> Result<Reference<ImageEngine>> {
+        let options = options.unwrap_or_default();
+        let (matrix, translate) = validation::sanitize_affine(matrix, options.translate)
+            .map_err(|e| napi_err(&env, e))?;
+        let background = validation::sanitize_color("background", options.background, Color::BLACK)
+            .map_err(|e| napi_err(&env, e))?;
+        let interpolation = match options.interpolator {
+            Some(value) => RotateInterpolation::from_str(&value).map_err(|reason| {
+                napi_err(
+                    &env,
+                    LazyImageError::invalid_argument("interpolator", value, reason),
+                )
+            })?,
+            None => RotateInterpolation::default(),
+        };
+        self.ops.push(Operation::Affine {
+            matrix,
+            translate,
+            background,
+            interpolation,
+        });
+        Ok(this)
     }
 
     /// Flip horizontally
@@ -594,6 +1191,37 @@ impl ImageEngine {
         this
     }
 
+    /// Colour transparent pixels are flattened onto when the output format
+    /// has no alpha channel (JPEG). Default: white.
+    #[napi(js_name = "flattenBackground")]
+    pub fn flatten_background(
+        &mut self,
+        env: Env,
+        this: Reference<ImageEngine>,
+        background: String,
+    ) -> Result<Reference<ImageEngine>> {
+        self.flatten_background =
+            validation::sanitize_color("background", Some(background), Color::WHITE)
+                .map_err(|e| napi_err(&env, e))?;
+        Ok(this)
+    }
+
+    /// Bits per sample written by the encoder: 8, 10, 12 or 16.
+    /// PNG stores 8 or 16 (10 and 12 round up to 16), AVIF stores 8, 10 or 12
+    /// (16 is capped at 12), JPEG and WebP are always 8-bit.
+    /// By default 16-bit sources stay 16-bit in PNG and everything else is 8-bit.
+    /// 16-bit pixels written at 8 bits are dithered to avoid banding.
+    #[napi(js_name = "bitDepth")]
+    pub fn bit_depth(
+        &mut self,
+        env: Env,
+        this: Reference<ImageEngine>,
+        bits: f64,
+    ) -> Result<Reference<ImageEngine>> {
+        self.output_depth = validation::sanitize_bit_depth(bits).map_err(|e| napi_err(&env, e))?;
+        Ok(this)
+    }
+
     /// Preserve metadata in output.
     /// - ICC profile: Preserved when `icc: true` (default when options provided)
     /// - EXIF: Preserved when `exif: true`. Orientation is auto-reset to 1 after auto-orient.
@@ -776,9 +1404,339 @@ impl ImageEngine {
         Ok(this)
     }
 
+    /// Adjust in CIE LCh: `brightness` and `saturation` are multipliers
+    /// (default 1), `hue` rotates by degrees and `lightness` is added to L
+    /// (-100 to 100).
+    #[napi]
+    pub fn modulate(
+        &mut self,
+        env: Env,
+        this: Reference<ImageEngine>,
+        options: Option<ModulateOptions>,
+    ) -> Result<Reference<ImageEngine>> {
+        let options = options.unwrap_or_default();
+        let brightness =
+            validation::sanitize_float("brightness", options.brightness, 1.0, 0.0..=10.0)
+                .map_err(|e| napi_err(&env, e))?;
+        let saturation =
+            validation::sanitize_float("saturation", options.saturation, 1.0, 0.0..=10.0)
+                .map_err(|e| napi_err(&env, e))?;
+        let lightness =
+            validation::sanitize_float("lightness", options.lightness, 0.0, -100.0..=100.0)
+                .map_err(|e| napi_err(&env, e))?;
+        let hue = options.hue.unwrap_or(0.0);
+        if !hue.is_finite() {
+            return Err(napi_err(
+                &env,
+                LazyImageError::invalid_argument("hue", hue.to_string(), "must be a finite number"),
+            ));
+        }
+
+        self.ops.push(Operation::Modulate {
+            brightness,
+            saturation,
+            hue: hue.rem_euclid(360.0),
+            lightness,
+        });
+        Ok(this)
+    }
+
+    /// Apply the power curve `out = in^(1 / gamma)` (0.1 to 10). Values
+    /// above 1 brighten mid-tones; 1 is a no-op.
+    #[napi]
+    pub fn gamma(
+        &mut self,
+        env: Env,
+        this: Reference<ImageEngine>,
+        gamma: f64,
+    ) -> Result<Reference<ImageEngine>> {
+        let gamma = validation::sanitize_float("gamma", Some(gamma), 1.0, 0.1..=10.0)
+            .map_err(|e| napi_err(&env, e))?;
+        self.ops.push(Operation::Gamma { gamma });
+        Ok(this)
+    }
+
+    /// Invert the colour channels; alpha is kept.
+    #[napi]
+    pub fn negate(&mut self, this: Reference<ImageEngine>) -> Result<Reference<ImageEngine>> {
+        self.ops.push(Operation::Negate);
+        Ok(this)
+    }
+
+    /// Binarize at `value` (0 to 255, default 128): samples at or above it
+    /// become white, the rest black. Converts to greyscale first unless
+    /// `options.grayscale` is false, in which case each colour channel is
+    /// thresholded on its own.
+    #[napi]
+    pub fn threshold(
+        &mut self,
+        env: Env,
+        this: Reference<ImageEngine>,
+        value: Option<f64>,
+        options: Option<ThresholdOptions>,
+    ) -> Result<Reference<ImageEngine>> {
+        let value = validation::sanitize_threshold(value, 128).map_err(|e| napi_err(&env, e))?;
+        let grayscale = options.and_then(|o| o.grayscale).unwrap_or(true);
+        self.ops.push(Operation::Threshold { value, grayscale });
+        Ok(this)
+    }
+
+    /// Stretch luminance so the `lower` and `upper` percentiles (0 to 100,
+    /// default 1 and 99) of its histogram become black and white.
+    #[napi]
+    pub fn normalize(
+        &mut self,
+        env: Env,
+        this: Reference<ImageEngine>,
+        options: Option<NormalizeOptions>,
+    ) -> Result<Reference<ImageEngine>> {
+        let options = options.unwrap_or_default();
+        let lower = validation::sanitize_float("lower", options.lower, 1.0, 0.0..=100.0)
+            .map_err(|e| napi_err(&env, e))?;
+        let upper = validation::sanitize_float("upper", options.upper, 99.0, 0.0..=100.0)
+            .map_err(|e| napi_err(&env, e))?;
+        if upper <= lower {
+            return Err(napi_err(
+                &env,
+                LazyImageError::invalid_argument(
+                    "upper",
+                    upper.to_string(),
+                    "must be greater than lower",
+                ),
+            ));
+        }
+        self.ops.push(Operation::Normalize { lower, upper });
+        Ok(this)
+    }
+
+    /// Contrast-limited adaptive histogram equalization over `width` x
+    /// `height` pixel tiles. `maxSlope` (0 to 100, default 3) caps the
+    /// contrast gain; 0 disables the limit.
+    #[napi]
+    pub fn clahe(
+        &mut self,
+        env: Env,
+        this: Reference<ImageEngine>,
+        options: ClaheOptions,
+    ) -> Result<Reference<ImageEngine>> {
+        let tile = |name, value| {
+            validation::sanitize_integer(name, value, 1..=crate::engine::MAX_DIMENSION as i64)
+                .map(|int| int as u32)
+                .map_err(|e| napi_err(&env, e))
+        };
+        let width = tile("width", options.width)?;
+        let height = tile("height", options.height)?;
+        let max_slope =
+            validation::sanitize_integer("maxSlope", options.max_slope.unwrap_or(3.0), 0..=100)
+                .map_err(|e| napi_err(&env, e))? as u32;
+        self.ops.push(Operation::Clahe {
+            width,
+            height,
+            max_slope,
+        });
+        Ok(this)
+    }
+
+    /// Stretch `inBlack..inWhite` to `outBlack..outWhite` (0 to 255) with a
+    /// mid-tone `gamma` (0.1 to 10, default 1). Reversed output bounds invert.
+    #[napi]
+    pub fn levels(
+        &mut self,
+        env: Env,
+        this: Reference<ImageEngine>,
+        options: Option<LevelsOptions>,
+    ) -> Result<Reference<ImageEngine>> {
+        let options = options.unwrap_or_default();
+        let level = |name, value, default| {
+            validation::sanitize_float(name, value, default, 0.0..=255.0)
+                .map_err(|e| napi_err(&env, e))
+        };
+        let levels = Levels {
+            in_black: level("inBlack", options.in_black, 0.0)?,
+            in_white: level("inWhite", options.in_white, 255.0)?,
+            gamma: validation::sanitize_float("gamma", options.gamma, 1.0, 0.1..=10.0)
+                .map_err(|e| napi_err(&env, e))?,
+            out_black: level("outBlack", options.out_black, 0.0)?,
+            out_white: level("outWhite", options.out_white, 255.0)?,
+        };
+        if levels.in_white <= levels.in_black {
+            return Err(napi_err(
+                &env,
+                LazyImageError::invalid_argument(
+                    "inWhite",
+                    levels.in_white.to_string(),
+                    "must be greater than inBlack",
+                ),
+            ));
+        }
+        self.ops.push(Operation::Levels { levels });
+        Ok(this)
+    }
+
+    /// Unsharp mask: colour samples move away from their Gaussian blur
+    /// (`sigma` 0.3 to 10 pixels, default 1) by `amount` (0 to 10, default 1)
+    /// times the difference, skipping differences below `threshold`
+    /// (0 to 255, default 0). Alpha is kept.
+    #[napi]
+    pub fn sharpen(
+        &mut self,
+        env: Env,
+        this: Reference<ImageEngine>,
+        options: Option<SharpenOptions>,
+    ) -> Result<Reference<ImageEngine>> {
+        let options = options.unwrap_or_default();
+        let defaults = Sharpen::default();
+        let sharpen = Sharpen {
+            sigma: validation::sanitize_float("sigma", options.sigma, defaults.sigma, 0.3..=10.0)
+                .map_err(|e| napi_err(&env, e))?,
+            amount: validation::sanitize_float(
+                "amount",
+                options.amount,
+                defaults.amount,
+                0.0..=10.0,
+            )
+            .map_err(|e| napi_err(&env, e))?,
+            threshold: validation::sanitize_threshold(options.threshold, defaults.threshold)
+                .map_err(|e| napi_err(&env, e))?,
+        };
+        self.ops.push(Operation::Sharpen { sharpen });
+        Ok(this)
+    }
+
+    /// Multiply each pixel by a colour matrix given as rows: 3x3 acts on RGB
+    /// (alpha kept), 4x4 on RGBA (alpha reads as opaque when the image has
+    /// none). Greyscale input becomes RGB.
+    #[napi]
+    pub fn recomb(
+        &mut self,
+        env: Env,
+        this: Reference<ImageEngine>,
+        matrix: Vec<Vec<f64>>,
+    ) -> Result<Reference<ImageEngine>> {
+        let matrix = validation::sanitize_matrix(matrix).map_err(|e| napi_err(&env, e))?;
+        self.ops.push(Operation::Recomb { matrix });
+        Ok(this)
+    }
+
+    /// Map colours through an Adobe `.cube` 3D LUT (trilinear
+    /// interpolation; alpha kept). The file is parsed and validated here,
+    /// so malformed LUTs throw `E220` and oversized ones `E221`.
+    #[napi(js_name = "applyLut")]
+    pub fn apply_lut(
+        &mut self,
+        env: Env,
+        this: Reference<ImageEngine>,
+        cube: Buffer,
+    ) -> Result<Reference<ImageEngine>> {
+        let lut = super::grade::parse_cube(&cube).map_err(|e| napi_err(&env, e))?;
+        self.ops.push(Operation::Lut3d { lut: Arc::new(lut) });
+        Ok(this)
+    }
+
+    /// Gaussian blur with `sigma` (0.3 to 100 pixels) of every channel. With
+    /// `regions`, only those rectangles (clipped to the image) are blurred;
+    /// their edges still sample the surrounding pixels.
+    #[napi]
+    pub fn blur(
+        &mut self,
+        env: Env,
+        this: Reference<ImageEngine>,
+        sigma: f64,
+        regions: Option<Vec<Region>>,
+    ) -> Result<Reference<ImageEngine>> {
+        let sigma = validation::sanitize_float("sigma", Some(sigma), 1.0, 0.3..=100.0)
+            .map_err(|e| napi_err(&env, e))?;
+        let regions = regions
+            .unwrap_or_default()
+            .iter()
+            .map(validation::sanitize_region)
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|e| napi_err(&env, e))?;
+        self.ops.push(Operation::Blur { sigma, regions });
+        Ok(this)
+    }
+
+    /// Replace `blockSize` x `blockSize` squares (1 to 4096 pixels) with
+    /// their mean colour. With `regions`, only those rectangles (clipped to
+    /// the image) are pixelated, on a grid starting at each region's corner.
+    #[napi]
+    pub fn pixelate(
+        &mut self,
+        env: Env,
+        this: Reference<ImageEngine>,
+        block_size: f64,
+        regions: Option<Vec<Region>>,
+    ) -> Result<Reference<ImageEngine>> {
+        let block_size = validation::sanitize_integer("blockSize", block_size, 1..=4096)
+            .map_err(|e| napi_err(&env, e))? as u32;
+        let regions = regions
+            .unwrap_or_default()
+            .iter()
+            .map(validation::sanitize_region)
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|e| napi_err(&env, e))?;
+        self.ops.push(Operation::Pixelate {
+            block_size,
+            regions,
+        });
+        Ok(this)
+    }
+
+    /// Blend overlay images onto the image, in order, at this point of the
+    /// pipeline (queue it after resize/crop to place it on the final frame).
+    /// Each overlay is decoded when the pipeline runs, under the same
+    /// firewall limits as the source.
+    #[napi]
+    pub fn composite(
+        &mut self,
+        env: Env,
+        this: Reference<ImageEngine>,
+        layers: Vec<CompositeLayer>,
+    ) -> Result<Reference<ImageEngine>> {
+        let layers = layers
+            .into_iter()
+            .map(validation::sanitize_overlay)
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|e| napi_err(&env, e))?;
+        if !layers.is_empty() {
+            self.ops.push(Operation::Composite { layers });
+        }
+        Ok(this)
+    }
+
+    /// Convert colours from the input's embedded ICC profile (sRGB when
+    /// untagged) to `profile`: `'srgb'`, `'p3'` or the bytes of an RGB ICC
+    /// profile. The target profile is embedded in the output, except sRGB
+    /// when metadata is stripped. Unusable profile bytes throw `E210`.
+    #[napi(js_name = "toColorProfile")]
+    pub fn to_color_profile(
+        &mut self,
+        env: Env,
+        this: Reference<ImageEngine>,
+        profile: Either<String, Buffer>,
+    ) -> Result<Reference<ImageEngine>> {
+        let target = match profile {
+            Either::A(name) => IccProfile::from_str(&name).map_err(|reason| {
+                napi_err(
+                    &env,
+                    LazyImageError::invalid_argument("profile", name, reason),
+                )
+            })?,
+            Either::B(bytes) => {
+                super::icc::check_target(&bytes).map_err(|e| napi_err(&env, e))?;
+                IccProfile::Bytes(Arc::new(bytes.to_vec()))
+            }
+        };
+        self.ops.push(Operation::IccTransform {
+            source: None,
+            target,
+        });
+        Ok(this)
+    }
+
     /// Normalize pixel format to RGB/RGBA without performing any color space transformation.
     /// This does not apply ICC profile conversion; it only guarantees the pixel layout is RGB/RGBA.
-    /// Use a dedicated color management library for true color space conversions.
+    /// Use `toColorProfile()` for true color space conversions.
     #[napi(js_name = "normalizePixelFormat")]
     pub fn normalize_pixel_format(
         &mut self,
@@ -833,6 +1791,12 @@ impl ImageEngine {
             width: config.width,
             height: config.height,
             fit: ResizeFit::Inside,
+            position: ResizePosition::Center,
+            without_enlargement: false,
+            without_reduction: false,
+            kernel: ResizeKernel::Lanczos3,
+            linear: false,
+            sharpen: false,
         });
 
         // Return preset info for the user to use with toBuffer/toFile
@@ -891,11 +1855,8 @@ impl ImageEngine {
         let keep_exif = self.keep_exif && !self.firewall.reject_metadata;
         let auto_orient = self.auto_orient;
         let icc_present = self.icc_profile.is_some();
-        let icc_profile = if keep_icc {
-            self.icc_profile.clone()
-        } else {
-            None
-        };
+        // Passed even when stripped: the task converts the pixels to sRGB first.
+        let icc_profile = self.icc_profile.clone();
         let exif_data = if keep_exif {
             self.exif_data.clone()
         } else {
@@ -915,6 +1876,8 @@ impl ImageEngine {
             keep_exif,
             strip_gps: self.strip_gps,
             firewall: self.firewall.clone(),
+            flatten_background: self.flatten_background,
+            output_depth: self.output_depth,
             #[cfg(feature = "napi")]
             last_error: None,
         }))
@@ -941,6 +1904,12 @@ impl ImageEngine {
             width: preset.width,
             height: preset.height,
             fit: ResizeFit::Inside,
+            position: ResizePosition::Center,
+            without_enlargement: false,
+            without_reduction: false,
+            kernel: ResizeKernel::Lanczos3,
+            linear: false,
+            sharpen: false,
         });
 
         self.last_preset = Some(preset.clone());
@@ -991,11 +1960,8 @@ impl ImageEngine {
         let keep_exif = self.keep_exif && !self.firewall.reject_metadata;
         let auto_orient = self.auto_orient;
         let icc_present = self.icc_profile.is_some();
-        let icc_profile = if keep_icc {
-            self.icc_profile.clone()
-        } else {
-            None
-        };
+        // Passed even when stripped: the task converts the pixels to sRGB first.
+        let icc_profile = self.icc_profile.clone();
         let exif_data = if keep_exif {
             self.exif_data.clone()
         } else {
@@ -1015,6 +1981,8 @@ impl ImageEngine {
             keep_exif,
             strip_gps: self.strip_gps,
             firewall: self.firewall.clone(),
+            flatten_background: self.flatten_background,
+            output_depth: self.output_depth,
             #[cfg(feature = "napi")]
             last_error: None,
         }))
@@ -1043,6 +2011,12 @@ impl ImageEngine {
             width: preset.width,
             height: preset.height,
             fit: ResizeFit::Inside,
+            position: ResizePosition::Center,
+            without_enlargement: false,
+            without_reduction: false,
+            kernel: ResizeKernel::Lanczos3,
+            linear: false,
+            sharpen: false,
         });
 
         self.last_preset = Some(preset.clone());
@@ -1099,11 +2073,8 @@ impl ImageEngine {
         let keep_exif = self.keep_exif && !self.firewall.reject_metadata;
         let auto_orient = self.auto_orient;
         let icc_present = self.icc_profile.is_some();
-        let icc_profile = if keep_icc {
-            self.icc_profile.clone()
-        } else {
-            None
-        };
+        // Passed even when stripped: the task converts the pixels to sRGB first.
+        let icc_profile = self.icc_profile.clone();
         let exif_data = if keep_exif {
             self.exif_data.clone()
         } else {
@@ -1123,6 +2094,8 @@ impl ImageEngine {
             keep_exif,
             strip_gps: self.strip_gps,
             firewall: self.firewall.clone(),
+            flatten_background: self.flatten_background,
+            output_depth: self.output_depth,
             output_path: path,
             #[cfg(feature = "napi")]
             last_error: None,
@@ -1149,6 +2122,12 @@ impl ImageEngine {
             width: preset.width,
             height: preset.height,
             fit: ResizeFit::Inside,
+            position: ResizePosition::Center,
+            without_enlargement: false,
+            without_reduction: false,
+            kernel: ResizeKernel::Lanczos3,
+            linear: false,
+            sharpen: false,
         });
 
         self.last_preset = Some(preset.clone());
@@ -1245,6 +2224,42 @@ impl ImageEngine {
         self.icc_profile.as_ref().map(|p| p.len() as u32)
     }
 
+    /// Read EXIF, XMP, IPTC, ICC and density metadata from the source
+    /// headers WITHOUT decoding pixels (internal method, no Env required).
+    pub fn metadata_internal(
+        &self,
+        include_gps: bool,
+    ) -> std::result::Result<metadata::Report, LazyImageError> {
+        let source = self
+            .source
+            .as_ref()
+            .ok_or_else(LazyImageError::source_consumed)?;
+        self.firewall.enforce_source_len(source.len())?;
+        let bytes = source
+            .as_bytes()
+            .ok_or_else(LazyImageError::source_consumed)?;
+        self.firewall.scan_metadata(bytes)?;
+        if detect_format(bytes).is_none() {
+            return Err(LazyImageError::decode_failed(
+                "failed to read image header: unrecognised format",
+            ));
+        }
+        let icc = self.icc_profile.as_deref().map(Vec::as_slice);
+        Ok(metadata::read(bytes, icc, include_gps))
+    }
+
+    /// Read camera make/model, lens, capture date, orientation, exposure,
+    /// copyright, XMP packet, IPTC keywords, ICC description and density
+    /// from the source headers WITHOUT decoding pixels.
+    /// GPS coordinates are only included with `{ gps: true }`.
+    #[napi]
+    pub fn metadata(&self, env: Env, options: Option<MetadataOptions>) -> Result<EmbeddedMetadata> {
+        let include_gps = options.and_then(|o| o.gps).unwrap_or(false);
+        self.metadata_internal(include_gps)
+            .map(EmbeddedMetadata::from)
+            .map_err(|e| napi_err(&env, e))
+    }
+
     /// Process multiple images in parallel with the same operations.
     ///
     /// - inputs: Array of input file paths
@@ -1320,6 +2335,8 @@ impl ImageEngine {
             strip_gps: self.strip_gps,
             auto_orient: self.auto_orient,
             firewall: self.firewall.clone(),
+            flatten_background: self.flatten_background,
+            output_depth: self.output_depth,
             #[cfg(feature = "napi")]
             last_error: None,
         }))
@@ -1340,6 +2357,204 @@ pub struct FirewallLimitOptions {
     pub timeout_ms: Option<f64>,
 }
 
+#[cfg(feature = "napi")]
+/// Fractional focal point used by `resize(..., 'cover', position)`
+#[napi(object)]
+pub struct FocalPoint {
+    /// Horizontal position (0.0 = left edge, 1.0 = right edge)
+    pub x: f64,
+    /// Vertical position (0.0 = top edge, 1.0 = bottom edge)
+    pub y: f64,
+}
+
+#[cfg(feature = "napi")]
+/// Extra options for `resize()`
+#[napi(object)]
+#[derive(Default)]
+pub struct ResizeOptions {
+    /// Padding colour for "contain" (`#rgb`, `#rrggbb`, `#rrggbbaa`, `transparent`, ...)
+    pub background: Option<String>,
+    /// Keep the current size instead of scaling up
+    pub without_enlargement: Option<bool>,
+    /// Keep the current size instead of scaling down
+    pub without_reduction: Option<bool>,
+    /// Resampling kernel (default "lanczos3")
+    pub kernel: Option<String>,
+    /// Resample in linear light (gamma-correct) instead of sRGB
+    pub linear: Option<bool>,
+    /// "auto" sharpens after downscaling, scaled to the reduction ratio
+    pub sharpen: Option<Either<String, bool>>,
+}
+
+#[cfg(feature = "napi")]
+/// Options for `trim()`
+#[napi(object)]
+#[derive(Default)]
+pub struct TrimOptions {
+    /// Allowed per-channel difference from the background (0-255, default 10)
+    pub threshold: Option<f64>,
+    /// Border colour; defaults to the top-left pixel
+    pub background: Option<String>,
+}
+
+#[cfg(feature = "napi")]
+/// Options for `modulate()`
+#[napi(object)]
+#[derive(Default)]
+pub struct ModulateOptions {
+    /// Lightness multiplier (0 to 10, default 1)
+    pub brightness: Option<f64>,
+    /// Chroma multiplier (0 to 10, default 1; 0 = greyscale)
+    pub saturation: Option<f64>,
+    /// Hue rotation in degrees (default 0)
+    pub hue: Option<f64>,
+    /// Added to lightness L (-100 to 100, default 0)
+    pub lightness: Option<f64>,
+}
+
+#[cfg(feature = "napi")]
+/// Options for `threshold()`
+#[napi(object)]
+#[derive(Default)]
+pub struct ThresholdOptions {
+    /// Convert to greyscale before thresholding (default true)
+    pub grayscale: Option<bool>,
+}
+
+#[cfg(feature = "napi")]
+/// Options for `levels()`
+#[napi(object)]
+#[derive(Default)]
+pub struct LevelsOptions {
+    /// Input value mapped to `outBlack` (0-255, default 0)
+    pub in_black: Option<f64>,
+    /// Input value mapped to `outWhite` (0-255, default 255)
+    pub in_white: Option<f64>,
+    /// Mid-tone curve `in^(1 / gamma)` (0.1-10, default 1)
+    pub gamma: Option<f64>,
+    /// Output for `inBlack` and below (0-255, default 0)
+    pub out_black: Option<f64>,
+    /// Output for `inWhite` and above (0-255, default 255)
+    pub out_white: Option<f64>,
+}
+
+#[cfg(feature = "napi")]
+/// Options for `normalize()`
+#[napi(object)]
+#[derive(Default)]
+pub struct NormalizeOptions {
+    /// Percentile mapped to black (0-100, default 1)
+    pub lower: Option<f64>,
+    /// Percentile mapped to white (0-100, default 99)
+    pub upper: Option<f64>,
+}
+
+#[cfg(feature = "napi")]
+/// Options for `clahe()`
+#[napi(object)]
+pub struct ClaheOptions {
+    /// Tile width in pixels
+    pub width: f64,
+    /// Tile height in pixels
+    pub height: f64,
+    /// Contrast gain cap (0-100, default 3; 0 = unlimited)
+    pub max_slope: Option<f64>,
+}
+
+#[cfg(feature = "napi")]
+/// Options for `sharpen()`
+#[napi(object)]
+#[derive(Default)]
+pub struct SharpenOptions {
+    /// Gaussian radius in pixels (0.3-10, default 1)
+    pub sigma: Option<f64>,
+    /// Strength of the mask (0-10, default 1)
+    pub amount: Option<f64>,
+    /// Smallest difference sharpened (0-255, default 0)
+    pub threshold: Option<f64>,
+}
+
+#[cfg(feature = "napi")]
+/// Rectangle limiting `blur()` / `pixelate()`
+#[napi(object)]
+pub struct Region {
+    pub left: f64,
+    pub top: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+#[cfg(feature = "napi")]
+/// One overlay for `composite()`
+#[napi(object)]
+pub struct CompositeLayer {
+    /// Encoded image bytes or a file path
+    pub input: Either<Buffer, String>,
+    /// Compass anchor (default "centre"); not combined with `left` / `top`
+    pub gravity: Option<String>,
+    /// Pixel offset from the left edge (may be negative); requires `top`
+    pub left: Option<f64>,
+    /// Pixel offset from the top edge (may be negative); requires `left`
+    pub top: Option<f64>,
+    /// "over" (default), "multiply" or "screen"
+    pub blend: Option<String>,
+    /// Multiplier on the overlay's alpha (0-1, default 1)
+    pub opacity: Option<f64>,
+    /// Repeat the overlay over the whole image (default false)
+    pub tile: Option<bool>,
+}
+
+#[cfg(feature = "napi")]
+/// Options for `flatten()`
+#[napi(object)]
+#[derive(Default)]
+pub struct FlattenOptions {
+    /// Colour composited under transparent pixels (default white)
+    pub background: Option<String>,
+}
+
+#[cfg(feature = "napi")]
+/// Options for `rotate()`
+#[napi(object)]
+#[derive(Default)]
+pub struct RotateOptions {
+    /// Fill for the corners uncovered by non-right-angle rotations (default opaque black)
+    pub background: Option<String>,
+    /// "bilinear" (default) or "bicubic"
+    pub interpolation: Option<String>,
+}
+
+#[cfg(feature = "napi")]
+/// Options for `affine()`
+#[napi(object)]
+#[derive(Default)]
+pub struct AffineOptions {
+    /// Output shift `[tx, ty]` in pixels (default `[0, 0]`)
+    pub translate: Option<Vec<f64>>,
+    /// "bilinear" (default) or "bicubic"
+    pub interpolator: Option<String>,
+    /// Fill for areas the warped image does not cover (default opaque black)
+    pub background: Option<String>,
+}
+
+#[cfg(feature = "napi")]
+/// Options for `extend()`
+#[napi(object)]
+pub struct ExtendOptions {
+    /// Pixels added above the image (default 0)
+    pub top: Option<f64>,
+    /// Pixels added right of the image (default 0)
+    pub right: Option<f64>,
+    /// Pixels added below the image (default 0)
+    pub bottom: Option<f64>,
+    /// Pixels added left of the image (default 0)
+    pub left: Option<f64>,
+    /// Fill colour for "background" mode (default transparent)
+    pub background: Option<String>,
+    /// "background" (default), "copy" or "mirror"
+    pub extend_with: Option<String>,
+}
+
 #[cfg(feature = "napi")]
 #[napi(object)]
 pub struct Dimensions {
@@ -1347,6 +2562,90 @@ pub struct Dimensions {
     pub height: u32,
 }
 
+#[cfg(feature = "napi")]
+/// Options for `metadata()`
+#[napi(object)]
+#[derive(Default)]
+pub struct MetadataOptions {
+    /// Include GPS coordinates (default false)
+    pub gps: Option<bool>,
+}
+
+#[cfg(feature = "napi")]
+/// GPS position returned by `metadata({ gps: true })`
+#[napi(object)]
+pub struct GpsPosition {
+    /// Decimal degrees, negative south of the equator
+    pub latitude: f64,
+    /// Decimal degrees, negative west of Greenwich
+    pub longitude: f64,
+    /// Metres above sea level (negative below)
+    pub altitude: Option<f64>,
+}
+
+#[cfg(feature = "napi")]
+/// Metadata returned by `metadata()`; absent fields are undefined
+#[napi(object)]
+pub struct EmbeddedMetadata {
+    /// Camera manufacturer (EXIF Make)
+    pub make: Option<String>,
+    /// Camera model (EXIF Model)
+    pub model: Option<String>,
+    /// Lens model (EXIF LensModel, or LensMake)
+    pub lens: Option<String>,
+    /// Capture time as ISO 8601, with the EXIF offset when recorded
+    pub capture_date: Option<String>,
+    /// EXIF Orientation (1-8)
+    pub orientation: Option<u32>,
+    /// Exposure time in seconds
+    pub exposure_time: Option<f64>,
+    /// Aperture f-number
+    pub f_number: Option<f64>,
+    /// ISO sensitivity
+    pub iso: Option<u32>,
+    /// Focal length in millimetres
+    pub focal_length: Option<f64>,
+    /// Only present with `{ gps: true }`
+    pub gps: Option<GpsPosition>,
+    /// EXIF Copyright
+    pub copyright: Option<String>,
+    /// Raw XMP packet
+    pub xmp: Option<String>,
+    /// IPTC keywords (empty when none)
+    pub iptc_keywords: Vec<String>,
+    /// Description of the embedded ICC profile
+    pub icc_description: Option<String>,
+    /// Pixels per inch
+    pub density: Option<f64>,
+}
+
+#[cfg(feature = "napi")]
+impl From<metadata::Report> for EmbeddedMetadata {
+    fn from(report: metadata::Report) -> Self {
+        Self {
+            make: report.make,
+            model: report.model,
+            lens: report.lens,
+            capture_date: report.capture_date,
+            orientation: report.orientation.map(u32::from),
+            exposure_time: report.exposure_time,
+            f_number: report.f_number,
+            iso: report.iso,
+            focal_length: report.focal_length,
+            gps: report.gps.map(|gps| GpsPosition {
+                latitude: gps.latitude,
+                longitude: gps.longitude,
+                altitude: gps.altitude,
+            }),
+            copyright: report.copyright,
+            xmp: report.xmp,
+            iptc_keywords: report.iptc_keywords,
+            icc_description: report.icc_description,
+            density: report.density,
+        }
+    }
+}
+
 #[cfg(feature = "napi")]
 /// Result of applying a preset, contains recommended output settings
 #[napi(object)]
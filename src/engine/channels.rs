@@ -0,0 +1,234 @@
+// src/engine/channels.rs
+//
+// Alpha and channel manipulation for `EnsureAlpha`, `RemoveAlpha`,
+// `ExtractChannel` and `JoinChannel`.
+//
+// All four work on interleaved sample buffers and keep the bit depth of the
+// standard 8/16-bit layouts, so the tracked `ColorState` (and with it the
+// encoder's choice of PNG colour type) follows the pixels. Other layouts
+// (e.g. 32-bit float) are normalized to 8-bit first.
+
+use crate::ops::Channel;
+use image::{DynamicImage, ImageBuffer, Pixel};
+
+/// Add an alpha channel with opacity `alpha` (0.0-1.0) unless one exists.
+pub(crate) fn ensure_alpha(img: DynamicImage, alpha: f64) -> DynamicImage {
+    let alpha = alpha.clamp(0.0, 1.0);
+    let a8 = (alpha * 255.0).round() as u8;
+    let a16 = (alpha * 65535.0).round() as u16;
+    match img {
+        DynamicImage::ImageLuma8(gray) => DynamicImage::ImageLumaA8(with_alpha(&gray, |_| a8)),
+        DynamicImage::ImageRgb8(rgb) => DynamicImage::ImageRgba8(with_alpha(&rgb, |_| a8)),
+        DynamicImage::ImageLuma16(gray) => DynamicImage::ImageLumaA16(with_alpha(&gray, |_| a16)),
+        DynamicImage::ImageRgb16(rgb) => DynamicImage::ImageRgba16(with_alpha(&rgb, |_| a16)),
+        DynamicImage::ImageRgba8(_)
+        | DynamicImage::ImageLumaA8(_)
+        | DynamicImage::ImageRgba16(_)
+        | DynamicImage::ImageLumaA16(_) => img,
+        other => {
+            let had_alpha = other.color().has_alpha();
+            let mut rgba = other.into_rgba8();
+            if !had_alpha {
+                rgba.pixels_mut().for_each(|p| p[3] = a8);
+            }
+            DynamicImage::ImageRgba8(rgba)
+        }
+    }
+}
+
+/// Drop the alpha channel (no compositing).
+pub(crate) fn remove_alpha(img: DynamicImage) -> DynamicImage {
+    match img {
+        DynamicImage::ImageLumaA8(gray) => DynamicImage::ImageLuma8(without_alpha(&gray)),
+        DynamicImage::ImageRgba8(rgba) => DynamicImage::ImageRgb8(without_alpha(&rgba)),
+        DynamicImage::ImageLumaA16(gray) => DynamicImage::ImageLuma16(without_alpha(&gray)),
+        DynamicImage::ImageRgba16(rgba) => DynamicImage::ImageRgb16(without_alpha(&rgba)),
+        DynamicImage::ImageRgb8(_)
+        | DynamicImage::ImageLuma8(_)
+        | DynamicImage::ImageRgb16(_)
+        | DynamicImage::ImageLuma16(_) => img,
+        other => DynamicImage::ImageRgb8(other.into_rgb8()),
+    }
+}
+
+/// Single channel as a greyscale image. Colour channels of a greyscale image
+/// all read the luma; alpha of an opaque image is a fully opaque plane.
+pub(crate) fn extract_channel(img: DynamicImage, channel: Channel) -> DynamicImage {
+    match img {
+        DynamicImage::ImageLuma8(gray) => DynamicImage::ImageLuma8(plane(&gray, channel, u8::MAX)),
+        DynamicImage::ImageLumaA8(gray) => DynamicImage::ImageLuma8(plane(&gray, channel, u8::MAX)),
+        DynamicImage::ImageRgb8(rgb) => DynamicImage::ImageLuma8(plane(&rgb, channel, u8::MAX)),
+        DynamicImage::ImageRgba8(rgba) => DynamicImage::ImageLuma8(plane(&rgba, channel, u8::MAX)),
+        DynamicImage::ImageLuma16(gray) => {
+            DynamicImage::ImageLuma16(plane(&gray, channel, u16::MAX))
+        }
+        DynamicImage::ImageLumaA16(gray) => {
+            DynamicImage::ImageLuma16(plane(&gray, channel, u16::MAX))
+        }
+        DynamicImage::ImageRgb16(rgb) => DynamicImage::ImageLuma16(plane(&rgb, channel, u16::MAX)),
+        DynamicImage::ImageRgba16(rgba) => {
+            DynamicImage::ImageLuma16(plane(&rgba, channel, u16::MAX))
+        }
+        other => DynamicImage::ImageLuma8(plane(&other.into_rgba8(), channel, u8::MAX)),
+    }
+}
+
+/// Replace (or add) the alpha channel with the luma of `mask`, which must
+/// have the same dimensions as `img`.
+pub(crate) fn join_channel(img: DynamicImage, mask: &DynamicImage) -> DynamicImage {
+    match remove_alpha(img) {
+        DynamicImage::ImageLuma8(gray) => {
+            let mask = mask.to_luma8();
+            DynamicImage::ImageLumaA8(with_alpha(&gray, |i| mask.as_raw()[i]))
+        }
+        DynamicImage::ImageLuma16(gray) => {
+            let mask = mask.to_luma16();
+            DynamicImage::ImageLumaA16(with_alpha(&gray, |i| mask.as_raw()[i]))
+        }
+        DynamicImage::ImageRgb16(rgb) => {
+            let mask = mask.to_luma16();
+            DynamicImage::ImageRgba16(with_alpha(&rgb, |i| mask.as_raw()[i]))
+        }
+        other => {
+            let mask = mask.to_luma8();
+            DynamicImage::ImageRgba8(with_alpha(&other.into_rgb8(), |i| mask.as_raw()[i]))
+        }
+    }
+}
+
+/// Append an alpha sample (by pixel index) to every pixel.
+fn with_alpha<P, Q>(
+    src: &ImageBuffer<P, Vec<P::Subpixel>>,
+    alpha: impl Fn(usize) -> P::Subpixel,
+) -> ImageBuffer<Q, Vec<P::Subpixel>>
+where
+    P: Pixel,
+    Q: Pixel<Subpixel = P::Subpixel>,
+{
+    let channels = P::CHANNEL_COUNT as usize;
+    let mut out = Vec::with_capacity(src.as_raw().len() / channels * (channels + 1));
+    for (i, pixel) in src.as_raw().chunks_exact(channels).enumerate() {
+        out.extend_from_slice(pixel);
+        out.push(alpha(i));
+    }
+    ImageBuffer::from_raw(src.width(), src.height(), out).expect("buffer sized for image")
+}
+
+/// Drop the trailing alpha sample of every pixel.
+fn without_alpha<P, Q>(src: &ImageBuffer<P, Vec<P::Subpixel>>) -> ImageBuffer<Q, Vec<P::Subpixel>>
+where
+    P: Pixel,
+    Q: Pixel<Subpixel = P::Subpixel>,
+{
+    let channels = P::CHANNEL_COUNT as usize;
+    let out = src
+        .as_raw()
+        .chunks_exact(channels)
+        .flat_map(|pixel| pixel[..channels - 1].iter().copied())
+        .collect();
+    ImageBuffer::from_raw(src.width(), src.height(), out).expect("buffer sized for image")
+}
+
+/// One channel of `src` as a single-channel buffer; `opaque` fills a missing
+/// alpha channel.
+fn plane<P, Q>(
+    src: &ImageBuffer<P, Vec<P::Subpixel>>,
+    channel: Channel,
+    opaque: P::Subpixel,
+) -> ImageBuffer<Q, Vec<P::Subpixel>>
+where
+    P: Pixel,
+    Q: Pixel<Subpixel = P::Subpixel>,
+{
+    let channels = P::CHANNEL_COUNT as usize;
+    let has_alpha = P::HAS_ALPHA;
+    let color_channels = if has_alpha { channels - 1 } else { channels };
+    let index = match channel {
+        Channel::Alpha => has_alpha.then_some(channels - 1),
+        // Greyscale layouts have a single colour channel.
+        Channel::Red => Some(0),
+        Channel::Green => Some(1.min(color_channels - 1)),
+        Channel::Blue => Some(2.min(color_channels - 1)),
+    };
+    let out = match index {
+        Some(index) => src
+            .as_raw()
+            .chunks_exact(channels)
+            .map(|pixel| pixel[index])
+            .collect(),
+        None => vec![opaque; src.as_raw().len() / channels],
+    };
+    ImageBuffer::from_raw(src.width(), src.height(), out).expect("buffer sized for image")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GrayImage, Luma, LumaA, Rgb, RgbImage, Rgba, RgbaImage};
+
+    #[test]
+    fn ensure_and_remove_alpha_round_trip() {
+        let rgb = DynamicImage::ImageRgb8(RgbImage::from_pixel(3, 2, Rgb([10, 20, 30])));
+        let rgba = ensure_alpha(rgb.clone(), 0.5);
+        assert_eq!(rgba.to_rgba8().get_pixel(1, 1), &Rgba([10, 20, 30, 128]));
+        // Existing alpha is kept
+        assert_eq!(ensure_alpha(rgba.clone(), 1.0).to_rgba8(), rgba.to_rgba8());
+        assert_eq!(remove_alpha(rgba), rgb);
+
+        let gray = DynamicImage::ImageLuma16(ImageBuffer::from_pixel(2, 2, Luma([1000u16])));
+        let gray_alpha = ensure_alpha(gray, 1.0);
+        assert!(matches!(gray_alpha, DynamicImage::ImageLumaA16(_)));
+        assert_eq!(
+            gray_alpha.to_luma_alpha16().get_pixel(0, 0),
+            &LumaA([1000, 65535])
+        );
+    }
+
+    #[test]
+    fn extract_channel_yields_single_channel() {
+        let img = DynamicImage::ImageRgba8(RgbaImage::from_pixel(2, 2, Rgba([1, 2, 3, 4])));
+        for (channel, expected) in [
+            (Channel::Red, 1),
+            (Channel::Green, 2),
+            (Channel::Blue, 3),
+            (Channel::Alpha, 4),
+        ] {
+            let out = extract_channel(img.clone(), channel);
+            assert!(matches!(out, DynamicImage::ImageLuma8(_)));
+            assert_eq!(
+                out.to_luma8().get_pixel(1, 0),
+                &Luma([expected]),
+                "{channel:?}"
+            );
+        }
+
+        let gray = DynamicImage::ImageLuma8(GrayImage::from_pixel(2, 2, Luma([77])));
+        assert_eq!(
+            extract_channel(gray.clone(), Channel::Blue)
+                .to_luma8()
+                .get_pixel(0, 0),
+            &Luma([77])
+        );
+        assert_eq!(
+            extract_channel(gray, Channel::Alpha)
+                .to_luma8()
+                .get_pixel(0, 0),
+            &Luma([255])
+        );
+    }
+
+    #[test]
+    fn join_channel_uses_mask_luma_as_alpha() {
+        let img = DynamicImage::ImageRgba8(RgbaImage::from_pixel(2, 1, Rgba([9, 8, 7, 255])));
+        let mask = DynamicImage::ImageLuma8(GrayImage::from_fn(2, 1, |x, _| Luma([x as u8 * 200])));
+        let out = join_channel(img, &mask).to_rgba8();
+        assert_eq!(out.get_pixel(0, 0), &Rgba([9, 8, 7, 0]));
+        assert_eq!(out.get_pixel(1, 0), &Rgba([9, 8, 7, 200]));
+
+        let gray = DynamicImage::ImageLuma8(GrayImage::from_pixel(2, 1, Luma([50])));
+        assert!(matches!(
+            join_channel(gray, &mask),
+            DynamicImage::ImageLumaA8(_)
+        ));
+    }
+}
@@ -0,0 +1,249 @@
+// src/engine/composite.rs
+//
+// Overlay compositing: `Operation::Composite`.
+//
+// Each overlay is decoded to RGBA f32 and blended onto the image with
+// Porter-Duff source-over in alpha-premultiplied space, using the `over`,
+// `multiply` or `screen` blend function on sRGB-encoded samples. Opacity
+// scales the overlay's alpha first. Overlays are clipped to the image, or
+// repeated over all of it when tiled; a clipped overlay is cropped to its
+// visible part before the f32 conversion, so a large overlay on a small image
+// costs no more than the image. Greyscale input is expanded to RGB
+// (keeping 16-bit depth); an opaque image stays opaque, since source-over
+// onto an opaque backdrop always is. Rows are blended in parallel.
+
+use crate::engine::grade::expand_gray;
+use crate::engine::tone::{map_samples, Layout, Sample};
+use crate::ops::{BlendMode, Overlay};
+use image::{DynamicImage, GenericImageView, Rgba32FImage};
+use rayon::prelude::*;
+
+/// Blend the decoded `overlay` onto `img` as described by `layer`.
+pub(crate) fn composite(
+    img: DynamicImage,
+    overlay: &DynamicImage,
+    layer: &Overlay,
+) -> DynamicImage {
+    let origin = layer
+        .placement
+        .origin(img.dimensions(), overlay.dimensions());
+    let (source, origin) = if layer.tile {
+        (overlay.to_rgba32f(), origin)
+    } else {
+        visible_part(overlay, origin, img.dimensions())
+    };
+    let paint = Paint {
+        origin,
+        source,
+        blend: layer.blend,
+        opacity: layer.opacity.clamp(0.0, 1.0) as f32,
+        tile: layer.tile,
+    };
+    map_samples(
+        expand_gray(img),
+        |samples, layout| paint.apply(samples, layout),
+        |samples, layout| paint.apply(samples, layout),
+    )
+}
+
+/// The part of `overlay` placed at `origin` that lands on a `base`-sized
+/// image, as RGBA f32, with its own origin. Empty when nothing is visible.
+fn visible_part(
+    overlay: &DynamicImage,
+    origin: (i64, i64),
+    base: (u32, u32),
+) -> (Rgba32FImage, (i64, i64)) {
+    let span = |origin: i64, len: u32, base: u32| {
+        let start = origin.clamp(0, base as i64);
+        let end = (origin + len as i64).clamp(0, base as i64);
+        (start, (start - origin) as u32, (end - start).max(0) as u32)
+    };
+    let (x, source_x, width) = span(origin.0, overlay.width(), base.0);
+    let (y, source_y, height) = span(origin.1, overlay.height(), base.1);
+    if width == 0 || height == 0 {
+        return (Rgba32FImage::new(0, 0), (x, y));
+    }
+    if (width, height) == overlay.dimensions() {
+        return (overlay.to_rgba32f(), (x, y));
+    }
+    let visible = overlay.crop_imm(source_x, source_y, width, height);
+    (visible.to_rgba32f(), (x, y))
+}
+
+struct Paint {
+    source: Rgba32FImage,
+    /// Image coordinates of the overlay's top-left corner
+    origin: (i64, i64),
+    blend: BlendMode,
+    opacity: f32,
+    tile: bool,
+}
+
+impl Paint {
+    fn apply<T: Sample>(&self, samples: &mut [T], layout: Layout) {
+        let (source_w, source_h) = (self.source.width() as i64, self.source.height() as i64);
+        let row_len = layout.width * layout.channels;
+        if row_len == 0 || source_w == 0 || source_h == 0 || self.opacity == 0.0 {
+            return;
+        }
+        // Columns covered by the overlay; every column when tiled.
+        let (start, end) = if self.tile {
+            (0, layout.width)
+        } else {
+            let start = self.origin.0.clamp(0, layout.width as i64) as usize;
+            let end = (self.origin.0 + source_w).clamp(0, layout.width as i64) as usize;
+            (start, end)
+        };
+        samples
+            .par_chunks_mut(row_len)
+            .enumerate()
+            .for_each(|(y, row)| {
+                let Some(source_y) = self.source_offset(y, self.origin.1, source_h) else {
+                    return;
+                };
+                for x in start..end {
+                    let Some(source_x) = self.source_offset(x, self.origin.0, source_w) else {
+                        continue;
+                    };
+                    let source = self.source.get_pixel(source_x, source_y).0;
+                    let pixel = &mut row[x * layout.channels..(x + 1) * layout.channels];
+                    self.blend_pixel(pixel, source);
+                }
+            });
+    }
+
+    /// Overlay row/column shown at image position `position`, if any.
+    fn source_offset(&self, position: usize, origin: i64, len: i64) -> Option<u32> {
+        let offset = position as i64 - origin;
+        if self.tile {
+            Some(offset.rem_euclid(len) as u32)
+        } else {
+            (0..len).contains(&offset).then_some(offset as u32)
+        }
+    }
+
+    fn blend_pixel<T: Sample>(&self, pixel: &mut [T], source: [f32; 4]) {
+        let source_alpha = source[3].clamp(0.0, 1.0) * self.opacity;
+        if source_alpha <= 0.0 {
+            return;
+        }
+        let has_alpha = pixel.len() == 4;
+        let base_alpha = if has_alpha { pixel[3].to_unit() } else { 1.0 };
+        let alpha = source_alpha + base_alpha * (1.0 - source_alpha);
+        for (sample, value) in pixel.iter_mut().zip(source).take(3) {
+            let s = value.clamp(0.0, 1.0) * source_alpha;
+            let b = sample.to_unit() * base_alpha;
+            let premultiplied = match self.blend {
+                BlendMode::Over => s + b * (1.0 - source_alpha),
+                BlendMode::Multiply => s * b + s * (1.0 - base_alpha) + b * (1.0 - source_alpha),
+                BlendMode::Screen => s + b - s * b,
+            };
+            *sample = T::from_unit(premultiplied / alpha);
+        }
+        if has_alpha {
+            pixel[3] = T::from_unit(alpha);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ops::{OverlayPlacement, ResizePosition};
+    use image::{GrayImage, Luma, Rgb, RgbImage, Rgba, RgbaImage};
+
+    fn layer(placement: OverlayPlacement, blend: BlendMode, opacity: f64, tile: bool) -> Overlay {
+        Overlay {
+            input: Default::default(),
+            placement,
+            blend,
+            opacity,
+            tile,
+        }
+    }
+
+    #[test]
+    fn blend_modes_and_opacity() {
+        let base = DynamicImage::ImageRgb8(RgbImage::from_pixel(1, 1, Rgb([100, 150, 200])));
+        let overlay =
+            DynamicImage::ImageRgba8(RgbaImage::from_pixel(1, 1, Rgba([200, 100, 50, 255])));
+        let at = OverlayPlacement::Offset { left: 0, top: 0 };
+        let run = |blend, opacity| {
+            let out = composite(base.clone(), &overlay, &layer(at, blend, opacity, false));
+            out.to_rgb8().get_pixel(0, 0).0
+        };
+        assert_eq!(run(BlendMode::Over, 1.0), [200, 100, 50]);
+        assert_eq!(run(BlendMode::Over, 0.5), [150, 125, 125]);
+        assert_eq!(run(BlendMode::Multiply, 1.0), [78, 59, 39]);
+        assert_eq!(run(BlendMode::Screen, 1.0), [222, 191, 211]);
+    }
+
+    #[test]
+    fn transparent_base_keeps_overlay_colour() {
+        let base = DynamicImage::ImageRgba8(RgbaImage::new(2, 1));
+        let overlay = DynamicImage::ImageRgba8(RgbaImage::from_pixel(1, 1, Rgba([255, 0, 0, 128])));
+        let at = OverlayPlacement::Offset { left: 1, top: 0 };
+        let out = composite(base, &overlay, &layer(at, BlendMode::Over, 1.0, false)).to_rgba8();
+        assert_eq!(out.get_pixel(0, 0).0, [0, 0, 0, 0]);
+        assert_eq!(out.get_pixel(1, 0).0, [255, 0, 0, 128]);
+    }
+
+    #[test]
+    fn placement_clips_and_tiles() {
+        let base = DynamicImage::ImageLuma8(GrayImage::from_pixel(4, 4, Luma([0])));
+        let overlay = DynamicImage::ImageLuma8(GrayImage::from_fn(2, 2, |x, y| {
+            Luma([if (x, y) == (0, 0) { 255 } else { 100 }])
+        }));
+        let white = |img: &DynamicImage| {
+            let rgb = img.as_rgb8().expect("greyscale is expanded to RGB8");
+            rgb.enumerate_pixels()
+                .filter(|(_, _, p)| p.0 == [255, 255, 255])
+                .map(|(x, y, _)| (x, y))
+                .collect::<Vec<_>>()
+        };
+
+        let corner = OverlayPlacement::Gravity(ResizePosition::SouthEast);
+        let out = composite(
+            base.clone(),
+            &overlay,
+            &layer(corner, BlendMode::Over, 1.0, false),
+        );
+        assert_eq!(white(&out), vec![(2, 2)]);
+        assert_eq!(out.as_rgb8().unwrap().get_pixel(0, 0).0, [0, 0, 0]);
+
+        let offset = OverlayPlacement::Offset { left: -1, top: 3 };
+        let out = composite(
+            base.clone(),
+            &overlay,
+            &layer(offset, BlendMode::Over, 1.0, false),
+        );
+        assert!(white(&out).is_empty());
+        assert_eq!(out.as_rgb8().unwrap().get_pixel(0, 3).0, [100, 100, 100]);
+
+        let tiled = OverlayPlacement::Offset { left: 1, top: 0 };
+        let out = composite(base, &overlay, &layer(tiled, BlendMode::Over, 1.0, true));
+        assert_eq!(white(&out), vec![(1, 0), (3, 0), (1, 2), (3, 2)]);
+    }
+
+    #[test]
+    fn large_overlay_is_cropped_to_the_visible_part() {
+        let overlay = DynamicImage::ImageLuma8(GrayImage::from_fn(100, 80, |x, y| {
+            Luma([if (x, y) == (50, 40) { 255 } else { 0 }])
+        }));
+        let (visible, origin) = visible_part(&overlay, (-48, -39), (4, 4));
+        assert_eq!(visible.dimensions(), (4, 4));
+        assert_eq!(origin, (0, 0));
+        assert_eq!(visible.get_pixel(2, 1).0, [1.0, 1.0, 1.0, 1.0]);
+
+        let (hidden, _) = visible_part(&overlay, (10, 0), (4, 4));
+        assert_eq!(hidden.dimensions(), (0, 0));
+
+        // Centred: the overlay's middle lands on the image.
+        let base = DynamicImage::ImageLuma8(GrayImage::from_pixel(4, 4, Luma([9])));
+        let centre = OverlayPlacement::Gravity(ResizePosition::Center);
+        let out = composite(base, &overlay, &layer(centre, BlendMode::Over, 1.0, false));
+        let rgb = out.as_rgb8().unwrap();
+        assert_eq!(rgb.get_pixel(2, 2).0, [255, 255, 255]);
+        assert_eq!(rgb.get_pixel(0, 0).0, [0, 0, 0]);
+    }
+}
@@ -3,14 +3,18 @@
 // Decoder operations: JPEG (mozjpeg), PNG, WebP, etc.
 
 use crate::engine::common::run_with_panic_policy;
+use crate::engine::icc;
+use crate::engine::io::extract_icc_from_jpeg;
 use crate::error::LazyImageError;
+use crate::ops::IccProfile;
 use exif;
 use image::{
-    DynamicImage, GenericImageView, GrayAlphaImage, GrayImage, ImageFormat, ImageReader, RgbImage,
-    RgbaImage,
+    DynamicImage, GenericImageView, GrayAlphaImage, GrayImage, ImageBuffer, ImageFormat,
+    ImageReader, RgbImage, RgbaImage,
 };
-use mozjpeg::Decompress;
+use mozjpeg::{ColorSpace as JpegColorSpace, Decompress, Marker};
 use std::io::Cursor;
+use std::sync::Arc;
 use webp::{BitstreamFeatures, Decoder as WebPDecoder};
 use zune_png::zune_core::bytestream::ZCursor;
 use zune_png::zune_core::colorspace::ColorSpace;
@@ -27,8 +31,36 @@ type DecoderResult<T> = std::result::Result<T, LazyImageError>;
 // tasks.rs::EncodeTask::decode() and stress.rs::run_stress_iteration() have their own implementations.
 
 /// Decode JPEG using mozjpeg (backed by libjpeg-turbo)
-/// This is SIGNIFICANTLY faster than image crate's pure Rust decoder
+/// This is SIGNIFICANTLY faster than image crate's pure Rust decoder.
+/// CMYK and YCCK files are converted to sRGB through their embedded CMYK
+/// profile; `decode_jpeg_ink` keeps the ink samples instead.
 pub fn decode_jpeg_mozjpeg(data: &[u8]) -> DecoderResult<DynamicImage> {
+    let (img, cmyk) = decode_jpeg_ink(data)?;
+    if !cmyk {
+        return Ok(img);
+    }
+    let profile = extract_icc_from_jpeg(data).map(|bytes| IccProfile::Bytes(Arc::new(bytes)));
+    icc::cmyk_to_rgb(&img.into_rgba8(), profile.as_ref(), &IccProfile::Srgb)
+}
+
+/// Whether the JPEG frame header declares four components (CMYK or YCCK),
+/// read without decoding; these are the files `decode_jpeg_ink` leaves as ink.
+pub(crate) fn jpeg_header_is_cmyk(data: &[u8]) -> bool {
+    if !data.starts_with(&[0xFF, 0xD8]) {
+        return false;
+    }
+    // SOF0-SOF15, except DHT (C4), JPG (C8) and DAC (CC); component count follows
+    // precision, height and width
+    crate::engine::metadata::jpeg_segments(data)
+        .find(|(marker, _)| (0xC0..=0xCF).contains(marker) && ![0xC4, 0xC8, 0xCC].contains(marker))
+        .and_then(|(_, frame)| frame.get(5).copied())
+        == Some(4)
+}
+
+/// Decode JPEG, leaving CMYK and YCCK scans as ink samples (C, M, Y, K in an
+/// RGBA8 buffer, 0 = no ink); the flag reports whether it did. Adobe APP14
+/// files store inverted CMYK, which is undone here.
+pub(crate) fn decode_jpeg_ink(data: &[u8]) -> DecoderResult<(DynamicImage, bool)> {
     run_with_panic_policy("decode:mozjpeg", || {
         // EOI marker (0xFF 0xD9) is at the end of valid JPEGs.
         // Only check the last 256 bytes for O(1) performance instead of O(n).
@@ -44,13 +76,29 @@ pub fn decode_jpeg_mozjpeg(data: &[u8]) -> DecoderResult<DynamicImage> {
 
         validate_jpeg_structure(data)?;
 
-        let decompress = Decompress::new_mem(data).map_err(|e| {
-            LazyImageError::decode_failed(format!("mozjpeg decompress init failed: {e:?}"))
-        })?;
+        let decompress = Decompress::builder()
+            .with_markers(&[Marker::APP(14)])
+            .from_mem(data)
+            .map_err(|e| {
+                LazyImageError::decode_failed(format!("mozjpeg decompress init failed: {e:?}"))
+            })?;
 
-        // Get image info
-        let mut decompress = decompress.rgb().map_err(|e| {
-            LazyImageError::decode_failed(format!("mozjpeg rgb conversion failed: {e:?}"))
+        let cmyk = matches!(
+            decompress.color_space(),
+            JpegColorSpace::JCS_CMYK | JpegColorSpace::JCS_YCCK
+        );
+        let adobe = decompress
+            .markers()
+            .any(|marker| marker.data.starts_with(b"Adobe"));
+
+        // Get image info; libjpeg turns YCCK into CMYK itself
+        let (colorspace, label) = if cmyk {
+            (JpegColorSpace::JCS_CMYK, "cmyk")
+        } else {
+            (JpegColorSpace::JCS_RGB, "rgb")
+        };
+        let mut decompress = decompress.to_colorspace(colorspace).map_err(|e| {
+            LazyImageError::decode_failed(format!("mozjpeg {label} conversion failed: {e:?}"))
         })?;
 
         let width = decompress.width();
@@ -61,20 +109,25 @@ pub fn decode_jpeg_mozjpeg(data: &[u8]) -> DecoderResult<DynamicImage> {
         check_dimensions(width_u32, height_u32)?;
 
         // Read all scanlines
-        let pixels: Vec<[u8; 3]> = decompress.read_scanlines().map_err(|e| {
+        let mut samples: Vec<u8> = decompress.read_scanlines().map_err(|e| {
             LazyImageError::decode_failed(format!("mozjpeg: failed to read scanlines: {e:?}"))
         })?;
 
-        // Safe conversion from Vec<[u8; 3]> to Vec<u8>
-        let flat_pixels: Vec<u8> = pixels.into_iter().flatten().collect();
-
-        // Create DynamicImage from raw RGB data
-        let rgb_image =
-            RgbImage::from_raw(width_u32, height_u32, flat_pixels).ok_or_else(|| {
-                LazyImageError::decode_failed("mozjpeg: failed to create image from raw data")
-            })?;
-
-        Ok(DynamicImage::ImageRgb8(rgb_image))
+        let raw_failed =
+            || LazyImageError::decode_failed("mozjpeg: failed to create image from raw data");
+        if !cmyk {
+            // Create DynamicImage from raw RGB data
+            let rgb_image =
+                RgbImage::from_raw(width_u32, height_u32, samples).ok_or_else(raw_failed)?;
+            return Ok((DynamicImage::ImageRgb8(rgb_image), false));
+        }
+        if adobe {
+            samples
+                .iter_mut()
+                .for_each(|sample| *sample = 255 - *sample);
+        }
+        let ink = RgbaImage::from_raw(width_u32, height_u32, samples).ok_or_else(raw_failed)?;
+        Ok((DynamicImage::ImageRgba8(ink), true))
     })
 }
 
@@ -203,8 +256,9 @@ pub fn decode_png_zune(data: &[u8]) -> DecoderResult<DynamicImage> {
             }
         }
 
-        let options = DecoderOptions::default().png_set_strip_to_8bit(true);
-        let mut decoder = PngDecoder::new_with_options(ZCursor::new(data), options);
+        // 16-bit PNGs keep their depth; the pipeline and encoders handle U16 layouts.
+        let mut decoder =
+            PngDecoder::new_with_options(ZCursor::new(data), DecoderOptions::default());
         let pixels = decoder
             .decode()
             .map_err(|e| LazyImageError::decode_failed(format!("png: decode failed: {e}")))?;
@@ -216,52 +270,63 @@ pub fn decode_png_zune(data: &[u8]) -> DecoderResult<DynamicImage> {
         let height = height_usize as u32;
         check_dimensions(width, height)?;
 
-        let buf = match pixels {
-            DecodingResult::U8(v) => v,
-            _ => {
-                return Err(LazyImageError::decode_failed(
-                    "png: unexpected non-U8 pixel buffer",
-                ))
-            }
-        };
-
         let colorspace = decoder
             .colorspace()
             .ok_or_else(|| LazyImageError::decode_failed("png: missing colorspace"))?;
 
-        let img = match colorspace {
-            ColorSpace::RGB => RgbImage::from_raw(width, height, buf)
-                .map(DynamicImage::ImageRgb8)
-                .ok_or_else(|| LazyImageError::decode_failed("png: failed to build RGB image"))?,
-            ColorSpace::RGBA => RgbaImage::from_raw(width, height, buf)
-                .map(DynamicImage::ImageRgba8)
-                .ok_or_else(|| LazyImageError::decode_failed("png: failed to build RGBA image"))?,
-            ColorSpace::Luma => GrayImage::from_raw(width, height, buf)
-                .map(DynamicImage::ImageLuma8)
-                .ok_or_else(|| LazyImageError::decode_failed("png: failed to build Luma image"))?,
-            ColorSpace::LumaA => GrayAlphaImage::from_raw(width, height, buf)
-                .map(DynamicImage::ImageLumaA8)
-                .ok_or_else(|| LazyImageError::decode_failed("png: failed to build LumaA image"))?,
+        let img = match (colorspace, pixels) {
+            (ColorSpace::RGB, DecodingResult::U8(buf)) => {
+                RgbImage::from_raw(width, height, buf).map(DynamicImage::ImageRgb8)
+            }
+            (ColorSpace::RGBA, DecodingResult::U8(buf)) => {
+                RgbaImage::from_raw(width, height, buf).map(DynamicImage::ImageRgba8)
+            }
+            (ColorSpace::Luma, DecodingResult::U8(buf)) => {
+                GrayImage::from_raw(width, height, buf).map(DynamicImage::ImageLuma8)
+            }
+            (ColorSpace::LumaA, DecodingResult::U8(buf)) => {
+                GrayAlphaImage::from_raw(width, height, buf).map(DynamicImage::ImageLumaA8)
+            }
+            (ColorSpace::RGB, DecodingResult::U16(buf)) => {
+                ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageRgb16)
+            }
+            (ColorSpace::RGBA, DecodingResult::U16(buf)) => {
+                ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageRgba16)
+            }
+            (ColorSpace::Luma, DecodingResult::U16(buf)) => {
+                ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageLuma16)
+            }
+            (ColorSpace::LumaA, DecodingResult::U16(buf)) => {
+                ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageLumaA16)
+            }
             // Fallback to image crate for unsupported ordering/space
-            ColorSpace::BGRA | ColorSpace::ARGB => {
+            (ColorSpace::BGRA | ColorSpace::ARGB, _) => {
                 return Err(LazyImageError::decode_failed(
                     "png: BGRA/ARGB not supported by zune-png, fallback to image crate",
                 ));
             }
             // YCbCr is 3-channel, not compatible with RGBA (4-channel)
             // Fallback to image crate for proper handling
-            ColorSpace::YCbCr | ColorSpace::YCCK => {
+            (ColorSpace::YCbCr | ColorSpace::YCCK, _) => {
                 return Err(LazyImageError::decode_failed(
                     "png: YCbCr colorspace not supported by zune-png, fallback to image crate",
                 ));
             }
-            other => {
+            (other, DecodingResult::U8(_) | DecodingResult::U16(_)) => {
                 return Err(LazyImageError::decode_failed(format!(
                     "png: unsupported colorspace {:?}, fallback to image crate",
                     other
                 )))
             }
-        };
+            (_, _) => {
+                return Err(LazyImageError::decode_failed(
+                    "png: unexpected float pixel buffer",
+                ))
+            }
+        }
+        .ok_or_else(|| {
+            LazyImageError::decode_failed(format!("png: failed to build {:?} image", colorspace))
+        })?;
 
         Ok(img)
     })
@@ -350,6 +415,18 @@ pub fn decode_image(bytes: &[u8]) -> DecoderResult<(DynamicImage, Option<ImageFo
     Ok((img, detected))
 }
 
+/// Like `decode_image`, but CMYK JPEGs keep their ink samples for the
+/// pipeline to convert (see `decode_jpeg_ink`); the flag reports whether
+/// they did.
+pub fn decode_source(bytes: &[u8]) -> DecoderResult<(DynamicImage, bool)> {
+    if detect_format(bytes) != Some(ImageFormat::Jpeg) {
+        return decode_image(bytes).map(|(img, _)| (img, false));
+    }
+    let (img, cmyk) = decode_jpeg_ink(bytes)?;
+    check_dimensions(img.width(), img.height())?;
+    Ok((img, cmyk))
+}
+
 /// Check if image dimensions are within safe limits.
 /// Returns an error if the image is too large (potential decompression bomb).
 /// In fuzz builds, uses stricter limits (FUZZ_MAX_DIMENSION / FUZZ_MAX_PIXELS) so that
@@ -378,29 +455,35 @@ pub fn check_dimensions(width: u32, height: u32) -> DecoderResult<()> {
 
 /// Inspect encoded bytes and ensure the image dimensions are safe before decoding.
 pub fn ensure_dimensions_safe(bytes: &[u8]) -> DecoderResult<()> {
+    match read_dimensions(bytes)? {
+        Some((width, height)) => check_dimensions(width, height),
+        None => Ok(()),
+    }
+}
+
+/// Image dimensions from the encoded header, without decoding pixels.
+/// `Ok(None)` when the format is not recognised.
+pub fn read_dimensions(bytes: &[u8]) -> DecoderResult<Option<(u32, u32)>> {
     // WebP: parse bitstream features directly to avoid image crate OOM on malformed headers.
     if is_webp_riff(bytes) {
         let features = BitstreamFeatures::new(bytes).ok_or_else(|| {
             LazyImageError::decode_failed("webp: failed to read bitstream features")
         })?;
-        return check_dimensions(features.width(), features.height());
+        return Ok(Some((features.width(), features.height())));
     }
 
     // PNG: read header directly when available to avoid full decode.
-    if let Ok((width, height)) = read_png_dimensions(bytes) {
-        return check_dimensions(width, height);
+    if let Ok(dimensions) = read_png_dimensions(bytes) {
+        return Ok(Some(dimensions));
     }
 
     let cursor = Cursor::new(bytes);
     if let Ok(reader) = ImageReader::new(cursor).with_guessed_format() {
-        return reader
-            .into_dimensions()
-            .map_err(|_| {
-                LazyImageError::decode_failed("decode failed: could not read image dimensions")
-            })
-            .and_then(|(width, height)| check_dimensions(width, height));
+        return reader.into_dimensions().map(Some).map_err(|_| {
+            LazyImageError::decode_failed("decode failed: could not read image dimensions")
+        });
     }
-    Ok(())
+    Ok(None)
 }
 
 fn is_webp_riff(bytes: &[u8]) -> bool {
@@ -517,6 +600,54 @@ mod tests {
         let (img, fmt) = decode_image(&jpeg).unwrap();
         assert_eq!(fmt, Some(ImageFormat::Jpeg));
         assert_eq!(img.dimensions(), (2, 2));
+        assert!(!jpeg_header_is_cmyk(&jpeg));
+    }
+
+    /// Adobe-style CMYK (or YCCK) JPEG: libjpeg writes the APP14 marker, so
+    /// the stored samples are inverted ink.
+    fn encode_cmyk_jpeg(ink: [u8; 4], color_space: mozjpeg::ColorSpace) -> Vec<u8> {
+        let mut comp = mozjpeg::Compress::new(mozjpeg::ColorSpace::JCS_CMYK);
+        comp.set_size(4, 4);
+        comp.set_quality(100.0);
+        comp.set_color_space(color_space);
+        let row: Vec<u8> = ink.map(|v| 255 - v).repeat(4);
+        let mut output = Vec::new();
+        {
+            let mut writer = comp.start_compress(&mut output).unwrap();
+            for _ in 0..4 {
+                writer.write_scanlines(&row).unwrap();
+            }
+            writer.finish().unwrap();
+        }
+        output
+    }
+
+    #[test]
+    fn test_decode_cmyk_and_ycck_jpeg() {
+        for color_space in [mozjpeg::ColorSpace::JCS_CMYK, mozjpeg::ColorSpace::JCS_YCCK] {
+            let jpeg = encode_cmyk_jpeg([0, 255, 255, 0], color_space);
+
+            let (ink, cmyk) = decode_source(&jpeg).unwrap();
+            assert!(cmyk, "{color_space:?}");
+            assert!(jpeg_header_is_cmyk(&jpeg), "{color_space:?}");
+            let [c, m, y, k] = ink
+                .as_rgba8()
+                .expect("ink is held as RGBA8")
+                .get_pixel(1, 1)
+                .0;
+            assert!(c < 4 && m > 251 && y > 251 && k < 4, "{:?}", [c, m, y, k]);
+
+            // Without a profile the conversion is naive: full magenta and
+            // yellow make red.
+            let (rgb, fmt) = decode_image(&jpeg).unwrap();
+            assert_eq!(fmt, Some(ImageFormat::Jpeg));
+            let [r, g, b] = rgb.as_rgb8().expect("converted to RGB8").get_pixel(1, 1).0;
+            assert!(r > 251 && g < 4 && b < 4, "{:?}", [r, g, b]);
+        }
+
+        let (_, cmyk) = decode_source(&encode_png(2, 2)).unwrap();
+        assert!(!cmyk);
+        assert!(!jpeg_header_is_cmyk(&encode_png(2, 2)));
     }
 
     #[test]
@@ -0,0 +1,144 @@
+// src/engine/depth.rs
+//
+// Sample depth handed to the encoders.
+//
+// The pipeline keeps 16-bit sources at 16 bits. When the output is 8-bit the
+// samples are reduced here with the same 4x4 ordered dither the linear-light
+// resize uses, so smooth 16-bit gradients do not band. Alpha is rounded, not
+// dithered. A 16-bit PNG requested from an 8-bit pipeline is widened instead.
+
+use crate::engine::linear::dither_offset;
+use image::{DynamicImage, ImageBuffer};
+use std::borrow::Cow;
+
+/// `img` in the sample size an encoder writing `bits` per sample expects.
+/// 10/12-bit AVIF reads 16-bit samples itself, so only 8 and 16 convert here.
+pub(crate) fn for_encoder(img: Cow<'_, DynamicImage>, bits: u8) -> Cow<'_, DynamicImage> {
+    match (bits, is_sixteen_bit(&img)) {
+        (8, true) => Cow::Owned(reduce_to_8bit(&img)),
+        (16, false) => Cow::Owned(widen_to_16bit(img.into_owned())),
+        _ => img,
+    }
+}
+
+fn is_sixteen_bit(img: &DynamicImage) -> bool {
+    matches!(
+        img,
+        DynamicImage::ImageLuma16(_)
+            | DynamicImage::ImageLumaA16(_)
+            | DynamicImage::ImageRgb16(_)
+            | DynamicImage::ImageRgba16(_)
+    )
+}
+
+/// Dither 16-bit samples down to 8-bit, keeping the channel layout.
+fn dither(samples: &[u16], channels: usize, has_alpha: bool, width: usize) -> Vec<u8> {
+    samples
+        .iter()
+        .enumerate()
+        .map(|(i, &value)| {
+            let scaled = value as f32 / 257.0;
+            if has_alpha && i % channels == channels - 1 {
+                return scaled.round() as u8;
+            }
+            let pixel = i / channels;
+            let offset = dither_offset(pixel % width, pixel / width);
+            (scaled + offset).round().clamp(0.0, 255.0) as u8
+        })
+        .collect()
+}
+
+/// 8-bit copy of a 16-bit image with an ordered dither. Other layouts are
+/// returned unchanged.
+fn reduce_to_8bit(img: &DynamicImage) -> DynamicImage {
+    let (w, h) = (img.width(), img.height());
+    let width = w as usize;
+    let reduced = match img {
+        DynamicImage::ImageLuma16(buf) => {
+            ImageBuffer::from_raw(w, h, dither(buf.as_raw(), 1, false, width))
+                .map(DynamicImage::ImageLuma8)
+        }
+        DynamicImage::ImageLumaA16(buf) => {
+            ImageBuffer::from_raw(w, h, dither(buf.as_raw(), 2, true, width))
+                .map(DynamicImage::ImageLumaA8)
+        }
+        DynamicImage::ImageRgb16(buf) => {
+            ImageBuffer::from_raw(w, h, dither(buf.as_raw(), 3, false, width))
+                .map(DynamicImage::ImageRgb8)
+        }
+        DynamicImage::ImageRgba16(buf) => {
+            ImageBuffer::from_raw(w, h, dither(buf.as_raw(), 4, true, width))
+                .map(DynamicImage::ImageRgba8)
+        }
+        _ => None,
+    };
+    reduced.unwrap_or_else(|| img.clone())
+}
+
+/// 16-bit copy of an 8-bit image (each sample scaled by 257), keeping
+/// greyscale and alpha. Float layouts become RGB16/RGBA16.
+fn widen_to_16bit(img: DynamicImage) -> DynamicImage {
+    match img {
+        DynamicImage::ImageLuma8(_) => DynamicImage::ImageLuma16(img.into_luma16()),
+        DynamicImage::ImageLumaA8(_) => DynamicImage::ImageLumaA16(img.into_luma_alpha16()),
+        _ if img.color().has_alpha() => DynamicImage::ImageRgba16(img.into_rgba16()),
+        _ => DynamicImage::ImageRgb16(img.into_rgb16()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, Rgba};
+
+    #[test]
+    fn dither_breaks_up_banding_between_8bit_steps() {
+        // Halfway between 100 and 101: plain rounding gives one flat value,
+        // the dither mixes both so the average survives.
+        let value = 100 * 257 + 128;
+        let img = DynamicImage::ImageRgb16(ImageBuffer::from_pixel(4, 4, Rgb([value; 3])));
+        let DynamicImage::ImageRgb8(out) = reduce_to_8bit(&img) else {
+            panic!("RGB16 should reduce to RGB8");
+        };
+        let reds: Vec<u8> = out.pixels().map(|p| p[0]).collect();
+        assert!(reds.iter().all(|&v| v == 100 || v == 101), "{reds:?}");
+        let mean = reds.iter().map(|&v| v as f32).sum::<f32>() / reds.len() as f32;
+        assert!((mean - 100.5).abs() < 0.1, "mean {mean}");
+    }
+
+    #[test]
+    fn reduce_keeps_layout_and_rounds_alpha() {
+        let img =
+            DynamicImage::ImageRgba16(ImageBuffer::from_pixel(2, 2, Rgba([65535, 0, 0, 32896])));
+        let DynamicImage::ImageRgba8(out) = reduce_to_8bit(&img) else {
+            panic!("RGBA16 should reduce to RGBA8");
+        };
+        assert!(out.pixels().all(|p| p.0 == [255, 0, 0, 128]));
+
+        let grey = DynamicImage::ImageLuma16(ImageBuffer::new(3, 1));
+        assert!(matches!(reduce_to_8bit(&grey), DynamicImage::ImageLuma8(_)));
+    }
+
+    #[test]
+    fn for_encoder_converts_only_when_depth_differs() {
+        let eight = DynamicImage::ImageLumaA8(ImageBuffer::new(2, 2));
+        assert!(matches!(
+            for_encoder(Cow::Borrowed(&eight), 8),
+            Cow::Borrowed(_)
+        ));
+        assert!(matches!(
+            for_encoder(Cow::Borrowed(&eight), 16).as_ref(),
+            DynamicImage::ImageLumaA16(_)
+        ));
+
+        let sixteen = DynamicImage::ImageRgb16(ImageBuffer::new(2, 2));
+        assert!(matches!(
+            for_encoder(Cow::Borrowed(&sixteen), 10),
+            Cow::Borrowed(_)
+        ));
+        assert!(matches!(
+            for_encoder(Cow::Borrowed(&sixteen), 8).as_ref(),
+            DynamicImage::ImageRgb8(_)
+        ));
+    }
+}
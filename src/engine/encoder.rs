@@ -5,6 +5,7 @@
 use crate::codecs::avif_safe::{create_rgb_image, SafeAvifEncoder, SafeAvifImage, SafeAvifRwData};
 use crate::engine::check_dimensions;
 use crate::engine::common::run_with_panic_policy;
+use crate::engine::pipeline::Gamut;
 use crate::error::LazyImageError;
 use image::{DynamicImage, GenericImageView, ImageFormat};
 use img_parts::{jpeg::Jpeg, png::Png, ImageICC};
@@ -419,8 +420,21 @@ fn sanitize_exif_bytes(
     Ok(result)
 }
 
-/// Encode to PNG using image crate
+/// Encode to PNG using image crate (16-bit layouts are written as 16-bit PNGs)
 pub fn encode_png(img: &DynamicImage, icc: Option<&[u8]>) -> EncoderResult<Vec<u8>> {
+    encode_png_with_gamut(img, icc, Gamut::Unknown)
+}
+
+/// Encode to PNG and signal `gamut` with colour chunks
+///
+/// Display P3 and BT.2020 get a `cICP` chunk next to any ICC profile (readers
+/// that understand `cICP` ignore the profile). sRGB without an ICC profile
+/// gets `sRGB` and `gAMA` chunks. `Gamut::Unknown` adds no colour chunks.
+pub fn encode_png_with_gamut(
+    img: &DynamicImage,
+    icc: Option<&[u8]>,
+    gamut: Gamut,
+) -> EncoderResult<Vec<u8>> {
     run_with_panic_policy("encode:png", || {
         let (w, h) = img.dimensions();
         validate_encode_dimensions(w, h, "png")?;
@@ -433,19 +447,66 @@ pub fn encode_png(img: &DynamicImage, icc: Option<&[u8]>) -> EncoderResult<Vec<u
         let mut options = oxipng::Options::from_preset(4);
         // Preserve metadata (do not strip ICC)
         options.strip = oxipng::StripChunks::None;
+        // 16-bit input is written at 16 bits even when every sample would fit in 8
+        if img.color().bytes_per_pixel() > img.color().channel_count() {
+            options.bit_depth_reduction = false;
+        }
 
         let optimized = oxipng::optimize_from_memory(&buf, &options).map_err(|e| {
             LazyImageError::encode_failed("png", format!("oxipng optimization failed: {e}"))
         })?;
 
-        if let Some(icc_data) = icc {
-            embed_icc_png(optimized, icc_data)
-        } else {
-            Ok(optimized)
-        }
+        let png = match icc {
+            Some(icc_data) => embed_icc_png(optimized, icc_data)?,
+            None => optimized,
+        };
+        embed_color_chunks_png(png, gamut, icc.is_some())
     })
 }
 
+/// Insert the colour chunks for `gamut` (see `encode_png_with_gamut`) right
+/// after IHDR, replacing any already present.
+fn embed_color_chunks_png(
+    png_data: Vec<u8>,
+    gamut: Gamut,
+    has_icc: bool,
+) -> EncoderResult<Vec<u8>> {
+    use img_parts::png::PngChunk;
+    use img_parts::Bytes;
+
+    let chunks: Vec<([u8; 4], Vec<u8>)> = match gamut {
+        Gamut::DisplayP3 | Gamut::Bt2020 => {
+            // Matrix 0 (RGB), full range
+            let (primaries, transfer) = gamut.cicp();
+            vec![(*b"cICP", vec![primaries, transfer, 0, 1])]
+        }
+        // Perceptual intent; gAMA is 1/2.2 scaled by 100000 for older readers
+        Gamut::Srgb if !has_icc => vec![
+            (*b"sRGB", vec![0]),
+            (*b"gAMA", 45455u32.to_be_bytes().to_vec()),
+        ],
+        _ => return Ok(png_data),
+    };
+
+    let mut png = Png::from_bytes(Bytes::from(png_data)).map_err(|e| {
+        LazyImageError::decode_failed(format!("failed to parse PNG for colour chunks: {e}"))
+    })?;
+    for kind in [*b"cICP", *b"sRGB", *b"gAMA"] {
+        png.remove_chunks_by_type(kind);
+    }
+    for (offset, (kind, contents)) in chunks.into_iter().enumerate() {
+        // Index 0 is IHDR; colour chunks must precede PLTE and IDAT
+        png.chunks_mut()
+            .insert(1 + offset, PngChunk::new(kind, Bytes::from(contents)));
+    }
+
+    let mut output = Vec::new();
+    png.encoder().write_to(&mut output).map_err(|e| {
+        LazyImageError::encode_failed("png", format!("failed to write PNG colour chunks: {e}"))
+    })?;
+    Ok(output)
+}
+
 /// Embed ICC profile into PNG using img-parts
 pub fn embed_icc_png(png_data: Vec<u8>, icc: &[u8]) -> EncoderResult<Vec<u8>> {
     run_with_panic_policy("encode:png:embed_icc", || {
@@ -547,9 +608,32 @@ pub fn embed_icc_webp(webp_data: Vec<u8>, icc: &[u8]) -> EncoderResult<Vec<u8>>
 /// This function uses safe abstractions from `codecs::avif_safe` to minimize
 /// unsafe blocks and improve memory safety.
 pub fn encode_avif(img: &DynamicImage, quality: u8, icc: Option<&[u8]>) -> EncoderResult<Vec<u8>> {
+    encode_avif_with_settings(img, quality, icc, 8, Gamut::Srgb)
+}
+
+/// Encode to AVIF with an explicit bit depth (8, 10 or 12) and gamut
+///
+/// High bit depths are fed from 16-bit RGBA samples, so 16-bit pipelines reach
+/// the encoder without an 8-bit round trip. `gamut` selects the CICP colour
+/// primaries and transfer; `Gamut::Unknown` leaves them unspecified so the ICC
+/// profile describes the pixels.
+pub fn encode_avif_with_settings(
+    img: &DynamicImage,
+    quality: u8,
+    icc: Option<&[u8]>,
+    depth: u8,
+    gamut: Gamut,
+) -> EncoderResult<Vec<u8>> {
     run_with_panic_policy("encode:avif", || {
         use std::borrow::Cow;
 
+        if !matches!(depth, 8 | 10 | 12) {
+            return Err(LazyImageError::encode_failed(
+                "avif",
+                format!("unsupported AVIF bit depth {depth}"),
+            ));
+        }
+
         let clamped_quality = quality.min(100);
         let settings = QualitySettings::new(clamped_quality);
         let (width, height) = img.dimensions();
@@ -557,20 +641,62 @@ pub fn encode_avif(img: &DynamicImage, quality: u8, icc: Option<&[u8]>) -> Encod
 
         let has_alpha = img.color().has_alpha();
 
-        let rgba: Cow<'_, image::RgbaImage> = match img {
-            DynamicImage::ImageRgba8(rgba_img) => Cow::Borrowed(rgba_img),
-            _ => Cow::Owned(img.to_rgba8()),
+        // 8-bit output reads RGBA8; 10/12-bit output reads RGBA16 and lets
+        // libavif scale the samples down to the target depth.
+        let rgba8: Cow<'_, image::RgbaImage>;
+        let rgba16: Cow<'_, image::ImageBuffer<image::Rgba<u16>, Vec<u16>>>;
+        let (pixels_ptr, rgb_depth, alpha): (*const u8, u32, Vec<u16>) = if depth == 8 {
+            rgba8 = match img {
+                DynamicImage::ImageRgba8(rgba_img) => Cow::Borrowed(rgba_img),
+                _ => Cow::Owned(img.to_rgba8()),
+            };
+            let pixels = rgba8.as_raw();
+            validate_buffer_len(width, height, 4, pixels.len(), "avif")?;
+            let alpha = if has_alpha {
+                pixels
+                    .iter()
+                    .skip(3)
+                    .step_by(4)
+                    .map(|&a| a as u16)
+                    .collect()
+            } else {
+                Vec::new()
+            };
+            (pixels.as_ptr(), 8, alpha)
+        } else {
+            rgba16 = match img {
+                DynamicImage::ImageRgba16(rgba_img) => Cow::Borrowed(rgba_img),
+                _ => Cow::Owned(img.to_rgba16()),
+            };
+            let pixels = rgba16.as_raw();
+            validate_buffer_len(width, height, 4, pixels.len(), "avif")?;
+            let alpha = if has_alpha {
+                let shift = 16 - depth;
+                pixels
+                    .iter()
+                    .skip(3)
+                    .step_by(4)
+                    .map(|&a| a >> shift)
+                    .collect()
+            } else {
+                Vec::new()
+            };
+            (pixels.as_ptr().cast(), 16, alpha)
         };
-        let pixels = rgba.as_raw();
-        validate_buffer_len(width, height, 4, pixels.len(), "avif")?;
 
-        let mut avif_image = SafeAvifImage::new(width, height, 8, AVIF_PIXEL_FORMAT_YUV420)
-            .map_err(|e| LazyImageError::encode_failed("avif".to_string(), e.to_string()))?;
+        let mut avif_image =
+            SafeAvifImage::new(width, height, depth as u32, AVIF_PIXEL_FORMAT_YUV420)
+                .map_err(|e| LazyImageError::encode_failed("avif".to_string(), e.to_string()))?;
 
+        let (primaries, transfer) = gamut.cicp();
+        let matrix = match gamut {
+            Gamut::Bt2020 => AVIF_MATRIX_COEFFICIENTS_BT2020_NCL,
+            _ => AVIF_MATRIX_COEFFICIENTS_BT709,
+        };
         avif_image.set_color_properties(
-            AVIF_COLOR_PRIMARIES_BT709 as u16,
-            AVIF_TRANSFER_CHARACTERISTICS_SRGB as u16,
-            AVIF_MATRIX_COEFFICIENTS_BT709 as u16,
+            primaries as u16,
+            transfer as u16,
+            matrix as u16,
             AVIF_RANGE_FULL,
         );
 
@@ -580,7 +706,7 @@ pub fn encode_avif(img: &DynamicImage, quality: u8, icc: Option<&[u8]>) -> Encod
                 .map_err(|e| LazyImageError::encode_failed("avif".to_string(), e.to_string()))?;
         }
 
-        let rgb = create_rgb_image(&mut avif_image, pixels.as_ptr(), width, height)
+        let rgb = create_rgb_image(&mut avif_image, pixels_ptr, width, height, rgb_depth)
             .map_err(|e| LazyImageError::encode_failed("avif".to_string(), e.to_string()))?;
 
         avif_image
@@ -603,9 +729,19 @@ pub fn encode_avif(img: &DynamicImage, quality: u8, icc: Option<&[u8]>) -> Encod
                 let alpha_row_bytes = avif_image.alpha_row_bytes();
                 for y in 0..height as usize {
                     for x in 0..width as usize {
-                        let src_idx = (y * width as usize + x) * 4 + 3;
-                        let dst_idx = y * alpha_row_bytes + x;
-                        *alpha_plane.as_ptr().add(dst_idx) = pixels[src_idx];
+                        let value = alpha[y * width as usize + x];
+                        if depth == 8 {
+                            let dst_idx = y * alpha_row_bytes + x;
+                            *alpha_plane.as_ptr().add(dst_idx) = value as u8;
+                        } else {
+                            // High bit depth planes hold one u16 per sample.
+                            let dst_idx = y * alpha_row_bytes + x * 2;
+                            alpha_plane
+                                .as_ptr()
+                                .add(dst_idx)
+                                .cast::<u16>()
+                                .write_unaligned(value);
+                        }
                     }
                 }
             }
@@ -828,6 +964,47 @@ mod tests {
             );
         }
 
+        #[test]
+        fn test_encode_png_signals_gamut() {
+            let img = create_test_image(16, 16);
+            let chunk = |png: &[u8], kind: [u8; 4]| {
+                Png::from_bytes(png.to_vec().into())
+                    .unwrap()
+                    .chunk_by_type(kind)
+                    .map(|chunk| chunk.contents().to_vec())
+            };
+
+            let p3 = encode_png_with_gamut(&img, None, Gamut::DisplayP3).unwrap();
+            assert_eq!(chunk(&p3, *b"cICP"), Some(vec![12, 13, 0, 1]));
+            assert_eq!(chunk(&p3, *b"sRGB"), None);
+            // Colour chunks sit before the image data.
+            let png = Png::from_bytes(p3.clone().into()).unwrap();
+            assert_eq!(png.chunks()[1].kind(), *b"cICP");
+            assert_eq!(
+                image::load_from_memory(&p3).unwrap().to_rgb8(),
+                img.to_rgb8()
+            );
+
+            let bt2020 = encode_png_with_gamut(&img, None, Gamut::Bt2020).unwrap();
+            assert_eq!(chunk(&bt2020, *b"cICP"), Some(vec![9, 1, 0, 1]));
+
+            let srgb = encode_png_with_gamut(&img, None, Gamut::Srgb).unwrap();
+            assert_eq!(chunk(&srgb, *b"sRGB"), Some(vec![0]));
+            assert_eq!(
+                chunk(&srgb, *b"gAMA"),
+                Some(45455u32.to_be_bytes().to_vec())
+            );
+            assert_eq!(chunk(&srgb, *b"cICP"), None);
+
+            // A kept ICC profile already describes sRGB pixels.
+            let tagged = encode_png_with_gamut(&img, Some(&[0u8; 132]), Gamut::Srgb).unwrap();
+            assert_eq!(chunk(&tagged, *b"sRGB"), None);
+
+            let plain = encode_png(&img, None).unwrap();
+            assert_eq!(chunk(&plain, *b"sRGB"), None);
+            assert_eq!(chunk(&plain, *b"cICP"), None);
+        }
+
         #[test]
         fn test_encode_webp_produces_valid_webp() {
             let img = create_test_image(100, 100);
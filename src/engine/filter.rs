@@ -0,0 +1,401 @@
+// src/engine/filter.rs
+//
+// Neighbourhood filters: `Operation::Sharpen` (unsharp mask, also run by
+// `resize` with `sharpen: true` after a downscale), `Operation::Blur` and
+// `Operation::Pixelate`.
+//
+// The Gaussian is separable: a horizontal pass over each row into an `f32`
+// buffer, then a vertical pass that accumulates whole rows, both parallel
+// over rows. Edges repeat the nearest pixel. Blur and pixelate can be limited
+// to regions; a region still reads its neighbours outside the rectangle, so
+// only the pixels inside change. Both average alpha-premultiplied colour so
+// transparent pixels do not bleed their hidden colour. Filters keep the
+// layout and bit depth of 8/16-bit images (other layouts are normalized to
+// 8-bit first).
+
+use crate::engine::tone::{map_samples, Layout, Sample};
+use crate::ops::Sharpen;
+use crate::CropRegion;
+use image::DynamicImage;
+use rayon::prelude::*;
+
+/// Unsharp mask on the colour channels; alpha is kept.
+pub(crate) fn sharpen(img: DynamicImage, mask: Sharpen) -> DynamicImage {
+    if mask.amount == 0.0 {
+        return img;
+    }
+    let kernel = gaussian_kernel(mask.sigma);
+    map_samples(
+        img,
+        |samples, layout| unsharp(samples, layout, &kernel, mask),
+        |samples, layout| unsharp(samples, layout, &kernel, mask),
+    )
+}
+
+/// Gaussian blur of every channel inside `regions` (the whole image when
+/// empty).
+pub(crate) fn blur(img: DynamicImage, sigma: f64, regions: &[CropRegion]) -> DynamicImage {
+    let kernel = gaussian_kernel(sigma);
+    let height = img.height() as usize;
+    map_samples(
+        img,
+        |samples, layout| {
+            for window in windows(regions, layout.width, height) {
+                blur_window(samples, layout, window, &kernel);
+            }
+        },
+        |samples, layout| {
+            for window in windows(regions, layout.width, height) {
+                blur_window(samples, layout, window, &kernel);
+            }
+        },
+    )
+}
+
+/// Replace each `block_size` square (aligned to the region origin, clipped
+/// at its far edges) inside `regions` with its mean colour.
+pub(crate) fn pixelate(img: DynamicImage, block_size: u32, regions: &[CropRegion]) -> DynamicImage {
+    if block_size <= 1 {
+        return img;
+    }
+    let block = block_size as usize;
+    let height = img.height() as usize;
+    map_samples(
+        img,
+        |samples, layout| {
+            for window in windows(regions, layout.width, height) {
+                pixelate_window(samples, layout, window, block);
+            }
+        },
+        |samples, layout| {
+            for window in windows(regions, layout.width, height) {
+                pixelate_window(samples, layout, window, block);
+            }
+        },
+    )
+}
+
+/// Pixel rectangle a region filter writes to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Window {
+    left: usize,
+    top: usize,
+    width: usize,
+    height: usize,
+}
+
+impl Window {
+    fn full(width: usize, height: usize) -> Self {
+        Self {
+            left: 0,
+            top: 0,
+            width,
+            height,
+        }
+    }
+}
+
+/// `regions` clipped to the image, dropping those entirely outside it; the
+/// whole image when `regions` is empty.
+fn windows(regions: &[CropRegion], width: usize, height: usize) -> Vec<Window> {
+    if regions.is_empty() {
+        return vec![Window::full(width, height)];
+    }
+    regions
+        .iter()
+        .filter_map(|region| {
+            let left = region.left as usize;
+            let top = region.top as usize;
+            let right = (left + region.width as usize).min(width);
+            let bottom = (top + region.height as usize).min(height);
+            (left < right && top < bottom).then_some(Window {
+                left,
+                top,
+                width: right - left,
+                height: bottom - top,
+            })
+        })
+        .collect()
+}
+
+/// Channel `plane` of `pixel`, premultiplied by alpha when the layout has it.
+fn premultiplied<T: Sample>(pixel: &[T], plane: usize, layout: Layout) -> f32 {
+    if plane < layout.color && layout.channels > layout.color {
+        pixel[plane].raw() * pixel[layout.color].raw() / T::SCALE
+    } else {
+        pixel[plane].raw()
+    }
+}
+
+/// Write premultiplied `values` back into `pixel`.
+fn store_premultiplied<T: Sample>(pixel: &mut [T], values: &[f32], layout: Layout) {
+    let alpha = if layout.channels > layout.color {
+        values[layout.color]
+    } else {
+        T::SCALE
+    };
+    for (plane, (sample, &value)) in pixel.iter_mut().zip(values).enumerate() {
+        let value = if plane < layout.color && alpha < T::SCALE {
+            if alpha > 0.0 {
+                value * T::SCALE / alpha
+            } else {
+                0.0
+            }
+        } else {
+            value
+        };
+        *sample = T::from_raw(value.round().clamp(0.0, T::SCALE));
+    }
+}
+
+fn unsharp<T: Sample>(samples: &mut [T], layout: Layout, kernel: &[f32], mask: Sharpen) {
+    let row_len = layout.width * layout.channels;
+    if row_len == 0 {
+        return;
+    }
+    let planes = layout.color;
+    let window = Window::full(layout.width, samples.len() / row_len);
+    let blurred = gaussian(samples, layout, window, planes, kernel, |pixel, plane| {
+        pixel[plane].raw()
+    });
+    let amount = mask.amount as f32;
+    let threshold = mask.threshold as f32 * (T::SCALE / 255.0);
+    samples
+        .par_chunks_mut(row_len)
+        .zip(blurred.par_chunks(layout.width * planes))
+        .for_each(|(row, blurred)| {
+            for (pixel, blurred) in row
+                .chunks_exact_mut(layout.channels)
+                .zip(blurred.chunks_exact(planes))
+            {
+                for (sample, &blur) in pixel.iter_mut().zip(blurred) {
+                    let detail = sample.raw() - blur;
+                    if detail.abs() >= threshold {
+                        let value = sample.raw() + amount * detail;
+                        *sample = T::from_raw(value.round().clamp(0.0, T::SCALE));
+                    }
+                }
+            }
+        });
+}
+
+fn blur_window<T: Sample>(samples: &mut [T], layout: Layout, window: Window, kernel: &[f32]) {
+    let planes = layout.channels;
+    let blurred = gaussian(samples, layout, window, planes, kernel, |pixel, plane| {
+        premultiplied(pixel, plane, layout)
+    });
+    let row_len = layout.width * layout.channels;
+    samples[window.top * row_len..]
+        .par_chunks_mut(row_len)
+        .zip(blurred.par_chunks(window.width * planes))
+        .for_each(|(row, blurred)| {
+            let row = &mut row[window.left * planes..][..window.width * planes];
+            for (pixel, values) in row
+                .chunks_exact_mut(planes)
+                .zip(blurred.chunks_exact(planes))
+            {
+                store_premultiplied(pixel, values, layout);
+            }
+        });
+}
+
+fn pixelate_window<T: Sample>(samples: &mut [T], layout: Layout, window: Window, block: usize) {
+    let channels = layout.channels;
+    let row_len = layout.width * channels;
+    let rows = &mut samples[window.top * row_len..][..window.height * row_len];
+    rows.par_chunks_mut(block * row_len).for_each(|band| {
+        let mut sums = vec![0.0f32; channels];
+        for start in (window.left..window.left + window.width).step_by(block) {
+            let end = (start + block).min(window.left + window.width);
+            sums.fill(0.0);
+            for row in band.chunks_exact(row_len) {
+                for pixel in row[start * channels..end * channels].chunks_exact(channels) {
+                    for (plane, sum) in sums.iter_mut().enumerate() {
+                        *sum += premultiplied(pixel, plane, layout);
+                    }
+                }
+            }
+            let count = ((end - start) * band.len() / row_len) as f32;
+            sums.iter_mut().for_each(|sum| *sum /= count);
+            for row in band.chunks_exact_mut(row_len) {
+                for pixel in row[start * channels..end * channels].chunks_exact_mut(channels) {
+                    store_premultiplied(pixel, &sums, layout);
+                }
+            }
+        }
+    });
+}
+
+/// Normalized Gaussian weights out to three standard deviations.
+fn gaussian_kernel(sigma: f64) -> Vec<f32> {
+    let radius = (3.0 * sigma).ceil().max(1.0) as i64;
+    let weights: Vec<f64> = (-radius..=radius)
+        .map(|x| (-((x * x) as f64) / (2.0 * sigma * sigma)).exp())
+        .collect();
+    let total: f64 = weights.iter().sum();
+    weights.into_iter().map(|w| (w / total) as f32).collect()
+}
+
+/// Separable Gaussian of `planes` values per pixel (as given by `read`) over
+/// `window`, which reads up to a kernel radius beyond its edges. Returns
+/// `planes` values per window pixel, row by row.
+fn gaussian<T: Sample>(
+    samples: &[T],
+    layout: Layout,
+    window: Window,
+    planes: usize,
+    kernel: &[f32],
+    read: impl Fn(&[T], usize) -> f32 + Sync,
+) -> Vec<f32> {
+    let width = layout.width;
+    let row_len = width * layout.channels;
+    if row_len == 0 || window.width == 0 || window.height == 0 {
+        return Vec::new();
+    }
+    let height = samples.len() / row_len;
+    let out_len = window.width * planes;
+    let radius = kernel.len() / 2;
+    let clamp = |position: usize, len: usize| position.saturating_sub(radius).min(len - 1);
+
+    // Rows the vertical pass reads: the window plus a radius on each side.
+    let first = window.top.saturating_sub(radius);
+    let last = (window.top + window.height + radius).min(height);
+    let mut horizontal = vec![0.0f32; out_len * (last - first)];
+    horizontal
+        .par_chunks_mut(out_len)
+        .zip(samples[first * row_len..last * row_len].par_chunks(row_len))
+        .for_each(|(out, row)| {
+            for x in 0..window.width {
+                for plane in 0..planes {
+                    out[x * planes + plane] = kernel
+                        .iter()
+                        .enumerate()
+                        .map(|(k, weight)| {
+                            let source = clamp(window.left + x + k, width) * layout.channels;
+                            weight * read(&row[source..][..layout.channels], plane)
+                        })
+                        .sum();
+                }
+            }
+        });
+
+    let mut vertical = vec![0.0f32; out_len * window.height];
+    vertical
+        .par_chunks_mut(out_len)
+        .enumerate()
+        .for_each(|(y, out)| {
+            for (k, weight) in kernel.iter().enumerate() {
+                let source = clamp(window.top + y + k, height) - first;
+                let row = &horizontal[source * out_len..][..out_len];
+                for (sum, value) in out.iter_mut().zip(row) {
+                    *sum += weight * value;
+                }
+            }
+        });
+    vertical
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Luma, Rgba, RgbaImage};
+
+    #[test]
+    fn kernel_is_normalized_and_symmetric() {
+        let kernel = gaussian_kernel(1.5);
+        assert_eq!(kernel.len(), 11);
+        assert!((kernel.iter().sum::<f32>() - 1.0).abs() < 1e-5);
+        assert_eq!(kernel[0], kernel[10]);
+        assert!(kernel[5] > kernel[4]);
+    }
+
+    #[test]
+    fn sharpen_raises_edge_contrast_and_keeps_flat_areas() {
+        let img = DynamicImage::ImageLuma16(image::ImageBuffer::from_fn(16, 4, |x, _| {
+            Luma([if x < 8 { 20000u16 } else { 40000 }])
+        }));
+        let out = sharpen(img, Sharpen::default());
+        let DynamicImage::ImageLuma16(gray) = out else {
+            panic!("layout must be kept");
+        };
+        assert_eq!(gray.get_pixel(0, 0)[0], 20000);
+        assert!(gray.get_pixel(7, 2)[0] < 20000);
+        assert!(gray.get_pixel(8, 2)[0] > 40000);
+        assert_eq!(gray.get_pixel(15, 3)[0], 40000);
+    }
+
+    #[test]
+    fn sharpen_threshold_skips_small_detail_and_alpha() {
+        let img = DynamicImage::ImageRgba8(RgbaImage::from_fn(8, 8, |x, y| {
+            let v = 100 + ((x + y) % 2) as u8 * 3;
+            Rgba([v, v, v, 10 + x as u8])
+        }));
+        let mask = Sharpen {
+            threshold: 5,
+            ..Sharpen::default()
+        };
+        assert_eq!(sharpen(img.clone(), mask), img);
+        let out = sharpen(img, Sharpen::default()).to_rgba8();
+        assert_eq!(out.get_pixel(3, 0)[3], 13);
+        assert!(out.get_pixel(3, 0)[0] > 103);
+    }
+
+    #[test]
+    fn blur_regions_only_touch_pixels_inside() {
+        let img = DynamicImage::ImageRgb8(image::RgbImage::from_fn(12, 12, |x, _| {
+            let v = if x % 2 == 0 { 0 } else { 200 };
+            image::Rgb([v, v, v])
+        }));
+        let region = CropRegion {
+            left: 2,
+            top: 3,
+            width: 4,
+            height: 40,
+        };
+        let out = blur(img.clone(), 2.0, &[region]).to_rgb8();
+        let src = img.to_rgb8();
+        assert_eq!(out.get_pixel(1, 5), src.get_pixel(1, 5));
+        assert_eq!(out.get_pixel(3, 2), src.get_pixel(3, 2));
+        assert_eq!(out.get_pixel(6, 5), src.get_pixel(6, 5));
+        let inside = out.get_pixel(3, 11)[0];
+        assert!((80..=120).contains(&inside), "got {inside}");
+    }
+
+    #[test]
+    fn blur_ignores_colour_of_transparent_pixels() {
+        let img = DynamicImage::ImageRgba16(image::ImageBuffer::from_fn(8, 8, |x, _| {
+            if x < 4 {
+                Rgba([65535u16, 0, 0, 0])
+            } else {
+                Rgba([0, 0, 65535, 65535])
+            }
+        }));
+        let out = blur(img, 1.5, &[]).to_rgba16();
+        let edge = out.get_pixel(3, 4);
+        assert_eq!(edge[0], 0);
+        assert_eq!(edge[2], 65535);
+        assert!(edge[3] > 0 && edge[3] < 65535);
+    }
+
+    #[test]
+    fn pixelate_fills_blocks_with_their_mean() {
+        let img = DynamicImage::ImageLuma8(image::GrayImage::from_fn(10, 10, |x, y| {
+            Luma([(x * 10 + y) as u8])
+        }));
+        let region = CropRegion {
+            left: 1,
+            top: 1,
+            width: 5,
+            height: 4,
+        };
+        let out = pixelate(img.clone(), 4, &[region]).to_luma8();
+        // Block x 1..5, y 1..5: mean 27.5.
+        assert_eq!(out.get_pixel(1, 1)[0], 28);
+        assert_eq!(out.get_pixel(4, 4)[0], out.get_pixel(1, 1)[0]);
+        // Block clipped to the region: x 5..6, y 1..5, mean 52.5.
+        assert_eq!(out.get_pixel(5, 2)[0], 53);
+        assert_eq!(out.get_pixel(0, 0)[0], 0);
+        assert_eq!(out.get_pixel(6, 2), img.to_luma8().get_pixel(6, 2));
+        assert_eq!(pixelate(img.clone(), 1, &[]), img);
+    }
+}
@@ -2,8 +2,10 @@
 //
 // Image Firewall configuration and enforcement helpers.
 
+use crate::engine::decoder::read_dimensions;
 use crate::engine::io::extract_icc_profile;
 use crate::error::LazyImageError;
+use crate::ops::Operation;
 use std::time::Instant;
 
 const STRICT_MAX_PIXELS: u64 = 40_000_000; // ~8K x 5K
@@ -184,6 +186,28 @@ impl FirewallConfig {
 
         Ok(())
     }
+
+    /// Apply the source limits (bytes, metadata, pixels from the header) to
+    /// every image embedded in the ops (`Operation::Composite` overlays and
+    /// `Operation::JoinChannel` masks) before the pipeline decodes it.
+    pub fn enforce_overlays(&self, ops: &[Operation]) -> Result<(), LazyImageError> {
+        if !self.enabled {
+            return Ok(());
+        }
+        let inputs = ops.iter().flat_map(|op| match op {
+            Operation::Composite { layers } => layers.iter().map(|layer| &layer.input).collect(),
+            Operation::JoinChannel { mask } => vec![mask],
+            _ => Vec::new(),
+        });
+        for input in inputs {
+            self.enforce_source_len(input.len())?;
+            self.scan_metadata(input)?;
+            if let Some((width, height)) = read_dimensions(input)? {
+                self.enforce_pixels(width, height)?;
+            }
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -252,4 +276,44 @@ mod tests {
         let fake_start = Instant::now() - std::time::Duration::from_millis(5);
         assert!(cfg.enforce_timeout(fake_start, "decode").is_err());
     }
+
+    #[test]
+    fn overlays_are_checked_like_sources() {
+        let overlay = |input: Vec<u8>| Operation::Composite {
+            layers: vec![crate::ops::Overlay {
+                input: std::sync::Arc::new(input),
+                placement: crate::ops::OverlayPlacement::Offset { left: 0, top: 0 },
+                blend: crate::ops::BlendMode::Over,
+                opacity: 1.0,
+                tile: false,
+            }],
+        };
+        let with_icc = [Operation::Negate, overlay(png_with_icc(256))];
+        assert!(FirewallConfig::strict()
+            .enforce_overlays(&with_icc)
+            .is_err());
+        assert!(FirewallConfig::lenient()
+            .enforce_overlays(&with_icc)
+            .is_ok());
+        assert!(FirewallConfig::disabled()
+            .enforce_overlays(&with_icc)
+            .is_ok());
+
+        let mut cfg = FirewallConfig::custom();
+        cfg.max_pixels = Some(3);
+        assert!(cfg.enforce_overlays(&with_icc).is_err());
+    }
+
+    #[test]
+    fn join_channel_masks_are_checked_like_sources() {
+        let join = [Operation::JoinChannel {
+            mask: std::sync::Arc::new(png_with_icc(256)),
+        }];
+        assert!(FirewallConfig::strict().enforce_overlays(&join).is_err());
+        assert!(FirewallConfig::lenient().enforce_overlays(&join).is_ok());
+
+        let mut cfg = FirewallConfig::custom();
+        cfg.max_bytes = Some(16);
+        assert!(cfg.enforce_overlays(&join).is_err());
+    }
 }
@@ -0,0 +1,319 @@
+// src/engine/grade.rs
+//
+// Colour grading: `Operation::Recomb` (colour matrix) and `Operation::Lut3d`
+// (Adobe `.cube` 3D LUT).
+//
+// Both work on encoded RGB(A) samples, so greyscale input is expanded to RGB
+// first (keeping 16-bit depth) and the result stays colour. They run in one
+// parallel pass over whole rows. The LUT is parsed once when the op is
+// queued; lookups interpolate trilinearly between the eight grid points
+// around each colour.
+
+use crate::engine::tone::{map_samples, Layout, Sample};
+use crate::error::LazyImageError;
+use crate::ops::CubeLut;
+use image::DynamicImage;
+use rayon::prelude::*;
+
+/// Largest accepted `LUT_3D_SIZE` (points per axis).
+pub(crate) const MAX_LUT_SIZE: u32 = 65;
+
+/// Parse an Adobe `.cube` 3D LUT. Keywords (`TITLE`, `LUT_3D_SIZE`,
+/// `DOMAIN_MIN`, `DOMAIN_MAX`, `LUT_3D_INPUT_RANGE`) must precede the data;
+/// `#` starts a comment. Every value must be finite and exactly `size`^3
+/// entries must follow.
+pub(crate) fn parse_cube(bytes: &[u8]) -> Result<CubeLut, LazyImageError> {
+    let text = std::str::from_utf8(bytes)
+        .map_err(|_| LazyImageError::invalid_lut("file is not UTF-8 text"))?;
+    let mut size = None;
+    let mut domain_min = [0.0f32; 3];
+    let mut domain_max = [1.0f32; 3];
+    let mut table: Vec<[f32; 3]> = Vec::new();
+
+    for (index, line) in text.lines().enumerate() {
+        let line_no = index + 1;
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let mut tokens = line.split_whitespace();
+        let keyword = tokens.next().unwrap_or("");
+        let invalid =
+            |reason: &str| LazyImageError::invalid_lut(format!("line {line_no}: {reason}"));
+        let is_data =
+            keyword.starts_with(|c: char| c.is_ascii_digit() || matches!(c, '-' | '+' | '.'));
+        if !is_data && !table.is_empty() {
+            return Err(invalid("keywords must precede the table"));
+        }
+        match keyword {
+            "TITLE" => {}
+            "LUT_1D_SIZE" => return Err(invalid("1D LUTs are not supported")),
+            "LUT_3D_SIZE" => {
+                let value: u32 = tokens
+                    .next()
+                    .and_then(|token| token.parse().ok())
+                    .ok_or_else(|| invalid("LUT_3D_SIZE needs a positive integer"))?;
+                if value > MAX_LUT_SIZE {
+                    return Err(LazyImageError::lut_size_exceeds_limit(value, MAX_LUT_SIZE));
+                }
+                if value < 2 {
+                    return Err(invalid("LUT_3D_SIZE must be at least 2"));
+                }
+                size = Some(value as usize);
+            }
+            "DOMAIN_MIN" => domain_min = parse_values(tokens).ok_or_else(|| invalid(THREE))?,
+            "DOMAIN_MAX" => domain_max = parse_values(tokens).ok_or_else(|| invalid(THREE))?,
+            "LUT_3D_INPUT_RANGE" => {
+                let [min, max] =
+                    parse_values(tokens).ok_or_else(|| invalid("expected two finite numbers"))?;
+                domain_min = [min; 3];
+                domain_max = [max; 3];
+            }
+            _ if is_data => {
+                let size = size.ok_or_else(|| invalid("LUT_3D_SIZE must precede the table"))?;
+                if table.len() == size * size * size {
+                    return Err(invalid(&format!("more than {size}^3 table entries")));
+                }
+                if table.capacity() == 0 {
+                    table.reserve_exact(size * size * size);
+                }
+                table.push(parse_values(line.split_whitespace()).ok_or_else(|| invalid(THREE))?);
+            }
+            _ => return Err(invalid(&format!("unknown keyword '{keyword}'"))),
+        }
+    }
+
+    let size = size.ok_or_else(|| LazyImageError::invalid_lut("missing LUT_3D_SIZE"))?;
+    if table.len() != size * size * size {
+        return Err(LazyImageError::invalid_lut(format!(
+            "expected {} table entries, found {}",
+            size * size * size,
+            table.len()
+        )));
+    }
+    if (0..3).any(|c| domain_max[c] <= domain_min[c]) {
+        return Err(LazyImageError::invalid_lut(
+            "DOMAIN_MAX must be greater than DOMAIN_MIN",
+        ));
+    }
+    Ok(CubeLut {
+        size,
+        domain_min,
+        domain_max,
+        table,
+    })
+}
+
+const THREE: &str = "expected three finite numbers";
+
+/// Exactly `N` finite numbers.
+fn parse_values<'a, const N: usize>(mut tokens: impl Iterator<Item = &'a str>) -> Option<[f32; N]> {
+    let mut values = [0.0f32; N];
+    for value in &mut values {
+        *value = tokens
+            .next()?
+            .parse::<f32>()
+            .ok()
+            .filter(|value| value.is_finite())?;
+    }
+    tokens.next().is_none().then_some(values)
+}
+
+/// Multiply every pixel by `matrix` (see `Operation::Recomb`).
+pub(crate) fn recomb(img: DynamicImage, matrix: &[[f64; 4]; 4]) -> DynamicImage {
+    let matrix = matrix.map(|row| row.map(|value| value as f32));
+    map_samples(
+        expand_gray(img),
+        |samples, layout| recomb_samples(samples, layout, &matrix),
+        |samples, layout| recomb_samples(samples, layout, &matrix),
+    )
+}
+
+fn recomb_samples<T: Sample>(samples: &mut [T], layout: Layout, matrix: &[[f32; 4]; 4]) {
+    let channels = layout.channels;
+    samples
+        .par_chunks_mut(layout.chunk_len())
+        .for_each(|chunk| {
+            for pixel in chunk.chunks_exact_mut(channels) {
+                let alpha = if channels == 4 {
+                    pixel[3].raw()
+                } else {
+                    T::SCALE
+                };
+                let input = [pixel[0].raw(), pixel[1].raw(), pixel[2].raw(), alpha];
+                for (sample, row) in pixel.iter_mut().zip(matrix) {
+                    let value: f32 = row.iter().zip(&input).map(|(m, v)| m * v).sum();
+                    *sample = T::from_raw(value.round().clamp(0.0, T::SCALE));
+                }
+            }
+        });
+}
+
+/// Map RGB through `lut` (see `Operation::Lut3d`).
+pub(crate) fn apply_lut(img: DynamicImage, lut: &CubeLut) -> DynamicImage {
+    map_samples(
+        expand_gray(img),
+        |samples, layout| lut_samples(samples, layout, lut),
+        |samples, layout| lut_samples(samples, layout, lut),
+    )
+}
+
+fn lut_samples<T: Sample>(samples: &mut [T], layout: Layout, lut: &CubeLut) {
+    samples
+        .par_chunks_mut(layout.chunk_len())
+        .for_each(|chunk| {
+            for pixel in chunk.chunks_exact_mut(layout.channels) {
+                let rgb = [pixel[0].to_unit(), pixel[1].to_unit(), pixel[2].to_unit()];
+                let mapped = lookup(lut, rgb);
+                for (sample, value) in pixel.iter_mut().zip(mapped) {
+                    *sample = T::from_unit(value);
+                }
+            }
+        });
+}
+
+/// Trilinear interpolation of `lut` at `rgb` (inputs outside the domain are
+/// clamped to its edges).
+fn lookup(lut: &CubeLut, rgb: [f32; 3]) -> [f32; 3] {
+    let last = lut.size - 1;
+    let mut base = [0usize; 3];
+    let mut next = [0usize; 3];
+    let mut frac = [0.0f32; 3];
+    for c in 0..3 {
+        let span = lut.domain_max[c] - lut.domain_min[c];
+        let position = ((rgb[c] - lut.domain_min[c]) / span).clamp(0.0, 1.0) * last as f32;
+        base[c] = (position as usize).min(last);
+        next[c] = (base[c] + 1).min(last);
+        frac[c] = position - base[c] as f32;
+    }
+    let at = |r: usize, g: usize, b: usize| lut.table[r + lut.size * (g + lut.size * b)];
+    let mix = |a: [f32; 3], b: [f32; 3], t: f32| std::array::from_fn(|i| a[i] + (b[i] - a[i]) * t);
+    let [r0, g0, b0] = base;
+    let [r1, g1, b1] = next;
+    let [fr, fg, fb] = frac;
+    let g0b0 = mix(at(r0, g0, b0), at(r1, g0, b0), fr);
+    let g1b0 = mix(at(r0, g1, b0), at(r1, g1, b0), fr);
+    let g0b1 = mix(at(r0, g0, b1), at(r1, g0, b1), fr);
+    let g1b1 = mix(at(r0, g1, b1), at(r1, g1, b1), fr);
+    mix(mix(g0b0, g1b0, fg), mix(g0b1, g1b1, fg), fb)
+}
+
+/// RGB(A) copy of greyscale layouts at the same bit depth; colour layouts
+/// are returned untouched.
+pub(crate) fn expand_gray(img: DynamicImage) -> DynamicImage {
+    match img {
+        DynamicImage::ImageLuma8(_) => DynamicImage::ImageRgb8(img.into_rgb8()),
+        DynamicImage::ImageLumaA8(_) => DynamicImage::ImageRgba8(img.into_rgba8()),
+        DynamicImage::ImageLuma16(_) => DynamicImage::ImageRgb16(img.into_rgb16()),
+        DynamicImage::ImageLumaA16(_) => DynamicImage::ImageRgba16(img.into_rgba16()),
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{LumaA, Rgb, RgbImage};
+
+    fn identity_cube(size: usize) -> String {
+        let mut text = format!("TITLE \"identity\"\n# comment\nLUT_3D_SIZE {size}\n");
+        let step = 1.0 / (size - 1) as f32;
+        for b in 0..size {
+            for g in 0..size {
+                for r in 0..size {
+                    text += &format!(
+                        "{} {} {}\n",
+                        r as f32 * step,
+                        g as f32 * step,
+                        b as f32 * step
+                    );
+                }
+            }
+        }
+        text
+    }
+
+    #[test]
+    fn parse_cube_reads_size_domain_and_table() {
+        let mut text = identity_cube(2);
+        text = text.replace("# comment\n", "DOMAIN_MIN 0 0 0\nDOMAIN_MAX 1 1 2\n");
+        let lut = parse_cube(text.as_bytes()).unwrap();
+        assert_eq!(lut.size, 2);
+        assert_eq!(lut.domain_max, [1.0, 1.0, 2.0]);
+        assert_eq!(lut.table[1], [1.0, 0.0, 0.0]);
+        assert_eq!(lut.table[7], [1.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn parse_cube_rejects_malformed_files() {
+        let code = |text: &str| parse_cube(text.as_bytes()).unwrap_err().code().as_str();
+        let short = identity_cube(2).replace("1 1 1\n", "");
+        assert_eq!(code(&short), "E220");
+        assert_eq!(code(&identity_cube(2).replace("0 0 0", "0 NaN 0")), "E220");
+        assert_eq!(code(&identity_cube(2).replace("1 0 0", "1 0")), "E220");
+        assert_eq!(
+            code(&format!("{}LUT_3D_SIZE 2\n", identity_cube(2))),
+            "E220"
+        );
+        assert_eq!(code("LUT_1D_SIZE 4\n0 0 0\n"), "E220");
+        assert_eq!(code("0 0 0\n"), "E220");
+        assert_eq!(code("LUT_3D_SIZE 256\n"), "E221");
+        assert_eq!(code(&format!("{}1 1 1\n", identity_cube(2))), "E220");
+    }
+
+    #[test]
+    fn identity_lut_and_matrix_keep_pixels() {
+        let img = DynamicImage::ImageRgb8(RgbImage::from_fn(9, 7, |x, y| {
+            Rgb([(x * 28) as u8, (y * 36) as u8, ((x + y) * 15) as u8])
+        }));
+        let lut = parse_cube(identity_cube(5).as_bytes()).unwrap();
+        assert_eq!(apply_lut(img.clone(), &lut), img);
+        let mut identity = [[0.0; 4]; 4];
+        (0..4).for_each(|i| identity[i][i] = 1.0);
+        assert_eq!(recomb(img.clone(), &identity), img);
+    }
+
+    #[test]
+    fn lut_interpolates_between_grid_points() {
+        // Inverts red only; green and blue pass through.
+        let text = identity_cube(2)
+            .lines()
+            .map(
+                |line| match line.split_whitespace().collect::<Vec<_>>()[..] {
+                    [r, g, b] if r.parse::<f32>().is_ok() => {
+                        format!("{} {g} {b}", 1.0 - r.parse::<f32>().unwrap())
+                    }
+                    _ => line.to_string(),
+                },
+            )
+            .collect::<Vec<_>>()
+            .join("\n");
+        let lut = parse_cube(text.as_bytes()).unwrap();
+        let mapped = lookup(&lut, [0.25, 0.5, 0.75]);
+        assert!((mapped[0] - 0.75).abs() < 1e-6);
+        assert!((mapped[1] - 0.5).abs() < 1e-6);
+        assert!((mapped[2] - 0.75).abs() < 1e-6);
+    }
+
+    #[test]
+    fn recomb_expands_gray_and_keeps_alpha() {
+        let img = DynamicImage::ImageLumaA16(image::ImageBuffer::from_pixel(
+            2,
+            2,
+            LumaA([40000u16, 1234]),
+        ));
+        let sepia = [
+            [0.393, 0.769, 0.189, 0.0],
+            [0.349, 0.686, 0.168, 0.0],
+            [0.272, 0.534, 0.131, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ];
+        let DynamicImage::ImageRgba16(out) = recomb(img, &sepia) else {
+            panic!("greyscale must expand to RGBA16");
+        };
+        let pixel = out.get_pixel(1, 1);
+        assert_eq!(pixel[0], 54040);
+        assert_eq!(pixel[2], 37480);
+        assert_eq!(pixel[3], 1234);
+    }
+}
@@ -0,0 +1,617 @@
+// src/engine/icc.rs
+//
+// ICC colour management: `Operation::IccTransform`.
+//
+// Pixels are converted from the source profile (the input's embedded ICC
+// profile, or sRGB when untagged) to the target with moxcms, a pure-Rust
+// CMS, using relative colorimetric intent. 8- and 16-bit layouts keep their
+// depth and alpha; greyscale becomes RGB. Source profiles that cannot be
+// parsed, or that do not describe the decoded pixels (a CMYK profile on RGB
+// output of the decoder), are ignored as if the image were untagged.
+//
+// CMYK JPEGs reach the pipeline as ink samples and always start with a
+// conversion, through their CMYK profile or, without one, a naive device
+// conversion to sRGB; the output is opaque RGB8.
+//
+// Tasks call `bind_profiles` before the pipeline runs, so each conversion
+// knows its source, and non-sRGB input is converted to sRGB automatically
+// when its profile is about to be stripped. `gamut` classifies profiles as
+// sRGB, Display P3 or BT.2020 so encoders can signal them with CICP.
+
+use std::sync::{Arc, OnceLock};
+
+use crate::engine::grade::expand_gray;
+use crate::engine::pipeline::Gamut;
+use crate::error::LazyImageError;
+use crate::ops::{IccProfile, Operation};
+use image::{DynamicImage, GenericImageView, ImageBuffer, Pixel, RgbaImage};
+use moxcms::{
+    CmsError, ColorProfile, DataColorSpace, Layout, ProfileText, RenderingIntent,
+    TransformExecutor, TransformOptions,
+};
+use rayon::prelude::*;
+
+/// Rows handed to one transform call; keeps per-call overhead small while
+/// leaving enough chunks for rayon to balance.
+const ROWS_PER_CHUNK: usize = 16;
+
+/// Fill in the source of every queued conversion (the embedded profile,
+/// then each previous target). When nothing converts explicitly and the
+/// embedded profile will be stripped, prepend a conversion to sRGB unless
+/// the profile already is sRGB, so untagged output keeps its colours.
+///
+/// CMYK input must be converted before anything else runs: an explicit
+/// conversion preceded only by auto-orientation is moved to the front,
+/// otherwise a conversion to sRGB is prepended.
+pub(crate) fn bind_profiles(
+    ops: &mut Vec<Operation>,
+    embedded: Option<&Arc<Vec<u8>>>,
+    keep_icc: bool,
+    cmyk: bool,
+) {
+    let embedded = embedded.map(|bytes| IccProfile::Bytes(Arc::clone(bytes)));
+    if cmyk {
+        let first = ops
+            .iter()
+            .position(|op| !matches!(op, Operation::AutoOrient { .. }));
+        match first {
+            Some(index) if matches!(ops[index], Operation::IccTransform { .. }) => {
+                let conversion = ops.remove(index);
+                ops.insert(0, conversion);
+            }
+            _ => ops.insert(
+                0,
+                Operation::IccTransform {
+                    source: None,
+                    target: IccProfile::Srgb,
+                },
+            ),
+        }
+    }
+    let mut current = embedded.clone();
+    let mut converted = false;
+    for op in ops.iter_mut() {
+        if let Operation::IccTransform { source, target } = op {
+            *source = current.take();
+            current = Some(target.clone());
+            converted = true;
+        }
+    }
+    if converted || keep_icc {
+        return;
+    }
+    let needs_conversion = |profile: &IccProfile| load(profile).is_some_and(|p| !is_srgb(&p));
+    if let Some(profile) = embedded.filter(needs_conversion) {
+        ops.insert(
+            0,
+            Operation::IccTransform {
+                source: Some(profile),
+                target: IccProfile::Srgb,
+            },
+        );
+    }
+}
+
+/// Profile to embed in the output. After a conversion that is its target;
+/// sRGB is only written when ICC is kept, since untagged output reads as
+/// sRGB anyway, while other targets are always written because the pixels
+/// are meaningless without them. Otherwise the embedded profile, if kept.
+pub(crate) fn output_profile(
+    ops: &[Operation],
+    keep_icc: bool,
+    embedded: Option<&Arc<Vec<u8>>>,
+) -> Option<Arc<Vec<u8>>> {
+    let last_target = ops.iter().rev().find_map(|op| match op {
+        Operation::IccTransform { target, .. } => Some(target),
+        _ => None,
+    });
+    match last_target {
+        Some(IccProfile::Srgb) if !keep_icc => None,
+        Some(target) => encoded(target),
+        None if keep_icc => embedded.cloned(),
+        None => None,
+    }
+}
+
+/// Gamut described by `profile`. Untagged pixels and profiles `transform`
+/// ignores read as sRGB; RGB profiles are compared against the built-in ones.
+pub(crate) fn gamut(profile: Option<&IccProfile>) -> Gamut {
+    let profile = match profile {
+        None | Some(IccProfile::Srgb) => return Gamut::Srgb,
+        Some(IccProfile::DisplayP3) => return Gamut::DisplayP3,
+        Some(IccProfile::Bt2020) => return Gamut::Bt2020,
+        Some(bytes) => load(bytes),
+    };
+    match profile {
+        Some(profile) if profile.color_space == DataColorSpace::Rgb => {
+            let known = [
+                (Gamut::Srgb, ColorProfile::new_srgb()),
+                (Gamut::DisplayP3, ColorProfile::new_display_p3()),
+                (Gamut::Bt2020, ColorProfile::new_bt2020()),
+            ];
+            known
+                .into_iter()
+                .find(|(_, reference)| same_as(&profile, reference))
+                .map_or(Gamut::Unknown, |(gamut, _)| gamut)
+        }
+        Some(profile) if profile.color_space == DataColorSpace::Gray && !is_srgb(&profile) => {
+            Gamut::Unknown
+        }
+        _ => Gamut::Srgb,
+    }
+}
+
+/// `gamut` of the input's embedded profile.
+pub(crate) fn embedded_gamut(embedded: Option<&Arc<Vec<u8>>>) -> Gamut {
+    gamut(
+        embedded
+            .map(|bytes| IccProfile::Bytes(Arc::clone(bytes)))
+            .as_ref(),
+    )
+}
+
+/// Description (`desc`) of a profile, for `metadata()`. Localized
+/// descriptions prefer English.
+pub(crate) fn description(bytes: &[u8]) -> Option<String> {
+    let text = match ColorProfile::new_from_slice(bytes).ok()?.description? {
+        ProfileText::PlainString(text) => text,
+        ProfileText::Description(desc) => desc.ascii_string,
+        ProfileText::Localizable(strings) => strings
+            .iter()
+            .find(|s| s.language == "en")
+            .or(strings.first())?
+            .value
+            .clone(),
+    };
+    let text = text.trim_end_matches('\0').trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// Check a caller-supplied target profile: it must parse, describe RGB and
+/// be reachable from sRGB.
+pub(crate) fn check_target(bytes: &[u8]) -> Result<(), LazyImageError> {
+    let profile = ColorProfile::new_from_slice(bytes).map_err(|e| {
+        LazyImageError::unsupported_color_space(format!("ICC profile could not be parsed ({e})"))
+    })?;
+    if profile.color_space != DataColorSpace::Rgb {
+        return Err(LazyImageError::unsupported_color_space(format!(
+            "{:?} ICC profile (an RGB output profile is required)",
+            profile.color_space
+        )));
+    }
+    ColorProfile::new_srgb()
+        .create_transform_8bit(Layout::Rgb, &profile, Layout::Rgb, options())
+        .map_err(transform_failed)?;
+    Ok(())
+}
+
+/// Apply `Operation::IccTransform`; `cmyk` marks ink samples from a CMYK
+/// JPEG (see `cmyk_to_rgb`).
+pub(crate) fn transform(
+    img: DynamicImage,
+    source: Option<&IccProfile>,
+    target: &IccProfile,
+    cmyk: bool,
+) -> Result<DynamicImage, LazyImageError> {
+    if cmyk {
+        return cmyk_to_rgb(&img.into_rgba8(), source, target);
+    }
+    let target_profile = load_target(target)?;
+    let gray = matches!(
+        img,
+        DynamicImage::ImageLuma8(_)
+            | DynamicImage::ImageLumaA8(_)
+            | DynamicImage::ImageLuma16(_)
+            | DynamicImage::ImageLumaA16(_)
+    );
+    let source_profile = source.and_then(load).filter(|profile| {
+        profile.color_space == DataColorSpace::Rgb
+            || (gray && profile.color_space == DataColorSpace::Gray)
+    });
+    let source_profile = match source_profile {
+        Some(profile) => profile,
+        None if *target == IccProfile::Srgb => return Ok(expand_gray(img)),
+        None => ColorProfile::new_srgb(),
+    };
+    let img = if source_profile.color_space == DataColorSpace::Gray {
+        img
+    } else {
+        expand_gray(img)
+    };
+    convert(img, &source_profile, &target_profile).map_err(transform_failed)
+}
+
+/// Convert CMYK ink samples (C, M, Y, K in an RGBA8 buffer, 0 = no ink) to
+/// RGB8 in `target`, through `source` when it is a CMYK profile and through
+/// a naive device conversion to sRGB otherwise.
+pub(crate) fn cmyk_to_rgb(
+    ink: &RgbaImage,
+    source: Option<&IccProfile>,
+    target: &IccProfile,
+) -> Result<DynamicImage, LazyImageError> {
+    let target_profile = load_target(target)?;
+    let (width, height) = ink.dimensions();
+    let source_profile = source
+        .and_then(load)
+        .filter(|profile| profile.color_space == DataColorSpace::Cmyk);
+    if let Some(profile) = source_profile {
+        let layouts = (Layout::Rgba, Layout::Rgb);
+        let rgb = profile
+            .create_transform_8bit(layouts.0, &target_profile, layouts.1, options())
+            .and_then(|executor| run(executor.as_ref(), ink.as_raw(), width as usize, layouts))
+            .map_err(transform_failed)?;
+        return Ok(DynamicImage::ImageRgb8(image_buffer(width, height, rgb)));
+    }
+    let rgb = ink
+        .as_raw()
+        .par_chunks_exact(4)
+        .flat_map_iter(|ink| {
+            let white = 255 - ink[3] as u32;
+            ink[..3]
+                .iter()
+                .map(move |&c| ((255 - c as u32) * white / 255) as u8)
+        })
+        .collect();
+    let img = DynamicImage::ImageRgb8(image_buffer(width, height, rgb));
+    if *target == IccProfile::Srgb {
+        return Ok(img);
+    }
+    convert(img, &ColorProfile::new_srgb(), &target_profile).map_err(transform_failed)
+}
+
+fn convert(
+    img: DynamicImage,
+    source: &ColorProfile,
+    target: &ColorProfile,
+) -> Result<DynamicImage, CmsError> {
+    let (width, height) = img.dimensions();
+    let opts = options();
+    let run8 = |layouts: (Layout, Layout), src: &[u8]| {
+        let executor = source.create_transform_8bit(layouts.0, target, layouts.1, opts)?;
+        run(executor.as_ref(), src, width as usize, layouts)
+    };
+    let run16 = |layouts: (Layout, Layout), src: &[u16]| {
+        let executor = source.create_transform_16bit(layouts.0, target, layouts.1, opts)?;
+        run(executor.as_ref(), src, width as usize, layouts)
+    };
+    Ok(match &img {
+        DynamicImage::ImageLuma8(buf) => DynamicImage::ImageRgb8(image_buffer(
+            width,
+            height,
+            run8((Layout::Gray, Layout::Rgb), buf.as_raw())?,
+        )),
+        DynamicImage::ImageLumaA8(buf) => DynamicImage::ImageRgba8(image_buffer(
+            width,
+            height,
+            run8((Layout::GrayAlpha, Layout::Rgba), buf.as_raw())?,
+        )),
+        DynamicImage::ImageRgb8(buf) => DynamicImage::ImageRgb8(image_buffer(
+            width,
+            height,
+            run8((Layout::Rgb, Layout::Rgb), buf.as_raw())?,
+        )),
+        DynamicImage::ImageRgba8(buf) => DynamicImage::ImageRgba8(image_buffer(
+            width,
+            height,
+            run8((Layout::Rgba, Layout::Rgba), buf.as_raw())?,
+        )),
+        DynamicImage::ImageLuma16(buf) => DynamicImage::ImageRgb16(image_buffer(
+            width,
+            height,
+            run16((Layout::Gray, Layout::Rgb), buf.as_raw())?,
+        )),
+        DynamicImage::ImageLumaA16(buf) => DynamicImage::ImageRgba16(image_buffer(
+            width,
+            height,
+            run16((Layout::GrayAlpha, Layout::Rgba), buf.as_raw())?,
+        )),
+        DynamicImage::ImageRgb16(buf) => DynamicImage::ImageRgb16(image_buffer(
+            width,
+            height,
+            run16((Layout::Rgb, Layout::Rgb), buf.as_raw())?,
+        )),
+        DynamicImage::ImageRgba16(buf) => DynamicImage::ImageRgba16(image_buffer(
+            width,
+            height,
+            run16((Layout::Rgba, Layout::Rgba), buf.as_raw())?,
+        )),
+        // Float layouts never come out of the decoders; convert at 16 bits.
+        other => return convert(DynamicImage::ImageRgba16(other.to_rgba16()), source, target),
+    })
+}
+
+/// Transform `src` in parallel chunks of rows.
+fn run<T: Copy + Default + Send + Sync>(
+    executor: &(dyn TransformExecutor<T> + Send + Sync),
+    src: &[T],
+    width: usize,
+    (src_layout, dst_layout): (Layout, Layout),
+) -> Result<Vec<T>, CmsError> {
+    let src_row = width * src_layout.channels();
+    let dst_row = width * dst_layout.channels();
+    let mut dst = vec![T::default(); src.len() / src_layout.channels() * dst_layout.channels()];
+    if src_row == 0 {
+        return Ok(dst);
+    }
+    src.par_chunks(src_row * ROWS_PER_CHUNK)
+        .zip(dst.par_chunks_mut(dst_row * ROWS_PER_CHUNK))
+        .try_for_each(|(src, dst)| executor.transform(src, dst))?;
+    Ok(dst)
+}
+
+fn image_buffer<P: Pixel>(
+    width: u32,
+    height: u32,
+    data: Vec<P::Subpixel>,
+) -> ImageBuffer<P, Vec<P::Subpixel>> {
+    ImageBuffer::from_raw(width, height, data).expect("transform keeps the pixel count")
+}
+
+fn options() -> TransformOptions {
+    TransformOptions {
+        rendering_intent: RenderingIntent::RelativeColorimetric,
+        ..TransformOptions::default()
+    }
+}
+
+fn transform_failed(err: CmsError) -> LazyImageError {
+    LazyImageError::unsupported_color_space(format!("ICC transform failed ({err})"))
+}
+
+fn load_target(target: &IccProfile) -> Result<ColorProfile, LazyImageError> {
+    load(target).ok_or_else(|| {
+        LazyImageError::unsupported_color_space("target ICC profile could not be parsed")
+    })
+}
+
+fn load(profile: &IccProfile) -> Option<ColorProfile> {
+    match profile {
+        IccProfile::Srgb => Some(ColorProfile::new_srgb()),
+        IccProfile::DisplayP3 => Some(ColorProfile::new_display_p3()),
+        IccProfile::Bt2020 => Some(ColorProfile::new_bt2020()),
+        IccProfile::Bytes(bytes) => ColorProfile::new_from_slice(bytes).ok(),
+    }
+}
+
+/// ICC bytes for `profile`; the built-in profiles are encoded once.
+fn encoded(profile: &IccProfile) -> Option<Arc<Vec<u8>>> {
+    static SRGB: OnceLock<Option<Arc<Vec<u8>>>> = OnceLock::new();
+    static DISPLAY_P3: OnceLock<Option<Arc<Vec<u8>>>> = OnceLock::new();
+    static BT2020: OnceLock<Option<Arc<Vec<u8>>>> = OnceLock::new();
+    let build = |profile: ColorProfile| profile.encode().ok().map(Arc::new);
+    match profile {
+        IccProfile::Srgb => SRGB.get_or_init(|| build(ColorProfile::new_srgb())).clone(),
+        IccProfile::DisplayP3 => DISPLAY_P3
+            .get_or_init(|| build(ColorProfile::new_display_p3()))
+            .clone(),
+        IccProfile::Bt2020 => BT2020
+            .get_or_init(|| build(ColorProfile::new_bt2020()))
+            .clone(),
+        IccProfile::Bytes(bytes) => Some(Arc::clone(bytes)),
+    }
+}
+
+/// Whether `profile` maps primaries and a grey ramp onto sRGB within
+/// rounding, so converting would only cost time.
+fn is_srgb(profile: &ColorProfile) -> bool {
+    same_as(profile, &ColorProfile::new_srgb())
+}
+
+/// Whether `profile` maps primaries and a grey ramp onto `reference` (an RGB
+/// profile) within rounding.
+fn same_as(profile: &ColorProfile, reference: &ColorProfile) -> bool {
+    let gray = profile.color_space == DataColorSpace::Gray;
+    let layout = if gray { Layout::Gray } else { Layout::Rgb };
+    let Ok(executor) = profile.create_transform_8bit(layout, reference, Layout::Rgb, options())
+    else {
+        return false;
+    };
+    let probe: Vec<[u8; 3]> = (0..=255u8)
+        .step_by(15)
+        .flat_map(|v| {
+            if gray {
+                vec![[v, v, v]]
+            } else {
+                vec![[v, 0, 0], [0, v, 0], [0, 0, v], [v, v, v]]
+            }
+        })
+        .collect();
+    let src: Vec<u8> = probe
+        .iter()
+        .flat_map(|pixel| &pixel[..layout.channels()])
+        .copied()
+        .collect();
+    let mut dst = vec![0u8; probe.len() * 3];
+    executor.transform(&src, &mut dst).is_ok()
+        && dst
+            .chunks_exact(3)
+            .zip(&probe)
+            .all(|(out, want)| out.iter().zip(want).all(|(a, b)| a.abs_diff(*b) <= 2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GrayImage, Luma, Rgb, RgbImage, Rgba, RgbaImage};
+
+    fn profile_bytes(profile: ColorProfile) -> Arc<Vec<u8>> {
+        Arc::new(profile.encode().unwrap())
+    }
+
+    #[test]
+    fn wide_gamut_red_is_clipped_into_srgb() {
+        let img = DynamicImage::ImageRgba8(RgbaImage::from_pixel(2, 1, Rgba([255, 0, 0, 77])));
+        let adobe = IccProfile::Bytes(profile_bytes(ColorProfile::new_adobe_rgb()));
+        let out = transform(img, Some(&adobe), &IccProfile::Srgb, false).unwrap();
+        let pixel = out.as_rgba8().expect("layout is kept").get_pixel(1, 0).0;
+        assert_eq!(pixel[0], 255);
+        assert!(pixel[1] < 10 && pixel[2] < 10, "{pixel:?}");
+        assert_eq!(pixel[3], 77, "alpha passes through");
+
+        // sRGB red sits inside P3, so it loses saturation in P3 numbers.
+        let img = DynamicImage::ImageRgb16(ImageBuffer::from_pixel(1, 1, Rgb([65535, 0, 0])));
+        let out = transform(img, None, &IccProfile::DisplayP3, false).unwrap();
+        let pixel = out
+            .as_rgb16()
+            .expect("16-bit depth is kept")
+            .get_pixel(0, 0)
+            .0;
+        assert!(pixel[0] < 63000 && pixel[1] > 5000, "{pixel:?}");
+    }
+
+    #[test]
+    fn untagged_or_unusable_sources_read_as_srgb() {
+        let img = DynamicImage::ImageLuma8(GrayImage::from_pixel(1, 1, Luma([90])));
+        let junk = IccProfile::Bytes(Arc::new(vec![0; 64]));
+        let out = transform(img.clone(), Some(&junk), &IccProfile::Srgb, false).unwrap();
+        assert_eq!(out.as_rgb8().unwrap().get_pixel(0, 0).0, [90, 90, 90]);
+
+        let rgb = DynamicImage::ImageRgb8(RgbImage::from_pixel(1, 1, Rgb([90, 90, 90])));
+        let gray = IccProfile::Bytes(profile_bytes(ColorProfile::new_gray_with_gamma(2.2)));
+        let out = transform(rgb, Some(&gray), &IccProfile::Srgb, false).unwrap();
+        assert_eq!(out.as_rgb8().unwrap().get_pixel(0, 0).0, [90, 90, 90]);
+
+        let out = transform(img, Some(&gray), &IccProfile::Srgb, false).unwrap();
+        let [r, g, b] = out
+            .as_rgb8()
+            .expect("grey profiles convert to RGB")
+            .get_pixel(0, 0)
+            .0;
+        assert!(r == g && g == b && r.abs_diff(90) <= 3, "{r} {g} {b}");
+    }
+
+    #[test]
+    fn binding_chains_sources_and_converts_before_stripping() {
+        let adobe = profile_bytes(ColorProfile::new_adobe_rgb());
+        let srgb = profile_bytes(ColorProfile::new_srgb());
+
+        let mut ops = vec![Operation::Negate];
+        bind_profiles(&mut ops, Some(&adobe), false, false);
+        assert!(matches!(
+            &ops[0],
+            Operation::IccTransform {
+                source: Some(IccProfile::Bytes(_)),
+                target: IccProfile::Srgb,
+            }
+        ));
+        assert_eq!(output_profile(&ops, false, Some(&adobe)), None);
+
+        for (embedded, keep_icc) in [(&srgb, false), (&adobe, true)] {
+            let mut ops = vec![Operation::Negate];
+            bind_profiles(&mut ops, Some(embedded), keep_icc, false);
+            assert_eq!(ops.len(), 1);
+        }
+        assert_eq!(output_profile(&[], true, Some(&adobe)), Some(adobe.clone()));
+
+        let to = |target| Operation::IccTransform {
+            source: None,
+            target,
+        };
+        let mut ops = vec![to(IccProfile::DisplayP3), to(IccProfile::Srgb)];
+        bind_profiles(&mut ops, None, false, false);
+        assert!(matches!(
+            &ops[0],
+            Operation::IccTransform { source: None, .. }
+        ));
+        assert!(matches!(
+            &ops[1],
+            Operation::IccTransform {
+                source: Some(IccProfile::DisplayP3),
+                ..
+            }
+        ));
+        assert_eq!(output_profile(&ops, false, None), None);
+        assert!(output_profile(&ops, true, None).is_some());
+        assert!(output_profile(&ops[..1], false, None).is_some());
+    }
+
+    #[test]
+    fn cmyk_is_converted_before_anything_else() {
+        let ink = RgbaImage::from_fn(3, 1, |x, _| match x {
+            0 => Rgba([0, 0, 0, 0]),
+            1 => Rgba([255, 0, 0, 0]),
+            _ => Rgba([0, 0, 0, 128]),
+        });
+        let out = transform(
+            DynamicImage::ImageRgba8(ink.clone()),
+            None,
+            &IccProfile::Srgb,
+            true,
+        )
+        .unwrap();
+        let rgb = out.as_rgb8().expect("CMYK becomes opaque RGB8");
+        assert_eq!(rgb.get_pixel(0, 0).0, [255, 255, 255]);
+        assert_eq!(rgb.get_pixel(1, 0).0, [0, 255, 255]);
+        assert_eq!(rgb.get_pixel(2, 0).0, [127, 127, 127]);
+
+        // An RGB profile cannot describe ink; the naive route is taken.
+        let adobe = IccProfile::Bytes(profile_bytes(ColorProfile::new_adobe_rgb()));
+        let out = cmyk_to_rgb(&ink, Some(&adobe), &IccProfile::Srgb).unwrap();
+        assert_eq!(out.as_rgb8().unwrap().get_pixel(1, 0).0, [0, 255, 255]);
+
+        let orient = Operation::AutoOrient { orientation: 6 };
+        let to_p3 = Operation::IccTransform {
+            source: None,
+            target: IccProfile::DisplayP3,
+        };
+        let mut ops = vec![orient.clone(), to_p3, Operation::Negate];
+        bind_profiles(&mut ops, None, true, true);
+        assert!(matches!(
+            ops.as_slice(),
+            [
+                Operation::IccTransform {
+                    target: IccProfile::DisplayP3,
+                    ..
+                },
+                Operation::AutoOrient { .. },
+                Operation::Negate,
+            ]
+        ));
+
+        let mut ops = vec![orient.clone(), Operation::Negate];
+        bind_profiles(&mut ops, None, true, true);
+        assert!(matches!(
+            &ops[0],
+            Operation::IccTransform {
+                source: None,
+                target: IccProfile::Srgb,
+            }
+        ));
+        assert!(output_profile(&ops, true, None).is_some());
+        assert_eq!(output_profile(&ops, false, None), None);
+    }
+
+    #[test]
+    fn profiles_are_classified_by_gamut() {
+        let bytes = |profile| IccProfile::Bytes(profile_bytes(profile));
+        assert_eq!(gamut(None), Gamut::Srgb);
+        assert_eq!(gamut(Some(&IccProfile::Bt2020)), Gamut::Bt2020);
+        assert_eq!(gamut(Some(&bytes(ColorProfile::new_srgb()))), Gamut::Srgb);
+        assert_eq!(
+            gamut(Some(&bytes(ColorProfile::new_display_p3()))),
+            Gamut::DisplayP3
+        );
+        assert_eq!(
+            gamut(Some(&bytes(ColorProfile::new_bt2020()))),
+            Gamut::Bt2020
+        );
+        assert_eq!(
+            gamut(Some(&bytes(ColorProfile::new_adobe_rgb()))),
+            Gamut::Unknown
+        );
+        assert_eq!(
+            gamut(Some(&bytes(ColorProfile::new_gray_with_gamma(1.8)))),
+            Gamut::Unknown
+        );
+        // Unusable bytes are ignored like an untagged input.
+        assert_eq!(embedded_gamut(Some(&Arc::new(vec![0; 64]))), Gamut::Srgb);
+    }
+
+    #[test]
+    fn check_target_requires_rgb_profiles() {
+        assert!(check_target(&profile_bytes(ColorProfile::new_display_p3())).is_ok());
+        let gray = profile_bytes(ColorProfile::new_gray_with_gamma(2.2));
+        assert!(check_target(&gray).is_err());
+        assert!(check_target(b"not a profile").is_err());
+    }
+}
@@ -0,0 +1,299 @@
+// src/engine/linear.rs
+//
+// Gamma-correct (linear-light) resampling.
+//
+// 8-bit sRGB pixels are expanded to 16-bit linear light, resized with
+// fast_image_resize, then encoded back to 8-bit sRGB with an ordered dither so
+// smooth gradients do not band. 16-bit sources go through the same path but
+// come back as 16-bit sRGB without dithering. Alpha is kept linear (it has no
+// transfer curve) and colour is premultiplied in linear space.
+
+use fast_image_resize::{self as fir, MulDiv, PixelType, ResizeOptions};
+use image::{DynamicImage, RgbImage, RgbaImage};
+use std::sync::OnceLock;
+
+/// 4x4 Bayer matrix used for the ordered dither on the way back to 8-bit.
+const BAYER_4X4: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+pub(crate) fn srgb_to_linear(value: f64) -> f64 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+pub(crate) fn linear_to_srgb(value: f64) -> f64 {
+    if value <= 0.003_130_8 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// sRGB byte -> 16-bit linear light.
+fn decode_table() -> &'static [u16; 256] {
+    static TABLE: OnceLock<[u16; 256]> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        let mut table = [0u16; 256];
+        for (i, entry) in table.iter_mut().enumerate() {
+            *entry = (srgb_to_linear(i as f64 / 255.0) * 65535.0).round() as u16;
+        }
+        table
+    })
+}
+
+/// 16-bit sRGB -> 16-bit linear light.
+fn decode_table_16() -> &'static [u16] {
+    static TABLE: OnceLock<Vec<u16>> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        (0..=u16::MAX)
+            .map(|v| (srgb_to_linear(v as f64 / 65535.0) * 65535.0).round() as u16)
+            .collect()
+    })
+}
+
+/// 16-bit linear light -> 16-bit sRGB.
+fn encode_table_16() -> &'static [u16] {
+    static TABLE: OnceLock<Vec<u16>> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        (0..=u16::MAX)
+            .map(|v| (linear_to_srgb(v as f64 / 65535.0) * 65535.0).round() as u16)
+            .collect()
+    })
+}
+
+/// 16-bit linear light -> sRGB scaled to 0.0-255.0 (before dithering).
+fn encode_table() -> &'static [f32] {
+    static TABLE: OnceLock<Vec<f32>> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        (0..=u16::MAX)
+            .map(|v| (linear_to_srgb(v as f64 / 65535.0) * 255.0) as f32)
+            .collect()
+    })
+}
+
+/// Dither threshold for a pixel position, centred on zero (-0.5..0.5).
+#[inline]
+pub(crate) fn dither_offset(x: usize, y: usize) -> f32 {
+    (BAYER_4X4[y & 3][x & 3] as f32 + 0.5) / 16.0 - 0.5
+}
+
+/// Expand 8-bit sRGB samples into a 16-bit linear buffer (native endian).
+fn expand(src: &[u8], channels: usize, dst: &mut [u8]) {
+    let table = decode_table();
+    for (i, (&value, out)) in src.iter().zip(dst.chunks_exact_mut(2)).enumerate() {
+        let linear = if channels == 4 && i % 4 == 3 {
+            value as u16 * 257
+        } else {
+            table[value as usize]
+        };
+        out.copy_from_slice(&linear.to_ne_bytes());
+    }
+}
+
+/// Expand 16-bit sRGB samples into a 16-bit linear buffer (native endian).
+fn expand_16(src: &[u16], channels: usize, dst: &mut [u8]) {
+    let table = decode_table_16();
+    for (i, (&value, out)) in src.iter().zip(dst.chunks_exact_mut(2)).enumerate() {
+        let linear = if channels == 4 && i % 4 == 3 {
+            value
+        } else {
+            table[value as usize]
+        };
+        out.copy_from_slice(&linear.to_ne_bytes());
+    }
+}
+
+/// Encode a 16-bit linear buffer back to 16-bit sRGB.
+fn compress_16(src: &[u8], channels: usize) -> Vec<u16> {
+    let table = encode_table_16();
+    src.chunks_exact(2)
+        .enumerate()
+        .map(|(i, bytes)| {
+            let value = u16::from_ne_bytes([bytes[0], bytes[1]]);
+            if channels == 4 && i % 4 == 3 {
+                value
+            } else {
+                table[value as usize]
+            }
+        })
+        .collect()
+}
+
+/// Encode a 16-bit linear buffer back to dithered 8-bit sRGB.
+fn compress(src: &[u8], channels: usize, width: usize) -> Vec<u8> {
+    let table = encode_table();
+    src.chunks_exact(2)
+        .enumerate()
+        .map(|(i, bytes)| {
+            let value = u16::from_ne_bytes([bytes[0], bytes[1]]);
+            if channels == 4 && i % 4 == 3 {
+                return ((value as u32 + 128) / 257) as u8;
+            }
+            let pixel = i / channels;
+            let offset = dither_offset(pixel % width, pixel / width);
+            (table[value as usize] + offset).round().clamp(0.0, 255.0) as u8
+        })
+        .collect()
+}
+
+/// 16-bit linear-light copy of an 8/16-bit sRGB image (RGB or RGBA).
+pub(crate) struct LinearImage {
+    image: fir::images::Image<'static>,
+    has_alpha: bool,
+    /// The source had more than 8 bits per sample; encode back to 16-bit.
+    sixteen_bit: bool,
+}
+
+impl LinearImage {
+    /// Linearize `img`. Non-RGB layouts are normalized to RGB/RGBA first.
+    pub(crate) fn from_srgb(img: DynamicImage) -> Self {
+        let (width, height) = (img.width(), img.height());
+        let has_alpha = img.color().has_alpha();
+        let sixteen_bit = img.color().bytes_per_pixel() > img.color().channel_count();
+        let (pixel_type, channels) = if has_alpha {
+            (PixelType::U16x4, 4)
+        } else {
+            (PixelType::U16x3, 3)
+        };
+        let mut image = fir::images::Image::new(width, height, pixel_type);
+        match (sixteen_bit, has_alpha) {
+            (true, true) => expand_16(&img.into_rgba16().into_raw(), channels, image.buffer_mut()),
+            (true, false) => expand_16(&img.into_rgb16().into_raw(), channels, image.buffer_mut()),
+            (false, true) => expand(&img.into_rgba8().into_raw(), channels, image.buffer_mut()),
+            (false, false) => expand(&img.into_rgb8().into_raw(), channels, image.buffer_mut()),
+        }
+        Self {
+            image,
+            has_alpha,
+            sixteen_bit,
+        }
+    }
+
+    /// Whether `into_srgb` produces 16-bit samples.
+    pub(crate) fn is_sixteen_bit(&self) -> bool {
+        self.sixteen_bit
+    }
+
+    fn channels(&self) -> usize {
+        if self.has_alpha {
+            4
+        } else {
+            3
+        }
+    }
+
+    /// Resample in linear light; colour is premultiplied when alpha is present.
+    pub(crate) fn resize(
+        mut self,
+        dst_width: u32,
+        dst_height: u32,
+        options: &ResizeOptions,
+    ) -> std::result::Result<Self, String> {
+        let mul_div = MulDiv::default();
+        if self.has_alpha {
+            mul_div
+                .multiply_alpha_inplace(&mut self.image)
+                .map_err(|e| format!("failed to premultiply alpha: {e}"))?;
+        }
+
+        let mut dst_image = fir::images::Image::new(dst_width, dst_height, self.image.pixel_type());
+        fir::Resizer::new()
+            .resize(&self.image, &mut dst_image, options)
+            .map_err(|e| format!("fir linear resize error: {e:?}"))?;
+
+        if self.has_alpha {
+            mul_div
+                .divide_alpha_inplace(&mut dst_image)
+                .map_err(|e| format!("failed to unpremultiply alpha: {e}"))?;
+        }
+        Ok(Self {
+            image: dst_image,
+            has_alpha: self.has_alpha,
+            sixteen_bit: self.sixteen_bit,
+        })
+    }
+
+    /// Encode back to sRGB: 16-bit for 16-bit sources, dithered 8-bit otherwise.
+    pub(crate) fn into_srgb(self) -> std::result::Result<DynamicImage, String> {
+        let (width, height) = (self.image.width(), self.image.height());
+        if self.sixteen_bit {
+            let samples = compress_16(self.image.buffer(), self.channels());
+            let img = if self.has_alpha {
+                image::ImageBuffer::from_raw(width, height, samples).map(DynamicImage::ImageRgba16)
+            } else {
+                image::ImageBuffer::from_raw(width, height, samples).map(DynamicImage::ImageRgb16)
+            };
+            return img
+                .ok_or_else(|| "failed to create 16-bit image from linear resize".to_string());
+        }
+        let pixels = compress(self.image.buffer(), self.channels(), width as usize);
+        if self.has_alpha {
+            RgbaImage::from_raw(width, height, pixels)
+                .map(DynamicImage::ImageRgba8)
+                .ok_or_else(|| "failed to create rgba image from linear resize".to_string())
+        } else {
+            RgbImage::from_raw(width, height, pixels)
+                .map(DynamicImage::ImageRgb8)
+                .ok_or_else(|| "failed to create rgb image from linear resize".to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GenericImageView, Rgba};
+
+    fn resize_linear(img: DynamicImage, width: u32, height: u32) -> DynamicImage {
+        let options =
+            ResizeOptions::new().resize_alg(fir::ResizeAlg::Convolution(fir::FilterType::Lanczos3));
+        LinearImage::from_srgb(img)
+            .resize(width, height, &options)
+            .and_then(LinearImage::into_srgb)
+            .unwrap()
+    }
+
+    #[test]
+    fn round_trip_is_lossless_for_every_byte() {
+        let src: Vec<u8> = (0..=255).collect();
+        let mut linear = vec![0u8; src.len() * 2];
+        expand(&src, 3, &mut linear);
+        let back = compress(&linear, 3, src.len() / 3);
+        assert_eq!(back, src);
+    }
+
+    #[test]
+    fn sixteen_bit_round_trip_stays_sixteen_bit() {
+        let src: Vec<u16> = (0..=u16::MAX).step_by(97).collect();
+        let mut linear = vec![0u8; src.len() * 2];
+        expand_16(&src, 3, &mut linear);
+        let back = compress_16(&linear, 3);
+        for (a, b) in src.iter().zip(&back) {
+            // Deep shadows lose a little precision in 16-bit linear light.
+            assert!((*a as i32 - *b as i32).abs() <= 64, "{a} -> {b}");
+        }
+
+        let img = DynamicImage::ImageRgb16(image::ImageBuffer::from_pixel(
+            8,
+            8,
+            image::Rgb([40000u16, 1234, 65535]),
+        ));
+        let DynamicImage::ImageRgb16(out) = resize_linear(img, 4, 4) else {
+            panic!("16-bit input should stay 16-bit");
+        };
+        let px = out.get_pixel(1, 1).0;
+        assert!((px[0] as i32 - 40000).abs() <= 8, "{px:?}");
+    }
+
+    #[test]
+    fn alpha_is_preserved() {
+        let img = DynamicImage::ImageRgba8(RgbaImage::from_pixel(10, 10, Rgba([200, 50, 10, 128])));
+        let out = resize_linear(img, 5, 5);
+        assert_eq!(out.dimensions(), (5, 5));
+        let px = out.to_rgba8().get_pixel(2, 2).0;
+        assert_eq!(px[3], 128);
+        assert!((px[0] as i32 - 200).abs() <= 1, "{px:?}");
+    }
+}
@@ -5,8 +5,9 @@
 // This module detects container memory limits from cgroup v1/v2 to automatically
 // adjust thread pool size and prevent OOM kills in constrained environments.
 
-use crate::engine::pipeline::calc_resize_dimensions;
-use crate::ops::{Operation, OutputFormat, ResizeFit};
+use crate::engine::decoder::{jpeg_header_is_cmyk, read_dimensions};
+use crate::engine::pipeline::plan_resize;
+use crate::ops::{ExtendMode, Operation, OutputFormat, Overlay, ResizeFit};
 use image::ImageFormat;
 use parking_lot::{Condvar, Mutex};
 #[cfg(feature = "napi")]
@@ -31,7 +32,9 @@ const FILTER_OVERHEAD_BYTES: u64 = 4 * 1024 * 1024;
 
 /// Default bytes-per-pixel assumptions per format (decoded)
 const BPP_JPEG: u64 = 3; // YCbCr → RGB
+const BPP_JPEG_CMYK: u64 = 4; // CMYK/YCCK decode to ink in RGBA8
 const BPP_PNG: u64 = 4; // favor safety (alpha)
+const BPP_PNG_16: u64 = 8; // 16-bit channels decode at full depth
 const BPP_WEBP: u64 = 4;
 const BPP_AVIF: u64 = 4;
 const BPP_UNKNOWN: u64 = 4;
@@ -191,6 +194,8 @@ pub struct HeaderEstimate {
     pub width: u32,
     pub height: u32,
     pub format: Option<ImageFormat>,
+    /// Bytes per pixel of the decoded buffer
+    pub bytes_per_pixel: u64,
 }
 
 fn bytes_for_image(width: u32, height: u32, bytes_per_pixel: u64) -> u64 {
@@ -208,6 +213,16 @@ fn default_bpp(format: Option<ImageFormat>) -> u64 {
     }
 }
 
+/// Like `default_bpp`, but looks at the header for layouts that decode wider.
+fn header_bpp(bytes: &[u8], format: Option<ImageFormat>) -> u64 {
+    match format {
+        // IHDR bit depth sits right after the signature, length, type and dimensions
+        Some(ImageFormat::Png) if bytes.get(24) == Some(&16) => BPP_PNG_16,
+        Some(ImageFormat::Jpeg) if jpeg_header_is_cmyk(bytes) => BPP_JPEG_CMYK,
+        _ => default_bpp(format),
+    }
+}
+
 /// Reserve memory for OS / runtime based on container/host limit
 #[cfg(feature = "napi")]
 fn compute_reserved_memory(total_bytes: u64) -> u64 {
@@ -225,90 +240,297 @@ fn compute_reserved_memory(total_bytes: u64) -> u64 {
     MIN_RESERVED_MEMORY
 }
 
-fn calc_cover_resize_dimensions(
-    orig_w: u32,
-    orig_h: u32,
-    target_w: u32,
-    target_h: u32,
-) -> (u32, u32) {
-    if orig_w == 0 || orig_h == 0 {
-        return (target_w.max(1), target_h.max(1));
+/// Bytes per pixel once an alpha channel is added (alpha layouts unchanged).
+fn with_alpha_bpp(current_bpp: u64) -> u64 {
+    match current_bpp {
+        1 => 2,
+        3 => 4,
+        6 => 8,
+        other => other,
     }
-    let scale_w = target_w as f64 / orig_w as f64;
-    let scale_h = target_h as f64 / orig_h as f64;
-    let scale = scale_w.max(scale_h);
-    let resize_w = ((orig_w as f64 * scale).ceil() as u32).max(1);
-    let resize_h = ((orig_h as f64 * scale).ceil() as u32).max(1);
-    (resize_w, resize_h)
+}
+
+/// Bytes per pixel of a resampled image: 16-bit colour layouts (RGB16,
+/// RGBA16) are kept, anything narrower is assumed RGBA8.
+fn resized_bpp(current_bpp: u64) -> u64 {
+    if current_bpp >= 6 {
+        current_bpp
+    } else {
+        4
+    }
+}
+
+/// Worst-case bytes per pixel once greyscale layouts are expanded to RGB
+/// (2 and 4 bytes may be LumaA8 / Luma16 and RGBA8 / LumaA16).
+fn expanded_color_bpp(current_bpp: u64) -> u64 {
+    match current_bpp {
+        1 => 3,
+        2 => 6,
+        4 => 8,
+        other => other,
+    }
+}
+
+/// The two f32 passes of a separable Gaussian over `planes` channels.
+fn gaussian_planes_bytes(dims: (u32, u32), planes: u64) -> u64 {
+    bytes_for_image(dims.0, dims.1, planes * 4).saturating_mul(2)
 }
 
 fn project_operation(dims: (u32, u32), current_bpp: u64, op: &Operation) -> ((u32, u32), u64, u64) {
     match op {
-        Operation::Resize { width, height, fit } => {
-            let target = (
-                width.unwrap_or(dims.0).max(1),
-                height.unwrap_or(dims.1).max(1),
+        Operation::Resize {
+            width,
+            height,
+            fit,
+            without_enlargement,
+            without_reduction,
+            linear,
+            sharpen,
+            ..
+        } => {
+            // Same plan the pipeline executes, so guards and fits are projected exactly.
+            let plan = plan_resize(
+                dims,
+                *width,
+                *height,
+                fit,
+                *without_enlargement,
+                *without_reduction,
             );
-            match fit {
-                ResizeFit::Fill => ((target.0, target.1), 4, FILTER_OVERHEAD_BYTES),
-                ResizeFit::Inside => {
-                    let (w, h) = calc_resize_dimensions(dims.0, dims.1, *width, *height);
-                    ((w, h), 4, FILTER_OVERHEAD_BYTES)
-                }
+            let scaled_bpp = resized_bpp(current_bpp);
+            // Letterboxing pads onto an RGBA8 canvas.
+            let next_bpp = match fit {
+                ResizeFit::Contain { .. } if plan.scaled != plan.output => 4,
+                _ => scaled_bpp,
+            };
+            let scaled_bytes = bytes_for_image(plan.scaled.0, plan.scaled.1, scaled_bpp);
+            let output_bytes = bytes_for_image(plan.output.0, plan.output.1, next_bpp);
+            let overhead = match fit {
+                // Peak occurs after resize before crop; include the part of the
+                // intermediate buffer that the crop discards.
                 ResizeFit::Cover => {
-                    let (resize_w, resize_h) =
-                        calc_cover_resize_dimensions(dims.0, dims.1, target.0, target.1);
-                    // Peak occurs after resize before crop; use larger of resize and target
-                    // to model intermediate buffer.
-                    let resize_bytes = bytes_for_image(resize_w, resize_h, 4);
-                    let target_bytes = bytes_for_image(target.0, target.1, 4);
-                    let overhead = FILTER_OVERHEAD_BYTES;
-                    // Return final dims (after crop), but include resize_bytes in peak.
-                    (
-                        (target.0, target.1),
-                        4,
-                        overhead.saturating_add(resize_bytes.saturating_sub(target_bytes)),
-                    )
+                    FILTER_OVERHEAD_BYTES.saturating_add(scaled_bytes.saturating_sub(output_bytes))
                 }
-            }
+                // Scaled image and padded canvas are alive at the same time.
+                ResizeFit::Contain { .. } if plan.scaled != plan.output => {
+                    FILTER_OVERHEAD_BYTES.saturating_add(scaled_bytes)
+                }
+                _ => FILTER_OVERHEAD_BYTES,
+            };
+            // Linear light resamples 16-bit copies of the source and the result.
+            let overhead = if *linear && plan.scaled != dims {
+                overhead
+                    .saturating_add(bytes_for_image(dims.0, dims.1, 8))
+                    .saturating_add(bytes_for_image(plan.scaled.0, plan.scaled.1, 8))
+            } else {
+                overhead
+            };
+            let overhead = if *sharpen {
+                overhead.saturating_add(gaussian_planes_bytes(plan.scaled, 3))
+            } else {
+                overhead
+            };
+            (plan.output, next_bpp, overhead)
         }
         Operation::Extract {
             width,
             height,
             fit,
+            without_enlargement,
+            without_reduction,
             crop_width,
             crop_height,
             ..
         } => {
-            let target_resize = (
-                width.unwrap_or(dims.0).max(1),
-                height.unwrap_or(dims.1).max(1),
+            let plan = plan_resize(
+                dims,
+                *width,
+                *height,
+                fit,
+                *without_enlargement,
+                *without_reduction,
             );
-            let (resize_w, resize_h) = match fit {
-                ResizeFit::Fill => target_resize,
-                ResizeFit::Inside => calc_resize_dimensions(dims.0, dims.1, *width, *height),
-                ResizeFit::Cover => {
-                    calc_cover_resize_dimensions(dims.0, dims.1, target_resize.0, target_resize.1)
-                }
+            let final_w = (*crop_width).max(1).min(plan.output.0);
+            let final_h = (*crop_height).max(1).min(plan.output.1);
+            let next_bpp = match fit {
+                ResizeFit::Contain { .. } if plan.scaled != plan.output => 4,
+                _ => resized_bpp(current_bpp),
             };
-            let final_w = (*crop_width).max(1).min(resize_w);
-            let final_h = (*crop_height).max(1).min(resize_h);
-            ((final_w, final_h), 4, FILTER_OVERHEAD_BYTES)
+            ((final_w, final_h), next_bpp, FILTER_OVERHEAD_BYTES)
         }
         Operation::Crop { width, height, .. } => {
             let w = (*width).max(1).min(dims.0);
             let h = (*height).max(1).min(dims.1);
             ((w, h), current_bpp, FILTER_OVERHEAD_BYTES / 2)
         }
+        Operation::Extend {
+            top,
+            right,
+            bottom,
+            left,
+            background,
+            mode,
+        } => {
+            let grow = |len: u32, a: u32, b: u32| {
+                (len as u64 + a as u64 + b as u64).min(u32::MAX as u64) as u32
+            };
+            let next_dims = (grow(dims.0, *left, *right), grow(dims.1, *top, *bottom));
+            // Solid fills composite onto an RGBA canvas unless an opaque
+            // colour can keep the RGB layout; edge modes keep the layout.
+            let next_bpp = match mode {
+                ExtendMode::Background if !background.is_opaque() || current_bpp != 3 => 4,
+                _ => current_bpp,
+            };
+            (next_dims, next_bpp, FILTER_OVERHEAD_BYTES / 2)
+        }
+        // Output size depends on the pixels; project the worst case (nothing trimmed).
+        Operation::Trim { .. } => (dims, current_bpp, FILTER_OVERHEAD_BYTES / 2),
         Operation::Rotate { degrees } => {
             let rotated = matches!(degrees.rem_euclid(360), 90 | 270);
             let next_dims = if rotated { (dims.1, dims.0) } else { dims };
             (next_dims, current_bpp, FILTER_OVERHEAD_BYTES)
         }
+        Operation::Flatten { .. } => {
+            // Alpha layouts (LumaA8, RGBA8, RGBA16) lose their alpha channel.
+            let next_bpp = match current_bpp {
+                2 | 4 => 3,
+                8 => 6,
+                other => other,
+            };
+            (dims, next_bpp, FILTER_OVERHEAD_BYTES / 2)
+        }
+        Operation::EnsureAlpha { .. } => {
+            (dims, with_alpha_bpp(current_bpp), FILTER_OVERHEAD_BYTES / 2)
+        }
+        Operation::RemoveAlpha => {
+            let next_bpp = match current_bpp {
+                2 => 1,
+                4 => 3,
+                8 => 6,
+                other => other,
+            };
+            (dims, next_bpp, FILTER_OVERHEAD_BYTES / 2)
+        }
+        Operation::ExtractChannel { .. } => {
+            let next_bpp = if current_bpp >= 6 { 2 } else { 1 };
+            (dims, next_bpp, FILTER_OVERHEAD_BYTES / 2)
+        }
+        Operation::JoinChannel { .. } => {
+            // The mask is decoded (worst case RGBA8) next to the image.
+            let overhead =
+                (FILTER_OVERHEAD_BYTES / 2).saturating_add(bytes_for_image(dims.0, dims.1, 4));
+            (dims, with_alpha_bpp(current_bpp), overhead)
+        }
+        Operation::RotateArbitrary {
+            degrees,
+            background,
+            ..
+        } => {
+            let next_dims = super::rotate::rotated_dimensions(dims.0, dims.1, *degrees);
+            if super::rotate::right_angle(*degrees).is_some() {
+                (next_dims, current_bpp, FILTER_OVERHEAD_BYTES)
+            } else {
+                // Sampled from an RGBA8 copy of the source onto an 8-bit
+                // canvas that gains alpha when the source or the fill has it.
+                let next_bpp = if !background.is_opaque() || matches!(current_bpp, 2 | 4 | 8) {
+                    4
+                } else {
+                    3
+                };
+                let overhead =
+                    FILTER_OVERHEAD_BYTES.saturating_add(bytes_for_image(dims.0, dims.1, 4));
+                (next_dims, next_bpp, overhead)
+            }
+        }
+        Operation::Affine {
+            matrix,
+            translate,
+            background,
+            ..
+        } => {
+            // Output bounds are known before sampling; the warp reads from an
+            // RGBA8 copy like an arbitrary-angle rotation.
+            let next_dims = super::rotate::affine_dimensions(dims.0, dims.1, matrix, *translate);
+            let next_bpp = if !background.is_opaque() || matches!(current_bpp, 2 | 4 | 8) {
+                4
+            } else {
+                3
+            };
+            let overhead = FILTER_OVERHEAD_BYTES.saturating_add(bytes_for_image(dims.0, dims.1, 4));
+            (next_dims, next_bpp, overhead)
+        }
         Operation::FlipH | Operation::FlipV => (dims, current_bpp, FILTER_OVERHEAD_BYTES / 2),
         Operation::Brightness { .. } | Operation::Contrast { .. } => {
             (dims, current_bpp.max(3), FILTER_OVERHEAD_BYTES / 2)
         }
+        Operation::Modulate { .. }
+        | Operation::Gamma { .. }
+        | Operation::Negate
+        | Operation::Threshold {
+            grayscale: false, ..
+        }
+        | Operation::Levels { .. }
+        | Operation::Normalize { .. } => (dims, current_bpp, FILTER_OVERHEAD_BYTES / 2),
+        Operation::Clahe { .. } => {
+            // Per-pixel f32 lightness plane
+            let overhead = FILTER_OVERHEAD_BYTES.saturating_add(bytes_for_image(dims.0, dims.1, 4));
+            (dims, current_bpp, overhead)
+        }
+        Operation::Sharpen { .. } => (
+            dims,
+            current_bpp,
+            FILTER_OVERHEAD_BYTES.saturating_add(gaussian_planes_bytes(dims, 3)),
+        ),
+        // Worst case: a single region covering the whole image, all channels.
+        Operation::Blur { .. } => (
+            dims,
+            current_bpp,
+            FILTER_OVERHEAD_BYTES.saturating_add(gaussian_planes_bytes(dims, 4)),
+        ),
+        Operation::Pixelate { .. } => (dims, current_bpp, FILTER_OVERHEAD_BYTES / 2),
+        Operation::Recomb { .. } | Operation::Lut3d { .. } => (
+            dims,
+            expanded_color_bpp(current_bpp),
+            FILTER_OVERHEAD_BYTES / 2,
+        ),
+        Operation::Composite { layers } => {
+            // Overlays are decoded one at a time (up to RGBA16) and converted
+            // to RGBA f32: the visible part when clipped, all of it when
+            // tiled. Sizes come from the overlay headers; unreadable headers
+            // fall back to the image size.
+            let layer_bytes = |layer: &Overlay| {
+                let (w, h) = read_dimensions(&layer.input).ok().flatten().unwrap_or(dims);
+                let (fw, fh) = if layer.tile {
+                    (w, h)
+                } else {
+                    (w.min(dims.0), h.min(dims.1))
+                };
+                bytes_for_image(w, h, 8).saturating_add(bytes_for_image(fw, fh, 16))
+            };
+            let largest = layers.iter().map(layer_bytes).max().unwrap_or(0);
+            let overhead = (FILTER_OVERHEAD_BYTES / 2).saturating_add(largest);
+            (dims, expanded_color_bpp(current_bpp), overhead)
+        }
+        Operation::Threshold {
+            grayscale: true, ..
+        } => {
+            // 8-bit greyscale, keeping alpha
+            let next_bpp = if matches!(current_bpp, 2 | 4 | 8) {
+                2
+            } else {
+                1
+            };
+            (dims, next_bpp, FILTER_OVERHEAD_BYTES / 2)
+        }
+        Operation::PointLut { steps } => {
+            steps
+                .iter()
+                .fold((dims, current_bpp, 0), |(dims, bpp, overhead), step| {
+                    let (dims, bpp, step_overhead) =
+                        project_operation(dims, bpp, &step.to_operation());
+                    (dims, bpp, overhead.max(step_overhead))
+                })
+        }
         Operation::AutoOrient { orientation } => {
             let rotated = matches!(orientation, 5 | 6 | 7 | 8);
             let next_dims = if rotated { (dims.1, dims.0) } else { dims };
@@ -316,20 +538,38 @@ fn project_operation(dims: (u32, u32), current_bpp: u64, op: &Operation) -> ((u3
         }
         Operation::Grayscale => (dims, current_bpp.max(3), FILTER_OVERHEAD_BYTES / 2),
         Operation::ColorSpace { .. } => (dims, 3, FILTER_OVERHEAD_BYTES / 2),
+        Operation::IccTransform { .. } => {
+            // Greyscale gains colour channels; the output is a fresh buffer.
+            let next_bpp = expanded_color_bpp(current_bpp);
+            let overhead = (FILTER_OVERHEAD_BYTES / 2)
+                .saturating_add(bytes_for_image(dims.0, dims.1, next_bpp));
+            (dims, next_bpp, overhead)
+        }
     }
 }
 
+#[cfg(test)]
 fn estimate_memory_from_dimensions_with_context(
     width: u32,
     height: u32,
     format: Option<ImageFormat>,
     ops: &[Operation],
     output_format: Option<&OutputFormat>,
+) -> u64 {
+    estimate_memory_with_bpp(width, height, default_bpp(format), ops, output_format)
+}
+
+fn estimate_memory_with_bpp(
+    width: u32,
+    height: u32,
+    input_bpp: u64,
+    ops: &[Operation],
+    output_format: Option<&OutputFormat>,
 ) -> u64 {
     // Deterministic model: Calculate peak memory directly from input pixel count × BPP
     // and pipeline intermediate buffer count. No learning or observation-based corrections.
     let mut current_dims = (width, height);
-    let mut current_bpp = default_bpp(format);
+    let mut current_bpp = input_bpp;
 
     let mut peak = bytes_for_image(current_dims.0, current_dims.1, current_bpp)
         .saturating_add(DECODE_OVERHEAD_BYTES);
@@ -367,9 +607,9 @@ pub fn estimate_memory_from_dimensions(width: u32, height: u32) -> u64 {
 }
 
 /// Lightweight header parse; returns None if dimensions can't be read.
-/// Cache for memory estimates keyed by (width, height, format)
+/// Cache for memory estimates keyed by (width, height, bytes per pixel)
 /// No LRU needed - image dimensions are discrete (~20-50 common sizes)
-type EstimateCache = Mutex<HashMap<(u32, u32, Option<ImageFormat>), u64>>;
+type EstimateCache = Mutex<HashMap<(u32, u32, u64), u64>>;
 static ESTIMATE_CACHE: OnceLock<EstimateCache> = OnceLock::new();
 
 fn get_estimate_cache() -> &'static EstimateCache {
@@ -386,7 +626,7 @@ pub fn estimate_memory_from_header(
     // Only cache if ops are empty (common case for simple operations)
     // Complex pipelines with operations are computed fresh
     if ops.is_empty() && output_format.is_none() {
-        let cache_key = (header.width, header.height, header.format);
+        let cache_key = (header.width, header.height, header.bytes_per_pixel);
 
         // Try cache first (parking_lot try_lock returns Option)
         if let Some(cache) = get_estimate_cache().try_lock() {
@@ -396,10 +636,10 @@ pub fn estimate_memory_from_header(
         }
 
         // Compute estimate
-        let estimate = estimate_memory_from_dimensions_with_context(
+        let estimate = estimate_memory_with_bpp(
             header.width,
             header.height,
-            header.format,
+            header.bytes_per_pixel,
             ops,
             output_format,
         );
@@ -417,10 +657,10 @@ pub fn estimate_memory_from_header(
         Some(estimate)
     } else {
         // Don't cache complex pipelines
-        Some(estimate_memory_from_dimensions_with_context(
+        Some(estimate_memory_with_bpp(
             header.width,
             header.height,
-            header.format,
+            header.bytes_per_pixel,
             ops,
             output_format,
         ))
@@ -437,6 +677,7 @@ pub fn parse_header(bytes: &[u8]) -> Option<HeaderEstimate> {
                 width: w,
                 height: h,
                 format,
+                bytes_per_pixel: header_bpp(bytes, format),
             });
         }
     }
@@ -718,7 +959,7 @@ pub fn calculate_memory_based_concurrency(
 #[cfg(all(test, feature = "napi"))]
 mod tests {
     use super::*;
-    use crate::ops::{Operation, OutputFormat, ResizeFit};
+    use crate::ops::{Operation, OutputFormat, ResizeFit, ResizeKernel, ResizePosition};
     use std::sync::Arc;
 
     #[test]
@@ -855,18 +1096,295 @@ mod tests {
         assert!(png_est > jpeg_est);
     }
 
+    #[test]
+    fn test_cmyk_jpeg_header_uses_ink_bpp() {
+        let (width, height) = (64, 48);
+        let mut comp = mozjpeg::Compress::new(mozjpeg::ColorSpace::JCS_CMYK);
+        comp.set_size(width, height);
+        let mut cmyk = Vec::new();
+        {
+            let mut writer = comp.start_compress(&mut cmyk).unwrap();
+            writer
+                .write_scanlines(&vec![0u8; width * height * 4])
+                .unwrap();
+            writer.finish().unwrap();
+        }
+        let mut rgb = Vec::new();
+        image::DynamicImage::new_rgb8(width as u32, height as u32)
+            .write_to(&mut std::io::Cursor::new(&mut rgb), ImageFormat::Jpeg)
+            .unwrap();
+
+        let header = parse_header(&cmyk).unwrap();
+        assert_eq!((header.width, header.height), (64, 48));
+        assert_eq!(header.bytes_per_pixel, BPP_JPEG_CMYK);
+        assert_eq!(parse_header(&rgb).unwrap().bytes_per_pixel, BPP_JPEG);
+    }
+
+    #[test]
+    fn test_sixteen_bit_png_header_doubles_bpp() {
+        let encode = |img: image::DynamicImage| {
+            let mut bytes = Vec::new();
+            img.write_to(&mut std::io::Cursor::new(&mut bytes), ImageFormat::Png)
+                .unwrap();
+            bytes
+        };
+        let png8 = encode(image::DynamicImage::new_rgba8(64, 64));
+        let png16 = encode(image::DynamicImage::new_rgba16(64, 64));
+
+        assert_eq!(parse_header(&png8).unwrap().bytes_per_pixel, BPP_PNG);
+        assert_eq!(parse_header(&png16).unwrap().bytes_per_pixel, BPP_PNG_16);
+        assert!(
+            estimate_memory_with_bpp(4000, 4000, BPP_PNG_16, &[], None)
+                > estimate_memory_from_dimensions_with_context(
+                    4000,
+                    4000,
+                    Some(ImageFormat::Png),
+                    &[],
+                    None
+                )
+        );
+    }
+
     #[test]
     fn test_cover_resize_accounts_intermediate() {
         let ops = vec![Operation::Resize {
             width: Some(1000),
             height: Some(1000),
             fit: ResizeFit::Cover,
+            position: ResizePosition::Center,
+            without_enlargement: false,
+            without_reduction: false,
+            kernel: ResizeKernel::Lanczos3,
+            linear: false,
+            sharpen: false,
         }];
         let est = estimate_memory_from_dimensions_with_context(100, 10_000, None, &ops, None);
         let resize_bytes = bytes_for_image(1000, 10_000, 4);
         assert!(est >= resize_bytes);
     }
 
+    #[test]
+    fn test_linear_resize_projects_wide_buffers() {
+        let resize = |linear| Operation::Resize {
+            width: Some(1000),
+            height: None,
+            fit: ResizeFit::Inside,
+            position: ResizePosition::Center,
+            without_enlargement: false,
+            without_reduction: false,
+            kernel: ResizeKernel::Lanczos3,
+            linear,
+            sharpen: false,
+        };
+        let srgb =
+            estimate_memory_from_dimensions_with_context(4000, 3000, None, &[resize(false)], None);
+        let linear =
+            estimate_memory_from_dimensions_with_context(4000, 3000, None, &[resize(true)], None);
+        assert!(linear >= srgb + bytes_for_image(4000, 3000, 8));
+    }
+
+    #[test]
+    fn test_resize_keeps_sixteen_bit_projection() {
+        let resize = Operation::Resize {
+            width: Some(500),
+            height: None,
+            fit: ResizeFit::Inside,
+            position: ResizePosition::Center,
+            without_enlargement: false,
+            without_reduction: false,
+            kernel: ResizeKernel::Lanczos3,
+            linear: false,
+            sharpen: false,
+        };
+        // RGBA16 and RGB16 stay 16-bit; 8-bit layouts project as RGBA8.
+        assert_eq!(
+            project_operation((1000, 800), 8, &resize),
+            ((500, 400), 8, FILTER_OVERHEAD_BYTES)
+        );
+        assert_eq!(project_operation((1000, 800), 6, &resize).1, 6);
+        assert_eq!(project_operation((1000, 800), 3, &resize).1, 4);
+
+        // Later ops see the 16-bit layout: flattening RGBA16 gives RGB16.
+        let (dims, bpp, _) = project_operation((1000, 800), 8, &resize);
+        let flatten = Operation::Flatten {
+            background: crate::ops::Color::WHITE,
+        };
+        assert_eq!(project_operation(dims, bpp, &flatten).1, 6);
+    }
+
+    #[test]
+    fn test_extend_projection() {
+        let extend = |mode| Operation::Extend {
+            top: 10,
+            right: 20,
+            bottom: 30,
+            left: 40,
+            background: crate::ops::Color::TRANSPARENT,
+            mode,
+        };
+        let (dims, bpp, _) = project_operation((100, 100), 3, &extend(ExtendMode::Background));
+        assert_eq!(dims, (160, 140));
+        assert_eq!(bpp, 4, "transparent fill needs an alpha channel");
+        let (dims, bpp, _) = project_operation((100, 100), 3, &extend(ExtendMode::Mirror));
+        assert_eq!(dims, (160, 140));
+        assert_eq!(bpp, 3);
+    }
+
+    #[test]
+    fn test_rotate_arbitrary_projection() {
+        let rotate = |degrees, background| Operation::RotateArbitrary {
+            degrees,
+            background,
+            interpolation: crate::ops::RotateInterpolation::Bilinear,
+        };
+        let (dims, bpp, overhead) =
+            project_operation((100, 50), 3, &rotate(45.0, crate::ops::Color::BLACK));
+        assert_eq!(dims, (107, 107));
+        assert_eq!(bpp, 3);
+        assert!(overhead >= 100 * 50 * 4, "RGBA source copy must be counted");
+        let (_, bpp, _) =
+            project_operation((100, 50), 3, &rotate(45.0, crate::ops::Color::TRANSPARENT));
+        assert_eq!(bpp, 4, "transparent corners need an alpha channel");
+        let (dims, bpp, _) =
+            project_operation((100, 50), 1, &rotate(270.0, crate::ops::Color::TRANSPARENT));
+        assert_eq!((dims, bpp), ((50, 100), 1), "right angles keep the layout");
+    }
+
+    #[test]
+    fn test_affine_projection() {
+        let affine = Operation::Affine {
+            matrix: [[2.0, 0.0], [0.0, 0.5]],
+            translate: [10.0, -5.0],
+            background: crate::ops::Color::BLACK,
+            interpolation: crate::ops::RotateInterpolation::Bilinear,
+        };
+        let (dims, bpp, overhead) = project_operation((100, 50), 1, &affine);
+        assert_eq!(dims, (210, 20));
+        assert_eq!(bpp, 3);
+        assert!(overhead >= 100 * 50 * 4, "RGBA source copy must be counted");
+    }
+
+    #[test]
+    fn test_channel_ops_projection() {
+        let ensure = Operation::EnsureAlpha { alpha: 1.0 };
+        assert_eq!(project_operation((10, 10), 3, &ensure).1, 4);
+        assert_eq!(project_operation((10, 10), 6, &ensure).1, 8);
+        assert_eq!(project_operation((10, 10), 4, &Operation::RemoveAlpha).1, 3);
+        let extract = Operation::ExtractChannel {
+            channel: crate::ops::Channel::Alpha,
+        };
+        assert_eq!(project_operation((10, 10), 4, &extract).1, 1);
+        assert_eq!(project_operation((10, 10), 8, &extract).1, 2);
+        let join = Operation::JoinChannel {
+            mask: std::sync::Arc::new(Vec::new()),
+        };
+        let (_, bpp, overhead) = project_operation((100, 100), 1, &join);
+        assert_eq!(bpp, 2);
+        assert!(overhead >= 100 * 100 * 4, "decoded mask must be counted");
+        let threshold = |grayscale| Operation::Threshold {
+            value: 128,
+            grayscale,
+        };
+        assert_eq!(project_operation((10, 10), 8, &threshold(true)).1, 2);
+        assert_eq!(project_operation((10, 10), 3, &threshold(true)).1, 1);
+        assert_eq!(project_operation((10, 10), 6, &threshold(false)).1, 6);
+    }
+
+    #[test]
+    fn test_composite_projection_sizes_overlays_from_headers() {
+        let mut png = Vec::new();
+        image::DynamicImage::ImageRgba8(image::RgbaImage::new(2000, 1000))
+            .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .unwrap();
+        let composite = |tile| Operation::Composite {
+            layers: vec![Overlay {
+                input: Arc::new(png.clone()),
+                placement: crate::ops::OverlayPlacement::Offset { left: 0, top: 0 },
+                blend: crate::ops::BlendMode::Over,
+                opacity: 1.0,
+                tile,
+            }],
+        };
+
+        // Decoded overlay plus an f32 copy of the 100x100 visible part
+        let (dims, _, overhead) = project_operation((100, 100), 3, &composite(false));
+        assert_eq!(dims, (100, 100));
+        assert!(overhead >= bytes_for_image(2000, 1000, 8) + bytes_for_image(100, 100, 16));
+        assert!(overhead < bytes_for_image(2000, 1000, 16));
+
+        // Tiled overlays are converted whole
+        let (_, _, tiled) = project_operation((100, 100), 3, &composite(true));
+        assert!(tiled >= bytes_for_image(2000, 1000, 24));
+    }
+
+    #[test]
+    fn test_contain_and_outside_projection() {
+        let contain = Operation::Resize {
+            width: Some(800),
+            height: Some(800),
+            fit: ResizeFit::Contain {
+                background: crate::ops::Color::TRANSPARENT,
+            },
+            position: ResizePosition::Center,
+            without_enlargement: false,
+            without_reduction: false,
+            kernel: ResizeKernel::Lanczos3,
+            linear: false,
+            sharpen: false,
+        };
+        let (dims, bpp, overhead) = project_operation((4000, 2000), 3, &contain);
+        assert_eq!((dims, bpp), ((800, 800), 4));
+        // Scaled 800x400 image is held alongside the canvas
+        assert!(overhead >= bytes_for_image(800, 400, 4));
+
+        let outside = Operation::Resize {
+            width: Some(800),
+            height: Some(800),
+            fit: ResizeFit::Outside,
+            position: ResizePosition::Center,
+            without_enlargement: false,
+            without_reduction: false,
+            kernel: ResizeKernel::Lanczos3,
+            linear: false,
+            sharpen: false,
+        };
+        let (dims, _, _) = project_operation((4000, 2000), 3, &outside);
+        assert_eq!(dims, (1600, 800));
+    }
+
+    #[test]
+    fn test_guarded_resize_projection() {
+        let op = Operation::Resize {
+            width: Some(8000),
+            height: None,
+            fit: ResizeFit::Inside,
+            position: ResizePosition::Center,
+            without_enlargement: true,
+            without_reduction: false,
+            kernel: ResizeKernel::Lanczos3,
+            linear: false,
+            sharpen: false,
+        };
+        let (dims, _, _) = project_operation((400, 300), 3, &op);
+        assert_eq!(dims, (400, 300));
+
+        let est = estimate_memory_from_dimensions_with_context(1000, 750, None, &[op], None);
+        let unguarded = Operation::Resize {
+            width: Some(8000),
+            height: None,
+            fit: ResizeFit::Inside,
+            position: ResizePosition::Center,
+            without_enlargement: false,
+            without_reduction: false,
+            kernel: ResizeKernel::Lanczos3,
+            linear: false,
+            sharpen: false,
+        };
+        let est_unguarded =
+            estimate_memory_from_dimensions_with_context(1000, 750, None, &[unguarded], None);
+        assert!(est < est_unguarded);
+    }
+
     #[test]
     fn test_estimate_cache_has_bounded_size() {
         {
@@ -901,7 +1419,7 @@ mod tests {
 #[cfg(all(test, not(feature = "napi")))]
 mod non_napi_tests {
     use super::*;
-    use crate::ops::{Operation, OutputFormat, ResizeFit};
+    use crate::ops::{Operation, OutputFormat, ResizeFit, ResizeKernel, ResizePosition};
     use image::{ImageBuffer, ImageFormat, Rgba};
     use std::sync::Arc;
     use std::thread;
@@ -953,6 +1471,12 @@ mod non_napi_tests {
             width: Some(200),
             height: Some(200),
             fit: ResizeFit::Cover,
+            position: ResizePosition::Center,
+            without_enlargement: false,
+            without_reduction: false,
+            kernel: ResizeKernel::Lanczos3,
+            linear: false,
+            sharpen: false,
         }];
         let est_small = estimate_memory_from_dimensions_with_context(
             10,
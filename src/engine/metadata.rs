@@ -0,0 +1,604 @@
+// src/engine/metadata.rs
+//
+// Structured metadata for `metadata()`.
+//
+// Everything is read from the container headers: EXIF through kamadak-exif,
+// XMP packets from JPEG APP1 / PNG iTXt / WebP `XMP ` chunks, IPTC keywords
+// from the Photoshop APP13 segment, density from JFIF / pHYs (EXIF resolution
+// as a fallback) and the ICC description from the profile the engine already
+// extracted. Pixels are never decoded. Missing or malformed blocks leave
+// their fields empty instead of failing the call.
+//
+// GPS coordinates are only read when the caller asks for them, matching the
+// privacy-first default of `keepMetadata()`.
+
+use crate::engine::icc;
+use exif::{Exif, In, Tag, Value};
+use std::io::{Cursor, Read};
+
+const XMP_JPEG_ID: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+const XMP_PNG_KEYWORD: &[u8] = b"XML:com.adobe.xmp";
+const PHOTOSHOP_ID: &[u8] = b"Photoshop 3.0\0";
+const JFIF_ID: &[u8] = b"JFIF\0";
+/// Photoshop image resource holding the IPTC-IIM block.
+const IPTC_RESOURCE: u16 = 0x0404;
+/// IIM application record 2, dataset 25: Keywords.
+const IPTC_KEYWORDS: (u8, u8) = (2, 25);
+/// Upper bound for an inflated iTXt XMP packet.
+const MAX_XMP_BYTES: u64 = 4 * 1024 * 1024;
+
+/// Parsed metadata of one image. Fields are `None` (or empty) when the
+/// image does not carry them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Report {
+    pub make: Option<String>,
+    pub model: Option<String>,
+    pub lens: Option<String>,
+    /// `DateTimeOriginal` (or `DateTime`) as ISO 8601, with the EXIF offset
+    /// when one is recorded.
+    pub capture_date: Option<String>,
+    /// EXIF Orientation (1-8)
+    pub orientation: Option<u16>,
+    /// Exposure time in seconds
+    pub exposure_time: Option<f64>,
+    pub f_number: Option<f64>,
+    pub iso: Option<u32>,
+    /// Focal length in millimetres
+    pub focal_length: Option<f64>,
+    /// Only read when requested
+    pub gps: Option<Gps>,
+    pub copyright: Option<String>,
+    pub xmp: Option<String>,
+    pub iptc_keywords: Vec<String>,
+    pub icc_description: Option<String>,
+    /// Pixels per inch
+    pub density: Option<f64>,
+}
+
+/// GPS position in signed decimal degrees (south / west negative) and
+/// metres above sea level.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Gps {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub altitude: Option<f64>,
+}
+
+/// Read the metadata of the encoded image in `data`. `icc` is the embedded
+/// profile, already extracted by the engine.
+pub(crate) fn read(data: &[u8], icc: Option<&[u8]>, include_gps: bool) -> Report {
+    // Keep the fields that parsed when another IFD entry is broken.
+    let exif = exif::Reader::new()
+        .continue_on_error(true)
+        .read_from_container(&mut Cursor::new(data))
+        .or_else(|err| err.distill_partial_result(|_| {}))
+        .ok();
+    let mut report = Report {
+        icc_description: icc.and_then(icc::description),
+        ..Report::default()
+    };
+    if let Some(exif) = &exif {
+        read_exif(exif, include_gps, &mut report);
+    }
+
+    let container = Container::of(data);
+    report.xmp = container.xmp(data);
+    report.iptc_keywords = container.iptc_keywords(data);
+    report.density = container
+        .density(data)
+        .or_else(|| exif.as_ref().and_then(exif_density));
+    report
+}
+
+fn read_exif(exif: &Exif, include_gps: bool, report: &mut Report) {
+    report.make = text(exif, Tag::Make);
+    report.model = text(exif, Tag::Model);
+    report.lens = text(exif, Tag::LensModel).or_else(|| text(exif, Tag::LensMake));
+    report.capture_date = capture_date(exif);
+    report.orientation = uint(exif, Tag::Orientation)
+        .map(|value| value as u16)
+        .filter(|value| (1..=8).contains(value));
+    report.exposure_time = rational(exif, Tag::ExposureTime, 0);
+    report.f_number = rational(exif, Tag::FNumber, 0);
+    report.iso = uint(exif, Tag::PhotographicSensitivity);
+    report.focal_length = rational(exif, Tag::FocalLength, 0);
+    report.copyright = text(exif, Tag::Copyright);
+    if include_gps {
+        report.gps = gps(exif);
+    }
+}
+
+/// ASCII field with its NUL-separated parts joined (Copyright stores the
+/// photographer and editor that way).
+fn text(exif: &Exif, tag: Tag) -> Option<String> {
+    let field = exif.get_field(tag, In::PRIMARY)?;
+    let Value::Ascii(parts) = &field.value else {
+        return None;
+    };
+    let joined = parts
+        .iter()
+        .map(|part| String::from_utf8_lossy(part).trim().to_string())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("; ");
+    (!joined.is_empty()).then_some(joined)
+}
+
+fn uint(exif: &Exif, tag: Tag) -> Option<u32> {
+    exif.get_field(tag, In::PRIMARY)?.value.get_uint(0)
+}
+
+fn rational(exif: &Exif, tag: Tag, index: usize) -> Option<f64> {
+    match &exif.get_field(tag, In::PRIMARY)?.value {
+        Value::Rational(values) => values.get(index).map(|r| r.to_f64()),
+        Value::SRational(values) => values.get(index).map(|r| r.to_f64()),
+        _ => None,
+    }
+    .filter(|value| value.is_finite())
+}
+
+/// `YYYY:MM:DD HH:MM:SS` rewritten as `YYYY-MM-DDTHH:MM:SS[+HH:MM]`. Values
+/// that do not follow the EXIF layout are returned as written.
+fn capture_date(exif: &Exif) -> Option<String> {
+    let (raw, offset) = match text(exif, Tag::DateTimeOriginal) {
+        Some(raw) => (raw, text(exif, Tag::OffsetTimeOriginal)),
+        None => (text(exif, Tag::DateTime)?, text(exif, Tag::OffsetTime)),
+    };
+    let bytes = raw.as_bytes();
+    let exif_layout = bytes.len() == 19
+        && bytes[4] == b':'
+        && bytes[7] == b':'
+        && bytes[10] == b' '
+        && bytes[13] == b':'
+        && bytes[16] == b':';
+    if !exif_layout {
+        return Some(raw);
+    }
+    let mut iso = format!(
+        "{}-{}-{}T{}",
+        &raw[0..4],
+        &raw[5..7],
+        &raw[8..10],
+        &raw[11..19]
+    );
+    if let Some(offset) = offset {
+        iso.push_str(&offset);
+    }
+    Some(iso)
+}
+
+fn gps(exif: &Exif) -> Option<Gps> {
+    let coordinate = |tag: Tag, ref_tag: Tag, negative: &str| {
+        let degrees = rational(exif, tag, 0)?;
+        let minutes = rational(exif, tag, 1).unwrap_or(0.0);
+        let seconds = rational(exif, tag, 2).unwrap_or(0.0);
+        let value = degrees + minutes / 60.0 + seconds / 3600.0;
+        let flip = text(exif, ref_tag).is_some_and(|r| r.eq_ignore_ascii_case(negative));
+        Some(if flip { -value } else { value })
+    };
+    let latitude = coordinate(Tag::GPSLatitude, Tag::GPSLatitudeRef, "S")?;
+    let longitude = coordinate(Tag::GPSLongitude, Tag::GPSLongitudeRef, "W")?;
+    // GPSAltitudeRef 1 means below sea level.
+    let altitude =
+        rational(exif, Tag::GPSAltitude, 0).map(|metres| match uint(exif, Tag::GPSAltitudeRef) {
+            Some(1) => -metres,
+            _ => metres,
+        });
+    Some(Gps {
+        latitude,
+        longitude,
+        altitude,
+    })
+}
+
+/// XResolution in pixels per inch (ResolutionUnit 2 = inch, the default,
+/// 3 = centimetre).
+fn exif_density(exif: &Exif) -> Option<f64> {
+    let resolution = rational(exif, Tag::XResolution, 0).filter(|value| *value > 0.0)?;
+    match uint(exif, Tag::ResolutionUnit).unwrap_or(2) {
+        2 => Some(resolution),
+        3 => Some(round_density(resolution * 2.54)),
+        _ => None,
+    }
+}
+
+fn round_density(ppi: f64) -> f64 {
+    (ppi * 100.0).round() / 100.0
+}
+
+#[derive(Clone, Copy)]
+enum Container {
+    Jpeg,
+    Png,
+    WebP,
+    Other,
+}
+
+impl Container {
+    fn of(data: &[u8]) -> Self {
+        if data.starts_with(&[0xFF, 0xD8]) {
+            Container::Jpeg
+        } else if data.starts_with(&[0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A]) {
+            Container::Png
+        } else if data.len() >= 12 && data.starts_with(b"RIFF") && &data[8..12] == b"WEBP" {
+            Container::WebP
+        } else {
+            Container::Other
+        }
+    }
+
+    fn xmp(self, data: &[u8]) -> Option<String> {
+        let packet = match self {
+            Container::Jpeg => jpeg_segments(data)
+                .find(|(marker, payload)| *marker == 0xE1 && payload.starts_with(XMP_JPEG_ID))
+                .map(|(_, payload)| payload[XMP_JPEG_ID.len()..].to_vec()),
+            Container::Png => png_chunks(data)
+                .filter(|(kind, _)| kind == b"iTXt")
+                .find_map(|(_, payload)| xmp_from_itxt(payload)),
+            Container::WebP => riff_chunks(data)
+                .find(|(id, _)| id == b"XMP ")
+                .map(|(_, payload)| payload.to_vec()),
+            Container::Other => None,
+        }?;
+        let text = String::from_utf8_lossy(&packet);
+        let text = text.trim_end_matches('\0').trim();
+        (!text.is_empty()).then(|| text.to_string())
+    }
+
+    fn iptc_keywords(self, data: &[u8]) -> Vec<String> {
+        let Container::Jpeg = self else {
+            return Vec::new();
+        };
+        jpeg_segments(data)
+            .filter(|(marker, payload)| *marker == 0xED && payload.starts_with(PHOTOSHOP_ID))
+            .filter_map(|(_, payload)| photoshop_resource(&payload[PHOTOSHOP_ID.len()..]))
+            .flat_map(iim_keywords)
+            .collect()
+    }
+
+    fn density(self, data: &[u8]) -> Option<f64> {
+        match self {
+            // JFIF APP0: version (2), units (1), Xdensity (2), Ydensity (2)
+            Container::Jpeg => {
+                let (_, jfif) = jpeg_segments(data).find(|(marker, payload)| {
+                    *marker == 0xE0 && payload.starts_with(JFIF_ID) && payload.len() >= 14
+                })?;
+                let x_density = u16::from_be_bytes([jfif[8], jfif[9]]) as f64;
+                match jfif[7] {
+                    _ if x_density == 0.0 => None,
+                    1 => Some(x_density),
+                    2 => Some(round_density(x_density * 2.54)),
+                    // 0 only records the aspect ratio
+                    _ => None,
+                }
+            }
+            // pHYs: x (4), y (4), unit (1 = metre)
+            Container::Png => {
+                let (_, phys) = png_chunks(data).find(|(kind, _)| kind == b"pHYs")?;
+                if phys.len() < 9 || phys[8] != 1 {
+                    return None;
+                }
+                let per_metre = u32::from_be_bytes([phys[0], phys[1], phys[2], phys[3]]) as f64;
+                (per_metre > 0.0).then(|| round_density(per_metre * 0.0254))
+            }
+            Container::WebP | Container::Other => None,
+        }
+    }
+}
+
+/// Marker segments before the first scan, as `(marker, payload)`.
+pub(crate) fn jpeg_segments(data: &[u8]) -> impl Iterator<Item = (u8, &[u8])> {
+    let mut i = 2; // skip SOI
+    std::iter::from_fn(move || loop {
+        if i + 4 > data.len() || data[i] != 0xFF {
+            return None;
+        }
+        let marker = data[i + 1];
+        if marker == 0xFF {
+            i += 1; // fill byte
+            continue;
+        }
+        if marker == 0xDA || marker == 0xD9 {
+            return None;
+        }
+        if (0xD0..=0xD7).contains(&marker) || marker == 0x01 {
+            i += 2;
+            continue;
+        }
+        let len = u16::from_be_bytes([data[i + 2], data[i + 3]]) as usize;
+        let end = i + 2 + len;
+        if len < 2 || end > data.len() {
+            return None;
+        }
+        let payload = &data[i + 4..end];
+        i = end;
+        return Some((marker, payload));
+    })
+}
+
+/// PNG chunks after the signature, as `(type, data)`. Chunk data is sliced,
+/// never inflated, so IDAT costs nothing.
+fn png_chunks(data: &[u8]) -> impl Iterator<Item = ([u8; 4], &[u8])> {
+    let mut i = 8;
+    std::iter::from_fn(move || {
+        let header = data.get(i..i + 8)?;
+        let len = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        let kind = [header[4], header[5], header[6], header[7]];
+        let payload = data.get(i + 8..(i + 8).checked_add(len)?)?;
+        i += 12 + len; // length, type, data, CRC
+        (&kind != b"IEND").then_some((kind, payload))
+    })
+}
+
+/// RIFF chunks of a WebP file, as `(fourcc, data)`.
+fn riff_chunks(data: &[u8]) -> impl Iterator<Item = ([u8; 4], &[u8])> {
+    let mut i = 12;
+    std::iter::from_fn(move || {
+        let header = data.get(i..i + 8)?;
+        let id = [header[0], header[1], header[2], header[3]];
+        let len = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+        let payload = data.get(i + 8..(i + 8).checked_add(len)?)?;
+        i += 8 + len + len % 2; // chunks are padded to even sizes
+        Some((id, payload))
+    })
+}
+
+/// iTXt: keyword NUL, compression flag, method, language NUL, translated
+/// keyword NUL, text (zlib when flagged).
+fn xmp_from_itxt(payload: &[u8]) -> Option<Vec<u8>> {
+    let rest = payload.strip_prefix(XMP_PNG_KEYWORD)?.strip_prefix(b"\0")?;
+    let (&compressed, rest) = rest.split_first()?;
+    let rest = rest.get(1..)?; // compression method
+    let language_end = rest.iter().position(|&b| b == 0)?;
+    let rest = &rest[language_end + 1..];
+    let translated_end = rest.iter().position(|&b| b == 0)?;
+    let text = &rest[translated_end + 1..];
+    if compressed == 0 {
+        return Some(text.to_vec());
+    }
+    let mut inflated = Vec::new();
+    flate2::read::ZlibDecoder::new(text)
+        .take(MAX_XMP_BYTES)
+        .read_to_end(&mut inflated)
+        .ok()?;
+    Some(inflated)
+}
+
+/// Data of the IPTC resource in a Photoshop image resource block: repeated
+/// `8BIM`, id (2), padded Pascal name, size (4), data padded to even size.
+fn photoshop_resource(mut block: &[u8]) -> Option<&[u8]> {
+    while block.len() >= 12 && block.starts_with(b"8BIM") {
+        let id = u16::from_be_bytes([block[4], block[5]]);
+        let name_len = block[6] as usize;
+        let name_total = (name_len + 1) + (name_len + 1) % 2;
+        let size_at = 6 + name_total;
+        let size_bytes = block.get(size_at..size_at + 4)?;
+        let size = u32::from_be_bytes([size_bytes[0], size_bytes[1], size_bytes[2], size_bytes[3]])
+            as usize;
+        let data = block.get(size_at + 4..(size_at + 4).checked_add(size)?)?;
+        if id == IPTC_RESOURCE {
+            return Some(data);
+        }
+        block = block
+            .get(size_at + 4 + size + size % 2..)
+            .unwrap_or_default();
+    }
+    None
+}
+
+/// Keywords datasets of an IPTC-IIM block: `0x1C`, record, dataset, size
+/// (2), data. Extended (> 32767 bytes) datasets end the scan.
+fn iim_keywords(mut iim: &[u8]) -> Vec<String> {
+    let mut keywords = Vec::new();
+    while iim.len() >= 5 && iim[0] == 0x1C {
+        let size = u16::from_be_bytes([iim[3], iim[4]]) as usize;
+        if size & 0x8000 != 0 {
+            break;
+        }
+        let Some(value) = iim.get(5..5 + size) else {
+            break;
+        };
+        if (iim[1], iim[2]) == IPTC_KEYWORDS {
+            let keyword = String::from_utf8_lossy(value).trim().to_string();
+            if !keyword.is_empty() {
+                keywords.push(keyword);
+            }
+        }
+        iim = &iim[5 + size..];
+    }
+    keywords
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::encoder::{encode_jpeg, encode_png};
+    use exif::experimental::Writer;
+    use exif::{Field, Rational};
+    use image::{DynamicImage, RgbImage};
+
+    fn ascii(tag: Tag, value: &str) -> Field {
+        Field {
+            tag,
+            ifd_num: In::PRIMARY,
+            value: Value::Ascii(vec![value.as_bytes().to_vec()]),
+        }
+    }
+
+    fn rationals(tag: Tag, values: &[(u32, u32)]) -> Field {
+        Field {
+            tag,
+            ifd_num: In::PRIMARY,
+            value: Value::Rational(
+                values
+                    .iter()
+                    .map(|&(num, denom)| Rational { num, denom })
+                    .collect(),
+            ),
+        }
+    }
+
+    fn short(tag: Tag, value: u16) -> Field {
+        Field {
+            tag,
+            ifd_num: In::PRIMARY,
+            value: Value::Short(vec![value]),
+        }
+    }
+
+    fn tiff(fields: &[Field]) -> Vec<u8> {
+        let mut writer = Writer::new();
+        for field in fields {
+            writer.push_field(field);
+        }
+        let mut out = Cursor::new(Vec::new());
+        writer.write(&mut out, true).unwrap();
+        out.into_inner()
+    }
+
+    /// JPEG with `segments` inserted right after SOI.
+    fn jpeg_with(segments: &[(u8, Vec<u8>)]) -> Vec<u8> {
+        let img = DynamicImage::ImageRgb8(RgbImage::new(4, 4));
+        let jpeg = encode_jpeg(&img, 80, None).unwrap();
+        let mut out = jpeg[..2].to_vec();
+        for (marker, payload) in segments {
+            out.extend_from_slice(&[0xFF, *marker]);
+            out.extend_from_slice(&((payload.len() + 2) as u16).to_be_bytes());
+            out.extend_from_slice(payload);
+        }
+        out.extend_from_slice(&jpeg[2..]);
+        out
+    }
+
+    fn camera_exif() -> Vec<u8> {
+        let mut app1 = b"Exif\0\0".to_vec();
+        app1.extend(tiff(&[
+            ascii(Tag::Make, "Canon"),
+            ascii(Tag::Model, "EOS R5"),
+            ascii(Tag::LensModel, "RF24-70mm F2.8 L IS USM"),
+            ascii(Tag::DateTimeOriginal, "2024:05:01 12:34:56"),
+            ascii(Tag::OffsetTimeOriginal, "+09:00"),
+            short(Tag::Orientation, 6),
+            rationals(Tag::ExposureTime, &[(1, 250)]),
+            rationals(Tag::FNumber, &[(28, 10)]),
+            short(Tag::PhotographicSensitivity, 400),
+            rationals(Tag::FocalLength, &[(50, 1)]),
+            ascii(Tag::Copyright, "Jane Doe"),
+            rationals(Tag::XResolution, &[(300, 1)]),
+            short(Tag::ResolutionUnit, 2),
+            ascii(Tag::GPSLatitudeRef, "N"),
+            rationals(Tag::GPSLatitude, &[(35, 1), (30, 1), (0, 1)]),
+            ascii(Tag::GPSLongitudeRef, "W"),
+            rationals(Tag::GPSLongitude, &[(139, 1), (45, 1), (0, 1)]),
+            rationals(Tag::GPSAltitude, &[(40, 1)]),
+        ]));
+        app1
+    }
+
+    #[test]
+    fn reads_camera_fields_and_keeps_gps_opt_in() {
+        let jpeg = jpeg_with(&[(0xE1, camera_exif())]);
+
+        let report = read(&jpeg, None, false);
+        assert_eq!(report.make.as_deref(), Some("Canon"));
+        assert_eq!(report.model.as_deref(), Some("EOS R5"));
+        assert_eq!(report.lens.as_deref(), Some("RF24-70mm F2.8 L IS USM"));
+        assert_eq!(
+            report.capture_date.as_deref(),
+            Some("2024-05-01T12:34:56+09:00")
+        );
+        assert_eq!(report.orientation, Some(6));
+        assert_eq!(report.exposure_time, Some(0.004));
+        assert_eq!(report.f_number, Some(2.8));
+        assert_eq!(report.iso, Some(400));
+        assert_eq!(report.focal_length, Some(50.0));
+        assert_eq!(report.copyright.as_deref(), Some("Jane Doe"));
+        assert_eq!(report.gps, None);
+
+        let gps = read(&jpeg, None, true).gps.expect("GPS was requested");
+        assert!((gps.latitude - 35.5).abs() < 1e-9);
+        assert!((gps.longitude + 139.75).abs() < 1e-9);
+        assert_eq!(gps.altitude, Some(40.0));
+    }
+
+    #[test]
+    fn density_prefers_jfif_over_exif_resolution() {
+        // mozjpeg writes a JFIF header without units, so EXIF is the fallback
+        let exif_only = read(&jpeg_with(&[(0xE1, camera_exif())]), None, false);
+        assert_eq!(exif_only.density, Some(300.0));
+
+        // units 2 = dots per centimetre
+        let mut jfif = JFIF_ID.to_vec();
+        jfif.extend_from_slice(&[1, 1, 2, 0, 118, 0, 118, 0, 0]);
+        let report = read(
+            &jpeg_with(&[(0xE0, jfif), (0xE1, camera_exif())]),
+            None,
+            false,
+        );
+        assert_eq!(report.density, Some(299.72));
+    }
+
+    #[test]
+    fn reads_xmp_and_iptc_keywords_from_jpeg() {
+        let packet = "<x:xmpmeta xmlns:x=\"adobe:ns:meta/\"></x:xmpmeta>";
+        let mut xmp = XMP_JPEG_ID.to_vec();
+        xmp.extend_from_slice(packet.as_bytes());
+
+        let mut iim = Vec::new();
+        for (dataset, value) in [(25u8, "sunset"), (120, "caption"), (25, "beach")] {
+            iim.extend_from_slice(&[0x1C, 2, dataset]);
+            iim.extend_from_slice(&(value.len() as u16).to_be_bytes());
+            iim.extend_from_slice(value.as_bytes());
+        }
+        let mut app13 = PHOTOSHOP_ID.to_vec();
+        app13.extend_from_slice(b"8BIM");
+        app13.extend_from_slice(&IPTC_RESOURCE.to_be_bytes());
+        app13.extend_from_slice(&[0, 0]); // empty name, padded
+        app13.extend_from_slice(&(iim.len() as u32).to_be_bytes());
+        app13.extend_from_slice(&iim);
+
+        let report = read(&jpeg_with(&[(0xE1, xmp), (0xED, app13)]), None, false);
+        assert_eq!(report.xmp.as_deref(), Some(packet));
+        assert_eq!(report.iptc_keywords, vec!["sunset", "beach"]);
+        assert_eq!(report.make, None);
+    }
+
+    #[test]
+    fn reads_png_density_xmp_and_icc_description() {
+        use img_parts::png::{Png, PngChunk};
+        use img_parts::Bytes;
+
+        let img = DynamicImage::ImageRgb8(RgbImage::new(4, 4));
+        let mut png = Png::from_bytes(Bytes::from(encode_png(&img, None).unwrap())).unwrap();
+        let mut phys = 2835u32.to_be_bytes().repeat(2);
+        phys.push(1);
+        let mut itxt = XMP_PNG_KEYWORD.to_vec();
+        itxt.extend_from_slice(b"\0\0\0\0\0<x:xmpmeta/>");
+        png.chunks_mut()
+            .insert(1, PngChunk::new(*b"pHYs", Bytes::from(phys)));
+        png.chunks_mut()
+            .insert(2, PngChunk::new(*b"iTXt", Bytes::from(itxt)));
+        let mut bytes = Vec::new();
+        png.encoder().write_to(&mut bytes).unwrap();
+
+        let p3 = moxcms::ColorProfile::new_display_p3().encode().unwrap();
+        let report = read(&bytes, Some(&p3), false);
+        assert_eq!(report.density, Some(72.01));
+        assert_eq!(report.xmp.as_deref(), Some("<x:xmpmeta/>"));
+        assert!(report.icc_description.is_some());
+        assert_eq!(report.orientation, None);
+    }
+
+    #[test]
+    fn malformed_blocks_leave_fields_empty() {
+        let truncated = jpeg_with(&[
+            (0xED, PHOTOSHOP_ID.to_vec()),
+            (0xE1, b"Exif\0\0II".to_vec()),
+        ]);
+        assert_eq!(read(&truncated, None, true), Report::default());
+        assert_eq!(
+            read(b"not an image at all", Some(b"junk"), true),
+            Report::default()
+        );
+    }
+}
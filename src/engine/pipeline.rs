@@ -2,10 +2,29 @@
 //
 // Pipeline operations: apply_ops, optimize_ops, resize calculations
 
+use crate::engine::channels;
+use crate::engine::composite;
+use crate::engine::decoder::{
+    check_dimensions, decode_image, ensure_dimensions_safe, read_dimensions,
+};
+use crate::engine::filter;
+use crate::engine::grade;
+use crate::engine::icc;
+use crate::engine::linear;
+use crate::engine::rotate;
+use crate::engine::smartcrop::{self, SmartCropStrategy};
+use crate::engine::tone;
+use crate::engine::trim;
 use crate::error::LazyImageError;
-use crate::ops::{Operation, OperationContract, OperationEffect, OperationRequirement, ResizeFit};
+use crate::ops::{
+    Color, ExtendMode, Operation, OperationContract, OperationEffect, OperationRequirement,
+    PointOp, ResizeFit, ResizeKernel, ResizePosition, Sharpen,
+};
+use crate::CropRegion;
 use fast_image_resize::{self as fir, ImageBufferError, MulDiv, PixelType, ResizeOptions};
-use image::{imageops::FilterType, DynamicImage, RgbImage, RgbaImage};
+use image::{
+    imageops, imageops::FilterType, DynamicImage, ImageBuffer, Rgb, RgbImage, Rgba, RgbaImage,
+};
 use std::borrow::Cow;
 
 #[cfg(feature = "cow-debug")]
@@ -27,6 +46,7 @@ struct OperationCapabilities {
     decoded_pixels: bool,
     color_state_tracked: bool,
     orientation_available: bool,
+    srgb_transfer: bool,
 }
 
 impl OperationCapabilities {
@@ -36,6 +56,9 @@ impl OperationCapabilities {
             color_state_tracked: true,
             // EXIF Orientation is parsed during decode; assume available unless stripped.
             orientation_available: true,
+            // Decoders hand over sRGB-encoded samples; linear light only
+            // exists inside a `linear` resize.
+            srgb_transfer: true,
         }
     }
 
@@ -52,11 +75,16 @@ impl OperationCapabilities {
                 .requires
                 .contains(OperationRequirement::ORIENTATION)
                 || self.orientation_available)
+            && (!contract
+                .requires
+                .contains(OperationRequirement::SRGB_TRANSFER)
+                || self.srgb_transfer)
     }
 
     fn apply(&mut self, contract: &OperationContract) {
         if contract.effects.contains(OperationEffect::NORMALIZES_COLOR) {
             self.color_state_tracked = true;
+            self.srgb_transfer = true;
         }
     }
 }
@@ -81,9 +109,63 @@ fn validate_operation_sequence_with_caps(
         }
         caps.apply(&contract);
     }
+    if !caps.meets(&OperationContract::ENCODE) {
+        return Err(LazyImageError::invalid_argument(
+            "operation",
+            OperationContract::ENCODE.name,
+            "pipeline output does not satisfy the encoder contract",
+        ));
+    }
     Ok(())
 }
 
+/// Runtime check that the tracked state satisfies the encoder contract
+/// (see `OutputFormat::encode_contract`). Linear light must never reach an
+/// encoder, and alpha must never reach one that would silently drop it.
+pub fn ensure_encodable(state: &ColorState, contract: &OperationContract) -> PipelineResult<()> {
+    if state.meets(contract) {
+        Ok(())
+    } else {
+        Err(LazyImageError::internal_panic(format!(
+            "encoder received pixels with {:?} transfer and {:?} layout",
+            state.transfer, state.color_space
+        )))
+    }
+}
+
+/// Flatten onto `background` when `contract` forbids alpha and the tracked
+/// state still has it (e.g. a transparent PNG written as JPEG). Anything else
+/// is returned untouched, so the zero-copy path is kept.
+pub fn flatten_for_encode<'a>(
+    tracked: ColorTrackedImage<'a>,
+    contract: &OperationContract,
+    background: Color,
+) -> PipelineResult<ColorTrackedImage<'a>> {
+    if !contract.requires.contains(OperationRequirement::OPAQUE) || !tracked.state.has_alpha() {
+        return Ok(tracked);
+    }
+    let flattened = apply_ops_tracked(
+        tracked.image,
+        &[Operation::Flatten { background }],
+        tracked.state,
+    )?;
+    Ok(ColorTrackedImage {
+        smart_crop: tracked.smart_crop,
+        trim: tracked.trim,
+        ..flattened
+    })
+}
+
+/// Resampled onto an 8-bit canvas; alpha is added when either the image or
+/// the fill needs it.
+fn resampled(state: &mut ColorState, background: &Color) {
+    state.color_space = match state.color_space {
+        ColorSpace::Rgb | ColorSpace::Luma if background.is_opaque() => ColorSpace::Rgb,
+        _ => ColorSpace::Rgba,
+    };
+    state.bit_depth = BitDepth::Eight;
+}
+
 fn update_color_state(mut state: ColorState, op: &Operation) -> ColorState {
     match op {
         Operation::Grayscale => {
@@ -92,11 +174,11 @@ fn update_color_state(mut state: ColorState, op: &Operation) -> ColorState {
             // Pipeline uses to_luma8(), so bit depth is always 8-bit after this op.
             state.bit_depth = BitDepth::Eight;
         }
-        Operation::Brightness { .. } | Operation::Contrast { .. } => {
-            // These ops operate on 8-bit buffers in our pipeline; if we had 16-bit, mark it unknown.
-            if state.bit_depth == BitDepth::Sixteen {
-                state.bit_depth = BitDepth::Unknown;
-            }
+        Operation::PointLut { steps } => {
+            // Fused steps keep the per-step layout rules.
+            return steps.iter().fold(state, |state, step| {
+                update_color_state(state, &step.to_operation())
+            });
         }
         Operation::ColorSpace { target: _ } => {
             // Pixel-format normalization forces RGB8 (no alpha) today.
@@ -104,12 +186,132 @@ fn update_color_state(mut state: ColorState, op: &Operation) -> ColorState {
             state.bit_depth = BitDepth::Eight;
             state.transfer = TransferFn::Srgb;
         }
+        Operation::IccTransform { target, .. } => {
+            // Output is RGB(A) at the input depth (float layouts become RGBA16)
+            // and tagged with the target profile; CMYK ink becomes RGB8.
+            state.color_space = match state.color_space {
+                ColorSpace::Luma | ColorSpace::Rgb | ColorSpace::Cmyk => ColorSpace::Rgb,
+                _ => ColorSpace::Rgba,
+            };
+            if state.bit_depth == BitDepth::Unknown {
+                state.bit_depth = BitDepth::Sixteen;
+            }
+            state.icc = IccState::Converted;
+            state.gamut = icc::gamut(Some(target));
+        }
+        Operation::Resize {
+            width: Some(_),
+            height: Some(_),
+            fit: ResizeFit::Contain { background },
+            ..
+        } => {
+            // Letterboxing with a translucent background adds an alpha channel.
+            if !background.is_opaque() {
+                state.color_space = ColorSpace::Rgba;
+            }
+        }
+        Operation::Extend {
+            top,
+            right,
+            bottom,
+            left,
+            background,
+            mode: ExtendMode::Background,
+        } if (*top | *right | *bottom | *left) != 0 => {
+            // Only opaque fills on RGB input keep the layout; everything else
+            // is composited on an RGBA8 canvas.
+            if !background.is_opaque() || state.color_space != ColorSpace::Rgb {
+                state.color_space = ColorSpace::Rgba;
+            }
+            state.bit_depth = BitDepth::Eight;
+        }
+        Operation::Flatten { .. } => {
+            // 16-bit input stays 16-bit; everything else is flattened to RGB8.
+            if state.has_alpha() {
+                state.color_space = ColorSpace::Rgb;
+                if state.bit_depth != BitDepth::Sixteen {
+                    state.bit_depth = BitDepth::Eight;
+                }
+            }
+        }
+        Operation::EnsureAlpha { .. } => {
+            state.color_space = match state.color_space {
+                ColorSpace::Luma | ColorSpace::LumaA => ColorSpace::LumaA,
+                _ => ColorSpace::Rgba,
+            };
+            if state.bit_depth != BitDepth::Sixteen {
+                state.bit_depth = BitDepth::Eight;
+            }
+        }
+        Operation::RemoveAlpha => {
+            state.color_space = match state.color_space {
+                ColorSpace::Luma | ColorSpace::LumaA => ColorSpace::Luma,
+                _ => ColorSpace::Rgb,
+            };
+            if state.bit_depth != BitDepth::Sixteen {
+                state.bit_depth = BitDepth::Eight;
+            }
+        }
+        Operation::ExtractChannel { .. } => {
+            state.color_space = ColorSpace::Luma;
+            if state.bit_depth != BitDepth::Sixteen {
+                state.bit_depth = BitDepth::Eight;
+            }
+        }
+        Operation::JoinChannel { .. } => {
+            state.color_space = match state.color_space {
+                ColorSpace::Luma | ColorSpace::LumaA => ColorSpace::LumaA,
+                _ => ColorSpace::Rgba,
+            };
+            if state.bit_depth != BitDepth::Sixteen {
+                state.bit_depth = BitDepth::Eight;
+            }
+        }
+        Operation::Threshold {
+            grayscale: true, ..
+        } => {
+            state.color_space = match state.color_space {
+                ColorSpace::Rgba | ColorSpace::LumaA => ColorSpace::LumaA,
+                ColorSpace::Rgb | ColorSpace::Luma | ColorSpace::Cmyk => ColorSpace::Luma,
+                ColorSpace::Unknown => ColorSpace::Unknown,
+            };
+            state.bit_depth = BitDepth::Eight;
+        }
+        Operation::Recomb { .. } | Operation::Lut3d { .. } | Operation::Composite { .. } => {
+            // Greyscale is expanded to RGB(A) at the same bit depth.
+            state.color_space = match state.color_space {
+                ColorSpace::Luma => ColorSpace::Rgb,
+                ColorSpace::LumaA => ColorSpace::Rgba,
+                other => other,
+            };
+        }
+        Operation::RotateArbitrary {
+            degrees,
+            background,
+            ..
+        } if rotate::right_angle(*degrees).is_none() => resampled(&mut state, background),
+        Operation::Affine { background, .. } => resampled(&mut state, background),
         Operation::Resize { .. }
         | Operation::Extract { .. }
+        | Operation::Extend { .. }
+        | Operation::Trim { .. }
         | Operation::Crop { .. }
         | Operation::Rotate { .. }
+        | Operation::RotateArbitrary { .. }
         | Operation::FlipH
         | Operation::FlipV
+        | Operation::Modulate { .. }
+        | Operation::Brightness { .. }
+        | Operation::Contrast { .. }
+        | Operation::Gamma { .. }
+        | Operation::Negate
+        | Operation::Threshold { .. }
+        | Operation::Levels { .. }
+        | Operation::Normalize { .. }
+        | Operation::Clahe { .. }
+        | Operation::Sharpen { .. }
+        | Operation::Blur { .. }
+        | Operation::Pixelate { .. }
         | Operation::AutoOrient { .. } => {}
     }
     state
@@ -122,6 +324,9 @@ pub enum ColorSpace {
     Rgba,
     Luma,
     LumaA,
+    /// CMYK ink from a print JPEG, held in an RGBA8 buffer until the
+    /// `IccTransform` that `icc::bind_profiles` puts first converts it
+    Cmyk,
     Unknown,
 }
 
@@ -135,6 +340,8 @@ pub enum BitDepth {
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum TransferFn {
     Srgb,
+    /// Linear light (only inside a gamma-correct resize)
+    Linear,
     Unknown,
 }
 
@@ -143,15 +350,46 @@ pub enum TransferFn {
 pub enum IccState {
     Present,
     Absent,
+    /// Converted by an `IccTransform`; the target profile describes the pixels
+    Converted,
+}
+
+/// Colour primaries and transfer curve of the pixels, as far as encoders can
+/// signal them without an ICC profile (CICP in AVIF, `cICP`/`sRGB` in PNG).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Gamut {
+    /// sRGB (BT.709 primaries, sRGB curve); also untagged pixels
+    Srgb,
+    /// Display P3 (P3 primaries, D65 white, sRGB curve)
+    DisplayP3,
+    /// BT.2020 primaries with the BT.709 curve
+    Bt2020,
+    /// Only described by an ICC profile
+    Unknown,
+}
+
+impl Gamut {
+    /// ITU-T H.273 colour primaries and transfer characteristics
+    /// (2 = unspecified for `Unknown`).
+    pub fn cicp(self) -> (u8, u8) {
+        match self {
+            Gamut::Srgb => (1, 13),
+            Gamut::DisplayP3 => (12, 13),
+            Gamut::Bt2020 => (9, 1),
+            Gamut::Unknown => (2, 2),
+        }
+    }
 }
 
-/// Pipeline color state (color space + bit depth + transfer + ICC presence).
+/// Pipeline color state (color space + bit depth + transfer + ICC presence
+/// + gamut).
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct ColorState {
     pub color_space: ColorSpace,
     pub bit_depth: BitDepth,
     pub transfer: TransferFn,
     pub icc: IccState,
+    pub gamut: Gamut,
 }
 
 impl ColorState {
@@ -186,14 +424,39 @@ impl ColorState {
             bit_depth,
             transfer,
             icc,
+            gamut: Gamut::Srgb,
         }
     }
+
+    /// Whether the pixels may carry an alpha channel (unknown layouts are
+    /// assumed to).
+    pub fn has_alpha(&self) -> bool {
+        matches!(
+            self.color_space,
+            ColorSpace::Rgba | ColorSpace::LumaA | ColorSpace::Unknown
+        )
+    }
+
+    /// Whether the tracked state satisfies the runtime parts of `contract`.
+    /// CMYK ink never does.
+    pub fn meets(&self, contract: &OperationContract) -> bool {
+        self.color_space != ColorSpace::Cmyk
+            && (!contract
+                .requires
+                .contains(OperationRequirement::SRGB_TRANSFER)
+                || self.transfer != TransferFn::Linear)
+            && (!contract.requires.contains(OperationRequirement::OPAQUE) || !self.has_alpha())
+    }
 }
 
 /// Image plus tracked color state.
 pub struct ColorTrackedImage<'a> {
     pub image: Cow<'a, DynamicImage>,
     pub state: ColorState,
+    /// Window chosen by the last content-aware cover crop (for auditing)
+    pub smart_crop: Option<CropRegion>,
+    /// Region kept by the last trim, relative to the image it received
+    pub trim: Option<CropRegion>,
 }
 
 // Note: to_pipeline_error is no longer needed
@@ -224,7 +487,8 @@ impl ResizeError {
     }
 }
 
-/// Calculate resize dimensions maintaining aspect ratio (fit = inside semantics)
+/// Calculate resize dimensions maintaining aspect ratio (fit = inside semantics).
+/// Contain uses the same scaled size and pads the remainder afterwards.
 pub fn calc_resize_dimensions(
     orig_w: u32,
     orig_h: u32,
@@ -259,6 +523,30 @@ pub fn calc_resize_dimensions(
     }
 }
 
+/// Calculate the smallest aspect-preserving size that covers the box
+/// (fit = outside semantics). Unlike cover, nothing is cropped afterwards.
+pub fn calc_outside_resize_dimensions(
+    orig_w: u32,
+    orig_h: u32,
+    target_w: u32,
+    target_h: u32,
+) -> (u32, u32) {
+    if orig_w == 0 || orig_h == 0 {
+        return (target_w.max(1), target_h.max(1));
+    }
+    let orig_ratio = orig_w as f64 / orig_h as f64;
+    let target_ratio = target_w as f64 / target_h.max(1) as f64;
+    if orig_ratio > target_ratio {
+        // Original image is wider → match height, overflow width
+        let ratio = target_h as f64 / orig_h as f64;
+        (((orig_w as f64 * ratio).round() as u32).max(1), target_h)
+    } else {
+        // Original image is taller → match width, overflow height
+        let ratio = target_w as f64 / orig_w as f64;
+        (target_w, ((orig_h as f64 * ratio).round() as u32).max(1))
+    }
+}
+
 fn validate_resize_dimensions(width: u32, height: u32) -> PipelineResult<()> {
     if width == 0 || height == 0 {
         return Err(LazyImageError::invalid_resize_dimensions(
@@ -286,23 +574,358 @@ fn calc_cover_resize_dimensions(
     (resize_w, resize_h)
 }
 
-fn crop_to_dimensions(img: DynamicImage, target_w: u32, target_h: u32) -> DynamicImage {
+/// Resolve where a cover crop window sits inside `frame`.
+/// `img` is only inspected for content-aware positions and may be any
+/// resolution of the frame's content.
+fn cover_crop_offset(
+    img: &DynamicImage,
+    frame: (u32, u32),
+    target: (u32, u32),
+    position: ResizePosition,
+) -> (u32, u32) {
+    match position {
+        ResizePosition::Entropy => {
+            smartcrop::find_crop_offset(img, frame, target, SmartCropStrategy::Entropy)
+        }
+        ResizePosition::Attention => {
+            smartcrop::find_crop_offset(img, frame, target, SmartCropStrategy::Attention)
+        }
+        other => other.crop_offset(frame, target),
+    }
+}
+
+fn crop_to_dimensions(
+    img: DynamicImage,
+    target_w: u32,
+    target_h: u32,
+    position: ResizePosition,
+) -> (DynamicImage, CropRegion) {
     let crop_width = target_w.min(img.width()).max(1);
     let crop_height = target_h.min(img.height()).max(1);
-    let crop_x = if img.width() > crop_width {
-        (img.width() - crop_width) / 2
+    let (crop_x, crop_y) = cover_crop_offset(
+        &img,
+        (img.width(), img.height()),
+        (crop_width, crop_height),
+        position,
+    );
+    let region = CropRegion {
+        left: crop_x,
+        top: crop_y,
+        width: crop_width,
+        height: crop_height,
+    };
+    (
+        img.crop_imm(crop_x, crop_y, crop_width, crop_height),
+        region,
+    )
+}
+
+/// Place `img` at `offset` on a `canvas_w` x `canvas_h` canvas filled with
+/// `background`. RGB input stays RGB when the background is opaque.
+fn pad_to_canvas(
+    img: DynamicImage,
+    canvas_w: u32,
+    canvas_h: u32,
+    offset: (u32, u32),
+    background: Color,
+) -> DynamicImage {
+    let (x, y) = (offset.0 as i64, offset.1 as i64);
+    match img {
+        DynamicImage::ImageRgb8(rgb) if background.is_opaque() => {
+            let mut canvas = RgbImage::from_pixel(canvas_w, canvas_h, Rgb(background.to_rgb()));
+            imageops::replace(&mut canvas, &rgb, x, y);
+            DynamicImage::ImageRgb8(canvas)
+        }
+        other => {
+            let rgba = match other {
+                DynamicImage::ImageRgba8(rgba) => rgba,
+                other => other.to_rgba8(),
+            };
+            let mut canvas = RgbaImage::from_pixel(canvas_w, canvas_h, Rgba(background.to_rgba()));
+            imageops::replace(&mut canvas, &rgba, x, y);
+            DynamicImage::ImageRgba8(canvas)
+        }
+    }
+}
+
+/// `joinChannel()` masks must be exactly the size of the image they join.
+fn check_mask_size(width: u32, height: u32, img: &DynamicImage) -> PipelineResult<()> {
+    if (width, height) == (img.width(), img.height()) {
+        return Ok(());
+    }
+    Err(LazyImageError::invalid_argument(
+        "mask",
+        format!("{width}x{height}"),
+        format!(
+            "mask must match the image size ({}x{})",
+            img.width(),
+            img.height()
+        ),
+    ))
+}
+
+/// Composite onto the opaque `background` (its alpha is ignored) and drop
+/// the alpha channel. 16-bit images stay 16-bit.
+fn flatten(img: DynamicImage, background: Color) -> DynamicImage {
+    if !img.color().has_alpha() {
+        return img;
+    }
+    let [r, g, b] = background.to_rgb();
+    match img {
+        DynamicImage::ImageRgba16(_) | DynamicImage::ImageLumaA16(_) => {
+            let rgba = img.into_rgba16();
+            let fill = [r as u32 * 257, g as u32 * 257, b as u32 * 257];
+            let out = image::ImageBuffer::from_fn(rgba.width(), rgba.height(), |x, y| {
+                let p = rgba.get_pixel(x, y).0;
+                let a = p[3] as u32;
+                let blend =
+                    |c: u16, bg: u32| ((c as u32 * a + bg * (65535 - a) + 32767) / 65535) as u16;
+                Rgb([
+                    blend(p[0], fill[0]),
+                    blend(p[1], fill[1]),
+                    blend(p[2], fill[2]),
+                ])
+            });
+            DynamicImage::ImageRgb16(out)
+        }
+        other => {
+            let rgba = other.into_rgba8();
+            let fill = [r as u32, g as u32, b as u32];
+            let out = RgbImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+                let p = rgba.get_pixel(x, y).0;
+                let a = p[3] as u32;
+                let blend = |c: u8, bg: u32| ((c as u32 * a + bg * (255 - a) + 127) / 255) as u8;
+                Rgb([
+                    blend(p[0], fill[0]),
+                    blend(p[1], fill[1]),
+                    blend(p[2], fill[2]),
+                ])
+            });
+            DynamicImage::ImageRgb8(out)
+        }
+    }
+}
+
+/// Source index for position `pos` (relative to the image origin, may be
+/// negative or past the end) when extending by edge copy or mirroring.
+fn extend_source_index(pos: i64, len: u32, mode: ExtendMode) -> u32 {
+    let len = len as i64;
+    match mode {
+        ExtendMode::Mirror => {
+            // Symmetric reflection (edge pixel repeated), periodic in 2 * len.
+            let period = 2 * len;
+            let wrapped = pos.rem_euclid(period);
+            (if wrapped < len {
+                wrapped
+            } else {
+                period - 1 - wrapped
+            }) as u32
+        }
+        _ => pos.clamp(0, len - 1) as u32,
+    }
+}
+
+fn remap_pixels<P>(
+    src: &image::ImageBuffer<P, Vec<P::Subpixel>>,
+    xs: &[u32],
+    ys: &[u32],
+) -> image::ImageBuffer<P, Vec<P::Subpixel>>
+where
+    P: image::Pixel,
+{
+    image::ImageBuffer::from_fn(xs.len() as u32, ys.len() as u32, |x, y| {
+        *src.get_pixel(xs[x as usize], ys[y as usize])
+    })
+}
+
+/// Grow `img` to `canvas` with the image at `offset`, filling the border from
+/// the image's own edges. The pixel layout is preserved.
+fn extend_from_edges(
+    img: DynamicImage,
+    canvas: (u32, u32),
+    offset: (u32, u32),
+    mode: ExtendMode,
+) -> DynamicImage {
+    let (w, h) = (img.width(), img.height());
+    let xs: Vec<u32> = (0..canvas.0)
+        .map(|x| extend_source_index(x as i64 - offset.0 as i64, w, mode))
+        .collect();
+    let ys: Vec<u32> = (0..canvas.1)
+        .map(|y| extend_source_index(y as i64 - offset.1 as i64, h, mode))
+        .collect();
+    match img {
+        DynamicImage::ImageRgb8(buf) => DynamicImage::ImageRgb8(remap_pixels(&buf, &xs, &ys)),
+        DynamicImage::ImageRgba8(buf) => DynamicImage::ImageRgba8(remap_pixels(&buf, &xs, &ys)),
+        DynamicImage::ImageLuma8(buf) => DynamicImage::ImageLuma8(remap_pixels(&buf, &xs, &ys)),
+        DynamicImage::ImageLumaA8(buf) => DynamicImage::ImageLumaA8(remap_pixels(&buf, &xs, &ys)),
+        DynamicImage::ImageRgb16(buf) => DynamicImage::ImageRgb16(remap_pixels(&buf, &xs, &ys)),
+        DynamicImage::ImageRgba16(buf) => DynamicImage::ImageRgba16(remap_pixels(&buf, &xs, &ys)),
+        DynamicImage::ImageLuma16(buf) => DynamicImage::ImageLuma16(remap_pixels(&buf, &xs, &ys)),
+        DynamicImage::ImageLumaA16(buf) => DynamicImage::ImageLumaA16(remap_pixels(&buf, &xs, &ys)),
+        DynamicImage::ImageRgb32F(buf) => DynamicImage::ImageRgb32F(remap_pixels(&buf, &xs, &ys)),
+        DynamicImage::ImageRgba32F(buf) => DynamicImage::ImageRgba32F(remap_pixels(&buf, &xs, &ys)),
+        other => DynamicImage::ImageRgba8(remap_pixels(&other.to_rgba8(), &xs, &ys)),
+    }
+}
+
+/// Sizes a resize works with: the source is scaled to `scaled`, then cropped
+/// (cover) or padded (contain) to `output`. For the other fits both are equal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct ResizePlan {
+    pub scaled: (u32, u32),
+    pub output: (u32, u32),
+}
+
+/// Resolve a resize against the dimensions it will actually receive,
+/// applying the `withoutEnlargement` / `withoutReduction` guards.
+///
+/// Guards clamp the scale factor to 1.0: aspect-preserving fits keep the
+/// current size when the requested one would grow (or shrink) it and Fill
+/// clamps each axis on its own. Cover then crops what it still can, and
+/// Contain pads to the larger of the box and the kept image.
+pub(crate) fn plan_resize(
+    current: (u32, u32),
+    width: Option<u32>,
+    height: Option<u32>,
+    fit: &ResizeFit,
+    without_enlargement: bool,
+    without_reduction: bool,
+) -> ResizePlan {
+    let clamp_axis = |target: u32, current: u32| {
+        let mut value = target;
+        if without_enlargement {
+            value = value.min(current);
+        }
+        if without_reduction {
+            value = value.max(current);
+        }
+        value
+    };
+    let guard = |scaled: (u32, u32)| {
+        let enlarges = scaled.0 > current.0 || scaled.1 > current.1;
+        let reduces = scaled.0 < current.0 || scaled.1 < current.1;
+        if (without_enlargement && enlarges) || (without_reduction && reduces) {
+            current
+        } else {
+            scaled
+        }
+    };
+    let same = |dims: (u32, u32)| ResizePlan {
+        scaled: dims,
+        output: dims,
+    };
+
+    match (fit, width, height) {
+        (ResizeFit::Fill, Some(w), Some(h)) => {
+            same((clamp_axis(w, current.0), clamp_axis(h, current.1)))
+        }
+        (ResizeFit::Cover, Some(w), Some(h)) => {
+            let scaled = guard(calc_cover_resize_dimensions(current.0, current.1, w, h));
+            ResizePlan {
+                scaled,
+                output: (w.min(scaled.0), h.min(scaled.1)),
+            }
+        }
+        (ResizeFit::Contain { .. }, Some(w), Some(h)) => {
+            let scaled = guard(calc_resize_dimensions(
+                current.0,
+                current.1,
+                Some(w),
+                Some(h),
+            ));
+            ResizePlan {
+                scaled,
+                output: (w.max(scaled.0), h.max(scaled.1)),
+            }
+        }
+        (ResizeFit::Outside, Some(w), Some(h)) => same(guard(calc_outside_resize_dimensions(
+            current.0, current.1, w, h,
+        ))),
+        _ => same(guard(calc_resize_dimensions(
+            current.0, current.1, width, height,
+        ))),
+    }
+}
+
+/// Resample `img` to `dims` in linear light. `state.transfer` records the
+/// excursion so the result can be checked against the encoder contract.
+fn scale_linear(
+    img: DynamicImage,
+    dims: (u32, u32),
+    kernel: ResizeKernel,
+    state: &mut ColorState,
+) -> PipelineResult<DynamicImage> {
+    let src_dims = (img.width(), img.height());
+    let linear = linear::LinearImage::from_srgb(img);
+    let sixteen_bit = linear.is_sixteen_bit();
+    state.transfer = TransferFn::Linear;
+    let img = linear
+        .resize(dims.0, dims.1, &resize_options(kernel))
+        .and_then(linear::LinearImage::into_srgb)
+        .map_err(|reason| ResizeError::new(src_dims, dims, reason).into_lazy_image_error())?;
+    state.transfer = TransferFn::Srgb;
+    state.bit_depth = if sixteen_bit {
+        BitDepth::Sixteen
     } else {
-        0
+        BitDepth::Eight
     };
-    let crop_y = if img.height() > crop_height {
-        (img.height() - crop_height) / 2
+    state.color_space = if img.color().has_alpha() {
+        ColorSpace::Rgba
     } else {
-        0
+        ColorSpace::Rgb
     };
-    img.crop_imm(crop_x, crop_y, crop_width, crop_height)
+    Ok(img)
+}
+
+/// Resample `img` to `dims` with `kernel` (no-op when the size already matches).
+fn scale_to(
+    img: DynamicImage,
+    dims: (u32, u32),
+    kernel: ResizeKernel,
+    log_copy: &impl Fn(&str, (u32, u32)),
+) -> PipelineResult<DynamicImage> {
+    if dims == (img.width(), img.height()) {
+        return Ok(img);
+    }
+    if tone::Layout::of(&img).is_none() {
+        log_copy(
+            "to_rgba8 (normalize before resize)",
+            (img.width(), img.height()),
+        );
+    }
+    fast_resize_owned_impl(img, dims.0, dims.1, resize_options(kernel))
+        .map_err(|err| err.into_lazy_image_error())
+}
+
+/// Crop (cover) or pad (contain) an image already scaled to `plan.scaled`
+/// down/up to `plan.output`. Returns the window picked by a content-aware
+/// cover crop, if any.
+fn fit_to_output(
+    img: DynamicImage,
+    plan: ResizePlan,
+    fit: &ResizeFit,
+    position: ResizePosition,
+) -> (DynamicImage, Option<CropRegion>) {
+    if plan.output == (img.width(), img.height()) {
+        return (img, None);
+    }
+    match fit {
+        ResizeFit::Cover => {
+            let (cropped, region) = crop_to_dimensions(img, plan.output.0, plan.output.1, position);
+            (cropped, position.is_content_aware().then_some(region))
+        }
+        ResizeFit::Contain { background } => {
+            // Content-aware strategies have nothing to pick here; they behave like centre.
+            let offset = position.crop_offset(plan.output, plan.scaled);
+            let padded = pad_to_canvas(img, plan.output.0, plan.output.1, offset, *background);
+            (padded, None)
+        }
+        _ => (img, None),
+    }
 }
 
-/// Optimize operations by combining consecutive resize/crop operations
+/// Optimize operations by combining consecutive resize/crop operations and
+/// fusing runs of per-pixel tonal ops into a single `PointLut` pass
 pub fn optimize_ops(ops: &[Operation]) -> Vec<Operation> {
     if ops.len() < 2 {
         return ops.to_vec();
@@ -314,11 +937,19 @@ pub fn optimize_ops(ops: &[Operation]) -> Vec<Operation> {
     while i < ops.len() {
         let current = &ops[i];
 
-        // Try to combine consecutive resize operations
+        // Try to combine consecutive resize operations.
+        // Guarded resizes clamp against the dimensions they actually receive, which
+        // depend on every earlier resize, so they are never merged.
         if let Operation::Resize {
             width: w1,
             height: h1,
             fit,
+            position,
+            without_enlargement: false,
+            without_reduction: false,
+            kernel,
+            linear,
+            sharpen,
         } = current
         {
             let mut final_width = *w1;
@@ -332,9 +963,20 @@ pub fn optimize_ops(ops: &[Operation]) -> Vec<Operation> {
                     width: w2,
                     height: h2,
                     fit: fit2,
+                    position: position2,
+                    without_enlargement: false,
+                    without_reduction: false,
+                    kernel: kernel2,
+                    linear: linear2,
+                    sharpen: sharpen2,
                 } = &ops[j]
                 {
-                    if *fit2 != fit_mode {
+                    if *fit2 != fit_mode
+                        || position2 != position
+                        || kernel2 != kernel
+                        || linear2 != linear
+                        || sharpen2 != sharpen
+                    {
                         break;
                     }
                     // If both dimensions are specified, use the last one
@@ -361,32 +1003,62 @@ pub fn optimize_ops(ops: &[Operation]) -> Vec<Operation> {
                     width: final_width,
                     height: final_height,
                     fit: fit_mode,
+                    position: *position,
+                    without_enlargement: false,
+                    without_reduction: false,
+                    kernel: *kernel,
+                    linear: *linear,
+                    sharpen: *sharpen,
                 });
                 i = j;
                 continue;
             }
         }
 
+        // Collapse consecutive per-pixel tonal ops into one lookup-table pass.
+        let steps: Vec<PointOp> = ops[i..].iter().map_while(PointOp::from_operation).collect();
+        if steps.len() > 1 {
+            i += steps.len();
+            optimized.push(Operation::PointLut { steps });
+            continue;
+        }
+
         // Try to optimize crop + resize or resize + crop
         if i + 1 < ops.len() {
             match (&ops[i], &ops[i + 1]) {
                 // Resize then crop: fuse into single Extract to avoid intermediate buffer
                 (
-                    Operation::Resize { width, height, fit },
+                    Operation::Resize {
+                        width,
+                        height,
+                        fit,
+                        position,
+                        without_enlargement,
+                        without_reduction,
+                        kernel,
+                        linear: false,
+                        sharpen: false,
+                    },
                     Operation::Crop {
                         x,
                         y,
                         width: cw,
                         height: ch,
                     },
-                ) if *fit != ResizeFit::Cover => {
-                    // Cover fit scales to the larger dimension, maximizing intermediate buffers.
-                    // Fusing Cover into Extract doesn't reduce memory peak, so we only fuse
-                    // Inside/Fill to reduce peak memory and copies.
+                ) if matches!(fit, ResizeFit::Inside | ResizeFit::Fill) => {
+                    // Cover/Outside scale to the larger dimension, maximizing intermediate buffers,
+                    // and Contain pads after resizing. Fusing them into Extract doesn't reduce
+                    // memory peak, so we only fuse Inside/Fill to reduce peak memory and copies.
+                    // Linear-light resizes need a 16-bit working buffer anyway and stay unfused,
+                    // as do auto-sharpened ones, which must see the whole resized frame.
                     optimized.push(Operation::Extract {
                         width: *width,
                         height: *height,
                         fit: fit.clone(),
+                        position: *position,
+                        without_enlargement: *without_enlargement,
+                        without_reduction: *without_reduction,
+                        kernel: *kernel,
                         crop_x: *x,
                         crop_y: *y,
                         crop_width: *cw,
@@ -407,10 +1079,26 @@ pub fn optimize_ops(ops: &[Operation]) -> Vec<Operation> {
                         width: rw,
                         height: rh,
                         fit,
+                        position,
+                        without_enlargement,
+                        without_reduction,
+                        kernel,
+                        linear,
+                        sharpen,
                     },
                 ) => {
                     if *fit == ResizeFit::Inside {
-                        let (final_w, final_h) = calc_resize_dimensions(*cw, *ch, *rw, *rh);
+                        // The crop size is exactly what the resize will receive, so the
+                        // guards can be resolved here as well.
+                        let (final_w, final_h) = plan_resize(
+                            (*cw, *ch),
+                            *rw,
+                            *rh,
+                            fit,
+                            *without_enlargement,
+                            *without_reduction,
+                        )
+                        .output;
                         optimized.push(Operation::Crop {
                             x: *x,
                             y: *y,
@@ -421,6 +1109,12 @@ pub fn optimize_ops(ops: &[Operation]) -> Vec<Operation> {
                             width: Some(final_w),
                             height: Some(final_h),
                             fit: ResizeFit::Inside,
+                            position: *position,
+                            without_enlargement: *without_enlargement,
+                            without_reduction: *without_reduction,
+                            kernel: *kernel,
+                            linear: *linear,
+                            sharpen: *sharpen,
                         });
                         i += 2;
                         continue;
@@ -485,6 +1179,8 @@ pub fn apply_ops_tracked<'a>(
         return Ok(ColorTrackedImage {
             image: img,
             state: initial_state,
+            smart_crop: None,
+            trim: None,
         });
     }
 
@@ -496,83 +1192,70 @@ pub fn apply_ops_tracked<'a>(
     );
     let mut img = img.into_owned();
     let mut state = initial_state;
+    let mut smart_crop = None;
+    let mut trim = None;
 
     for op in &optimized_ops {
+        let cmyk = state.color_space == ColorSpace::Cmyk;
+        if cmyk && !matches!(op, Operation::IccTransform { .. }) {
+            return Err(LazyImageError::internal_panic(format!(
+                "{} received CMYK pixels before colour conversion",
+                op.contract().name
+            )));
+        }
         state = update_color_state(state, op);
         img = match op {
-            Operation::Resize { width, height, fit } => match (fit, width, height) {
-                (ResizeFit::Fill, Some(w), Some(h)) => {
-                    let target_w = *w;
-                    let target_h = *h;
-                    validate_resize_dimensions(target_w, target_h)?;
-                    if (target_w, target_h) == (img.width(), img.height()) {
-                        img
-                    } else {
-                        let src_image = match img {
-                            DynamicImage::ImageRgb8(_) | DynamicImage::ImageRgba8(_) => img,
-                            _ => {
-                                log_copy(
-                                    "to_rgba8 (normalize before resize)",
-                                    (img.width(), img.height()),
-                                );
-                                DynamicImage::ImageRgba8(img.to_rgba8())
-                            }
-                        };
-                        fast_resize_owned(src_image, target_w, target_h)
-                            .map_err(|err| err.into_lazy_image_error())?
-                    }
-                }
-                (ResizeFit::Cover, Some(target_w), Some(target_h)) => {
-                    validate_resize_dimensions(*target_w, *target_h)?;
-                    if (*target_w, *target_h) == (img.width(), img.height()) {
-                        img
-                    } else {
-                        let (resize_w, resize_h) = calc_cover_resize_dimensions(
-                            img.width(),
-                            img.height(),
-                            *target_w,
-                            *target_h,
-                        );
-                        let src_image = match img {
-                            DynamicImage::ImageRgb8(_) | DynamicImage::ImageRgba8(_) => img,
-                            _ => {
-                                log_copy(
-                                    "to_rgba8 (normalize before cover resize)",
-                                    (img.width(), img.height()),
-                                );
-                                DynamicImage::ImageRgba8(img.to_rgba8())
-                            }
-                        };
-                        let resized = fast_resize_owned(src_image, resize_w, resize_h)
-                            .map_err(|err| err.into_lazy_image_error())?;
-                        crop_to_dimensions(resized, *target_w, *target_h)
+            Operation::Resize {
+                width,
+                height,
+                fit,
+                position,
+                without_enlargement,
+                without_reduction,
+                kernel,
+                linear,
+                sharpen,
+            } => {
+                let plan = plan_resize(
+                    (img.width(), img.height()),
+                    *width,
+                    *height,
+                    fit,
+                    *without_enlargement,
+                    *without_reduction,
+                );
+                validate_resize_dimensions(plan.output.0, plan.output.1)?;
+                validate_resize_dimensions(plan.scaled.0, plan.scaled.1)?;
+                let source = (img.width(), img.height());
+                let mut scaled = if *linear && plan.scaled != source {
+                    scale_linear(img, plan.scaled, *kernel, &mut state)?
+                } else {
+                    scale_to(img, plan.scaled, *kernel, &log_copy)?
+                };
+                // Sharpen before any letterbox padding so its edges get no halo.
+                if *sharpen {
+                    let ratio = f64::max(
+                        source.0 as f64 / plan.scaled.0 as f64,
+                        source.1 as f64 / plan.scaled.1 as f64,
+                    );
+                    if let Some(mask) = Sharpen::for_downscale(ratio) {
+                        scaled = filter::sharpen(scaled, mask);
                     }
                 }
-                _ => {
-                    let (w, h) = calc_resize_dimensions(img.width(), img.height(), *width, *height);
-                    validate_resize_dimensions(w, h)?;
-                    if (w, h) == (img.width(), img.height()) {
-                        img
-                    } else {
-                        let src_image = match img {
-                            DynamicImage::ImageRgb8(_) | DynamicImage::ImageRgba8(_) => img,
-                            _ => {
-                                log_copy(
-                                    "to_rgba8 (normalize before resize/extract)",
-                                    (img.width(), img.height()),
-                                );
-                                DynamicImage::ImageRgba8(img.to_rgba8())
-                            }
-                        };
-                        fast_resize_owned(src_image, w, h)
-                            .map_err(|err| err.into_lazy_image_error())?
-                    }
+                let (resized, region) = fit_to_output(scaled, plan, fit, *position);
+                if region.is_some() {
+                    smart_crop = region;
                 }
-            },
+                resized
+            }
             Operation::Extract {
                 width,
                 height,
                 fit,
+                position,
+                without_enlargement,
+                without_reduction,
+                kernel,
                 crop_x,
                 crop_y,
                 crop_width,
@@ -586,28 +1269,53 @@ pub fn apply_ops_tracked<'a>(
                 }
 
                 // Calculate resize target identical to Resize branch
-                let (resize_w, resize_h) = match (fit, width, height) {
-                    (ResizeFit::Fill, Some(w), Some(h)) => (*w, *h),
-                    (ResizeFit::Cover, Some(target_w), Some(target_h)) => {
-                        calc_cover_resize_dimensions(
-                            img.width(),
-                            img.height(),
-                            *target_w,
-                            *target_h,
-                        )
-                    }
-                    _ => calc_resize_dimensions(img.width(), img.height(), *width, *height),
-                };
-
+                let plan = plan_resize(
+                    (img.width(), img.height()),
+                    *width,
+                    *height,
+                    fit,
+                    *without_enlargement,
+                    *without_reduction,
+                );
+                let (resize_w, resize_h) = plan.scaled;
                 validate_resize_dimensions(resize_w, resize_h)?;
 
-                let (frame_w, frame_h, offset_x, offset_y) = match (fit, width, height) {
-                    (ResizeFit::Cover, Some(target_w), Some(target_h)) => {
-                        let off_x = (resize_w.saturating_sub(*target_w)) / 2;
-                        let off_y = (resize_h.saturating_sub(*target_h)) / 2;
-                        (*target_w, *target_h, off_x, off_y)
+                // Padding has no source pixels to map back to, so letterbox first and crop after.
+                if matches!(fit, ResizeFit::Contain { .. }) && plan.output != plan.scaled {
+                    let scaled = scale_to(img, plan.scaled, *kernel, &log_copy)?;
+                    let (padded, _) = fit_to_output(scaled, plan, fit, *position);
+                    if *crop_x + *crop_width > padded.width()
+                        || *crop_y + *crop_height > padded.height()
+                    {
+                        return Err(LazyImageError::invalid_crop_bounds(
+                            *crop_x,
+                            *crop_y,
+                            *crop_width,
+                            *crop_height,
+                            padded.width(),
+                            padded.height(),
+                        ));
+                    }
+                    img = padded.crop_imm(*crop_x, *crop_y, *crop_width, *crop_height);
+                    continue;
+                }
+
+                let (frame_w, frame_h) = plan.output;
+                let (offset_x, offset_y) = match fit {
+                    ResizeFit::Cover if width.is_some() && height.is_some() => {
+                        let (off_x, off_y) =
+                            cover_crop_offset(&img, plan.scaled, plan.output, *position);
+                        if position.is_content_aware() {
+                            smart_crop = Some(CropRegion {
+                                left: off_x,
+                                top: off_y,
+                                width: frame_w,
+                                height: frame_h,
+                            });
+                        }
+                        (off_x, off_y)
                     }
-                    _ => (resize_w, resize_h, 0, 0),
+                    _ => (0, 0),
                 };
 
                 if *crop_x + *crop_width > frame_w || *crop_y + *crop_height > frame_h {
@@ -647,22 +1355,18 @@ pub fn apply_ops_tracked<'a>(
                         src_height = max_src_height;
                     }
 
-                    let src_image = match img {
-                        DynamicImage::ImageRgb8(_) | DynamicImage::ImageRgba8(_) => img,
-                        _ => {
-                            log_copy(
-                                "to_rgba8 (normalize before crop->resize path)",
-                                (img.width(), img.height()),
-                            );
-                            DynamicImage::ImageRgba8(img.to_rgba8())
-                        }
-                    };
+                    if tone::Layout::of(&img).is_none() {
+                        log_copy(
+                            "to_rgba8 (normalize before crop->resize path)",
+                            (img.width(), img.height()),
+                        );
+                    }
 
                     fast_resize_owned_impl(
-                        src_image,
+                        img,
                         *crop_width,
                         *crop_height,
-                        default_resize_options().crop(src_left, src_top, src_width, src_height),
+                        resize_options(*kernel).crop(src_left, src_top, src_width, src_height),
                     )
                     .map_err(|err| err.into_lazy_image_error())?
                 }
@@ -688,6 +1392,54 @@ pub fn apply_ops_tracked<'a>(
                 img.crop_imm(*x, *y, *width, *height)
             }
 
+            Operation::Extend {
+                top,
+                right,
+                bottom,
+                left,
+                background,
+                mode,
+            } => {
+                let edges = (*top, *right, *bottom, *left);
+                if edges == (0, 0, 0, 0) {
+                    img
+                } else {
+                    let width = (img.width() as u64 + *left as u64 + *right as u64)
+                        .min(u32::MAX as u64) as u32;
+                    let height = (img.height() as u64 + *top as u64 + *bottom as u64)
+                        .min(u32::MAX as u64) as u32;
+                    check_dimensions(width, height)?;
+                    match mode {
+                        ExtendMode::Background => {
+                            pad_to_canvas(img, width, height, (*left, *top), *background)
+                        }
+                        ExtendMode::Copy | ExtendMode::Mirror => {
+                            extend_from_edges(img, (width, height), (*left, *top), *mode)
+                        }
+                    }
+                }
+            }
+
+            Operation::Trim {
+                threshold,
+                background,
+            } => {
+                let full = CropRegion {
+                    left: 0,
+                    top: 0,
+                    width: img.width(),
+                    height: img.height(),
+                };
+                // A uniform image has nothing to keep; leave it untouched.
+                let region = trim::find_trim_region(&img, *threshold, *background).unwrap_or(full);
+                trim = Some(region);
+                if region == full {
+                    img
+                } else {
+                    img.crop_imm(region.left, region.top, region.width, region.height)
+                }
+            }
+
             Operation::Rotate { degrees } => {
                 match degrees {
                     90 => img.rotate90(),
@@ -703,17 +1455,123 @@ pub fn apply_ops_tracked<'a>(
                 }
             }
 
+            Operation::Flatten { background } => flatten(img, *background),
+            Operation::EnsureAlpha { alpha } => channels::ensure_alpha(img, *alpha),
+            Operation::RemoveAlpha => channels::remove_alpha(img),
+            Operation::ExtractChannel { channel } => channels::extract_channel(img, *channel),
+
+            Operation::JoinChannel { mask } => {
+                // Reject a size mismatch from the header, before decoding.
+                if let Some((width, height)) = read_dimensions(mask)? {
+                    check_dimensions(width, height)?;
+                    check_mask_size(width, height, &img)?;
+                }
+                let (mask, _) = decode_image(mask)?;
+                check_mask_size(mask.width(), mask.height(), &img)?;
+                channels::join_channel(img, &mask)
+            }
+
+            Operation::RotateArbitrary {
+                degrees,
+                background,
+                interpolation,
+            } => {
+                if !degrees.is_finite() {
+                    return Err(LazyImageError::invalid_argument(
+                        "degrees",
+                        degrees.to_string(),
+                        "rotation angle must be a finite number",
+                    ));
+                }
+                let (width, height) =
+                    rotate::rotated_dimensions(img.width(), img.height(), *degrees);
+                check_dimensions(width, height)?;
+                rotate::rotate(img, *degrees, *background, *interpolation)
+            }
+
+            Operation::Affine {
+                matrix,
+                translate,
+                background,
+                interpolation,
+            } => {
+                if rotate::invert(matrix).is_none() || !translate.iter().all(|v| v.is_finite()) {
+                    return Err(LazyImageError::invalid_argument(
+                        "matrix",
+                        format!("{matrix:?} + {translate:?}"),
+                        "affine matrix must be finite and invertible",
+                    ));
+                }
+                let (width, height) =
+                    rotate::affine_dimensions(img.width(), img.height(), matrix, *translate);
+                check_dimensions(width, height)?;
+                rotate::affine(img, matrix, *translate, *background, *interpolation)
+            }
+
             Operation::FlipH => img.fliph(),
             Operation::FlipV => img.flipv(),
             Operation::Grayscale => DynamicImage::ImageLuma8(img.to_luma8()),
 
-            Operation::Brightness { value } => img.brighten(*value),
+            Operation::Brightness { value } => tone::point_lut(img, &[PointOp::Brightness(*value)]),
+
+            Operation::Contrast { value } => tone::point_lut(img, &[PointOp::Contrast(*value)]),
+
+            Operation::Modulate {
+                brightness,
+                saturation,
+                hue,
+                lightness,
+            } => tone::modulate(img, *brightness, *saturation, *hue, *lightness),
+
+            Operation::Gamma { gamma } => tone::gamma(img, *gamma),
+
+            Operation::Negate => tone::point_lut(img, &[PointOp::Negate]),
+
+            Operation::Threshold {
+                value,
+                grayscale: true,
+            } => tone::threshold_gray(img, *value),
+
+            Operation::Threshold {
+                value,
+                grayscale: false,
+            } => tone::point_lut(img, &[PointOp::Threshold(*value)]),
+
+            Operation::Levels { levels } => tone::point_lut(img, &[PointOp::Levels(*levels)]),
+
+            Operation::Normalize { lower, upper } => tone::normalize(img, *lower, *upper),
+
+            Operation::Clahe {
+                width,
+                height,
+                max_slope,
+            } => tone::clahe(img, *width, *height, *max_slope),
+
+            Operation::Sharpen { sharpen } => filter::sharpen(img, *sharpen),
 
-            Operation::Contrast { value } => {
-                // image crate expects f32, convert from our -100..100 scale
-                img.adjust_contrast(*value as f32)
+            Operation::Blur { sigma, regions } => filter::blur(img, *sigma, regions),
+
+            Operation::Pixelate {
+                block_size,
+                regions,
+            } => filter::pixelate(img, *block_size, regions),
+
+            Operation::Recomb { matrix } => grade::recomb(img, matrix),
+
+            Operation::Lut3d { lut } => grade::apply_lut(img, lut),
+
+            Operation::Composite { layers } => {
+                let mut img = img;
+                for layer in layers {
+                    ensure_dimensions_safe(&layer.input)?;
+                    let (overlay, _) = decode_image(&layer.input)?;
+                    img = composite::composite(img, &overlay, layer);
+                }
+                img
             }
 
+            Operation::PointLut { steps } => tone::point_lut(img, steps),
+
             Operation::AutoOrient { orientation } => {
                 match orientation {
                     1 => img,
@@ -737,11 +1595,17 @@ pub fn apply_ops_tracked<'a>(
                     _ => DynamicImage::ImageRgb8(img.to_rgb8()),
                 }
             }
+
+            Operation::IccTransform { source, target } => {
+                icc::transform(img, source.as_ref(), target, cmyk)?
+            }
         };
     }
     Ok(ColorTrackedImage {
         image: Cow::Owned(img),
         state,
+        smart_crop,
+        trim,
     })
 }
 
@@ -754,7 +1618,7 @@ pub fn apply_ops<'a>(
     Ok(apply_ops_tracked(img, ops, init_state)?.image)
 }
 
-/// Fast resize with owned DynamicImage (zero-copy for RGB/RGBA)
+/// Fast resize with owned DynamicImage (zero-copy for 8-bit layouts)
 /// Returns Ok(resized) on success, Err(resize_error) on failure
 pub fn fast_resize_owned(
     img: DynamicImage,
@@ -765,7 +1629,19 @@ pub fn fast_resize_owned(
 }
 
 fn default_resize_options() -> ResizeOptions {
-    ResizeOptions::new().resize_alg(fir::ResizeAlg::Convolution(fir::FilterType::Lanczos3))
+    resize_options(ResizeKernel::default())
+}
+
+fn resize_options(kernel: ResizeKernel) -> ResizeOptions {
+    let algorithm = match kernel {
+        ResizeKernel::Nearest => fir::ResizeAlg::Nearest,
+        ResizeKernel::Box => fir::ResizeAlg::Convolution(fir::FilterType::Box),
+        ResizeKernel::Bilinear => fir::ResizeAlg::Convolution(fir::FilterType::Bilinear),
+        ResizeKernel::CatmullRom => fir::ResizeAlg::Convolution(fir::FilterType::CatmullRom),
+        ResizeKernel::Mitchell => fir::ResizeAlg::Convolution(fir::FilterType::Mitchell),
+        ResizeKernel::Lanczos3 => fir::ResizeAlg::Convolution(fir::FilterType::Lanczos3),
+    };
+    ResizeOptions::new().resize_alg(algorithm)
 }
 
 /// Fast resize with reference (for external API compatibility)
@@ -781,24 +1657,8 @@ pub fn fast_resize(
         return Err("invalid dimensions".to_string());
     }
 
-    // Select pixel layout without forcing RGBA when not needed
-    // Use into_raw() to avoid clone() - ownership transfer instead of copying
-    let (pixel_type, src_pixels): (PixelType, Vec<u8>) = match img {
-        DynamicImage::ImageRgb8(rgb) => {
-            // Clone is necessary when we only have a reference
-            let rgb_image = rgb.clone();
-            (PixelType::U8x3, rgb_image.into_raw())
-        }
-        DynamicImage::ImageRgba8(rgba) => {
-            // Clone is necessary when we only have a reference
-            let rgba_image = rgba.clone();
-            (PixelType::U8x4, rgba_image.into_raw())
-        }
-        _ => {
-            let rgba = img.to_rgba8();
-            (PixelType::U8x4, rgba.into_raw())
-        }
-    };
+    // Clone is necessary when we only have a reference
+    let (pixel_type, src_pixels) = fir_pixels(img.clone());
 
     fast_resize_internal_with_options(
         src_width,
@@ -863,23 +1723,7 @@ fn fast_resize_owned_impl(
         ));
     }
 
-    // Select pixel layout without forcing RGBA when not needed
-    // Use into_raw() to avoid clone() - ownership transfer instead of copying
-    let (pixel_type, src_pixels): (PixelType, Vec<u8>) = match img {
-        DynamicImage::ImageRgb8(rgb) => {
-            // Zero-copy: directly take ownership of the pixel buffer
-            (PixelType::U8x3, rgb.into_raw())
-        }
-        DynamicImage::ImageRgba8(rgba) => {
-            // Zero-copy: directly take ownership of the pixel buffer
-            (PixelType::U8x4, rgba.into_raw())
-        }
-        other => {
-            // For other formats, convert to RGBA (necessary conversion)
-            let rgba = other.to_rgba8();
-            (PixelType::U8x4, rgba.into_raw())
-        }
-    };
+    let (pixel_type, src_pixels) = fir_pixels(img);
 
     fast_resize_internal_impl(
         src_width, src_height, src_pixels, pixel_type, dst_width, dst_height, options,
@@ -887,18 +1731,76 @@ fn fast_resize_owned_impl(
     .map_err(|reason| ResizeError::new((src_width, src_height), (dst_width, dst_height), reason))
 }
 
-/// Decide whether alpha premultiplication is required for a given pixel layout.
-#[inline]
-fn requires_premultiply(pixel_type: PixelType) -> bool {
-    matches!(pixel_type, PixelType::U8x4)
+/// fast_image_resize layout and native-endian samples of `img`. 8/16-bit
+/// layouts keep their channels and depth (8-bit ones without a copy); others
+/// are converted to RGBA8.
+fn fir_pixels(img: DynamicImage) -> (PixelType, Vec<u8>) {
+    fn bytes(samples: Vec<u16>) -> Vec<u8> {
+        samples.into_iter().flat_map(u16::to_ne_bytes).collect()
+    }
+    match img {
+        DynamicImage::ImageLuma8(buf) => (PixelType::U8, buf.into_raw()),
+        DynamicImage::ImageLumaA8(buf) => (PixelType::U8x2, buf.into_raw()),
+        DynamicImage::ImageRgb8(buf) => (PixelType::U8x3, buf.into_raw()),
+        DynamicImage::ImageRgba8(buf) => (PixelType::U8x4, buf.into_raw()),
+        DynamicImage::ImageLuma16(buf) => (PixelType::U16, bytes(buf.into_raw())),
+        DynamicImage::ImageLumaA16(buf) => (PixelType::U16x2, bytes(buf.into_raw())),
+        DynamicImage::ImageRgb16(buf) => (PixelType::U16x3, bytes(buf.into_raw())),
+        DynamicImage::ImageRgba16(buf) => (PixelType::U16x4, bytes(buf.into_raw())),
+        other => (PixelType::U8x4, other.into_rgba8().into_raw()),
+    }
 }
 
-fn fast_resize_internal_impl(
-    src_width: u32,
-    src_height: u32,
-    mut src_pixels: Vec<u8>,
+/// Image of `pixel_type` samples produced by `fir_pixels`.
+fn from_fir_pixels(
+    width: u32,
+    height: u32,
     pixel_type: PixelType,
-    dst_width: u32,
+    pixels: Vec<u8>,
+) -> Option<DynamicImage> {
+    fn samples(bytes: Vec<u8>) -> Vec<u16> {
+        bytes
+            .chunks_exact(2)
+            .map(|pair| u16::from_ne_bytes([pair[0], pair[1]]))
+            .collect()
+    }
+    let (w, h) = (width, height);
+    match pixel_type {
+        PixelType::U8 => ImageBuffer::from_raw(w, h, pixels).map(DynamicImage::ImageLuma8),
+        PixelType::U8x2 => ImageBuffer::from_raw(w, h, pixels).map(DynamicImage::ImageLumaA8),
+        PixelType::U8x3 => ImageBuffer::from_raw(w, h, pixels).map(DynamicImage::ImageRgb8),
+        PixelType::U8x4 => ImageBuffer::from_raw(w, h, pixels).map(DynamicImage::ImageRgba8),
+        PixelType::U16 => {
+            ImageBuffer::from_raw(w, h, samples(pixels)).map(DynamicImage::ImageLuma16)
+        }
+        PixelType::U16x2 => {
+            ImageBuffer::from_raw(w, h, samples(pixels)).map(DynamicImage::ImageLumaA16)
+        }
+        PixelType::U16x3 => {
+            ImageBuffer::from_raw(w, h, samples(pixels)).map(DynamicImage::ImageRgb16)
+        }
+        PixelType::U16x4 => {
+            ImageBuffer::from_raw(w, h, samples(pixels)).map(DynamicImage::ImageRgba16)
+        }
+        _ => None,
+    }
+}
+
+/// Decide whether alpha premultiplication is required for a given pixel layout.
+#[inline]
+fn requires_premultiply(pixel_type: PixelType) -> bool {
+    matches!(
+        pixel_type,
+        PixelType::U8x2 | PixelType::U8x4 | PixelType::U16x2 | PixelType::U16x4
+    )
+}
+
+fn fast_resize_internal_impl(
+    src_width: u32,
+    src_height: u32,
+    mut src_pixels: Vec<u8>,
+    pixel_type: PixelType,
+    dst_width: u32,
     dst_height: u32,
     options: ResizeOptions,
 ) -> std::result::Result<DynamicImage, String> {
@@ -947,6 +1849,7 @@ fn fast_resize_internal_impl(
             pixel_type,
             dst_width,
             dst_height,
+            options.algorithm,
         )
         .map_err(|fallback_err| format!("{err}; image crate fallback failed: {fallback_err}")),
     }
@@ -971,6 +1874,26 @@ fn copy_pixels_to_aligned_image(
     Ok(aligned_image)
 }
 
+/// image crate filter closest to a fast_image_resize algorithm.
+/// `None` means box filtering, which the image crate only offers as
+/// `thumbnail()` (area average).
+fn fallback_filter(algorithm: fir::ResizeAlg) -> Option<FilterType> {
+    match algorithm {
+        fir::ResizeAlg::Nearest => Some(FilterType::Nearest),
+        fir::ResizeAlg::Convolution(filter)
+        | fir::ResizeAlg::Interpolation(filter)
+        | fir::ResizeAlg::SuperSampling(filter, _) => match filter {
+            fir::FilterType::Box => None,
+            fir::FilterType::Bilinear | fir::FilterType::Hamming => Some(FilterType::Triangle),
+            // image has no Mitchell kernel; Catmull-Rom is the nearest bicubic.
+            fir::FilterType::CatmullRom | fir::FilterType::Mitchell => Some(FilterType::CatmullRom),
+            fir::FilterType::Gaussian => Some(FilterType::Gaussian),
+            _ => Some(FilterType::Lanczos3),
+        },
+        _ => Some(FilterType::Lanczos3),
+    }
+}
+
 fn resize_with_image_crate_fallback(
     src_pixels: &[u8],
     src_width: u32,
@@ -978,35 +1901,37 @@ fn resize_with_image_crate_fallback(
     pixel_type: PixelType,
     dst_width: u32,
     dst_height: u32,
+    algorithm: fir::ResizeAlg,
 ) -> std::result::Result<DynamicImage, String> {
-    let filter = FilterType::Lanczos3;
-    match pixel_type {
-        PixelType::U8x3 => {
-            let rgb = RgbImage::from_raw(src_width, src_height, src_pixels.to_vec())
-                .ok_or_else(|| "failed to build rgb image for fallback resize".to_string())?;
-            Ok(DynamicImage::ImageRgb8(image::imageops::resize(
-                &rgb, dst_width, dst_height, filter,
-            )))
-        }
-        PixelType::U8x4 => {
-            let rgba = RgbaImage::from_raw(src_width, src_height, src_pixels.to_vec())
-                .ok_or_else(|| "failed to build rgba image for fallback resize".to_string())?;
-            Ok(DynamicImage::ImageRgba8(image::imageops::resize(
-                &rgba, dst_width, dst_height, filter,
-            )))
-        }
-        _ => Err("fallback resize supports only U8x3/U8x4 pixel types".to_string()),
-    }
+    let img = from_fir_pixels(src_width, src_height, pixel_type, src_pixels.to_vec())
+        .ok_or_else(|| format!("failed to build {pixel_type:?} image for fallback resize"))?;
+    Ok(match fallback_filter(algorithm) {
+        Some(filter) => img.resize_exact(dst_width, dst_height, filter),
+        // Box upscaling is nearest neighbour, downscaling is an area average.
+        None if dst_width > src_width || dst_height > src_height => {
+            img.resize_exact(dst_width, dst_height, FilterType::Nearest)
+        }
+        None => img.thumbnail_exact(dst_width, dst_height),
+    })
 }
 
 /// Check if an RGBA image is fully opaque (all alpha values are 255)
-/// For RGB images, always returns true (no alpha channel)
+/// For layouts without alpha, always returns true; other layouts with alpha
+/// are not scanned and always premultiplied.
 ///
 /// Only checks images ≥1MP - for smaller images, the check overhead exceeds
 /// the premultiply cost (SIMD premultiply is very fast for small images)
-fn is_fully_opaque(image: &fir::images::Image, pixel_type: PixelType, width: u32, height: u32) -> bool {
+fn is_fully_opaque(
+    image: &fir::images::Image,
+    pixel_type: PixelType,
+    width: u32,
+    height: u32,
+) -> bool {
+    if !requires_premultiply(pixel_type) {
+        return true; // no alpha channel
+    }
     if pixel_type != PixelType::U8x4 {
-        return true; // RGB images have no alpha channel
+        return false;
     }
 
     // Size threshold: Only check large images (≥1MP)
@@ -1057,26 +1982,14 @@ fn resize_with_source_image<'a>(
             .map_err(|e| format!("failed to unpremultiply alpha: {e}"))?;
     }
 
-    let dst_pixels = dst_image.into_vec();
-    match pixel_type {
-        PixelType::U8x3 => {
-            let rgb_image = RgbImage::from_raw(dst_width, dst_height, dst_pixels)
-                .ok_or("failed to create rgb image from resized data")?;
-            Ok(DynamicImage::ImageRgb8(rgb_image))
-        }
-        PixelType::U8x4 => {
-            let rgba_image = RgbaImage::from_raw(dst_width, dst_height, dst_pixels)
-                .ok_or("failed to create rgba image from resized data")?;
-            Ok(DynamicImage::ImageRgba8(rgba_image))
-        }
-        _ => Err("unsupported pixel type after resize".to_string()),
-    }
+    from_fir_pixels(dst_width, dst_height, pixel_type, dst_image.into_vec())
+        .ok_or_else(|| format!("failed to create {pixel_type:?} image from resized data"))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::ops::{Operation, ResizeFit};
+    use crate::ops::{Operation, ResizeFit, ResizePosition, RotateInterpolation};
     use image::{DynamicImage, GenericImageView, RgbImage, RgbaImage};
     use std::borrow::Cow;
 
@@ -1109,6 +2022,129 @@ mod tests {
             assert_eq!(state.bit_depth, BitDepth::Eight);
         }
 
+        #[test]
+        fn update_color_state_matches_output_layout() {
+            let sources = [
+                DynamicImage::ImageRgb8(RgbImage::new(2, 2)),
+                DynamicImage::ImageRgba8(RgbaImage::new(2, 2)),
+                DynamicImage::ImageLuma8(image::GrayImage::new(2, 2)),
+                DynamicImage::ImageRgba16(image::ImageBuffer::new(2, 2)),
+            ];
+            let mut overlay = Vec::new();
+            DynamicImage::ImageRgba8(RgbaImage::from_pixel(1, 1, image::Rgba([9, 8, 7, 128])))
+                .write_to(
+                    &mut std::io::Cursor::new(&mut overlay),
+                    image::ImageFormat::Png,
+                )
+                .unwrap();
+            let ops = [
+                Operation::EnsureAlpha { alpha: 1.0 },
+                Operation::RemoveAlpha,
+                Operation::ExtractChannel {
+                    channel: crate::ops::Channel::Green,
+                },
+                Operation::Modulate {
+                    brightness: 1.2,
+                    saturation: 0.5,
+                    hue: 90.0,
+                    lightness: 0.0,
+                },
+                Operation::Gamma { gamma: 2.2 },
+                Operation::Negate,
+                Operation::Threshold {
+                    value: 128,
+                    grayscale: true,
+                },
+                Operation::Threshold {
+                    value: 128,
+                    grayscale: false,
+                },
+                Operation::Levels {
+                    levels: crate::ops::Levels::default(),
+                },
+                Operation::Normalize {
+                    lower: 1.0,
+                    upper: 99.0,
+                },
+                Operation::Clahe {
+                    width: 1,
+                    height: 1,
+                    max_slope: 3,
+                },
+                Operation::Sharpen {
+                    sharpen: Sharpen::default(),
+                },
+                Operation::Blur {
+                    sigma: 1.0,
+                    regions: vec![],
+                },
+                Operation::Pixelate {
+                    block_size: 3,
+                    regions: vec![CropRegion {
+                        left: 1,
+                        top: 1,
+                        width: 4,
+                        height: 4,
+                    }],
+                },
+                Operation::Recomb {
+                    matrix: [
+                        [0.4, 0.8, 0.2, 0.0],
+                        [0.3, 0.7, 0.2, 0.0],
+                        [0.3, 0.5, 0.1, 0.0],
+                        [0.0, 0.0, 0.0, 1.0],
+                    ],
+                },
+                Operation::Lut3d {
+                    lut: std::sync::Arc::new(crate::ops::CubeLut {
+                        size: 2,
+                        domain_min: [0.0; 3],
+                        domain_max: [1.0; 3],
+                        table: (0..8)
+                            .map(|i| [(i & 1) as f32, (i >> 1 & 1) as f32, 0.5])
+                            .collect(),
+                    }),
+                },
+                Operation::Composite {
+                    layers: vec![crate::ops::Overlay {
+                        input: std::sync::Arc::new(overlay),
+                        placement: crate::ops::OverlayPlacement::Offset { left: 1, top: 1 },
+                        blend: crate::ops::BlendMode::Multiply,
+                        opacity: 0.5,
+                        tile: true,
+                    }],
+                },
+                Operation::IccTransform {
+                    source: None,
+                    target: crate::ops::IccProfile::DisplayP3,
+                },
+                Operation::IccTransform {
+                    source: Some(crate::ops::IccProfile::DisplayP3),
+                    target: crate::ops::IccProfile::Srgb,
+                },
+                Operation::Affine {
+                    matrix: [[1.0, 0.3], [0.1, 1.0]],
+                    translate: [1.0, 0.0],
+                    background: Color::BLACK,
+                    interpolation: crate::ops::RotateInterpolation::Bicubic,
+                },
+            ];
+            for img in sources {
+                for op in &ops {
+                    let state = ColorState::from_dynamic_image(&img, IccState::Absent);
+                    let tracked =
+                        apply_ops_tracked(Cow::Borrowed(&img), std::slice::from_ref(op), state)
+                            .unwrap();
+                    // ICC presence and gamut are not visible in the pixels.
+                    let expected = ColorState {
+                        gamut: tracked.state.gamut,
+                        ..ColorState::from_dynamic_image(&tracked.image, tracked.state.icc)
+                    };
+                    assert_eq!(tracked.state, expected, "{op:?} on {:?}", img.color());
+                }
+            }
+        }
+
         #[test]
         fn update_color_state_converts_bit_depth_on_grayscale_16bit() {
             let img = DynamicImage::ImageRgb16(
@@ -1149,6 +2185,12 @@ mod tests {
                 width: Some(2),
                 height: Some(2),
                 fit: ResizeFit::Inside,
+                position: ResizePosition::Center,
+                without_enlargement: false,
+                without_reduction: false,
+                kernel: ResizeKernel::Lanczos3,
+                linear: false,
+                sharpen: false,
             }];
             let init = ColorState::from_dynamic_image(&img, IccState::Present);
             let tracked = apply_ops_tracked(Cow::Owned(img), &ops, init).unwrap();
@@ -1250,6 +2292,83 @@ mod tests {
             let (w, h) = calc_resize_dimensions(1000, 500, Some(800), Some(400));
             assert_eq!((w, h), (800, 400));
         }
+
+        #[test]
+        fn test_plan_without_enlargement_keeps_current_size() {
+            let inside = plan_resize(
+                (400, 300),
+                Some(1200),
+                None,
+                &ResizeFit::Inside,
+                true,
+                false,
+            );
+            assert_eq!(inside.output, (400, 300));
+            // Shrinking is still allowed
+            let shrink = plan_resize((400, 300), Some(200), None, &ResizeFit::Inside, true, false);
+            assert_eq!(shrink.output, (200, 150));
+            // Fill clamps each axis independently
+            let fill = plan_resize(
+                (400, 300),
+                Some(800),
+                Some(100),
+                &ResizeFit::Fill,
+                true,
+                false,
+            );
+            assert_eq!(fill.output, (400, 100));
+            // Cover stops at scale 1.0 and crops what it still can
+            let cover = plan_resize(
+                (400, 300),
+                Some(600),
+                Some(200),
+                &ResizeFit::Cover,
+                true,
+                false,
+            );
+            assert_eq!(cover.scaled, (400, 300));
+            assert_eq!(cover.output, (400, 200));
+        }
+
+        #[test]
+        fn test_plan_without_reduction_keeps_current_size() {
+            let inside = plan_resize((400, 300), Some(200), None, &ResizeFit::Inside, false, true);
+            assert_eq!(inside.output, (400, 300));
+            let grow = plan_resize((400, 300), Some(800), None, &ResizeFit::Inside, false, true);
+            assert_eq!(grow.output, (800, 600));
+            // Contain canvas grows to fit the unreduced image
+            let contain = plan_resize(
+                (400, 300),
+                Some(200),
+                Some(200),
+                &ResizeFit::Contain {
+                    background: Color::TRANSPARENT,
+                },
+                false,
+                true,
+            );
+            assert_eq!(contain.scaled, (400, 300));
+            assert_eq!(contain.output, (400, 300));
+        }
+
+        #[test]
+        fn test_outside_covers_box_without_crop() {
+            // Wide image: height matches, width overflows
+            assert_eq!(
+                calc_outside_resize_dimensions(6000, 4000, 800, 800),
+                (1200, 800)
+            );
+            // Tall image: width matches, height overflows
+            assert_eq!(
+                calc_outside_resize_dimensions(4000, 6000, 800, 600),
+                (800, 1200)
+            );
+            // Same aspect ratio: exact box
+            assert_eq!(
+                calc_outside_resize_dimensions(1000, 500, 800, 400),
+                (800, 400)
+            );
+        }
     }
 
     mod resize_fallback_tests {
@@ -1273,6 +2392,7 @@ mod tests {
                 pixel_type,
                 4,
                 2,
+                default_resize_options().algorithm,
             )
             .expect("fallback resize should succeed for RGB");
 
@@ -1294,12 +2414,68 @@ mod tests {
                 pixel_type,
                 3,
                 2,
+                default_resize_options().algorithm,
             )
             .expect("fallback resize should succeed for RGBA");
 
             assert_eq!(resized.dimensions(), (3, 2));
             assert!(matches!(resized, DynamicImage::ImageRgba8(_)));
         }
+
+        #[test]
+        fn image_crate_fallback_resizes_sixteen_bit() {
+            let samples: Vec<u16> = (0..8 * 4 * 2).map(|i| i * 1000).collect();
+            let src_pixels: Vec<u8> = samples.into_iter().flat_map(u16::to_ne_bytes).collect();
+
+            let resized = resize_with_image_crate_fallback(
+                &src_pixels,
+                8,
+                4,
+                PixelType::U16x2,
+                4,
+                2,
+                default_resize_options().algorithm,
+            )
+            .expect("fallback resize should succeed for 16-bit grey with alpha");
+
+            assert_eq!(resized.dimensions(), (4, 2));
+            assert!(matches!(resized, DynamicImage::ImageLumaA16(_)));
+        }
+
+        #[test]
+        fn image_crate_fallback_follows_kernel() {
+            assert_eq!(
+                fallback_filter(resize_options(ResizeKernel::Nearest).algorithm),
+                Some(FilterType::Nearest)
+            );
+            assert_eq!(
+                fallback_filter(resize_options(ResizeKernel::Bilinear).algorithm),
+                Some(FilterType::Triangle)
+            );
+            assert_eq!(
+                fallback_filter(resize_options(ResizeKernel::Mitchell).algorithm),
+                Some(FilterType::CatmullRom)
+            );
+            assert_eq!(
+                fallback_filter(resize_options(ResizeKernel::Box).algorithm),
+                None
+            );
+
+            // Box downscale through the fallback is an exact area average.
+            let src = [0u8, 0, 0, 255, 255, 255];
+            let resized = resize_with_image_crate_fallback(
+                &src,
+                2,
+                1,
+                PixelType::U8x3,
+                1,
+                1,
+                resize_options(ResizeKernel::Box).algorithm,
+            )
+            .unwrap();
+            let value = resized.to_rgb8().get_pixel(0, 0)[0];
+            assert!((127..=128).contains(&value), "got {value}");
+        }
     }
 
     mod operation_contract_tests {
@@ -1312,6 +2488,12 @@ mod tests {
                     width: Some(200),
                     height: Some(100),
                     fit: ResizeFit::Inside,
+                    position: ResizePosition::Center,
+                    without_enlargement: false,
+                    without_reduction: false,
+                    kernel: ResizeKernel::Lanczos3,
+                    linear: false,
+                    sharpen: false,
                 },
                 Operation::Grayscale,
                 Operation::Rotate { degrees: 90 },
@@ -1327,6 +2509,20 @@ mod tests {
             let result = validate_operation_sequence_with_caps(&ops, &mut caps);
             assert!(result.is_err(), "should fail without orientation metadata");
         }
+
+        #[test]
+        fn encode_contract_rejects_linear_light() {
+            let img = DynamicImage::ImageRgb8(RgbImage::new(2, 2));
+            let mut state = ColorState::from_dynamic_image(&img, IccState::Absent);
+            assert!(ensure_encodable(&state, &OperationContract::ENCODE).is_ok());
+            state.transfer = TransferFn::Linear;
+            assert!(ensure_encodable(&state, &OperationContract::ENCODE).is_err());
+
+            let mut caps = OperationCapabilities::with_defaults();
+            caps.srgb_transfer = false;
+            let result = validate_operation_sequence_with_caps(&[], &mut caps);
+            assert!(result.is_err(), "encode stage needs sRGB samples");
+        }
     }
 
     mod apply_ops_tests {
@@ -1339,11 +2535,46 @@ mod tests {
                 width: Some(50),
                 height: Some(50),
                 fit: ResizeFit::Inside,
+                position: ResizePosition::Center,
+                without_enlargement: false,
+                without_reduction: false,
+                kernel: ResizeKernel::Lanczos3,
+                linear: false,
+                sharpen: false,
             }];
             let result = apply_ops(Cow::Owned(img), &ops).unwrap();
             assert_eq!(result.dimensions(), (50, 50));
         }
 
+        #[test]
+        fn test_resize_keeps_sixteen_bit_samples() {
+            // A ramp finer than 8-bit steps must survive resize without
+            // being quantized to multiples of 257.
+            let img = DynamicImage::ImageRgb16(image::ImageBuffer::from_fn(64, 8, |x, _| {
+                let v = 30000 + x as u16;
+                image::Rgb([v, v, v])
+            }));
+            let ops = vec![Operation::Resize {
+                width: Some(32),
+                height: None,
+                fit: ResizeFit::Inside,
+                position: ResizePosition::Center,
+                without_enlargement: false,
+                without_reduction: false,
+                kernel: ResizeKernel::Lanczos3,
+                linear: false,
+                sharpen: false,
+            }];
+            let state = ColorState::from_dynamic_image(&img, IccState::Absent);
+            let tracked = apply_ops_tracked(Cow::Owned(img), &ops, state).unwrap();
+            assert_eq!(tracked.state.bit_depth, BitDepth::Sixteen);
+            let DynamicImage::ImageRgb16(rgb) = tracked.image.as_ref() else {
+                panic!("16-bit input should stay 16-bit");
+            };
+            assert_eq!(rgb.dimensions(), (32, 4));
+            assert!(rgb.pixels().any(|p| p[0] % 257 != 0));
+        }
+
         #[test]
         fn test_resize_width_only() {
             let img = create_test_image(100, 50);
@@ -1351,6 +2582,12 @@ mod tests {
                 width: Some(50),
                 height: None,
                 fit: ResizeFit::Inside,
+                position: ResizePosition::Center,
+                without_enlargement: false,
+                without_reduction: false,
+                kernel: ResizeKernel::Lanczos3,
+                linear: false,
+                sharpen: false,
             }];
             let result = apply_ops(Cow::Owned(img), &ops).unwrap();
             assert_eq!(result.dimensions(), (50, 25));
@@ -1363,6 +2600,12 @@ mod tests {
                 width: None,
                 height: Some(25),
                 fit: ResizeFit::Inside,
+                position: ResizePosition::Center,
+                without_enlargement: false,
+                without_reduction: false,
+                kernel: ResizeKernel::Lanczos3,
+                linear: false,
+                sharpen: false,
             }];
             let result = apply_ops(Cow::Owned(img), &ops).unwrap();
             assert_eq!(result.dimensions(), (50, 25));
@@ -1375,11 +2618,667 @@ mod tests {
                 width: Some(80),
                 height: Some(80),
                 fit: ResizeFit::Cover,
+                position: ResizePosition::Center,
+                without_enlargement: false,
+                without_reduction: false,
+                kernel: ResizeKernel::Lanczos3,
+                linear: false,
+                sharpen: false,
             }];
             let result = apply_ops(Cow::Owned(img), &ops).unwrap();
             assert_eq!(result.dimensions(), (80, 80));
         }
 
+        #[test]
+        fn test_resize_cover_respects_position() {
+            let img = create_test_image(200, 100);
+            let cover = |position| {
+                let ops = vec![Operation::Resize {
+                    width: Some(80),
+                    height: Some(80),
+                    fit: ResizeFit::Cover,
+                    position,
+                    without_enlargement: false,
+                    without_reduction: false,
+                    kernel: ResizeKernel::Lanczos3,
+                    linear: false,
+                    sharpen: false,
+                }];
+                apply_ops(Cow::Owned(img.clone()), &ops).unwrap().to_rgb8()
+            };
+
+            let west = cover(ResizePosition::West);
+            let east = cover(ResizePosition::East);
+            let focal = cover(ResizePosition::Focal { x: 1.0, y: 0.5 });
+            assert_eq!(west.dimensions(), (80, 80));
+            // Red channel encodes the source x coordinate
+            assert!(west.get_pixel(0, 0)[0] < 10);
+            assert!(east.get_pixel(79, 0)[0] > 190);
+            assert_eq!(east.into_raw(), focal.into_raw());
+        }
+
+        #[test]
+        fn test_resize_cover_entropy_reports_region() {
+            // Flat left half, detailed right half
+            let img = DynamicImage::ImageRgb8(RgbImage::from_fn(200, 100, |x, y| {
+                if x < 100 {
+                    image::Rgb([90, 90, 90])
+                } else {
+                    let v = ((x * 31 + y * 57) % 256) as u8;
+                    image::Rgb([v, 255 - v, v / 2])
+                }
+            }));
+            let ops = vec![Operation::Resize {
+                width: Some(50),
+                height: Some(50),
+                fit: ResizeFit::Cover,
+                position: ResizePosition::Entropy,
+                without_enlargement: false,
+                without_reduction: false,
+                kernel: ResizeKernel::Lanczos3,
+                linear: false,
+                sharpen: false,
+            }];
+            let state = ColorState::from_dynamic_image(&img, IccState::Absent);
+            let tracked = apply_ops_tracked(Cow::Owned(img), &ops, state).unwrap();
+
+            assert_eq!(tracked.image.dimensions(), (50, 50));
+            let region = tracked.smart_crop.expect("smart crop region reported");
+            assert_eq!((region.width, region.height), (50, 50));
+            assert!(region.left >= 40, "expected detailed half, got {region:?}");
+            assert_eq!(region.top, 0);
+        }
+
+        #[test]
+        fn test_compass_cover_does_not_report_region() {
+            let img = create_test_image(200, 100);
+            let ops = vec![Operation::Resize {
+                width: Some(50),
+                height: Some(50),
+                fit: ResizeFit::Cover,
+                position: ResizePosition::North,
+                without_enlargement: false,
+                without_reduction: false,
+                kernel: ResizeKernel::Lanczos3,
+                linear: false,
+                sharpen: false,
+            }];
+            let state = ColorState::from_dynamic_image(&img, IccState::Absent);
+            let tracked = apply_ops_tracked(Cow::Owned(img), &ops, state).unwrap();
+            assert!(tracked.smart_crop.is_none());
+        }
+
+        #[test]
+        fn test_resize_contain_letterboxes_with_transparency() {
+            let img = create_test_image(200, 100);
+            let ops = vec![Operation::Resize {
+                width: Some(80),
+                height: Some(80),
+                fit: ResizeFit::Contain {
+                    background: Color::TRANSPARENT,
+                },
+                position: ResizePosition::Center,
+                without_enlargement: false,
+                without_reduction: false,
+                kernel: ResizeKernel::Lanczos3,
+                linear: false,
+                sharpen: false,
+            }];
+            let state = ColorState::from_dynamic_image(&img, IccState::Absent);
+            let tracked = apply_ops_tracked(Cow::Owned(img), &ops, state).unwrap();
+            assert_eq!(tracked.state.color_space, ColorSpace::Rgba);
+
+            let out = tracked.image.to_rgba8();
+            assert_eq!(out.dimensions(), (80, 80));
+            // 80x40 image centred vertically: bands of 20px above and below
+            assert_eq!(out.get_pixel(40, 10)[3], 0);
+            assert_eq!(out.get_pixel(40, 70)[3], 0);
+            assert_eq!(out.get_pixel(40, 40)[3], 255);
+        }
+
+        #[test]
+        fn test_resize_contain_opaque_background_keeps_rgb() {
+            let img = create_test_image(100, 200);
+            let ops = vec![Operation::Resize {
+                width: Some(60),
+                height: Some(60),
+                fit: ResizeFit::Contain {
+                    background: Color::rgba(10, 20, 30, 255),
+                },
+                position: ResizePosition::West,
+                without_enlargement: false,
+                without_reduction: false,
+                kernel: ResizeKernel::Lanczos3,
+                linear: false,
+                sharpen: false,
+            }];
+            let result = apply_ops(Cow::Owned(img), &ops).unwrap();
+            let DynamicImage::ImageRgb8(out) = result.as_ref() else {
+                panic!("expected RGB output for opaque background");
+            };
+            assert_eq!(out.dimensions(), (60, 60));
+            // West gravity: image on the left, padding on the right
+            assert_eq!(out.get_pixel(59, 30), &image::Rgb([10, 20, 30]));
+            assert_ne!(out.get_pixel(0, 30), &image::Rgb([10, 20, 30]));
+        }
+
+        #[test]
+        fn test_resize_outside_keeps_whole_image() {
+            let img = create_test_image(200, 100);
+            let ops = vec![Operation::Resize {
+                width: Some(80),
+                height: Some(80),
+                fit: ResizeFit::Outside,
+                position: ResizePosition::Center,
+                without_enlargement: false,
+                without_reduction: false,
+                kernel: ResizeKernel::Lanczos3,
+                linear: false,
+                sharpen: false,
+            }];
+            let result = apply_ops(Cow::Owned(img), &ops).unwrap();
+            assert_eq!(result.dimensions(), (160, 80));
+        }
+
+        #[test]
+        fn test_resize_without_enlargement_is_noop_for_small_input() {
+            let img = create_test_image(40, 30);
+            let ops = vec![Operation::Resize {
+                width: Some(120),
+                height: None,
+                fit: ResizeFit::Inside,
+                position: ResizePosition::Center,
+                without_enlargement: true,
+                without_reduction: false,
+                kernel: ResizeKernel::Lanczos3,
+                linear: false,
+                sharpen: false,
+            }];
+            let result = apply_ops(Cow::Owned(img), &ops).unwrap();
+            assert_eq!(result.dimensions(), (40, 30));
+        }
+
+        #[test]
+        fn test_resize_nearest_kernel_keeps_hard_edges() {
+            let img = DynamicImage::ImageRgb8(RgbImage::from_fn(4, 4, |x, _| {
+                if x < 2 {
+                    Rgb([0, 0, 0])
+                } else {
+                    Rgb([255, 255, 255])
+                }
+            }));
+            let resize = |kernel| Operation::Resize {
+                width: Some(16),
+                height: Some(16),
+                fit: ResizeFit::Fill,
+                position: ResizePosition::Center,
+                without_enlargement: false,
+                without_reduction: false,
+                kernel,
+                linear: false,
+                sharpen: false,
+            };
+
+            let nearest = apply_ops(Cow::Borrowed(&img), &[resize(ResizeKernel::Nearest)])
+                .unwrap()
+                .to_rgb8();
+            assert!(nearest.pixels().all(|p| p[0] == 0 || p[0] == 255));
+
+            let lanczos = apply_ops(Cow::Borrowed(&img), &[resize(ResizeKernel::Lanczos3)])
+                .unwrap()
+                .to_rgb8();
+            assert!(lanczos.pixels().any(|p| p[0] != 0 && p[0] != 255));
+        }
+
+        #[test]
+        fn test_resize_linear_keeps_fine_detail_brightness() {
+            // 1px black/white checkerboard: sRGB averaging gives ~128,
+            // linear-light averaging gives ~188.
+            let img = DynamicImage::ImageRgb8(RgbImage::from_fn(64, 64, |x, y| {
+                if (x + y) % 2 == 0 {
+                    Rgb([0, 0, 0])
+                } else {
+                    Rgb([255, 255, 255])
+                }
+            }));
+            let resize = |linear| Operation::Resize {
+                width: Some(8),
+                height: None,
+                fit: ResizeFit::Inside,
+                position: ResizePosition::Center,
+                without_enlargement: false,
+                without_reduction: false,
+                kernel: ResizeKernel::Lanczos3,
+                linear,
+                sharpen: false,
+            };
+            let mean = |img: &DynamicImage| {
+                let rgb = img.to_rgb8();
+                rgb.pixels().map(|p| p[1] as f64).sum::<f64>() / rgb.pixels().len() as f64
+            };
+
+            let initial = ColorState::from_dynamic_image(&img, IccState::Absent);
+            let tracked = apply_ops_tracked(Cow::Borrowed(&img), &[resize(true)], initial).unwrap();
+            assert_eq!(tracked.state.transfer, TransferFn::Srgb);
+            assert_eq!(tracked.image.dimensions(), (8, 8));
+            let linear_mean = mean(&tracked.image);
+
+            let srgb_mean = mean(&apply_ops(Cow::Borrowed(&img), &[resize(false)]).unwrap());
+            assert!(linear_mean > 180.0, "linear mean {linear_mean}");
+            assert!(srgb_mean < 140.0, "sRGB mean {srgb_mean}");
+        }
+
+        fn extend(mode: ExtendMode, background: Color) -> Operation {
+            Operation::Extend {
+                top: 1,
+                right: 2,
+                bottom: 0,
+                left: 3,
+                background,
+                mode,
+            }
+        }
+
+        /// 3x2 RGB image whose red channel encodes the pixel index.
+        fn indexed_image() -> DynamicImage {
+            DynamicImage::ImageRgb8(RgbImage::from_fn(3, 2, |x, y| {
+                Rgb([(y * 3 + x) as u8 * 10, 0, 0])
+            }))
+        }
+
+        fn red_rows(img: &DynamicImage) -> Vec<Vec<u8>> {
+            let rgb = img.to_rgb8();
+            rgb.rows().map(|row| row.map(|p| p[0]).collect()).collect()
+        }
+
+        #[test]
+        fn test_resize_auto_sharpen_only_after_downscale() {
+            // Hard vertical edge; a sharpened downscale overshoots next to it.
+            let img = DynamicImage::ImageRgb8(RgbImage::from_fn(64, 64, |x, _| {
+                if x < 32 {
+                    Rgb([60, 60, 60])
+                } else {
+                    Rgb([190, 190, 190])
+                }
+            }));
+            let resize = |width, sharpen| Operation::Resize {
+                width: Some(width),
+                height: None,
+                fit: ResizeFit::Inside,
+                position: ResizePosition::Center,
+                without_enlargement: false,
+                without_reduction: false,
+                kernel: ResizeKernel::Bilinear,
+                linear: false,
+                sharpen,
+            };
+            let run = |op| apply_ops(Cow::Borrowed(&img), &[op]).unwrap().to_rgb8();
+
+            let plain = run(resize(16, false));
+            let sharpened = run(resize(16, true));
+            assert!(sharpened.get_pixel(6, 8)[0] < plain.get_pixel(6, 8)[0]);
+            assert!(sharpened.get_pixel(9, 8)[0] > plain.get_pixel(9, 8)[0]);
+            assert_eq!(sharpened.get_pixel(0, 0), plain.get_pixel(0, 0));
+
+            assert_eq!(run(resize(128, true)), run(resize(128, false)));
+        }
+
+        #[test]
+        fn test_extend_background_keeps_rgb_for_opaque_fill() {
+            let img = indexed_image();
+            let white = Color::WHITE;
+            let result = apply_ops(
+                Cow::Borrowed(&img),
+                &[extend(ExtendMode::Background, white)],
+            )
+            .unwrap();
+            assert_eq!(result.dimensions(), (8, 3));
+            assert!(matches!(result.as_ref(), DynamicImage::ImageRgb8(_)));
+            let rgb = result.to_rgb8();
+            assert_eq!(rgb.get_pixel(0, 0), &Rgb([255, 255, 255]));
+            assert_eq!(rgb.get_pixel(3, 1), &Rgb([0, 0, 0]));
+            assert_eq!(rgb.get_pixel(5, 2), &Rgb([50, 0, 0]));
+
+            let result = apply_ops(
+                Cow::Borrowed(&img),
+                &[extend(ExtendMode::Background, Color::TRANSPARENT)],
+            )
+            .unwrap();
+            assert_eq!(result.to_rgba8().get_pixel(0, 0)[3], 0);
+        }
+
+        #[test]
+        fn test_extend_copy_repeats_edges() {
+            let img = indexed_image();
+            let result = apply_ops(
+                Cow::Borrowed(&img),
+                &[extend(ExtendMode::Copy, Color::TRANSPARENT)],
+            )
+            .unwrap();
+            assert!(matches!(result.as_ref(), DynamicImage::ImageRgb8(_)));
+            assert_eq!(
+                red_rows(&result),
+                vec![
+                    vec![0, 0, 0, 0, 10, 20, 20, 20],
+                    vec![0, 0, 0, 0, 10, 20, 20, 20],
+                    vec![30, 30, 30, 30, 40, 50, 50, 50],
+                ]
+            );
+        }
+
+        #[test]
+        fn test_extend_mirror_reflects_edges() {
+            let img = indexed_image();
+            let result = apply_ops(
+                Cow::Borrowed(&img),
+                &[extend(ExtendMode::Mirror, Color::TRANSPARENT)],
+            )
+            .unwrap();
+            assert_eq!(
+                red_rows(&result),
+                vec![
+                    vec![20, 10, 0, 0, 10, 20, 20, 10],
+                    vec![20, 10, 0, 0, 10, 20, 20, 10],
+                    vec![50, 40, 30, 30, 40, 50, 50, 40],
+                ]
+            );
+        }
+
+        #[test]
+        fn test_extend_edge_modes_preserve_pixel_layout() {
+            let img = DynamicImage::ImageLuma16(image::ImageBuffer::from_pixel(
+                2,
+                2,
+                image::Luma([40_000u16]),
+            ));
+            let result = apply_ops(
+                Cow::Borrowed(&img),
+                &[extend(ExtendMode::Mirror, Color::TRANSPARENT)],
+            )
+            .unwrap();
+            assert_eq!(result.dimensions(), (7, 3));
+            assert!(matches!(result.as_ref(), DynamicImage::ImageLuma16(_)));
+        }
+
+        #[test]
+        fn test_extend_rejects_oversized_canvas() {
+            let img = create_test_image(10, 10);
+            let ops = vec![Operation::Extend {
+                top: 0,
+                right: crate::engine::MAX_DIMENSION,
+                bottom: 0,
+                left: 0,
+                background: Color::BLACK,
+                mode: ExtendMode::Background,
+            }];
+            assert!(apply_ops(Cow::Owned(img), &ops).is_err());
+        }
+
+        #[test]
+        fn test_flatten_composites_onto_background() {
+            let img = DynamicImage::ImageRgba8(RgbaImage::from_fn(2, 1, |x, _| {
+                if x == 0 {
+                    image::Rgba([0, 0, 255, 0])
+                } else {
+                    image::Rgba([0, 0, 0, 128])
+                }
+            }));
+            let ops = vec![Operation::Flatten {
+                background: Color::WHITE,
+            }];
+            let state = ColorState::from_dynamic_image(&img, IccState::Absent);
+            let tracked = apply_ops_tracked(Cow::Owned(img), &ops, state).unwrap();
+            assert_eq!(tracked.state.color_space, ColorSpace::Rgb);
+            let DynamicImage::ImageRgb8(rgb) = tracked.image.as_ref() else {
+                panic!("flatten should produce RGB8");
+            };
+            assert_eq!(rgb.get_pixel(0, 0), &image::Rgb([255, 255, 255]));
+            assert_eq!(rgb.get_pixel(1, 0), &image::Rgb([127, 127, 127]));
+        }
+
+        #[test]
+        fn test_flatten_keeps_sixteen_bit_depth() {
+            let img = DynamicImage::ImageRgba16(image::ImageBuffer::from_pixel(
+                1,
+                1,
+                image::Rgba([65535u16, 0, 0, 0]),
+            ));
+            let ops = vec![Operation::Flatten {
+                background: Color::BLACK,
+            }];
+            let state = ColorState::from_dynamic_image(&img, IccState::Absent);
+            let tracked = apply_ops_tracked(Cow::Owned(img), &ops, state).unwrap();
+            assert_eq!(tracked.state.bit_depth, BitDepth::Sixteen);
+            let DynamicImage::ImageRgb16(rgb) = tracked.image.as_ref() else {
+                panic!("16-bit input should stay 16-bit");
+            };
+            assert_eq!(rgb.get_pixel(0, 0), &image::Rgb([0u16, 0, 0]));
+        }
+
+        #[test]
+        fn test_flatten_for_encode_only_when_contract_forbids_alpha() {
+            let img = create_test_image_rgba(4, 4);
+            let state = ColorState::from_dynamic_image(&img, IccState::Absent);
+
+            let tracked = apply_ops_tracked(Cow::Borrowed(&img), &[], state).unwrap();
+            let kept =
+                flatten_for_encode(tracked, &OperationContract::ENCODE, Color::WHITE).unwrap();
+            assert!(
+                matches!(kept.image, Cow::Borrowed(_)),
+                "PNG-like output keeps alpha"
+            );
+            assert!(ensure_encodable(&kept.state, &OperationContract::ENCODE_OPAQUE).is_err());
+
+            let tracked = apply_ops_tracked(Cow::Borrowed(&img), &[], state).unwrap();
+            let flat = flatten_for_encode(tracked, &OperationContract::ENCODE_OPAQUE, Color::WHITE)
+                .unwrap();
+            assert!(matches!(flat.image.as_ref(), DynamicImage::ImageRgb8(_)));
+            assert!(ensure_encodable(&flat.state, &OperationContract::ENCODE_OPAQUE).is_ok());
+        }
+
+        #[test]
+        fn test_join_channel_decodes_mask_as_alpha() {
+            let mask = DynamicImage::ImageLuma8(image::GrayImage::from_fn(4, 4, |x, _| {
+                image::Luma([if x < 2 { 0 } else { 255 }])
+            }));
+            let mut encoded = Vec::new();
+            mask.write_to(
+                &mut std::io::Cursor::new(&mut encoded),
+                image::ImageFormat::Png,
+            )
+            .unwrap();
+            let ops = vec![Operation::JoinChannel {
+                mask: std::sync::Arc::new(encoded),
+            }];
+
+            let img = create_test_image(4, 4);
+            let state = ColorState::from_dynamic_image(&img, IccState::Absent);
+            let tracked = apply_ops_tracked(Cow::Borrowed(&img), &ops, state).unwrap();
+            assert_eq!(tracked.state.color_space, ColorSpace::Rgba);
+            let rgba = tracked.image.to_rgba8();
+            assert_eq!(rgba.get_pixel(0, 0)[3], 0);
+            assert_eq!(rgba.get_pixel(3, 0)[3], 255);
+
+            let img = create_test_image(5, 4);
+            let state = ColorState::from_dynamic_image(&img, IccState::Absent);
+            let err = apply_ops_tracked(Cow::Borrowed(&img), &ops, state)
+                .err()
+                .expect("mask size mismatch should fail");
+            assert!(matches!(err, LazyImageError::InvalidArgument { .. }));
+
+            // The mismatch comes from the header: a mask truncated after IHDR
+            // never reaches the decoder.
+            let Operation::JoinChannel { mask } = &ops[0] else {
+                unreachable!()
+            };
+            let header_only = vec![Operation::JoinChannel {
+                mask: std::sync::Arc::new(mask[..33].to_vec()),
+            }];
+            let state = ColorState::from_dynamic_image(&img, IccState::Absent);
+            let err = apply_ops_tracked(Cow::Borrowed(&img), &header_only, state)
+                .err()
+                .expect("mask size mismatch should fail");
+            assert!(matches!(err, LazyImageError::InvalidArgument { .. }));
+        }
+
+        #[test]
+        fn test_rotate_arbitrary_expands_canvas_and_tracks_alpha() {
+            let img = create_test_image(100, 50);
+            let ops = vec![Operation::RotateArbitrary {
+                degrees: 45.0,
+                background: Color::TRANSPARENT,
+                interpolation: RotateInterpolation::Bicubic,
+            }];
+            let state = ColorState::from_dynamic_image(&img, IccState::Absent);
+            let tracked = apply_ops_tracked(Cow::Owned(img), &ops, state).unwrap();
+            assert_eq!(tracked.image.dimensions(), (107, 107));
+            assert!(matches!(
+                tracked.image.as_ref(),
+                DynamicImage::ImageRgba8(_)
+            ));
+            assert_eq!(tracked.state.color_space, ColorSpace::Rgba);
+            assert_eq!(tracked.image.to_rgba8().get_pixel(0, 0)[3], 0);
+        }
+
+        #[test]
+        fn test_rotate_arbitrary_right_angle_is_lossless() {
+            let img = create_test_image(6, 4);
+            let expected = img.rotate270();
+            let ops = vec![Operation::RotateArbitrary {
+                degrees: -90.0,
+                background: Color::TRANSPARENT,
+                interpolation: RotateInterpolation::Bilinear,
+            }];
+            let state = ColorState::from_dynamic_image(&img, IccState::Absent);
+            let tracked = apply_ops_tracked(Cow::Owned(img), &ops, state).unwrap();
+            assert_eq!(tracked.image.as_ref(), &expected);
+            assert_eq!(tracked.state.color_space, ColorSpace::Rgb);
+        }
+
+        #[test]
+        fn test_trim_crops_margin_and_reports_region() {
+            let img = DynamicImage::ImageRgb8(image::RgbImage::from_fn(40, 30, |x, y| {
+                if (10..25).contains(&x) && (5..20).contains(&y) {
+                    image::Rgb([200, 30, 30])
+                } else {
+                    image::Rgb([255, 255, 255])
+                }
+            }));
+            let ops = vec![Operation::Trim {
+                threshold: 10,
+                background: None,
+            }];
+            let state = ColorState::from_dynamic_image(&img, IccState::Absent);
+            let tracked = apply_ops_tracked(Cow::Owned(img), &ops, state).unwrap();
+
+            assert_eq!(tracked.image.dimensions(), (15, 15));
+            assert_eq!(
+                tracked.trim,
+                Some(CropRegion {
+                    left: 10,
+                    top: 5,
+                    width: 15,
+                    height: 15
+                })
+            );
+        }
+
+        #[test]
+        fn test_trim_leaves_uniform_image_unchanged() {
+            let img = DynamicImage::ImageRgb8(RgbImage::from_pixel(12, 8, image::Rgb([7, 7, 7])));
+            let ops = vec![Operation::Trim {
+                threshold: 0,
+                background: None,
+            }];
+            let state = ColorState::from_dynamic_image(&img, IccState::Absent);
+            let tracked = apply_ops_tracked(Cow::Owned(img), &ops, state).unwrap();
+            assert_eq!(tracked.image.dimensions(), (12, 8));
+            assert_eq!(tracked.trim.map(|r| (r.width, r.height)), Some((12, 8)));
+        }
+
+        #[test]
+        fn test_guard_uses_runtime_dimensions() {
+            // The guard is evaluated against what the resize receives after
+            // the crop, not against the original input size.
+            let img = create_test_image(200, 200);
+            let ops = vec![
+                Operation::Crop {
+                    x: 0,
+                    y: 0,
+                    width: 50,
+                    height: 50,
+                },
+                Operation::Resize {
+                    width: Some(100),
+                    height: Some(100),
+                    fit: ResizeFit::Inside,
+                    position: ResizePosition::Center,
+                    without_enlargement: true,
+                    without_reduction: false,
+                    kernel: ResizeKernel::Lanczos3,
+                    linear: false,
+                    sharpen: false,
+                },
+            ];
+            let result = apply_ops(Cow::Owned(img), &ops).unwrap();
+            assert_eq!(result.dimensions(), (50, 50));
+        }
+
+        #[test]
+        fn test_extract_contain_crops_padded_canvas() {
+            let img = create_test_image(200, 100);
+            let ops = vec![Operation::Extract {
+                width: Some(80),
+                height: Some(80),
+                fit: ResizeFit::Contain {
+                    background: Color::WHITE,
+                },
+                position: ResizePosition::Center,
+                without_enlargement: false,
+                without_reduction: false,
+                kernel: ResizeKernel::Lanczos3,
+                crop_x: 0,
+                crop_y: 0,
+                crop_width: 80,
+                crop_height: 30,
+            }];
+            let out = apply_ops(Cow::Owned(img), &ops).unwrap().to_rgb8();
+            assert_eq!(out.dimensions(), (80, 30));
+            assert_eq!(out.get_pixel(40, 5), &image::Rgb([255, 255, 255]));
+        }
+
+        #[test]
+        fn test_extract_cover_uses_position_offset() {
+            let img = create_test_image(160, 80);
+            let ops = vec![Operation::Extract {
+                width: Some(80),
+                height: Some(80),
+                fit: ResizeFit::Cover,
+                position: ResizePosition::East,
+                without_enlargement: false,
+                without_reduction: false,
+                kernel: ResizeKernel::Lanczos3,
+                crop_x: 0,
+                crop_y: 0,
+                crop_width: 80,
+                crop_height: 80,
+            }];
+            let result = apply_ops(Cow::Owned(img.clone()), &ops).unwrap();
+
+            let resized = fast_resize_owned(img, 160, 80).unwrap();
+            let (expected, _) = crop_to_dimensions(resized, 80, 80, ResizePosition::East);
+            assert_eq!(result.dimensions(), (80, 80));
+            // Extract resamples from source coordinates, so allow rounding differences
+            let diff = result
+                .to_rgb8()
+                .pixels()
+                .zip(expected.to_rgb8().pixels())
+                .map(|(a, b)| (a[0] as i32 - b[0] as i32).abs())
+                .max()
+                .unwrap();
+            assert!(diff <= 2, "max channel diff {diff}");
+        }
+
         #[test]
         fn test_resize_fill_ignores_aspect_ratio() {
             let img = create_test_image(200, 100);
@@ -1387,6 +3286,12 @@ mod tests {
                 width: Some(40),
                 height: Some(90),
                 fit: ResizeFit::Fill,
+                position: ResizePosition::Center,
+                without_enlargement: false,
+                without_reduction: false,
+                kernel: ResizeKernel::Lanczos3,
+                linear: false,
+                sharpen: false,
             }];
             let result = apply_ops(Cow::Owned(img), &ops).unwrap();
             assert_eq!(result.dimensions(), (40, 90));
@@ -1453,6 +3358,12 @@ mod tests {
                     width: Some(60),
                     height: Some(60),
                     fit: ResizeFit::Inside,
+                    position: ResizePosition::Center,
+                    without_enlargement: false,
+                    without_reduction: false,
+                    kernel: ResizeKernel::Lanczos3,
+                    linear: false,
+                    sharpen: false,
                 },
                 Operation::Crop {
                     x: 5,
@@ -1482,6 +3393,12 @@ mod tests {
                     width: Some(80),
                     height: Some(80),
                     fit: ResizeFit::Cover,
+                    position: ResizePosition::Center,
+                    without_enlargement: false,
+                    without_reduction: false,
+                    kernel: ResizeKernel::Lanczos3,
+                    linear: false,
+                    sharpen: false,
                 },
                 Operation::Crop {
                     x: 10,
@@ -1497,7 +3414,7 @@ mod tests {
             let result = apply_ops(Cow::Owned(img.clone()), &ops).unwrap();
             let (resize_w, resize_h) = calc_cover_resize_dimensions(160, 80, 80, 80);
             let resized = fast_resize_owned(img, resize_w, resize_h).unwrap();
-            let centered = crop_to_dimensions(resized, 80, 80);
+            let (centered, _) = crop_to_dimensions(resized, 80, 80, ResizePosition::Center);
             let expected = centered.crop_imm(10, 5, 40, 30);
 
             assert_eq!(result.dimensions(), (40, 30));
@@ -1512,6 +3429,12 @@ mod tests {
                     width: Some(90),
                     height: Some(60),
                     fit: ResizeFit::Fill,
+                    position: ResizePosition::Center,
+                    without_enlargement: false,
+                    without_reduction: false,
+                    kernel: ResizeKernel::Lanczos3,
+                    linear: false,
+                    sharpen: false,
                 },
                 Operation::Crop {
                     x: 20,
@@ -1538,6 +3461,12 @@ mod tests {
                     width: Some(100),
                     height: Some(100),
                     fit: ResizeFit::Fill,
+                    position: ResizePosition::Center,
+                    without_enlargement: false,
+                    without_reduction: false,
+                    kernel: ResizeKernel::Lanczos3,
+                    linear: false,
+                    sharpen: false,
                 },
                 Operation::Crop {
                     x: 90,
@@ -1564,6 +3493,12 @@ mod tests {
                     width: Some(1),
                     height: Some(1),
                     fit: ResizeFit::Inside,
+                    position: ResizePosition::Center,
+                    without_enlargement: false,
+                    without_reduction: false,
+                    kernel: ResizeKernel::Lanczos3,
+                    linear: false,
+                    sharpen: false,
                 },
                 Operation::Crop {
                     x: 0,
@@ -1590,6 +3525,12 @@ mod tests {
                     width: Some(100),
                     height: Some(100),
                     fit: ResizeFit::Inside,
+                    position: ResizePosition::Center,
+                    without_enlargement: false,
+                    without_reduction: false,
+                    kernel: ResizeKernel::Lanczos3,
+                    linear: false,
+                    sharpen: false,
                 },
                 Operation::Crop {
                     x: 0,
@@ -1750,6 +3691,12 @@ mod tests {
                     width: Some(100),
                     height: None,
                     fit: ResizeFit::Inside,
+                    position: ResizePosition::Center,
+                    without_enlargement: false,
+                    without_reduction: false,
+                    kernel: ResizeKernel::Lanczos3,
+                    linear: false,
+                    sharpen: false,
                 },
                 Operation::Rotate { degrees: 90 },
                 Operation::Grayscale,
@@ -1768,6 +3715,12 @@ mod tests {
                     width: Some(30),
                     height: Some(30),
                     fit: ResizeFit::Inside,
+                    position: ResizePosition::Center,
+                    without_enlargement: false,
+                    without_reduction: false,
+                    kernel: ResizeKernel::Lanczos3,
+                    linear: false,
+                    sharpen: false,
                 },
                 Operation::Crop {
                     x: 4,
@@ -1807,6 +3760,12 @@ mod tests {
                     width: Some(50),
                     height: None,
                     fit: ResizeFit::Inside,
+                    position: ResizePosition::Center,
+                    without_enlargement: false,
+                    without_reduction: false,
+                    kernel: ResizeKernel::Lanczos3,
+                    linear: false,
+                    sharpen: false,
                 },
             ];
 
@@ -1832,6 +3791,183 @@ mod tests {
     mod optimize_ops_tests {
         use super::*;
 
+        #[test]
+        fn test_guarded_resizes_not_merged() {
+            let ops = vec![
+                Operation::Resize {
+                    width: Some(400),
+                    height: None,
+                    fit: ResizeFit::Inside,
+                    position: ResizePosition::Center,
+                    without_enlargement: false,
+                    without_reduction: false,
+                    kernel: ResizeKernel::Lanczos3,
+                    linear: false,
+                    sharpen: false,
+                },
+                Operation::Resize {
+                    width: Some(1200),
+                    height: None,
+                    fit: ResizeFit::Inside,
+                    position: ResizePosition::Center,
+                    without_enlargement: true,
+                    without_reduction: false,
+                    kernel: ResizeKernel::Lanczos3,
+                    linear: false,
+                    sharpen: false,
+                },
+            ];
+            let optimized = optimize_ops(&ops);
+            assert_eq!(optimized.len(), 2);
+
+            // Merging would resize 2000px straight to 1200px; sequentially the
+            // guard keeps the 400px result.
+            let img = create_test_image(2000, 1000);
+            let result = apply_ops(Cow::Owned(img), &ops).unwrap();
+            assert_eq!(result.dimensions(), (400, 200));
+        }
+
+        #[test]
+        fn test_point_ops_fused_into_single_lut() {
+            let ops = vec![
+                Operation::Brightness { value: 20 },
+                Operation::Contrast { value: -30 },
+                Operation::Gamma { gamma: 1.8 },
+                Operation::FlipH,
+                Operation::Gamma { gamma: 0.7 },
+            ];
+            let optimized = optimize_ops(&ops);
+            assert_eq!(optimized.len(), 3);
+            match &optimized[0] {
+                Operation::PointLut { steps } => assert_eq!(
+                    steps,
+                    &[
+                        PointOp::Brightness(20),
+                        PointOp::Contrast(-30),
+                        PointOp::Gamma(1.8)
+                    ]
+                ),
+                other => panic!("expected PointLut, got {other:?}"),
+            }
+            // A lone point op is left as is.
+            assert!(matches!(optimized[2], Operation::Gamma { .. }));
+
+            let img = DynamicImage::ImageRgba8(image::RgbaImage::from_fn(64, 8, |x, y| {
+                image::Rgba([
+                    (x * 4) as u8,
+                    (y * 30) as u8,
+                    255 - (x * 4) as u8,
+                    (x * 3) as u8,
+                ])
+            }));
+            let sequential = ops[..3].iter().fold(img.clone(), |img, op| {
+                apply_ops(Cow::Owned(img), std::slice::from_ref(op))
+                    .unwrap()
+                    .into_owned()
+            });
+            let fused = apply_ops(Cow::Owned(img), &ops[..3]).unwrap();
+            assert_eq!(fused.into_owned(), sequential);
+        }
+
+        #[test]
+        fn test_resizes_with_different_kernels_not_merged() {
+            let resize = |width, kernel| Operation::Resize {
+                width: Some(width),
+                height: None,
+                fit: ResizeFit::Inside,
+                position: ResizePosition::Center,
+                without_enlargement: false,
+                without_reduction: false,
+                kernel,
+                linear: false,
+                sharpen: false,
+            };
+            let ops = vec![
+                resize(400, ResizeKernel::Nearest),
+                resize(200, ResizeKernel::Lanczos3),
+            ];
+            assert_eq!(optimize_ops(&ops).len(), 2);
+
+            let ops = vec![
+                resize(400, ResizeKernel::Mitchell),
+                resize(200, ResizeKernel::Mitchell),
+            ];
+            let optimized = optimize_ops(&ops);
+            assert_eq!(optimized.len(), 1);
+            assert!(matches!(
+                optimized[0],
+                Operation::Resize {
+                    kernel: ResizeKernel::Mitchell,
+                    ..
+                }
+            ));
+        }
+
+        #[test]
+        fn test_crop_then_guarded_resize_resolves_guard() {
+            let ops = vec![
+                Operation::Crop {
+                    x: 0,
+                    y: 0,
+                    width: 300,
+                    height: 200,
+                },
+                Operation::Resize {
+                    width: Some(100),
+                    height: None,
+                    fit: ResizeFit::Inside,
+                    position: ResizePosition::Center,
+                    without_enlargement: false,
+                    without_reduction: true,
+                    kernel: ResizeKernel::Lanczos3,
+                    linear: false,
+                    sharpen: false,
+                },
+            ];
+            let optimized = optimize_ops(&ops);
+            assert_eq!(optimized.len(), 2);
+            match &optimized[1] {
+                Operation::Resize {
+                    width,
+                    height,
+                    without_reduction,
+                    ..
+                } => {
+                    assert_eq!((*width, *height), (Some(300), Some(200)));
+                    assert!(*without_reduction);
+                }
+                other => panic!("Expected Resize, got {other:?}"),
+            }
+        }
+
+        #[test]
+        fn test_contain_resize_then_crop_not_fused() {
+            let ops = vec![
+                Operation::Resize {
+                    width: Some(100),
+                    height: Some(100),
+                    fit: ResizeFit::Contain {
+                        background: Color::BLACK,
+                    },
+                    position: ResizePosition::Center,
+                    without_enlargement: false,
+                    without_reduction: false,
+                    kernel: ResizeKernel::Lanczos3,
+                    linear: false,
+                    sharpen: false,
+                },
+                Operation::Crop {
+                    x: 0,
+                    y: 0,
+                    width: 50,
+                    height: 50,
+                },
+            ];
+            let optimized = optimize_ops(&ops);
+            assert_eq!(optimized.len(), 2);
+            assert!(matches!(optimized[0], Operation::Resize { .. }));
+        }
+
         #[test]
         fn test_consecutive_resizes_combined() {
             let ops = vec![
@@ -1839,11 +3975,23 @@ mod tests {
                     width: Some(800),
                     height: None,
                     fit: ResizeFit::Inside,
+                    position: ResizePosition::Center,
+                    without_enlargement: false,
+                    without_reduction: false,
+                    kernel: ResizeKernel::Lanczos3,
+                    linear: false,
+                    sharpen: false,
                 },
                 Operation::Resize {
                     width: Some(400),
                     height: None,
                     fit: ResizeFit::Inside,
+                    position: ResizePosition::Center,
+                    without_enlargement: false,
+                    without_reduction: false,
+                    kernel: ResizeKernel::Lanczos3,
+                    linear: false,
+                    sharpen: false,
                 },
             ];
             let optimized = optimize_ops(&ops);
@@ -1852,6 +4000,7 @@ mod tests {
                 width,
                 height: _,
                 fit,
+                ..
             } = &optimized[0]
             {
                 assert_eq!(*width, Some(400));
@@ -1868,24 +4017,72 @@ mod tests {
                     width: Some(800),
                     height: None,
                     fit: ResizeFit::Inside,
+                    position: ResizePosition::Center,
+                    without_enlargement: false,
+                    without_reduction: false,
+                    kernel: ResizeKernel::Lanczos3,
+                    linear: false,
+                    sharpen: false,
                 },
                 Operation::Grayscale,
                 Operation::Resize {
                     width: Some(400),
                     height: None,
                     fit: ResizeFit::Inside,
+                    position: ResizePosition::Center,
+                    without_enlargement: false,
+                    without_reduction: false,
+                    kernel: ResizeKernel::Lanczos3,
+                    linear: false,
+                    sharpen: false,
                 },
             ];
             let optimized = optimize_ops(&ops);
             assert_eq!(optimized.len(), 3);
         }
 
+        #[test]
+        fn test_cover_resizes_with_different_positions_not_combined() {
+            let ops = vec![
+                Operation::Resize {
+                    width: Some(400),
+                    height: Some(400),
+                    fit: ResizeFit::Cover,
+                    position: ResizePosition::North,
+                    without_enlargement: false,
+                    without_reduction: false,
+                    kernel: ResizeKernel::Lanczos3,
+                    linear: false,
+                    sharpen: false,
+                },
+                Operation::Resize {
+                    width: Some(200),
+                    height: Some(200),
+                    fit: ResizeFit::Cover,
+                    position: ResizePosition::South,
+                    without_enlargement: false,
+                    without_reduction: false,
+                    kernel: ResizeKernel::Lanczos3,
+                    linear: false,
+                    sharpen: false,
+                },
+            ];
+            let optimized = optimize_ops(&ops);
+            assert_eq!(optimized.len(), 2);
+        }
+
         #[test]
         fn test_single_operation() {
             let ops = vec![Operation::Resize {
                 width: Some(100),
                 height: None,
                 fit: ResizeFit::Inside,
+                position: ResizePosition::Center,
+                without_enlargement: false,
+                without_reduction: false,
+                kernel: ResizeKernel::Lanczos3,
+                linear: false,
+                sharpen: false,
             }];
             let optimized = optimize_ops(&ops);
             assert_eq!(optimized.len(), 1);
@@ -1898,6 +4095,35 @@ mod tests {
             assert_eq!(optimized.len(), 0);
         }
 
+        #[test]
+        fn test_linear_resize_then_crop_is_not_fused() {
+            let ops = vec![
+                Operation::Resize {
+                    width: Some(200),
+                    height: None,
+                    fit: ResizeFit::Inside,
+                    position: ResizePosition::Center,
+                    without_enlargement: false,
+                    without_reduction: false,
+                    kernel: ResizeKernel::Lanczos3,
+                    linear: true,
+                    sharpen: false,
+                },
+                Operation::Crop {
+                    x: 0,
+                    y: 0,
+                    width: 50,
+                    height: 50,
+                },
+            ];
+            let optimized = optimize_ops(&ops);
+            assert_eq!(optimized.len(), 2);
+            assert!(matches!(
+                optimized[0],
+                Operation::Resize { linear: true, .. }
+            ));
+        }
+
         #[test]
         fn test_resize_then_crop_is_fused_into_extract() {
             let ops = vec![
@@ -1905,6 +4131,12 @@ mod tests {
                     width: Some(200),
                     height: Some(150),
                     fit: ResizeFit::Inside,
+                    position: ResizePosition::Center,
+                    without_enlargement: false,
+                    without_reduction: false,
+                    kernel: ResizeKernel::Lanczos3,
+                    linear: false,
+                    sharpen: false,
                 },
                 Operation::Crop {
                     x: 10,
@@ -1925,6 +4157,7 @@ mod tests {
                     crop_y,
                     crop_width,
                     crop_height,
+                    ..
                 } => {
                     assert_eq!(*width, Some(200));
                     assert_eq!(*height, Some(150));
@@ -1945,16 +4178,34 @@ mod tests {
                     width: Some(1000),
                     height: None,
                     fit: ResizeFit::Inside,
+                    position: ResizePosition::Center,
+                    without_enlargement: false,
+                    without_reduction: false,
+                    kernel: ResizeKernel::Lanczos3,
+                    linear: false,
+                    sharpen: false,
                 },
                 Operation::Resize {
                     width: Some(800),
                     height: None,
                     fit: ResizeFit::Inside,
+                    position: ResizePosition::Center,
+                    without_enlargement: false,
+                    without_reduction: false,
+                    kernel: ResizeKernel::Lanczos3,
+                    linear: false,
+                    sharpen: false,
                 },
                 Operation::Resize {
                     width: Some(400),
                     height: None,
                     fit: ResizeFit::Inside,
+                    position: ResizePosition::Center,
+                    without_enlargement: false,
+                    without_reduction: false,
+                    kernel: ResizeKernel::Lanczos3,
+                    linear: false,
+                    sharpen: false,
                 },
             ];
             let optimized = optimize_ops(&ops);
@@ -1963,6 +4214,7 @@ mod tests {
                 width,
                 height: _,
                 fit,
+                ..
             } = &optimized[0]
             {
                 assert_eq!(*width, Some(400));
@@ -1977,16 +4229,31 @@ mod tests {
                     width: Some(800),
                     height: None,
                     fit: ResizeFit::Inside,
+                    position: ResizePosition::Center,
+                    without_enlargement: false,
+                    without_reduction: false,
+                    kernel: ResizeKernel::Lanczos3,
+                    linear: false,
+                    sharpen: false,
                 },
                 Operation::Resize {
                     width: Some(400),
                     height: Some(300),
                     fit: ResizeFit::Inside,
+                    position: ResizePosition::Center,
+                    without_enlargement: false,
+                    without_reduction: false,
+                    kernel: ResizeKernel::Lanczos3,
+                    linear: false,
+                    sharpen: false,
                 },
             ];
             let optimized = optimize_ops(&ops);
             assert_eq!(optimized.len(), 1);
-            if let Operation::Resize { width, height, fit } = &optimized[0] {
+            if let Operation::Resize {
+                width, height, fit, ..
+            } = &optimized[0]
+            {
                 assert_eq!(*width, Some(400));
                 assert_eq!(*height, Some(300));
                 assert_eq!(*fit, ResizeFit::Inside);
@@ -2050,9 +4317,11 @@ mod tests {
         }
 
         #[test]
-        fn test_requires_premultiply_only_for_rgba() {
+        fn test_requires_premultiply_only_with_alpha() {
             assert!(requires_premultiply(PixelType::U8x4));
+            assert!(requires_premultiply(PixelType::U16x2));
             assert!(!requires_premultiply(PixelType::U8x3));
+            assert!(!requires_premultiply(PixelType::U16));
         }
 
         #[test]
@@ -2125,8 +4394,9 @@ mod tests {
         #[test]
         fn test_is_fully_opaque_uses_wide_multiplication_for_threshold() {
             let mut pixels = vec![255u8; 4];
-            let image = fir::images::Image::from_slice_u8(1, 1, pixels.as_mut_slice(), PixelType::U8x4)
-                .expect("valid RGBA image");
+            let image =
+                fir::images::Image::from_slice_u8(1, 1, pixels.as_mut_slice(), PixelType::U8x4)
+                    .expect("valid RGBA image");
 
             assert!(
                 is_fully_opaque(&image, PixelType::U8x4, u32::MAX, u32::MAX),
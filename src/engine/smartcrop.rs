@@ -0,0 +1,323 @@
+// src/engine/smartcrop.rs
+//
+// Content-aware crop window selection for `ResizeFit::Cover`.
+//
+// Scoring runs on a small downscaled copy of the image, so its cost is bounded
+// regardless of the source resolution and the fast_resize hot path is untouched.
+// Two strategies are available:
+// - Entropy: window whose luma histogram has the highest Shannon entropy
+// - Attention: window with the highest sum of detail (edges), saturation
+//   and skin-tone scores
+
+use image::{DynamicImage, GenericImageView, RgbImage};
+
+/// Longest edge of the analysis copy.
+const ANALYSIS_MAX_DIMENSION: u32 = 128;
+
+/// Upper bound on candidate window positions per axis.
+const MAX_STEPS_PER_AXIS: u32 = 64;
+
+/// Attention weights (relative importance of each saliency cue).
+const DETAIL_WEIGHT: f64 = 1.0;
+const SATURATION_WEIGHT: f64 = 0.3;
+const SKIN_WEIGHT: f64 = 1.8;
+
+/// Normalized RGB direction of a typical skin tone and the minimum
+/// similarity for a pixel to count as skin.
+const SKIN_COLOR: [f64; 3] = [0.78, 0.57, 0.44];
+const SKIN_THRESHOLD: f64 = 0.8;
+const SKIN_MIN_LUMA: f64 = 0.2;
+
+/// Scores closer than this are considered equal (ties prefer the centre).
+const SCORE_EPSILON: f64 = 1e-9;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum SmartCropStrategy {
+    Entropy,
+    Attention,
+}
+
+/// Pick the offset of a `target` sized window inside `frame`.
+///
+/// `frame` is the size of the (scaled) image the window will be cut from.
+/// `img` can be that image or any other resolution of the same content,
+/// e.g. the source before the cover resize.
+pub(crate) fn find_crop_offset(
+    img: &DynamicImage,
+    frame: (u32, u32),
+    target: (u32, u32),
+    strategy: SmartCropStrategy,
+) -> (u32, u32) {
+    let excess_x = frame.0.saturating_sub(target.0);
+    let excess_y = frame.1.saturating_sub(target.1);
+    if (excess_x == 0 && excess_y == 0) || img.width() == 0 || img.height() == 0 {
+        return (0, 0);
+    }
+
+    let analysis = downscale_for_analysis(img);
+    let (aw, ah) = analysis.dimensions();
+    let win_w = scale_length(target.0, frame.0, aw).clamp(1, aw);
+    let win_h = scale_length(target.1, frame.1, ah).clamp(1, ah);
+
+    let (fraction_x, fraction_y) = match strategy {
+        SmartCropStrategy::Entropy => {
+            let luma = luma_plane(&analysis);
+            best_window(aw - win_w, ah - win_h, |x, y| {
+                window_entropy(&luma, aw, x, y, win_w, win_h)
+            })
+        }
+        SmartCropStrategy::Attention => {
+            let table = SummedArea::new(&saliency_map(&analysis), aw, ah);
+            best_window(aw - win_w, ah - win_h, |x, y| table.sum(x, y, win_w, win_h))
+        }
+    };
+
+    (
+        ((fraction_x * excess_x as f64).round() as u32).min(excess_x),
+        ((fraction_y * excess_y as f64).round() as u32).min(excess_y),
+    )
+}
+
+fn scale_length(value: u32, from: u32, to: u32) -> u32 {
+    (value as f64 * to as f64 / from.max(1) as f64).round() as u32
+}
+
+fn downscale_for_analysis(img: &DynamicImage) -> RgbImage {
+    let (w, h) = img.dimensions();
+    if w.max(h) <= ANALYSIS_MAX_DIMENSION {
+        img.to_rgb8()
+    } else {
+        img.thumbnail(ANALYSIS_MAX_DIMENSION, ANALYSIS_MAX_DIMENSION)
+            .to_rgb8()
+    }
+}
+
+fn luma(pixel: &image::Rgb<u8>) -> f64 {
+    (0.2126 * pixel[0] as f64 + 0.7152 * pixel[1] as f64 + 0.0722 * pixel[2] as f64) / 255.0
+}
+
+fn luma_plane(img: &RgbImage) -> Vec<u8> {
+    img.pixels()
+        .map(|p| (luma(p) * 255.0).round() as u8)
+        .collect()
+}
+
+/// Candidate offsets along one axis: every position when cheap, otherwise
+/// evenly spaced positions that always include both ends.
+fn axis_positions(excess: u32) -> Vec<u32> {
+    if excess < MAX_STEPS_PER_AXIS {
+        (0..=excess).collect()
+    } else {
+        let steps = MAX_STEPS_PER_AXIS;
+        (0..=steps)
+            .map(|i| (excess as u64 * i as u64 / steps as u64) as u32)
+            .collect()
+    }
+}
+
+/// Evaluate every candidate window and return the best one as a fraction
+/// (0.0-1.0) of the available travel on each axis. A window has to beat the
+/// centred one outright to win, so flat images keep the exact centre like
+/// the default gravity.
+fn best_window(excess_x: u32, excess_y: u32, score: impl Fn(u32, u32) -> f64) -> (f64, f64) {
+    let mut best_score = score(excess_x / 2, excess_y / 2);
+    let mut best = None;
+    for y in axis_positions(excess_y) {
+        for x in axis_positions(excess_x) {
+            let s = score(x, y);
+            if s > best_score + SCORE_EPSILON {
+                best = Some((x, y));
+                best_score = s;
+            }
+        }
+    }
+
+    let fraction = |value: u32, excess: u32| {
+        if excess == 0 {
+            0.0
+        } else {
+            value as f64 / excess as f64
+        }
+    };
+    match best {
+        Some((x, y)) => (fraction(x, excess_x), fraction(y, excess_y)),
+        None => (0.5, 0.5),
+    }
+}
+
+fn window_entropy(luma: &[u8], stride: u32, x: u32, y: u32, w: u32, h: u32) -> f64 {
+    let mut histogram = [0u32; 256];
+    for row in y..y + h {
+        let start = (row * stride + x) as usize;
+        for &value in &luma[start..start + w as usize] {
+            histogram[value as usize] += 1;
+        }
+    }
+    let total = (w as f64) * (h as f64);
+    histogram
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f64 / total;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// Per-pixel saliency: edge strength + saturation + skin likelihood.
+fn saliency_map(img: &RgbImage) -> Vec<f64> {
+    let (w, h) = img.dimensions();
+    let lumas: Vec<f64> = img.pixels().map(luma).collect();
+    let at = |x: u32, y: u32| lumas[(y * w + x) as usize];
+
+    let mut scores = Vec::with_capacity(lumas.len());
+    for y in 0..h {
+        for x in 0..w {
+            let center = at(x, y);
+            let neighbours = at(x.saturating_sub(1), y)
+                + at((x + 1).min(w - 1), y)
+                + at(x, y.saturating_sub(1))
+                + at(x, (y + 1).min(h - 1));
+            let detail = (4.0 * center - neighbours).abs().min(1.0);
+
+            let pixel = img.get_pixel(x, y);
+            let (r, g, b) = (pixel[0] as f64, pixel[1] as f64, pixel[2] as f64);
+            let max = r.max(g).max(b);
+            let min = r.min(g).min(b);
+            let saturation = if max > 0.0 { (max - min) / max } else { 0.0 };
+
+            let magnitude = (r * r + g * g + b * b).sqrt();
+            let skin = if magnitude > 0.0 && center >= SKIN_MIN_LUMA {
+                let d = ((r / magnitude - SKIN_COLOR[0]).powi(2)
+                    + (g / magnitude - SKIN_COLOR[1]).powi(2)
+                    + (b / magnitude - SKIN_COLOR[2]).powi(2))
+                .sqrt();
+                let similarity = 1.0 - d;
+                if similarity > SKIN_THRESHOLD {
+                    (similarity - SKIN_THRESHOLD) / (1.0 - SKIN_THRESHOLD)
+                } else {
+                    0.0
+                }
+            } else {
+                0.0
+            };
+
+            scores
+                .push(DETAIL_WEIGHT * detail + SATURATION_WEIGHT * saturation + SKIN_WEIGHT * skin);
+        }
+    }
+    scores
+}
+
+/// Summed-area table for O(1) window sums.
+struct SummedArea {
+    table: Vec<f64>,
+    stride: usize,
+}
+
+impl SummedArea {
+    fn new(values: &[f64], width: u32, height: u32) -> Self {
+        let stride = width as usize + 1;
+        let mut table = vec![0.0; stride * (height as usize + 1)];
+        for y in 0..height as usize {
+            let mut row_sum = 0.0;
+            for x in 0..width as usize {
+                row_sum += values[y * width as usize + x];
+                table[(y + 1) * stride + x + 1] = table[y * stride + x + 1] + row_sum;
+            }
+        }
+        Self { table, stride }
+    }
+
+    fn sum(&self, x: u32, y: u32, w: u32, h: u32) -> f64 {
+        let (x0, y0) = (x as usize, y as usize);
+        let (x1, y1) = (x0 + w as usize, y0 + h as usize);
+        self.table[y1 * self.stride + x1]
+            - self.table[y0 * self.stride + x1]
+            - self.table[y1 * self.stride + x0]
+            + self.table[y0 * self.stride + x0]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgb;
+
+    /// Flat grey on the left, high-frequency pattern on the right.
+    fn busy_right_half(width: u32, height: u32) -> DynamicImage {
+        DynamicImage::ImageRgb8(RgbImage::from_fn(width, height, |x, y| {
+            if x < width / 2 {
+                Rgb([128, 128, 128])
+            } else {
+                let v = ((x * 37 + y * 91) % 256) as u8;
+                Rgb([v, v.wrapping_mul(3), v.wrapping_add(90)])
+            }
+        }))
+    }
+
+    #[test]
+    fn entropy_prefers_detailed_region() {
+        let img = busy_right_half(400, 100);
+        let offset = find_crop_offset(&img, (400, 100), (100, 100), SmartCropStrategy::Entropy);
+        assert!(offset.0 >= 200, "expected right half, got {offset:?}");
+        assert_eq!(offset.1, 0);
+    }
+
+    #[test]
+    fn attention_prefers_detailed_region() {
+        let img = busy_right_half(400, 100);
+        let offset = find_crop_offset(&img, (400, 100), (100, 100), SmartCropStrategy::Attention);
+        assert!(offset.0 >= 200, "expected right half, got {offset:?}");
+    }
+
+    #[test]
+    fn attention_finds_skin_tones() {
+        let img = DynamicImage::ImageRgb8(RgbImage::from_fn(100, 300, |_, y| {
+            if (200..260).contains(&y) {
+                Rgb([224, 172, 140])
+            } else {
+                Rgb([40, 40, 40])
+            }
+        }));
+        let offset = find_crop_offset(&img, (100, 300), (100, 100), SmartCropStrategy::Attention);
+        assert!(
+            (160..=200).contains(&offset.1),
+            "window should cover the skin band, got {offset:?}"
+        );
+    }
+
+    #[test]
+    fn flat_image_falls_back_to_centre() {
+        let img = DynamicImage::ImageRgb8(RgbImage::from_pixel(300, 100, Rgb([10, 20, 30])));
+        for strategy in [SmartCropStrategy::Entropy, SmartCropStrategy::Attention] {
+            let offset = find_crop_offset(&img, (300, 100), (100, 100), strategy);
+            assert_eq!(offset, (100, 0), "{strategy:?}");
+        }
+    }
+
+    #[test]
+    fn analysis_resolution_is_mapped_back_to_frame() {
+        // Source analysed at a different size than the frame the crop is cut from.
+        let img = busy_right_half(1600, 400);
+        let offset = find_crop_offset(&img, (800, 200), (200, 200), SmartCropStrategy::Entropy);
+        assert!(offset.0 >= 400 && offset.0 <= 600, "got {offset:?}");
+    }
+
+    #[test]
+    fn no_excess_returns_origin() {
+        let img = busy_right_half(100, 100);
+        let offset = find_crop_offset(&img, (100, 100), (100, 100), SmartCropStrategy::Entropy);
+        assert_eq!(offset, (0, 0));
+    }
+
+    #[test]
+    fn axis_positions_include_both_ends() {
+        assert_eq!(axis_positions(0), vec![0]);
+        assert_eq!(axis_positions(3), vec![0, 1, 2, 3]);
+        let coarse = axis_positions(1000);
+        assert_eq!(coarse.first(), Some(&0));
+        assert_eq!(coarse.last(), Some(&1000));
+        assert!(coarse.len() <= MAX_STEPS_PER_AXIS as usize + 1);
+    }
+}
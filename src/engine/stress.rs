@@ -14,7 +14,7 @@ use crate::engine::encoder::{encode_avif, encode_jpeg_with_settings, encode_png,
 #[cfg(feature = "stress")]
 use crate::engine::pipeline::apply_ops;
 #[cfg(feature = "stress")]
-use crate::ops::{Operation, OutputFormat, ResizeFit};
+use crate::ops::{Operation, OutputFormat, ResizeFit, ResizeKernel, ResizePosition};
 #[cfg(feature = "stress")]
 use std::borrow::Cow;
 
@@ -36,6 +36,12 @@ pub fn run_stress_iteration(data: &[u8]) -> EngineResult<()> {
             width: Some(1200),
             height: Some(800),
             fit: ResizeFit::Inside,
+            position: ResizePosition::Center,
+            without_enlargement: false,
+            without_reduction: false,
+            kernel: ResizeKernel::Lanczos3,
+            linear: false,
+            sharpen: false,
         },
         Operation::Rotate { degrees: 90 },
         Operation::Brightness { value: 12 },
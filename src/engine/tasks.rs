@@ -5,20 +5,26 @@
 
 use super::firewall::FirewallConfig;
 use crate::engine::decoder::{
-    check_dimensions, decode_image, detect_format, ensure_dimensions_safe,
+    check_dimensions, decode_source, detect_format, ensure_dimensions_safe, jpeg_header_is_cmyk,
 };
+use crate::engine::depth;
 use crate::engine::encoder::{
-    embed_exif_jpeg, encode_avif, encode_jpeg_with_settings, encode_png, encode_webp,
+    embed_exif_jpeg, encode_avif_with_settings, encode_jpeg_with_settings, encode_png_with_gamut,
+    encode_webp,
 };
+use crate::engine::icc;
 #[allow(unused_imports)]
-use crate::engine::io::{extract_exif_raw, extract_icc_profile, Source};
+use crate::engine::io::{extract_exif_raw, extract_icc_profile, extract_icc_profile_lossy, Source};
 use crate::engine::memory;
-use crate::engine::pipeline::{apply_ops_tracked, ColorState, IccState};
+use crate::engine::pipeline::{
+    apply_ops_tracked, ensure_encodable, flatten_for_encode, BitDepth, ColorSpace, ColorState,
+    IccState,
+};
 #[cfg(feature = "napi")]
 use crate::engine::pool;
 #[allow(unused_imports)]
 use crate::error::{ErrorCategory, LazyImageError};
-use crate::ops::{Operation, OutputFormat};
+use crate::ops::{Color, Operation, OutputDepth, OutputFormat};
 use crate::PROCESSING_METRICS_VERSION;
 use image::{DynamicImage, GenericImageView, ImageFormat};
 #[cfg(feature = "napi")]
@@ -92,12 +98,31 @@ struct MetricsContext {
     icc_preserved: bool,
     metadata_stripped: bool,
     policy_violations: Vec<String>,
+    smart_crop: Option<crate::CropRegion>,
+    trim: Option<crate::CropRegion>,
 }
 
 fn detect_input_format(bytes: &[u8]) -> Option<String> {
     detect_format(bytes).map(format_to_string)
 }
 
+/// Header-based memory estimate for `ops` as they will run once
+/// `icc::bind_profiles` has added its profile conversions (including the
+/// CMYK one, which the decoder reports only after decoding).
+fn estimate_bound_memory(
+    bytes: &[u8],
+    ops: &[Operation],
+    icc_profile: Option<&Arc<Vec<u8>>>,
+    keep_icc: bool,
+    format: &OutputFormat,
+) -> u64 {
+    let mut bound = ops.to_vec();
+    let cmyk = jpeg_header_is_cmyk(bytes);
+    icc::bind_profiles(&mut bound, icc_profile, keep_icc, cmyk);
+    memory::estimate_memory_from_header(bytes, &bound, Some(format))
+        .unwrap_or(memory::ESTIMATED_MEMORY_PER_OPERATION)
+}
+
 fn format_to_string(fmt: ImageFormat) -> String {
     match fmt {
         ImageFormat::Jpeg => "jpeg",
@@ -195,6 +220,8 @@ impl<'m> MetricsRecorder<'m> {
             m.icc_preserved = context.icc_preserved;
             m.metadata_stripped = context.metadata_stripped;
             m.policy_violations = context.policy_violations;
+            m.smart_crop = context.smart_crop;
+            m.trim = context.trim;
         }
     }
 }
@@ -231,6 +258,10 @@ pub struct EncodeTask {
     /// Whether to strip GPS tags from EXIF (default: true for privacy protection)
     pub strip_gps: bool,
     pub firewall: FirewallConfig,
+    /// Colour alpha is flattened onto when the output format has no alpha channel
+    pub flatten_background: Color,
+    /// Requested output bit depth (see `OutputFormat::bit_depth`)
+    pub output_depth: OutputDepth,
     /// Last error that occurred during compute (for use in reject)
     #[cfg(feature = "napi")]
     pub(crate) last_error: Option<LazyImageError>,
@@ -244,17 +275,20 @@ impl EncodeTask {
     /// `Cow::Owned` if decoding was required. The caller can avoid deep copies
     /// when no mutation is needed (e.g., format conversion only).
     ///
+    /// CMYK JPEGs come back as ink samples, flagged by the second value, and
+    /// are converted by the pipeline.
+    ///
     /// Returns LazyImageError directly (not wrapped in napi::Error) for use in process_and_encode.
     pub(crate) fn decode_internal(
         &self,
-    ) -> std::result::Result<Cow<'_, DynamicImage>, LazyImageError> {
+    ) -> std::result::Result<(Cow<'_, DynamicImage>, bool), LazyImageError> {
         // Prefer already decoded image (already validated)
         // Return borrowed reference - no deep copy until mutation is needed
         if let Some(ref img_arc) = self.decoded {
             check_dimensions(img_arc.width(), img_arc.height())?;
             self.firewall
                 .enforce_pixels(img_arc.width(), img_arc.height())?;
-            return Ok(Cow::Borrowed(img_arc.as_ref()));
+            return Ok((Cow::Borrowed(img_arc.as_ref()), false));
         }
 
         // Get bytes from source - zero-copy for Memory and Mapped sources
@@ -280,14 +314,14 @@ impl EncodeTask {
 
         ensure_dimensions_safe(bytes)?;
 
-        let (img, _detected_format) = decode_image(bytes)?;
+        let (img, cmyk) = decode_source(bytes)?;
 
         // Security check: reject decompression bombs
         let (w, h) = img.dimensions();
         check_dimensions(w, h)?;
         self.firewall.enforce_pixels(w, h)?;
 
-        Ok(Cow::Owned(img))
+        Ok((Cow::Owned(img), cmyk))
     }
 
     /// Process image: decode → apply ops → encode
@@ -311,8 +345,14 @@ impl EncodeTask {
             .source
             .as_ref()
             .and_then(|s| s.as_bytes())
-            .and_then(|bytes| {
-                memory::estimate_memory_from_header(bytes, &self.ops, Some(&self.format))
+            .map(|bytes| {
+                estimate_bound_memory(
+                    bytes,
+                    &self.ops,
+                    self.icc_profile.as_ref(),
+                    self.keep_icc,
+                    &self.format,
+                )
             })
             .unwrap_or(memory::ESTIMATED_MEMORY_PER_OPERATION);
         let permit = memory::memory_semaphore().acquire(estimated_memory);
@@ -336,8 +376,11 @@ impl EncodeTask {
             None
         };
 
+        // Overlays are decoded by the pipeline; hold them to the same limits
+        self.firewall.enforce_overlays(&self.ops)?;
+
         // 1. Decode
-        let img = self.decode_internal()?;
+        let (img, cmyk) = self.decode_internal()?;
         self.firewall
             .enforce_timeout(metrics_recorder.start_total, "decode")?;
         metrics_recorder.mark_decode_done();
@@ -348,34 +391,56 @@ impl EncodeTask {
             // Insert at the very beginning to normalize before user operations
             effective_ops.insert(0, Operation::AutoOrient { orientation: o });
         }
+        icc::bind_profiles(
+            &mut effective_ops,
+            self.icc_profile.as_ref(),
+            self.keep_icc,
+            cmyk,
+        );
         let icc_state = if self.icc_present {
             IccState::Present
         } else {
             IccState::Absent
         };
-        let initial_state = ColorState::from_dynamic_image(&img, icc_state);
+        let mut initial_state = ColorState::from_dynamic_image(&img, icc_state);
+        initial_state.gamut = icc::embedded_gamut(self.icc_profile.as_ref());
+        if cmyk {
+            initial_state.color_space = ColorSpace::Cmyk;
+        }
+        let contract = self.format.encode_contract();
         let tracked = apply_ops_tracked(img, &effective_ops, initial_state)?;
+        let tracked = flatten_for_encode(tracked, &contract, self.flatten_background)?;
+        ensure_encodable(&tracked.state, &contract)?;
         let final_color_state = tracked.state;
-        let processed = tracked.image;
+        let smart_crop = tracked.smart_crop;
+        let trim = tracked.trim;
+        let bits = self.format.bit_depth(
+            self.output_depth,
+            final_color_state.bit_depth == BitDepth::Sixteen,
+        );
+        let gamut = final_color_state.gamut;
+        let processed = depth::for_encoder(tracked.image, bits);
         self.firewall
             .enforce_timeout(metrics_recorder.start_total, "process")?;
         metrics_recorder.mark_process_done();
 
-        // 3. Encode - only preserve ICC profile if keep_icc is true
-        let icc = if self.keep_icc {
-            self.icc_profile.as_ref().map(|v| v.as_slice())
-        } else {
-            None // Strip metadata by default for security & smaller files
-        };
+        // 3. Encode - the source profile is only preserved if keep_icc is true
+        // (stripped by default for security & smaller files); converted
+        // pixels carry their target profile instead.
+        let output_icc =
+            icc::output_profile(&effective_ops, self.keep_icc, self.icc_profile.as_ref());
+        let icc = output_icc.as_deref().map(Vec::as_slice);
 
         // 4. Encode image to target format
         let mut result = match &self.format {
             OutputFormat::Jpeg { quality, fast_mode } => {
                 encode_jpeg_with_settings(&processed, *quality, icc, *fast_mode)
             }
-            OutputFormat::Png => encode_png(&processed, icc),
+            OutputFormat::Png => encode_png_with_gamut(&processed, icc, gamut),
             OutputFormat::WebP { quality } => encode_webp(&processed, *quality, icc),
-            OutputFormat::Avif { quality } => encode_avif(&processed, *quality, icc),
+            OutputFormat::Avif { quality } => {
+                encode_avif_with_settings(&processed, *quality, icc, bits, gamut)
+            }
         }?;
 
         // 5. Embed EXIF metadata if requested (JPEG only for now)
@@ -401,8 +466,8 @@ impl EncodeTask {
         // Get final resource usage & finalize metrics
         let final_usage = get_resource_usage();
         // Use tracked color state to reason about ICC preservation.
-        let icc_present = matches!(final_color_state.icc, IccState::Present);
-        let icc_preserved = self.keep_icc && icc_present;
+        let icc_present = final_color_state.icc != IccState::Absent;
+        let icc_preserved = icc_present && output_icc.is_some();
         // metadata_stripped: true when source had ICC but we did not preserve it
         let metadata_stripped = icc_present && !icc_preserved;
         let metadata_blocked_by_policy =
@@ -418,6 +483,8 @@ impl EncodeTask {
             icc_preserved,
             metadata_stripped,
             policy_violations,
+            smart_crop,
+            trim,
         };
         metrics_recorder.finalize(
             processed.dimensions(),
@@ -434,7 +501,7 @@ impl EncodeTask {
 #[cfg(test)]
 impl EncodeTask {
     pub(crate) fn decode(&self) -> std::result::Result<Cow<'_, DynamicImage>, LazyImageError> {
-        self.decode_internal()
+        self.decode_internal().map(|(img, _)| img)
     }
 }
 
@@ -485,7 +552,7 @@ mod non_napi_tests {
     use super::*;
     use crate::engine::firewall::FirewallConfig;
     use crate::engine::io::Source;
-    use crate::ops::ResizeFit;
+    use crate::ops::{ResizeFit, ResizeKernel, ResizePosition};
     use image::{ImageBuffer, ImageFormat, Rgba};
 
     fn sample_png_bytes() -> Vec<u8> {
@@ -508,6 +575,12 @@ mod non_napi_tests {
                 width: Some(2),
                 height: Some(2),
                 fit: ResizeFit::Inside,
+                position: ResizePosition::Center,
+                without_enlargement: false,
+                without_reduction: false,
+                kernel: ResizeKernel::Lanczos3,
+                linear: false,
+                sharpen: false,
             }],
             format,
             icc_profile: None,
@@ -518,6 +591,8 @@ mod non_napi_tests {
             keep_exif: false,
             strip_gps: true,
             firewall: FirewallConfig::disabled(),
+            flatten_background: Color::WHITE,
+            output_depth: OutputDepth::Auto,
         }
     }
 
@@ -545,6 +620,8 @@ mod non_napi_tests {
             keep_exif: false,
             strip_gps: true,
             firewall: FirewallConfig::disabled(),
+            flatten_background: Color::WHITE,
+            output_depth: OutputDepth::Auto,
         };
         let err = task.decode_internal().unwrap_err();
         assert!(matches!(err, LazyImageError::SourceConsumed));
@@ -569,10 +646,29 @@ mod non_napi_tests {
             keep_exif: false,
             strip_gps: true,
             firewall,
+            flatten_background: Color::WHITE,
+            output_depth: OutputDepth::Auto,
         };
         let err = task.decode_internal().unwrap_err();
         assert!(matches!(err, LazyImageError::FirewallViolation { .. }));
     }
+
+    #[test]
+    fn memory_estimate_counts_bound_profile_conversion() {
+        let img: ImageBuffer<Rgba<u8>, Vec<u8>> =
+            ImageBuffer::from_pixel(2000, 2000, Rgba([10, 20, 30, 255]));
+        let mut png = Vec::new();
+        img.write_to(&mut std::io::Cursor::new(&mut png), ImageFormat::Png)
+            .unwrap();
+        let p3 = Arc::new(moxcms::ColorProfile::new_display_p3().encode().unwrap());
+        let estimate = |icc: Option<&Arc<Vec<u8>>>, keep_icc: bool| {
+            estimate_bound_memory(&png, &[], icc, keep_icc, &OutputFormat::Png)
+        };
+
+        // Stripping a P3 profile converts to sRGB, which needs a second buffer.
+        assert!(estimate(Some(&p3), false) > estimate(None, false));
+        assert_eq!(estimate(Some(&p3), true), estimate(None, false));
+    }
 }
 
 pub struct EncodeWithMetricsTask {
@@ -593,6 +689,10 @@ pub struct EncodeWithMetricsTask {
     /// Whether to strip GPS tags from EXIF
     pub strip_gps: bool,
     pub firewall: FirewallConfig,
+    /// Colour alpha is flattened onto when the output format has no alpha channel
+    pub flatten_background: Color,
+    /// Requested output bit depth (see `OutputFormat::bit_depth`)
+    pub output_depth: OutputDepth,
     /// Last error that occurred during compute (for use in reject)
     #[cfg(feature = "napi")]
     pub(crate) last_error: Option<LazyImageError>,
@@ -620,6 +720,8 @@ impl Task for EncodeWithMetricsTask {
             keep_exif: self.keep_exif,
             strip_gps: self.strip_gps,
             firewall: self.firewall.clone(),
+            flatten_background: self.flatten_background,
+            output_depth: self.output_depth,
             #[cfg(feature = "napi")]
             last_error: None,
         };
@@ -677,6 +779,10 @@ pub struct WriteFileTask {
     /// Whether to strip GPS tags from EXIF
     pub strip_gps: bool,
     pub firewall: FirewallConfig,
+    /// Colour alpha is flattened onto when the output format has no alpha channel
+    pub flatten_background: Color,
+    /// Requested output bit depth (see `OutputFormat::bit_depth`)
+    pub output_depth: OutputDepth,
     pub output_path: String,
     /// Last error that occurred during compute (for use in reject)
     #[cfg(feature = "napi")]
@@ -707,6 +813,8 @@ impl Task for WriteFileTask {
             keep_exif: self.keep_exif,
             strip_gps: self.strip_gps,
             firewall: self.firewall.clone(),
+            flatten_background: self.flatten_background,
+            output_depth: self.output_depth,
             #[cfg(feature = "napi")]
             last_error: None,
         };
@@ -807,6 +915,10 @@ pub struct BatchTask {
     /// Whether to strip GPS tags from EXIF
     pub strip_gps: bool,
     pub firewall: FirewallConfig,
+    /// Colour alpha is flattened onto when the output format has no alpha channel
+    pub flatten_background: Color,
+    /// Requested output bit depth (see `OutputFormat::bit_depth`)
+    pub output_depth: OutputDepth,
     /// Last error that occurred during compute (for use in reject)
     #[cfg(feature = "napi")]
     pub(crate) last_error: Option<LazyImageError>,
@@ -837,6 +949,8 @@ impl Task for BatchTask {
         let keep_icc = self.keep_icc;
         let keep_exif = self.keep_exif;
         let strip_gps = self.strip_gps;
+        let flatten_background = self.flatten_background;
+        let output_depth = self.output_depth;
         let firewall = self.firewall.clone();
         let process_one = |input_path: &String| -> BatchResult {
             let result = (|| -> std::result::Result<String, LazyImageError> {
@@ -867,10 +981,18 @@ impl Task for BatchTask {
 
                 firewall.enforce_source_len(data.len())?;
                 firewall.scan_metadata(data)?;
+                firewall.enforce_overlays(ops)?;
+
+                // Needed even when stripped, to convert the pixels to sRGB
+                let icc_profile = if keep_icc {
+                    extract_icc_profile(data)?
+                } else {
+                    extract_icc_profile_lossy(data)
+                }
+                .map(Arc::new);
 
                 let estimated_memory =
-                    memory::estimate_memory_from_header(data, &ops, Some(format))
-                        .unwrap_or(memory::ESTIMATED_MEMORY_PER_OPERATION);
+                    estimate_bound_memory(data, ops, icc_profile.as_ref(), keep_icc, format);
                 let _permit_guard = memory::memory_semaphore().acquire(estimated_memory);
 
                 let start_total = std::time::Instant::now();
@@ -880,11 +1002,6 @@ impl Task for BatchTask {
                 } else {
                     None
                 };
-                let icc_profile = if keep_icc {
-                    extract_icc_profile(data)?.map(Arc::new)
-                } else {
-                    None
-                };
                 // Extract EXIF data for preservation (JPEG only)
                 let exif_data = if keep_exif {
                     extract_exif_raw(data).map(Arc::new)
@@ -892,7 +1009,7 @@ impl Task for BatchTask {
                     None
                 };
 
-                let (img, _detected_format) = decode_image(data)?;
+                let (img, cmyk) = decode_source(data)?;
                 firewall.enforce_timeout(start_total, "decode")?;
 
                 let (w, h) = img.dimensions();
@@ -903,31 +1020,42 @@ impl Task for BatchTask {
                 if let Some(o) = orientation {
                     effective_ops.insert(0, Operation::AutoOrient { orientation: o });
                 }
+                icc::bind_profiles(&mut effective_ops, icc_profile.as_ref(), keep_icc, cmyk);
 
                 let icc_state = if icc_profile.is_some() {
                     IccState::Present
                 } else {
                     IccState::Absent
                 };
-                let initial_state = ColorState::from_dynamic_image(&img, icc_state);
+                let mut initial_state = ColorState::from_dynamic_image(&img, icc_state);
+                initial_state.gamut = icc::embedded_gamut(icc_profile.as_ref());
+                if cmyk {
+                    initial_state.color_space = ColorSpace::Cmyk;
+                }
+                let contract = format.encode_contract();
                 let tracked = apply_ops_tracked(Cow::Owned(img), &effective_ops, initial_state)?;
-                let processed = tracked.image;
+                let tracked = flatten_for_encode(tracked, &contract, flatten_background)?;
+                ensure_encodable(&tracked.state, &contract)?;
+                let bits =
+                    format.bit_depth(output_depth, tracked.state.bit_depth == BitDepth::Sixteen);
+                let gamut = tracked.state.gamut;
+                let processed = depth::for_encoder(tracked.image, bits);
                 firewall.enforce_timeout(start_total, "process")?;
 
-                // Encode - only preserve ICC profile if keep_icc is true
-                let icc = if keep_icc {
-                    icc_profile.as_ref().map(|v| v.as_slice())
-                } else {
-                    None // Strip metadata by default for security & smaller files
-                };
+                // Encode - the source profile is only preserved if keep_icc is true
+                let output_icc =
+                    icc::output_profile(&effective_ops, keep_icc, icc_profile.as_ref());
+                let icc = output_icc.as_deref().map(Vec::as_slice);
 
                 let mut encoded = match format {
                     OutputFormat::Jpeg { quality, fast_mode } => {
                         encode_jpeg_with_settings(&processed, *quality, icc, *fast_mode)?
                     }
-                    OutputFormat::Png => encode_png(&processed, icc)?,
+                    OutputFormat::Png => encode_png_with_gamut(&processed, icc, gamut)?,
                     OutputFormat::WebP { quality } => encode_webp(&processed, *quality, icc)?,
-                    OutputFormat::Avif { quality } => encode_avif(&processed, *quality, icc)?,
+                    OutputFormat::Avif { quality } => {
+                        encode_avif_with_settings(&processed, *quality, icc, bits, gamut)?
+                    }
                 };
 
                 // Embed EXIF metadata if requested (JPEG only)
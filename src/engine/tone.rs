@@ -0,0 +1,850 @@
+// src/engine/tone.rs
+//
+// Tonal adjustments: `Operation::Modulate` and the point operations
+// (`Brightness`, `Contrast`, `Gamma`, `Negate`, `Threshold`, `Levels`, and
+// fused `PointLut` chains).
+//
+// Modulate works in CIE LCh (D65): lightness is scaled and offset, chroma is
+// scaled and hue is rotated, which keeps perceived lightness stable while the
+// hue turns (unlike a rotation in RGB or HSL).
+//
+// Point operations map each sample independently, so any chain of them
+// (including the `PointLut` chains `optimize_ops` fuses) is folded into one
+// lookup table and applied in a single parallel pass over whole rows. All of
+// them keep the pixel layout and bit depth of 8/16-bit images; only contrast
+// touches alpha, matching the `image` crate op it replaces.
+
+use crate::engine::linear::{linear_to_srgb, srgb_to_linear};
+use crate::ops::{Levels, PointOp};
+use image::{ColorType, DynamicImage};
+use rayon::prelude::*;
+use std::sync::OnceLock;
+
+/// Pixels (rounded to whole rows) handed to one rayon task.
+const CHUNK_PIXELS: usize = 4096;
+
+/// CIE constants (exact rational forms).
+const EPSILON: f32 = 216.0 / 24389.0;
+const KAPPA: f32 = 24389.0 / 27.0;
+/// D65 reference white.
+const WHITE: [f32; 3] = [0.950_47, 1.0, 1.088_83];
+
+pub(crate) trait Sample: Copy + Send + Sync {
+    const SCALE: f32;
+    fn raw(self) -> f32;
+    /// Truncating conversion of an in-range raw value.
+    fn from_raw(value: f32) -> Self;
+    fn index(self) -> usize;
+    fn to_unit(self) -> f32;
+    fn from_unit(value: f32) -> Self;
+    /// Linear-light value of an sRGB-encoded sample.
+    fn to_linear(self) -> f32 {
+        srgb_to_linear(self.to_unit() as f64) as f32
+    }
+}
+
+impl Sample for u8 {
+    const SCALE: f32 = 255.0;
+    fn raw(self) -> f32 {
+        self as f32
+    }
+    fn from_raw(value: f32) -> Self {
+        value as u8
+    }
+    fn index(self) -> usize {
+        self as usize
+    }
+    fn to_unit(self) -> f32 {
+        self as f32 / Self::SCALE
+    }
+    fn from_unit(value: f32) -> Self {
+        (value.clamp(0.0, 1.0) * Self::SCALE).round() as u8
+    }
+    fn to_linear(self) -> f32 {
+        static TABLE: OnceLock<[f32; 256]> = OnceLock::new();
+        TABLE.get_or_init(|| std::array::from_fn(|i| srgb_to_linear(i as f64 / 255.0) as f32))
+            [self as usize]
+    }
+}
+
+impl Sample for u16 {
+    const SCALE: f32 = 65535.0;
+    fn raw(self) -> f32 {
+        self as f32
+    }
+    fn from_raw(value: f32) -> Self {
+        value as u16
+    }
+    fn index(self) -> usize {
+        self as usize
+    }
+    fn to_unit(self) -> f32 {
+        self as f32 / Self::SCALE
+    }
+    fn from_unit(value: f32) -> Self {
+        (value.clamp(0.0, 1.0) * Self::SCALE).round() as u16
+    }
+}
+
+/// Scale brightness (`L * brightness + lightness`) and chroma, rotate hue
+/// by `hue` degrees. Identity parameters return the image untouched.
+pub(crate) fn modulate(
+    img: DynamicImage,
+    brightness: f64,
+    saturation: f64,
+    hue: f64,
+    lightness: f64,
+) -> DynamicImage {
+    if brightness == 1.0 && saturation == 1.0 && hue.rem_euclid(360.0) == 0.0 && lightness == 0.0 {
+        return img;
+    }
+    let (sin, cos) = hue.to_radians().sin_cos();
+    let shift = LchShift {
+        scale_l: brightness as f32,
+        offset_l: lightness as f32,
+        // Chroma scaling and hue rotation combined into one 2x2 matrix on (a, b).
+        rotate: [(saturation * cos) as f32, (saturation * sin) as f32],
+    };
+    map_samples(
+        img,
+        |samples, layout| shift.apply(samples, layout),
+        |samples, layout| shift.apply(samples, layout),
+    )
+}
+
+/// Power curve `out = in^(1 / gamma)`; values above 1 brighten mid-tones.
+pub(crate) fn gamma(img: DynamicImage, gamma: f64) -> DynamicImage {
+    if gamma == 1.0 {
+        return img;
+    }
+    lut_pass(img, &[PointOp::Gamma(gamma)])
+}
+
+/// Run a chain of point operations as one pass over the samples: the chain is
+/// folded into a lookup table per sample type and every sample is read and
+/// written once. Output is identical to running the steps one by one.
+pub(crate) fn point_lut(img: DynamicImage, steps: &[PointOp]) -> DynamicImage {
+    if Layout::of(&img).is_none() {
+        // Float layouts have no finite table; keep the per-step semantics.
+        return steps.iter().fold(img, |img, step| apply_step(img, *step));
+    }
+    lut_pass(img, steps)
+}
+
+fn lut_pass(img: DynamicImage, steps: &[PointOp]) -> DynamicImage {
+    map_samples(
+        img,
+        |samples, layout| PointTables::<u8>::build(steps).apply(samples, layout),
+        |samples, layout| PointTables::<u16>::build(steps).apply(samples, layout),
+    )
+}
+
+/// Convert to 8-bit greyscale (keeping alpha) and binarize at `value`.
+pub(crate) fn threshold_gray(img: DynamicImage, value: u8) -> DynamicImage {
+    let gray = if img.color().has_alpha() {
+        DynamicImage::ImageLumaA8(img.to_luma_alpha8())
+    } else {
+        DynamicImage::ImageLuma8(img.to_luma8())
+    };
+    lut_pass(gray, &[PointOp::Threshold(value)])
+}
+
+/// Stretch luminance so the `lower` and `upper` percentiles (0-100) of its
+/// histogram become black and white. All colour channels share the stretch,
+/// so hues are kept; fully transparent pixels are not counted. A flat
+/// histogram leaves the image untouched.
+pub(crate) fn normalize(img: DynamicImage, lower: f64, upper: f64) -> DynamicImage {
+    let img = match Layout::of(&img) {
+        Some(_) => img,
+        None => to_standard_layout(img),
+    };
+    let bounds = read_samples(&img, luma_histogram, luma_histogram).and_then(|histogram| {
+        // Bounds on the 0-255 scale `Levels` works in.
+        let scale = (histogram.len() - 1) as f64 / 255.0;
+        let low = percentile(&histogram, lower)? as f64 / scale;
+        let high = percentile(&histogram, upper)? as f64 / scale;
+        (high > low).then_some((low, high))
+    });
+    let Some((in_black, in_white)) = bounds else {
+        return img;
+    };
+    lut_pass(
+        img,
+        &[PointOp::Levels(Levels {
+            in_black,
+            in_white,
+            ..Levels::default()
+        })],
+    )
+}
+
+/// Histogram of Rec. 709 luma (of the encoded values), one bin per sample
+/// value.
+fn luma_histogram<T: Sample>(samples: &[T], layout: Layout) -> Vec<u64> {
+    let bins = T::SCALE as usize + 1;
+    samples
+        .par_chunks(layout.chunk_len())
+        .fold(
+            || vec![0u64; bins],
+            |mut histogram, rows| {
+                for pixel in rows.chunks_exact(layout.channels) {
+                    if layout.channels > layout.color && pixel[layout.color].index() == 0 {
+                        continue;
+                    }
+                    let luma = if layout.color == 1 {
+                        pixel[0].index()
+                    } else {
+                        let [r, g, b] = [0, 1, 2].map(|i| pixel[i].index() as u64);
+                        ((2126 * r + 7152 * g + 722 * b + 5000) / 10000) as usize
+                    };
+                    histogram[luma] += 1;
+                }
+                histogram
+            },
+        )
+        .reduce(
+            || vec![0u64; bins],
+            |mut total, part| {
+                total
+                    .iter_mut()
+                    .zip(part)
+                    .for_each(|(sum, count)| *sum += count);
+                total
+            },
+        )
+}
+
+/// Value at the `percent` (0-100) rank of a histogram; `None` when empty.
+fn percentile(histogram: &[u64], percent: f64) -> Option<usize> {
+    let total: u64 = histogram.iter().sum();
+    let rank = ((total.checked_sub(1)? as f64) * percent / 100.0).round() as u64;
+    let mut seen = 0;
+    histogram.iter().position(|&count| {
+        seen += count;
+        seen > rank
+    })
+}
+
+/// Contrast-limited adaptive histogram equalization (CLAHE) of lightness.
+/// The image is split into `tile_width` x `tile_height` tiles; each tile's
+/// histogram is clipped at `max_slope` times its mean bin (0 disables the
+/// limit), the clipped excess is spread evenly, and the equalized mappings of
+/// the four nearest tiles are blended bilinearly per pixel. Colour images are
+/// equalized on CIE L with a and b kept; alpha is untouched.
+pub(crate) fn clahe(
+    img: DynamicImage,
+    tile_width: u32,
+    tile_height: u32,
+    max_slope: u32,
+) -> DynamicImage {
+    let clahe = Clahe {
+        tile: (tile_width as usize, tile_height as usize),
+        max_slope,
+    };
+    map_samples(
+        img,
+        |samples, layout| clahe.apply(samples, layout),
+        |samples, layout| clahe.apply(samples, layout),
+    )
+}
+
+/// Histogram bins used by `clahe`; lightness is interpolated between them.
+const CLAHE_BINS: usize = 256;
+
+struct Clahe {
+    tile: (usize, usize),
+    max_slope: u32,
+}
+
+impl Clahe {
+    fn apply<T: Sample>(&self, samples: &mut [T], layout: Layout) {
+        let row_len = layout.width * layout.channels;
+        if row_len == 0 || samples.is_empty() {
+            return;
+        }
+        let (width, height) = (layout.width, samples.len() / row_len);
+        let tile_w = self.tile.0.clamp(1, width);
+        let tile_h = self.tile.1.clamp(1, height);
+        let tiles_x = width.div_ceil(tile_w);
+        let tiles_y = height.div_ceil(tile_h);
+        let top = (CLAHE_BINS - 1) as f32;
+
+        // Lightness of every pixel in bin units (0..=255).
+        let lightness: Vec<f32> = samples
+            .par_chunks(row_len)
+            .flat_map_iter(|row| {
+                row.chunks_exact(layout.channels)
+                    .map(|pixel| lightness(pixel, layout.color) * top)
+            })
+            .collect();
+
+        let mappings: Vec<Vec<f32>> = (0..tiles_x * tiles_y)
+            .into_par_iter()
+            .map(|tile| {
+                let (x0, y0) = ((tile % tiles_x) * tile_w, (tile / tiles_x) * tile_h);
+                let mut histogram = vec![0u32; CLAHE_BINS];
+                for y in y0..(y0 + tile_h).min(height) {
+                    for x in x0..(x0 + tile_w).min(width) {
+                        histogram[lightness[y * width + x].round() as usize] += 1;
+                    }
+                }
+                self.equalize(histogram)
+            })
+            .collect();
+
+        samples
+            .par_chunks_mut(row_len)
+            .enumerate()
+            .for_each(|(y, row)| {
+                let (ty0, ty1, wy) = blend_tiles(y, tile_h, tiles_y);
+                for (x, pixel) in row.chunks_exact_mut(layout.channels).enumerate() {
+                    let (tx0, tx1, wx) = blend_tiles(x, tile_w, tiles_x);
+                    let position = lightness[y * width + x];
+                    let bin = (position as usize).min(CLAHE_BINS - 2);
+                    let fraction = position - bin as f32;
+                    let map = |tx: usize, ty: usize| {
+                        let mapping = &mappings[ty * tiles_x + tx];
+                        mapping[bin] + (mapping[bin + 1] - mapping[bin]) * fraction
+                    };
+                    let upper = map(tx0, ty0) + (map(tx1, ty0) - map(tx0, ty0)) * wx;
+                    let lower = map(tx0, ty1) + (map(tx1, ty1) - map(tx0, ty1)) * wx;
+                    set_lightness(pixel, layout.color, upper + (lower - upper) * wy);
+                }
+            });
+    }
+
+    /// Clipped, redistributed cumulative histogram as a 0-1 mapping.
+    fn equalize(&self, mut histogram: Vec<u32>) -> Vec<f32> {
+        let total: u32 = histogram.iter().sum();
+        if self.max_slope > 0 {
+            let limit = (u64::from(self.max_slope) * u64::from(total) / CLAHE_BINS as u64).max(1);
+            let limit = limit as u32;
+            let mut excess = 0;
+            for count in &mut histogram {
+                excess += count.saturating_sub(limit);
+                *count = (*count).min(limit);
+            }
+            let (share, rest) = (excess / CLAHE_BINS as u32, excess as usize % CLAHE_BINS);
+            for (bin, count) in histogram.iter_mut().enumerate() {
+                *count += share + u32::from(bin < rest);
+            }
+        }
+        let mut cumulative = 0;
+        histogram
+            .into_iter()
+            .map(|count| {
+                cumulative += count;
+                cumulative as f32 / total.max(1) as f32
+            })
+            .collect()
+    }
+}
+
+/// Neighbouring tile indices along one axis and the weight of the second,
+/// measured between tile centres.
+fn blend_tiles(position: usize, tile: usize, tiles: usize) -> (usize, usize, f32) {
+    let offset = (position as f32 + 0.5) / tile as f32 - 0.5;
+    if offset <= 0.0 {
+        return (0, 0, 0.0);
+    }
+    let first = offset as usize;
+    if first + 1 >= tiles {
+        return (tiles - 1, tiles - 1, 0.0);
+    }
+    (first, first + 1, offset - first as f32)
+}
+
+/// Lightness (0-1) of a pixel: CIE L / 100 for colour, the sample for grey.
+fn lightness<T: Sample>(pixel: &[T], color: usize) -> f32 {
+    if color == 1 {
+        return pixel[0].to_unit();
+    }
+    let rgb = [
+        pixel[0].to_linear(),
+        pixel[1].to_linear(),
+        pixel[2].to_linear(),
+    ];
+    (linear_to_lab(rgb)[0] / 100.0).clamp(0.0, 1.0)
+}
+
+fn set_lightness<T: Sample>(pixel: &mut [T], color: usize, value: f32) {
+    if color == 1 {
+        pixel[0] = T::from_unit(value);
+        return;
+    }
+    let rgb = [
+        pixel[0].to_linear(),
+        pixel[1].to_linear(),
+        pixel[2].to_linear(),
+    ];
+    let [_, a, b] = linear_to_lab(rgb);
+    let out = lab_to_linear([value * 100.0, a, b]).map(|v| v.clamp(0.0, 1.0));
+    for (sample, linear) in pixel.iter_mut().zip(out) {
+        *sample = T::from_unit(linear_to_srgb(linear as f64) as f32);
+    }
+}
+
+/// One point operation on its own, with the same semantics as the
+/// standalone pipeline op.
+fn apply_step(img: DynamicImage, step: PointOp) -> DynamicImage {
+    match step {
+        PointOp::Brightness(value) => {
+            let sixteen = img.color().bytes_per_pixel() / img.color().channel_count() == 2;
+            img.brighten(if sixteen { value * 257 } else { value })
+        }
+        PointOp::Contrast(value) => img.adjust_contrast(value as f32),
+        PointOp::Gamma(value) => gamma(img, value),
+        PointOp::Negate | PointOp::Threshold(_) | PointOp::Levels(_) => lut_pass(img, &[step]),
+    }
+}
+
+/// Interleaved sample layout of an 8/16-bit image.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Layout {
+    pub(crate) channels: usize,
+    /// Leading colour channels; a trailing channel beyond these is alpha.
+    pub(crate) color: usize,
+    pub(crate) width: usize,
+}
+
+impl Layout {
+    /// Layout of the standard 8/16-bit images; `None` for other layouts.
+    pub(crate) fn of(img: &DynamicImage) -> Option<Self> {
+        let (channels, color) = match img.color() {
+            ColorType::L8 | ColorType::L16 => (1, 1),
+            ColorType::La8 | ColorType::La16 => (2, 1),
+            ColorType::Rgb8 | ColorType::Rgb16 => (3, 3),
+            ColorType::Rgba8 | ColorType::Rgba16 => (4, 3),
+            _ => return None,
+        };
+        Some(Self {
+            channels,
+            color,
+            width: img.width() as usize,
+        })
+    }
+
+    /// Samples per rayon task: whole rows, about `CHUNK_PIXELS` pixels.
+    pub(crate) fn chunk_len(self) -> usize {
+        let width = self.width.max(1);
+        (CHUNK_PIXELS / width).max(1) * width * self.channels
+    }
+}
+
+/// Run `f8` / `f16` over the interleaved samples of 8/16-bit layouts. Other
+/// layouts are normalized to RGB8 / RGBA8 first.
+pub(crate) fn map_samples(
+    mut img: DynamicImage,
+    f8: impl Fn(&mut [u8], Layout),
+    f16: impl Fn(&mut [u16], Layout),
+) -> DynamicImage {
+    let Some(layout) = Layout::of(&img) else {
+        return map_samples(to_standard_layout(img), f8, f16);
+    };
+    match &mut img {
+        DynamicImage::ImageLuma8(buf) => f8(buf, layout),
+        DynamicImage::ImageLumaA8(buf) => f8(buf, layout),
+        DynamicImage::ImageRgb8(buf) => f8(buf, layout),
+        DynamicImage::ImageRgba8(buf) => f8(buf, layout),
+        DynamicImage::ImageLuma16(buf) => f16(buf, layout),
+        DynamicImage::ImageLumaA16(buf) => f16(buf, layout),
+        DynamicImage::ImageRgb16(buf) => f16(buf, layout),
+        DynamicImage::ImageRgba16(buf) => f16(buf, layout),
+        _ => unreachable!("Layout::of covers the 8/16-bit layouts"),
+    }
+    img
+}
+
+/// Read-only counterpart of `map_samples`; `None` for layouts other than
+/// 8/16-bit.
+fn read_samples<R>(
+    img: &DynamicImage,
+    f8: impl FnOnce(&[u8], Layout) -> R,
+    f16: impl FnOnce(&[u16], Layout) -> R,
+) -> Option<R> {
+    let layout = Layout::of(img)?;
+    Some(match img {
+        DynamicImage::ImageLuma8(buf) => f8(buf, layout),
+        DynamicImage::ImageLumaA8(buf) => f8(buf, layout),
+        DynamicImage::ImageRgb8(buf) => f8(buf, layout),
+        DynamicImage::ImageRgba8(buf) => f8(buf, layout),
+        DynamicImage::ImageLuma16(buf) => f16(buf, layout),
+        DynamicImage::ImageLumaA16(buf) => f16(buf, layout),
+        DynamicImage::ImageRgb16(buf) => f16(buf, layout),
+        DynamicImage::ImageRgba16(buf) => f16(buf, layout),
+        _ => return None,
+    })
+}
+
+/// RGB8 / RGBA8 copy of a layout without a finite sample table (e.g. float).
+fn to_standard_layout(img: DynamicImage) -> DynamicImage {
+    if img.color().has_alpha() {
+        DynamicImage::ImageRgba8(img.into_rgba8())
+    } else {
+        DynamicImage::ImageRgb8(img.into_rgb8())
+    }
+}
+
+/// Lookup tables for a `PointOp` chain, indexed by sample value.
+struct PointTables<T> {
+    color: Vec<T>,
+    /// `None` when the chain leaves alpha alone.
+    alpha: Option<Vec<T>>,
+}
+
+impl<T: Sample> PointTables<T> {
+    fn build(steps: &[PointOp]) -> Self {
+        let table = |alpha: bool| -> Vec<T> {
+            (0..=T::SCALE as usize)
+                .map(|index| {
+                    let sample = T::from_raw(index as f32);
+                    steps
+                        .iter()
+                        .fold(sample, |sample, step| step.map(sample, alpha))
+                })
+                .collect()
+        };
+        let touches_alpha = steps.iter().any(|step| step.touches_alpha());
+        Self {
+            color: table(false),
+            alpha: touches_alpha.then(|| table(true)),
+        }
+    }
+
+    fn apply(&self, samples: &mut [T], layout: Layout) {
+        let alpha = self
+            .alpha
+            .as_deref()
+            .filter(|_| layout.channels > layout.color);
+        samples.par_chunks_mut(layout.chunk_len()).for_each(|rows| {
+            for pixel in rows.chunks_exact_mut(layout.channels) {
+                for sample in &mut pixel[..layout.color] {
+                    *sample = self.color[sample.index()];
+                }
+                if let Some(alpha) = alpha {
+                    let sample = &mut pixel[layout.color];
+                    *sample = alpha[sample.index()];
+                }
+            }
+        });
+    }
+}
+
+impl PointOp {
+    /// One sample through this step; `alpha` selects the alpha-channel
+    /// behaviour. Mirrors the `image` crate's `brighten` / `adjust_contrast`
+    /// arithmetic exactly so fusing never changes output; brightness is on
+    /// the 0-255 scale at every depth.
+    fn map<T: Sample>(self, sample: T, alpha: bool) -> T {
+        if alpha && !self.touches_alpha() {
+            return sample;
+        }
+        match self {
+            PointOp::Brightness(value) => {
+                let offset = value as i64 * (T::SCALE / 255.0) as i64;
+                let raw = (sample.raw() as i64 + offset).clamp(0, T::SCALE as i64);
+                T::from_raw(raw as f32)
+            }
+            PointOp::Contrast(value) => {
+                let percent = ((100.0 + value as f32) / 100.0).powi(2);
+                let unit = sample.raw() / T::SCALE;
+                let contrasted = ((unit - 0.5) * percent + 0.5) * T::SCALE;
+                T::from_raw(contrasted.clamp(0.0, T::SCALE))
+            }
+            PointOp::Gamma(value) => {
+                T::from_unit((sample.to_unit() as f64).powf(1.0 / value) as f32)
+            }
+            PointOp::Negate => T::from_raw(T::SCALE - sample.raw()),
+            PointOp::Threshold(value) => {
+                // SCALE / 255 is exactly 1 or 257, so the cut is exact.
+                let cut = value as f32 * (T::SCALE / 255.0);
+                T::from_raw(if sample.raw() >= cut { T::SCALE } else { 0.0 })
+            }
+            PointOp::Levels(levels) => {
+                let input = sample.to_unit() as f64 * 255.0;
+                let unit = ((input - levels.in_black) / (levels.in_white - levels.in_black))
+                    .clamp(0.0, 1.0)
+                    .powf(1.0 / levels.gamma);
+                let output = levels.out_black + unit * (levels.out_white - levels.out_black);
+                T::from_unit((output / 255.0) as f32)
+            }
+        }
+    }
+
+    /// Only contrast remaps alpha (the standalone op maps every channel).
+    fn touches_alpha(self) -> bool {
+        matches!(self, PointOp::Contrast(_))
+    }
+}
+
+struct LchShift {
+    scale_l: f32,
+    offset_l: f32,
+    rotate: [f32; 2],
+}
+
+impl LchShift {
+    fn apply<T: Sample>(&self, samples: &mut [T], layout: Layout) {
+        let Layout {
+            channels, color, ..
+        } = layout;
+        samples
+            .par_chunks_mut(layout.chunk_len())
+            .for_each(|chunk| {
+                for pixel in chunk.chunks_exact_mut(channels) {
+                    let rgb = if color == 3 {
+                        [
+                            pixel[0].to_linear(),
+                            pixel[1].to_linear(),
+                            pixel[2].to_linear(),
+                        ]
+                    } else {
+                        [pixel[0].to_linear(); 3]
+                    };
+                    let out = self.shift(rgb);
+                    let encode = |linear: f32| T::from_unit(linear_to_srgb(linear as f64) as f32);
+                    if color == 3 {
+                        for (sample, value) in pixel.iter_mut().zip(out) {
+                            *sample = encode(value);
+                        }
+                    } else {
+                        // Rec. 709 luminance of the shifted (possibly tinted) grey
+                        pixel[0] = encode(0.2126 * out[0] + 0.7152 * out[1] + 0.0722 * out[2]);
+                    }
+                }
+            });
+    }
+
+    /// Linear RGB in, linear RGB out (clamped to the sRGB gamut).
+    fn shift(&self, rgb: [f32; 3]) -> [f32; 3] {
+        let [l, a, b] = linear_to_lab(rgb);
+        let l = l * self.scale_l + self.offset_l;
+        let [cos, sin] = self.rotate;
+        let (a, b) = (a * cos - b * sin, a * sin + b * cos);
+        lab_to_linear([l, a, b]).map(|v| v.clamp(0.0, 1.0))
+    }
+}
+
+fn linear_to_lab([r, g, b]: [f32; 3]) -> [f32; 3] {
+    let x = 0.412_456_4 * r + 0.357_576_1 * g + 0.180_437_5 * b;
+    let y = 0.212_672_9 * r + 0.715_152_2 * g + 0.072_175 * b;
+    let z = 0.019_333_9 * r + 0.119_192 * g + 0.950_304_1 * b;
+    let f = |t: f32| {
+        if t > EPSILON {
+            t.cbrt()
+        } else {
+            (KAPPA * t + 16.0) / 116.0
+        }
+    };
+    let (fx, fy, fz) = (f(x / WHITE[0]), f(y / WHITE[1]), f(z / WHITE[2]));
+    [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+fn lab_to_linear([l, a, b]: [f32; 3]) -> [f32; 3] {
+    let fy = (l + 16.0) / 116.0;
+    let (fx, fz) = (fy + a / 500.0, fy - b / 200.0);
+    let inverse = |f: f32| {
+        let cube = f * f * f;
+        if cube > EPSILON {
+            cube
+        } else {
+            (116.0 * f - 16.0) / KAPPA
+        }
+    };
+    let (x, y, z) = (
+        inverse(fx) * WHITE[0],
+        inverse(fy) * WHITE[1],
+        inverse(fz) * WHITE[2],
+    );
+    [
+        3.240_454_2 * x - 1.537_138_5 * y - 0.498_531_4 * z,
+        -0.969_266 * x + 1.876_010_8 * y + 0.041_556 * z,
+        0.055_643_4 * x - 0.204_025_9 * y + 1.057_225_2 * z,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Luma, Rgb, RgbImage, Rgba, RgbaImage};
+
+    fn rgb(pixel: [u8; 3]) -> DynamicImage {
+        DynamicImage::ImageRgb8(RgbImage::from_pixel(2, 2, Rgb(pixel)))
+    }
+
+    #[test]
+    fn lab_round_trip_is_lossless_for_8_bit() {
+        let identity = LchShift {
+            scale_l: 1.0,
+            offset_l: 0.0,
+            rotate: [1.0, 0.0],
+        };
+        let mut samples: Vec<u8> = (0..=255u8).flat_map(|v| [v, 255 - v, v / 2]).collect();
+        let original = samples.clone();
+        let layout = Layout {
+            channels: 3,
+            color: 3,
+            width: 256,
+        };
+        identity.apply(&mut samples, layout);
+        assert_eq!(samples, original);
+        // A full hue turn is skipped outright
+        let img = rgb([200, 80, 30]);
+        assert_eq!(modulate(img.clone(), 1.0, 1.0, 360.0, 0.0), img);
+    }
+
+    #[test]
+    fn hue_rotation_turns_red_towards_green() {
+        let out = modulate(rgb([220, 40, 40]), 1.0, 1.0, 120.0, 0.0).to_rgb8();
+        let [r, g, _] = out.get_pixel(0, 0).0;
+        assert!(g > r, "{:?}", out.get_pixel(0, 0));
+    }
+
+    #[test]
+    fn zero_saturation_is_grey_and_alpha_is_kept() {
+        let img = DynamicImage::ImageRgba8(RgbaImage::from_pixel(1, 1, Rgba([30, 160, 90, 77])));
+        let out = modulate(img, 1.0, 0.0, 0.0, 0.0).to_rgba8();
+        let [r, g, b, a] = out.get_pixel(0, 0).0;
+        assert!(r.abs_diff(g) <= 1 && g.abs_diff(b) <= 1, "{r} {g} {b}");
+        assert_eq!(a, 77);
+    }
+
+    #[test]
+    fn brightness_and_lightness_keep_luma_layout() {
+        let img = DynamicImage::ImageLuma16(image::ImageBuffer::from_pixel(1, 1, Luma([20000u16])));
+        let out = modulate(img, 1.5, 1.0, 0.0, 0.0);
+        let DynamicImage::ImageLuma16(gray) = out else {
+            panic!("layout must be kept");
+        };
+        assert!(gray.get_pixel(0, 0)[0] > 20000);
+        let darker = modulate(rgb([128, 128, 128]), 1.0, 1.0, 0.0, -20.0).to_rgb8();
+        assert!(darker.get_pixel(0, 0)[0] < 128);
+    }
+
+    #[test]
+    fn point_lut_matches_sequential_steps() {
+        let steps = [
+            PointOp::Contrast(35),
+            PointOp::Brightness(-12),
+            PointOp::Gamma(1.6),
+            PointOp::Negate,
+            PointOp::Levels(Levels {
+                in_black: 12.0,
+                in_white: 240.0,
+                gamma: 0.8,
+                out_black: 5.0,
+                out_white: 250.0,
+            }),
+            PointOp::Contrast(-20),
+            PointOp::Threshold(90),
+        ];
+        let run = |img: DynamicImage| {
+            let sequential = steps
+                .iter()
+                .fold(img.clone(), |img, step| apply_step(img, *step));
+            assert_eq!(point_lut(img, &steps), sequential);
+        };
+        run(DynamicImage::ImageRgba8(RgbaImage::from_fn(
+            300,
+            20,
+            |x, y| Rgba([x as u8, (x + y) as u8, 255 - x as u8, (y * 12) as u8]),
+        )));
+        run(DynamicImage::ImageRgb16(image::ImageBuffer::from_fn(
+            300,
+            20,
+            |x, y| Rgb([x as u16 * 218, y as u16 * 3000, 65535 - x as u16 * 100]),
+        )));
+        run(DynamicImage::ImageRgb32F(image::ImageBuffer::from_fn(
+            5,
+            5,
+            |x, y| Rgb([x as f32 / 5.0, y as f32 / 5.0, 0.5]),
+        )));
+    }
+
+    #[test]
+    fn negate_threshold_and_levels_map_colour_only() {
+        let img = DynamicImage::ImageRgba8(RgbaImage::from_pixel(1, 1, Rgba([10, 128, 200, 90])));
+        let out = point_lut(img.clone(), &[PointOp::Negate]).to_rgba8();
+        assert_eq!(out.get_pixel(0, 0), &Rgba([245, 127, 55, 90]));
+        let out = point_lut(img.clone(), &[PointOp::Threshold(128)]).to_rgba8();
+        assert_eq!(out.get_pixel(0, 0), &Rgba([0, 255, 255, 90]));
+        let levels = Levels {
+            in_black: 10.0,
+            in_white: 210.0,
+            gamma: 1.0,
+            out_black: 255.0,
+            out_white: 55.0,
+        };
+        let out = point_lut(img.clone(), &[PointOp::Levels(levels)]).to_rgba8();
+        // Reversed output range: 10 -> 255, 128 -> 255 - 118 = 137, 200 -> 65
+        assert_eq!(out.get_pixel(0, 0), &Rgba([255, 137, 65, 90]));
+
+        // 16-bit cut is value * 257; greyscale threshold keeps alpha
+        let gray = DynamicImage::ImageLumaA16(image::ImageBuffer::from_fn(2, 1, |x, _| {
+            image::LumaA([32895 + x as u16, 1234])
+        }));
+        let out = point_lut(gray.clone(), &[PointOp::Threshold(128)]);
+        assert_eq!(
+            out.as_luma_alpha16().unwrap().as_raw(),
+            &[0, 1234, 65535, 1234]
+        );
+        let binary = threshold_gray(img, 100);
+        assert_eq!(binary.as_luma_alpha8().unwrap().as_raw(), &[255, 90]);
+    }
+
+    #[test]
+    fn normalize_stretches_percentile_bounds() {
+        // Luma 60..=187 in a ramp; the 0/100 percentiles are its ends
+        let img = DynamicImage::ImageLuma8(image::GrayImage::from_fn(128, 2, |x, _| {
+            Luma([60 + x as u8])
+        }));
+        let out = normalize(img, 0.0, 100.0).to_luma8();
+        assert_eq!(out.get_pixel(0, 0), &Luma([0]));
+        assert_eq!(out.get_pixel(127, 1), &Luma([255]));
+        // Transparent pixels do not count; flat images are left alone
+        let rgba = DynamicImage::ImageRgba8(RgbaImage::from_fn(2, 1, |x, _| {
+            Rgba([
+                if x == 0 { 0 } else { 90 },
+                90,
+                90,
+                if x == 0 { 0 } else { 255 },
+            ])
+        }));
+        assert_eq!(normalize(rgba.clone(), 1.0, 99.0), rgba);
+        assert_eq!(percentile(&[0, 3, 0, 1], 50.0), Some(1));
+        assert_eq!(percentile(&[0, 0], 50.0), None);
+    }
+
+    #[test]
+    fn clahe_raises_local_contrast_and_keeps_layout() {
+        let img = DynamicImage::ImageLuma16(image::ImageBuffer::from_fn(64, 64, |x, y| {
+            Luma([30000 + ((x + y) % 8) as u16 * 200])
+        }));
+        let out = clahe(img, 16, 16, 3);
+        let DynamicImage::ImageLuma16(gray) = out else {
+            panic!("layout must be kept");
+        };
+        let (min, max) = gray
+            .as_raw()
+            .iter()
+            .fold((u16::MAX, 0), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+        assert!(max - min > 1400 * 2, "range {min}..{max}");
+
+        let colour = DynamicImage::ImageRgba8(RgbaImage::from_fn(40, 30, |x, _| {
+            Rgba([100 + (x % 10) as u8, 60, 40, 200])
+        }));
+        let out = clahe(colour, 100, 100, 0).to_rgba8();
+        let [r, g, b, a] = out.get_pixel(9, 3).0;
+        assert!(r > 109 && r > g && g > b, "{r} {g} {b}");
+        assert_eq!(a, 200);
+    }
+
+    #[test]
+    fn gamma_curve_brightens_midtones() {
+        let out = gamma(rgb([0, 64, 255]), 2.0).to_rgb8();
+        // (64/255)^(1/2) * 255 = 127.75
+        assert_eq!(out.get_pixel(0, 0), &Rgb([0, 128, 255]));
+        let img = DynamicImage::ImageRgba8(RgbaImage::from_pixel(1, 1, Rgba([64, 64, 64, 64])));
+        assert_eq!(gamma(img, 0.5).to_rgba8().get_pixel(0, 0)[3], 64);
+    }
+}
@@ -0,0 +1,135 @@
+// src/engine/trim.rs
+//
+// Bounding box detection for `Operation::Trim`.
+//
+// A pixel belongs to the border when every RGBA channel is within `threshold`
+// of the reference colour (the top-left pixel unless a background is given).
+// Fully transparent pixels match a fully transparent reference regardless of
+// their colour channels, so transparent margins trim cleanly.
+
+use crate::ops::Color;
+use crate::CropRegion;
+use image::DynamicImage;
+use std::borrow::Cow;
+
+/// Region that survives trimming, or `None` when every pixel matches the
+/// reference (nothing but border).
+pub(crate) fn find_trim_region(
+    img: &DynamicImage,
+    threshold: u8,
+    background: Option<Color>,
+) -> Option<CropRegion> {
+    let (width, height) = (img.width(), img.height());
+    if width == 0 || height == 0 {
+        return None;
+    }
+
+    // RGB8/RGBA8 are scanned in place; other layouts are normalized once.
+    let (pixels, channels): (Cow<'_, [u8]>, usize) = match img {
+        DynamicImage::ImageRgb8(rgb) => (Cow::Borrowed(rgb.as_raw().as_slice()), 3),
+        DynamicImage::ImageRgba8(rgba) => (Cow::Borrowed(rgba.as_raw().as_slice()), 4),
+        other => (Cow::Owned(other.to_rgba8().into_raw()), 4),
+    };
+    let pixel_at = |x: u32, y: u32| -> [u8; 4] {
+        let i = (y as usize * width as usize + x as usize) * channels;
+        match channels {
+            3 => [pixels[i], pixels[i + 1], pixels[i + 2], 255],
+            _ => [pixels[i], pixels[i + 1], pixels[i + 2], pixels[i + 3]],
+        }
+    };
+
+    let reference = background.map_or_else(|| pixel_at(0, 0), |c| c.to_rgba());
+    let is_content = |x: u32, y: u32| differs(pixel_at(x, y), reference, threshold);
+    let row_has_content = |y: u32| (0..width).any(|x| is_content(x, y));
+
+    let top = (0..height).find(|&y| row_has_content(y))?;
+    let bottom = (top..height).rev().find(|&y| row_has_content(y))?;
+    let column_has_content = |x: u32| (top..=bottom).any(|y| is_content(x, y));
+    let left = (0..width).find(|&x| column_has_content(x))?;
+    let right = (left..width).rev().find(|&x| column_has_content(x))?;
+
+    Some(CropRegion {
+        left,
+        top,
+        width: right - left + 1,
+        height: bottom - top + 1,
+    })
+}
+
+fn differs(pixel: [u8; 4], reference: [u8; 4], threshold: u8) -> bool {
+    if pixel[3] == 0 && reference[3] == 0 {
+        return false;
+    }
+    pixel
+        .iter()
+        .zip(reference.iter())
+        .any(|(&a, &b)| a.abs_diff(b) > threshold)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage, Rgba, RgbaImage};
+
+    #[test]
+    fn finds_content_inside_white_margin() {
+        let img = DynamicImage::ImageRgb8(RgbImage::from_fn(20, 10, |x, y| {
+            if (4..12).contains(&x) && (2..7).contains(&y) {
+                Rgb([30, 60, 90])
+            } else {
+                Rgb([255, 255, 255])
+            }
+        }));
+        let region = find_trim_region(&img, 10, None).unwrap();
+        assert_eq!(
+            region,
+            CropRegion {
+                left: 4,
+                top: 2,
+                width: 8,
+                height: 5
+            }
+        );
+    }
+
+    #[test]
+    fn threshold_absorbs_noise() {
+        let img = DynamicImage::ImageRgb8(RgbImage::from_fn(10, 10, |x, y| {
+            if x == 5 && y == 5 {
+                Rgb([0, 0, 0])
+            } else if x % 2 == 0 {
+                Rgb([250, 250, 250])
+            } else {
+                Rgb([255, 255, 255])
+            }
+        }));
+        let region = find_trim_region(&img, 10, Some(Color::WHITE)).unwrap();
+        assert_eq!(
+            (region.left, region.top, region.width, region.height),
+            (5, 5, 1, 1)
+        );
+        let strict = find_trim_region(&img, 0, Some(Color::WHITE)).unwrap();
+        // Noise sits on the even columns 0..=8
+        assert_eq!((strict.left, strict.width, strict.height), (0, 9, 10));
+    }
+
+    #[test]
+    fn transparent_margin_ignores_hidden_colour() {
+        let img = DynamicImage::ImageRgba8(RgbaImage::from_fn(8, 8, |x, y| {
+            if x >= 6 {
+                Rgba([255, 0, 0, 255])
+            } else {
+                // Invisible pixels with arbitrary colour data
+                Rgba([(x * 40) as u8, (y * 30) as u8, 7, 0])
+            }
+        }));
+        let region = find_trim_region(&img, 0, None).unwrap();
+        assert_eq!((region.left, region.width, region.height), (6, 2, 8));
+    }
+
+    #[test]
+    fn uniform_image_has_no_content() {
+        let img = DynamicImage::ImageRgb8(RgbImage::from_pixel(5, 5, Rgb([9, 9, 9])));
+        assert_eq!(find_trim_region(&img, 0, None), None);
+    }
+}
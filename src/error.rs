@@ -67,6 +67,8 @@ pub enum ErrorCode {
     InvalidResizeDimensions = 203,
     InvalidResizeFit = 204,
     UnsupportedColorSpace = 210,
+    InvalidLut = 220,
+    LutSizeExceedsLimit = 221,
     ResizeFailed = 299,
 
     // Output errors (E3xx)
@@ -103,6 +105,8 @@ impl ErrorCode {
             ErrorCode::InvalidResizeDimensions => "E203",
             ErrorCode::InvalidResizeFit => "E204",
             ErrorCode::UnsupportedColorSpace => "E210",
+            ErrorCode::InvalidLut => "E220",
+            ErrorCode::LutSizeExceedsLimit => "E221",
             ErrorCode::ResizeFailed => "E299",
             ErrorCode::EncodeFailed => "E300",
             ErrorCode::FileWriteFailed => "E301",
@@ -133,6 +137,8 @@ impl ErrorCode {
             ErrorCode::InvalidResizeDimensions => ErrorCategory::UserError,
             ErrorCode::InvalidResizeFit => ErrorCategory::UserError,
             ErrorCode::UnsupportedColorSpace => ErrorCategory::CodecError,
+            ErrorCode::InvalidLut => ErrorCategory::UserError,
+            ErrorCode::LutSizeExceedsLimit => ErrorCategory::ResourceLimit,
             ErrorCode::ResizeFailed => ErrorCategory::CodecError,
             ErrorCode::EncodeFailed => ErrorCategory::CodecError,
             ErrorCode::FileWriteFailed => ErrorCategory::ResourceLimit,
@@ -232,7 +238,7 @@ pub enum LazyImageError {
         height: Option<u32>,
     },
 
-    #[error("Invalid resize fit: '{value}'. Expected inside, cover, or fill")]
+    #[error("Invalid resize fit: '{value}'. Expected inside, cover, fill, contain, or outside")]
     InvalidResizeFit { value: Cow<'static, str> },
 
     #[error("Resize failed ({source_width}x{source_height} -> {target_width}x{target_height}): {message}")]
@@ -247,6 +253,12 @@ pub enum LazyImageError {
     #[error("Unsupported color space: {color_space}")]
     UnsupportedColorSpace { color_space: Cow<'static, str> },
 
+    #[error("Invalid .cube LUT: {reason}")]
+    InvalidLut { reason: Cow<'static, str> },
+
+    #[error("LUT_3D_SIZE {size} exceeds maximum {max}")]
+    LutSizeExceedsLimit { size: u32, max: u32 },
+
     // Encode Errors
     #[error("Failed to encode as {format}: {message}")]
     EncodeFailed {
@@ -360,6 +372,13 @@ impl Clone for LazyImageError {
             Self::UnsupportedColorSpace { color_space } => Self::UnsupportedColorSpace {
                 color_space: color_space.clone(),
             },
+            Self::InvalidLut { reason } => Self::InvalidLut {
+                reason: reason.clone(),
+            },
+            Self::LutSizeExceedsLimit { size, max } => Self::LutSizeExceedsLimit {
+                size: *size,
+                max: *max,
+            },
             Self::EncodeFailed { format, message } => Self::EncodeFailed {
                 format: format.clone(),
                 message: message.clone(),
@@ -411,6 +430,8 @@ impl LazyImageError {
             Self::InvalidResizeDimensions { .. } => ErrorCode::InvalidResizeDimensions,
             Self::InvalidResizeFit { .. } => ErrorCode::InvalidResizeFit,
             Self::UnsupportedColorSpace { .. } => ErrorCode::UnsupportedColorSpace,
+            Self::InvalidLut { .. } => ErrorCode::InvalidLut,
+            Self::LutSizeExceedsLimit { .. } => ErrorCode::LutSizeExceedsLimit,
             Self::ResizeFailed { .. } => ErrorCode::ResizeFailed,
 
             // Output errors (E3xx)
@@ -468,10 +489,18 @@ impl LazyImageError {
             ErrorCode::InvalidResizeDimensions => {
                 "Provide at least one positive dimension (width or height)."
             }
-            ErrorCode::InvalidResizeFit => "Use fit values: inside, cover, or fill.",
+            ErrorCode::InvalidResizeFit => {
+                "Use fit values: inside, cover, fill, contain, or outside."
+            }
             ErrorCode::UnsupportedColorSpace => {
                 "Convert the image to sRGB or a supported color space before processing."
             }
+            ErrorCode::InvalidLut => {
+                "Export the LUT as an Adobe .cube 3D LUT with finite values and LUT_3D_SIZE entries."
+            }
+            ErrorCode::LutSizeExceedsLimit => {
+                "Resample the LUT to a smaller grid (for example 33 or 65 points per axis)."
+            }
             ErrorCode::ResizeFailed => {
                 "Try different resize parameters or re-encode the input before resizing."
             }
@@ -615,6 +644,16 @@ impl LazyImageError {
         }
     }
 
+    pub fn invalid_lut(reason: impl Into<Cow<'static, str>>) -> Self {
+        Self::InvalidLut {
+            reason: reason.into(),
+        }
+    }
+
+    pub fn lut_size_exceeds_limit(size: u32, max: u32) -> Self {
+        Self::LutSizeExceedsLimit { size, max }
+    }
+
     pub fn encode_failed(
         format: impl Into<Cow<'static, str>>,
         message: impl Into<Cow<'static, str>>,
@@ -689,6 +728,7 @@ impl LazyImageError {
             | Self::InvalidPreset { .. }
             | Self::InvalidFirewallPolicy { .. }
             | Self::InvalidArgument { .. }
+            | Self::InvalidLut { .. }
             | Self::SourceConsumed => ErrorCategory::UserError,
 
             // CodecError: Format/encoding issues
@@ -712,6 +752,7 @@ impl LazyImageError {
             Self::DimensionExceedsLimit { .. }
             | Self::PixelCountExceedsLimit { .. }
             | Self::FirewallViolation { .. }
+            | Self::LutSizeExceedsLimit { .. }
             | Self::FileReadFailed { .. }
             | Self::MmapFailed { .. }
             | Self::FileWriteFailed { .. } => ErrorCategory::ResourceLimit,
@@ -876,6 +917,8 @@ mod tests {
         let _ = LazyImageError::invalid_resize_dimensions(None, None);
         let _ = LazyImageError::resize_failed((100, 100), (50, 50), "test");
         let _ = LazyImageError::unsupported_color_space("CMYK");
+        let _ = LazyImageError::invalid_lut("line 3: expected three values");
+        let _ = LazyImageError::lut_size_exceeds_limit(256, 65);
         let _ = LazyImageError::encode_failed("jpeg", "test");
         let _ = LazyImageError::invalid_preset("unknown");
         let _ = LazyImageError::invalid_argument("width", "0", "must be positive");
@@ -1006,6 +1049,12 @@ mod tests {
             LazyImageError::encode_failed("jpeg", "oops").code(),
             ErrorCode::EncodeFailed
         );
+        let lut = LazyImageError::invalid_lut("line 2: expected three values");
+        assert_eq!(lut.code().as_str(), "E220");
+        assert_eq!(lut.category(), ErrorCategory::UserError);
+        let size = LazyImageError::lut_size_exceeds_limit(256, 65);
+        assert_eq!(size.code().as_str(), "E221");
+        assert_eq!(size.category(), ErrorCategory::ResourceLimit);
     }
 
     #[test]
@@ -178,6 +178,18 @@ pub fn supported_output_formats() -> Vec<String> {
     ]
 }
 
+/// Crop window chosen by a content-aware cover resize (`position: 'entropy' | 'attention'`)
+/// or kept by `trim()`. Coordinates are relative to the image the operation received
+/// (for cover, the scaled image the window was cut from).
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CropRegion {
+    pub left: u32,
+    pub top: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
 /// Metrics payload version. Keep in sync with docs/metrics-schema.json
 pub const PROCESSING_METRICS_VERSION: &str = "1.0.0";
 
@@ -222,6 +234,10 @@ pub struct ProcessingMetrics {
     pub metadata_stripped: bool,
     /// Non-fatal policy rejections (e.g., strict policy forcing metadata strip)
     pub policy_violations: Vec<String>,
+    /// Crop window picked by the last content-aware cover resize, if any
+    pub smart_crop: Option<CropRegion>,
+    /// Region kept by the last trim(), relative to the image it received
+    pub trim: Option<CropRegion>,
     // ----------------------------------------------------------------------
     // Legacy fields preserved for backward compatibility
     /// Time taken to decode the image (milliseconds) - legacy alias of decode_ms
@@ -277,6 +293,10 @@ pub struct ProcessingMetrics {
     pub metadata_stripped: bool,
     /// Non-fatal policy rejections (e.g., strict policy forcing metadata strip)
     pub policy_violations: Vec<String>,
+    /// Crop window picked by the last content-aware cover resize, if any
+    pub smart_crop: Option<CropRegion>,
+    /// Region kept by the last trim(), relative to the image it received
+    pub trim: Option<CropRegion>,
     // ----------------------------------------------------------------------
     // Legacy fields preserved for backward compatibility
     /// Time taken to decode the image (milliseconds) - legacy alias of decode_ms
@@ -313,6 +333,8 @@ impl Default for ProcessingMetrics {
             icc_preserved: false,
             metadata_stripped: true,
             policy_violations: Vec::new(),
+            smart_crop: None,
+            trim: None,
             decode_time: 0.0,
             process_time: 0.0,
             encode_time: 0.0,
@@ -343,6 +365,8 @@ impl Default for ProcessingMetrics {
             icc_preserved: false,
             metadata_stripped: true,
             policy_violations: Vec::new(),
+            smart_crop: None,
+            trim: None,
             decode_time: 0.0,
             process_time: 0.0,
             encode_time: 0.0,
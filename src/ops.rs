@@ -4,9 +4,12 @@
 // These are cheap to create and store - the expensive work happens in compute().
 
 use std::str::FromStr;
+use std::sync::Arc;
 
 use bitflags::bitflags;
 
+use crate::CropRegion;
+
 bitflags! {
     /// Requirements an operation needs before execution.
     #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -17,6 +20,10 @@ bitflags! {
         const COLOR_STATE = 0b0010;
         /// Orientation metadata (EXIF Orientation) must be available.
         const ORIENTATION = 0b0100;
+        /// Pixels must be sRGB-encoded (not linear light).
+        const SRGB_TRANSFER = 0b1000;
+        /// Pixels must not carry an alpha channel.
+        const OPAQUE = 0b1_0000;
     }
 }
 
@@ -53,6 +60,23 @@ impl OperationContract {
             effects,
         }
     }
+
+    /// Contract of the encode stage that consumes the pipeline output.
+    /// Encoders expect 8-bit sRGB-encoded samples.
+    pub const ENCODE: OperationContract = OperationContract::new(
+        "encode",
+        OperationRequirement::DECODED_PIXELS
+            .union(OperationRequirement::COLOR_STATE)
+            .union(OperationRequirement::SRGB_TRANSFER),
+        OperationEffect::empty(),
+    );
+
+    /// Encode contract for formats without an alpha channel (JPEG).
+    pub const ENCODE_OPAQUE: OperationContract = OperationContract::new(
+        "encode",
+        Self::ENCODE.requires.union(OperationRequirement::OPAQUE),
+        OperationEffect::empty(),
+    );
 }
 
 /// Image operations that can be queued for lazy execution.
@@ -67,6 +91,20 @@ pub enum Operation {
         width: Option<u32>,
         height: Option<u32>,
         fit: ResizeFit,
+        /// Region kept when `fit` is Cover, or where the image sits on the
+        /// canvas when `fit` is Contain (ignored otherwise)
+        position: ResizePosition,
+        /// Never scale above the current pipeline dimensions
+        without_enlargement: bool,
+        /// Never scale below the current pipeline dimensions
+        without_reduction: bool,
+        /// Resampling kernel
+        kernel: ResizeKernel,
+        /// Resample in linear light instead of sRGB (gamma-correct)
+        linear: bool,
+        /// Unsharp-mask the result with a strength that grows with the
+        /// downscale ratio (`Sharpen::for_downscale`); no-op when enlarging
+        sharpen: bool,
     },
 
     /// Fused resize + crop operation.
@@ -76,6 +114,10 @@ pub enum Operation {
         width: Option<u32>,
         height: Option<u32>,
         fit: ResizeFit,
+        position: ResizePosition,
+        without_enlargement: bool,
+        without_reduction: bool,
+        kernel: ResizeKernel,
         crop_x: u32,
         crop_y: u32,
         crop_width: u32,
@@ -90,9 +132,69 @@ pub enum Operation {
         height: u32,
     },
 
+    /// Grow the canvas by the given number of pixels on each edge.
+    /// New pixels are filled according to `mode`.
+    Extend {
+        top: u32,
+        right: u32,
+        bottom: u32,
+        left: u32,
+        /// Fill colour for `ExtendMode::Background`
+        background: Color,
+        mode: ExtendMode,
+    },
+
+    /// Crop away uniform borders. A pixel is border when every channel is
+    /// within `threshold` of `background` (the top-left pixel when `None`).
+    /// The output size depends on the pixels, so it is only known at run time.
+    Trim {
+        threshold: u8,
+        background: Option<Color>,
+    },
+
+    /// Composite onto an opaque `background` and drop the alpha channel.
+    /// Images without alpha are left unchanged.
+    Flatten { background: Color },
+
+    /// Add an alpha channel with the given opacity (0.0-1.0).
+    /// Images that already have alpha are left unchanged.
+    EnsureAlpha { alpha: f64 },
+
+    /// Drop the alpha channel without compositing
+    RemoveAlpha,
+
+    /// Keep a single channel as a greyscale image. Extracting alpha from an
+    /// image without it yields a fully opaque plane.
+    ExtractChannel { channel: Channel },
+
+    /// Use an encoded image (reduced to greyscale) as the alpha channel.
+    /// The mask is decoded when the pipeline runs and must match the image size.
+    JoinChannel { mask: Arc<Vec<u8>> },
+
     /// Rotate by 90, 180, or 270 degrees
     Rotate { degrees: i32 },
 
+    /// Rotate clockwise by any angle. The canvas grows to hold the whole
+    /// rotated image and the uncovered corners are filled with `background`.
+    /// Right angles should use `Rotate`, which is lossless.
+    RotateArbitrary {
+        degrees: f64,
+        background: Color,
+        interpolation: RotateInterpolation,
+    },
+
+    /// Map each pixel position `p` to `matrix * p + translate` (y axis
+    /// pointing down). The transformed image is first moved so its bounding
+    /// box starts at the origin; `translate` then shifts it on a canvas that
+    /// ends at its far edges, so content pushed past the top/left is cropped.
+    /// Uncovered areas are filled with `background`.
+    Affine {
+        matrix: [[f64; 2]; 2],
+        translate: [f64; 2],
+        background: Color,
+        interpolation: RotateInterpolation,
+    },
+
     /// Flip horizontally
     FlipH,
 
@@ -105,6 +207,78 @@ pub enum Operation {
     /// Adjust contrast (-100 to 100)
     Contrast { value: i32 },
 
+    /// Adjust in CIE LCh: lightness becomes `L * brightness + lightness`,
+    /// chroma is multiplied by `saturation` and hue rotated by `hue` degrees.
+    Modulate {
+        brightness: f64,
+        saturation: f64,
+        hue: f64,
+        lightness: f64,
+    },
+
+    /// Power curve `out = in^(1 / gamma)` on the colour channels
+    Gamma { gamma: f64 },
+
+    /// Invert the colour channels; alpha is kept
+    Negate,
+
+    /// Binarize: samples at or above `value` (0-255 scale) become white, the
+    /// rest black. With `grayscale` the image is converted to 8-bit greyscale
+    /// (alpha kept) first; otherwise each colour channel is thresholded.
+    Threshold { value: u8, grayscale: bool },
+
+    /// Input/output range remap with a mid-tone curve on the colour channels
+    Levels { levels: Levels },
+
+    /// Stretch luminance so the `lower` / `upper` percentiles (0-100) of its
+    /// histogram become black and white
+    Normalize { lower: f64, upper: f64 },
+
+    /// Contrast-limited adaptive histogram equalization of lightness over
+    /// `width` x `height` pixel tiles; `max_slope` limits the contrast gain
+    /// (0 = unlimited)
+    Clahe {
+        width: u32,
+        height: u32,
+        max_slope: u32,
+    },
+
+    /// Unsharp mask on the colour channels; alpha is kept
+    Sharpen { sharpen: Sharpen },
+
+    /// Gaussian blur (`sigma` pixels) of every channel, limited to `regions`
+    /// (clipped to the image) when any are given
+    Blur {
+        sigma: f64,
+        regions: Vec<CropRegion>,
+    },
+
+    /// Replace `block_size` squares with their mean colour, limited to
+    /// `regions` (clipped to the image) when any are given
+    Pixelate {
+        block_size: u32,
+        regions: Vec<CropRegion>,
+    },
+
+    /// Colour matrix on RGBA samples: `out[i] = sum(matrix[i][j] * in[j])`.
+    /// 3x3 matrices are stored with an identity alpha row and column. Alpha
+    /// reads as opaque on images without it and the alpha row is then
+    /// unused. Greyscale input is expanded to RGB first.
+    Recomb { matrix: [[f64; 4]; 4] },
+
+    /// Map RGB through a 3D lookup table with trilinear interpolation; alpha
+    /// is kept. Greyscale input is expanded to RGB first.
+    Lut3d { lut: Arc<CubeLut> },
+
+    /// Blend encoded overlay images onto the image, in order. Overlays are
+    /// decoded when the pipeline runs and blended in premultiplied sRGB.
+    /// Greyscale input is expanded to RGB first.
+    Composite { layers: Vec<Overlay> },
+
+    /// Consecutive per-pixel tonal ops fused by `optimize_ops` into a single
+    /// lookup-table pass; the output matches running `steps` in order.
+    PointLut { steps: Vec<PointOp> },
+
     /// Auto-orient based on EXIF Orientation tag (1-8)
     AutoOrient { orientation: u16 },
 
@@ -112,8 +286,17 @@ pub enum Operation {
     Grayscale,
 
     /// Ensure RGB/RGBA pixel format (not true color space conversion)
-    /// This operation only normalizes pixel format, not color space transformation.
+    /// This operation only normalizes pixel format; `IccTransform` converts colours.
     ColorSpace { target: ColorSpace },
+
+    /// Convert colours from `source` to `target` through an ICC transform.
+    /// `source` is `None` (untagged, read as sRGB) when queued; tasks fill it
+    /// in from the input's embedded profile, or the previous conversion's
+    /// target, before the pipeline runs.
+    IccTransform {
+        source: Option<IccProfile>,
+        target: IccProfile,
+    },
 }
 
 impl Operation {
@@ -135,11 +318,56 @@ impl Operation {
                 OperationRequirement::DECODED_PIXELS | OperationRequirement::COLOR_STATE,
                 OperationEffect::MUTATES_PIXELS | OperationEffect::CHANGES_GEOMETRY,
             ),
+            Operation::Extend { .. } => OperationContract::new(
+                "extend",
+                OperationRequirement::DECODED_PIXELS | OperationRequirement::COLOR_STATE,
+                OperationEffect::MUTATES_PIXELS | OperationEffect::CHANGES_GEOMETRY,
+            ),
+            Operation::Trim { .. } => OperationContract::new(
+                "trim",
+                OperationRequirement::DECODED_PIXELS | OperationRequirement::COLOR_STATE,
+                OperationEffect::MUTATES_PIXELS | OperationEffect::CHANGES_GEOMETRY,
+            ),
+            Operation::Flatten { .. } => OperationContract::new(
+                "flatten",
+                OperationRequirement::DECODED_PIXELS | OperationRequirement::COLOR_STATE,
+                OperationEffect::MUTATES_PIXELS | OperationEffect::NORMALIZES_COLOR,
+            ),
+            Operation::EnsureAlpha { .. } => OperationContract::new(
+                "ensure_alpha",
+                OperationRequirement::DECODED_PIXELS | OperationRequirement::COLOR_STATE,
+                OperationEffect::MUTATES_PIXELS | OperationEffect::NORMALIZES_COLOR,
+            ),
+            Operation::RemoveAlpha => OperationContract::new(
+                "remove_alpha",
+                OperationRequirement::DECODED_PIXELS | OperationRequirement::COLOR_STATE,
+                OperationEffect::MUTATES_PIXELS | OperationEffect::NORMALIZES_COLOR,
+            ),
+            Operation::ExtractChannel { .. } => OperationContract::new(
+                "extract_channel",
+                OperationRequirement::DECODED_PIXELS | OperationRequirement::COLOR_STATE,
+                OperationEffect::MUTATES_PIXELS | OperationEffect::NORMALIZES_COLOR,
+            ),
+            Operation::JoinChannel { .. } => OperationContract::new(
+                "join_channel",
+                OperationRequirement::DECODED_PIXELS | OperationRequirement::COLOR_STATE,
+                OperationEffect::MUTATES_PIXELS | OperationEffect::NORMALIZES_COLOR,
+            ),
             Operation::Rotate { .. } => OperationContract::new(
                 "rotate",
                 OperationRequirement::DECODED_PIXELS | OperationRequirement::COLOR_STATE,
                 OperationEffect::MUTATES_PIXELS | OperationEffect::CHANGES_GEOMETRY,
             ),
+            Operation::RotateArbitrary { .. } => OperationContract::new(
+                "rotate_arbitrary",
+                OperationRequirement::DECODED_PIXELS | OperationRequirement::COLOR_STATE,
+                OperationEffect::MUTATES_PIXELS | OperationEffect::CHANGES_GEOMETRY,
+            ),
+            Operation::Affine { .. } => OperationContract::new(
+                "affine",
+                OperationRequirement::DECODED_PIXELS | OperationRequirement::COLOR_STATE,
+                OperationEffect::MUTATES_PIXELS | OperationEffect::CHANGES_GEOMETRY,
+            ),
             Operation::FlipH | Operation::FlipV => OperationContract::new(
                 "flip",
                 OperationRequirement::DECODED_PIXELS | OperationRequirement::COLOR_STATE,
@@ -150,6 +378,84 @@ impl Operation {
                 OperationRequirement::DECODED_PIXELS | OperationRequirement::COLOR_STATE,
                 OperationEffect::MUTATES_PIXELS | OperationEffect::NORMALIZES_COLOR,
             ),
+            Operation::Modulate { .. } => OperationContract::new(
+                "modulate",
+                OperationRequirement::DECODED_PIXELS
+                    | OperationRequirement::COLOR_STATE
+                    | OperationRequirement::SRGB_TRANSFER,
+                OperationEffect::MUTATES_PIXELS,
+            ),
+            Operation::Gamma { .. } => OperationContract::new(
+                "gamma",
+                OperationRequirement::DECODED_PIXELS | OperationRequirement::COLOR_STATE,
+                OperationEffect::MUTATES_PIXELS,
+            ),
+            Operation::Negate => OperationContract::new(
+                "negate",
+                OperationRequirement::DECODED_PIXELS | OperationRequirement::COLOR_STATE,
+                OperationEffect::MUTATES_PIXELS,
+            ),
+            Operation::Threshold { .. } => OperationContract::new(
+                "threshold",
+                OperationRequirement::DECODED_PIXELS | OperationRequirement::COLOR_STATE,
+                OperationEffect::MUTATES_PIXELS | OperationEffect::NORMALIZES_COLOR,
+            ),
+            Operation::Levels { .. } => OperationContract::new(
+                "levels",
+                OperationRequirement::DECODED_PIXELS | OperationRequirement::COLOR_STATE,
+                OperationEffect::MUTATES_PIXELS,
+            ),
+            Operation::Normalize { .. } => OperationContract::new(
+                "normalize",
+                OperationRequirement::DECODED_PIXELS | OperationRequirement::COLOR_STATE,
+                OperationEffect::MUTATES_PIXELS,
+            ),
+            Operation::Clahe { .. } => OperationContract::new(
+                "clahe",
+                OperationRequirement::DECODED_PIXELS
+                    | OperationRequirement::COLOR_STATE
+                    | OperationRequirement::SRGB_TRANSFER,
+                OperationEffect::MUTATES_PIXELS,
+            ),
+            Operation::Sharpen { .. } => OperationContract::new(
+                "sharpen",
+                OperationRequirement::DECODED_PIXELS | OperationRequirement::COLOR_STATE,
+                OperationEffect::MUTATES_PIXELS,
+            ),
+            Operation::Blur { .. } => OperationContract::new(
+                "blur",
+                OperationRequirement::DECODED_PIXELS | OperationRequirement::COLOR_STATE,
+                OperationEffect::MUTATES_PIXELS,
+            ),
+            Operation::Pixelate { .. } => OperationContract::new(
+                "pixelate",
+                OperationRequirement::DECODED_PIXELS | OperationRequirement::COLOR_STATE,
+                OperationEffect::MUTATES_PIXELS,
+            ),
+            Operation::Recomb { .. } => OperationContract::new(
+                "recomb",
+                OperationRequirement::DECODED_PIXELS | OperationRequirement::COLOR_STATE,
+                OperationEffect::MUTATES_PIXELS | OperationEffect::NORMALIZES_COLOR,
+            ),
+            Operation::Lut3d { .. } => OperationContract::new(
+                "lut_3d",
+                OperationRequirement::DECODED_PIXELS
+                    | OperationRequirement::COLOR_STATE
+                    | OperationRequirement::SRGB_TRANSFER,
+                OperationEffect::MUTATES_PIXELS | OperationEffect::NORMALIZES_COLOR,
+            ),
+            Operation::Composite { .. } => OperationContract::new(
+                "composite",
+                OperationRequirement::DECODED_PIXELS
+                    | OperationRequirement::COLOR_STATE
+                    | OperationRequirement::SRGB_TRANSFER,
+                OperationEffect::MUTATES_PIXELS | OperationEffect::NORMALIZES_COLOR,
+            ),
+            Operation::PointLut { .. } => OperationContract::new(
+                "point_lut",
+                OperationRequirement::DECODED_PIXELS | OperationRequirement::COLOR_STATE,
+                OperationEffect::MUTATES_PIXELS | OperationEffect::NORMALIZES_COLOR,
+            ),
             Operation::AutoOrient { .. } => OperationContract::new(
                 "auto_orient",
                 OperationRequirement::DECODED_PIXELS
@@ -169,6 +475,13 @@ impl Operation {
                 OperationRequirement::DECODED_PIXELS | OperationRequirement::COLOR_STATE,
                 OperationEffect::MUTATES_PIXELS | OperationEffect::NORMALIZES_COLOR,
             ),
+            Operation::IccTransform { .. } => OperationContract::new(
+                "icc_transform",
+                OperationRequirement::DECODED_PIXELS
+                    | OperationRequirement::COLOR_STATE
+                    | OperationRequirement::SRGB_TRANSFER,
+                OperationEffect::MUTATES_PIXELS | OperationEffect::NORMALIZES_COLOR,
+            ),
         }
     }
 }
@@ -179,6 +492,35 @@ pub enum ColorSpace {
     Srgb,
 }
 
+/// ICC profile used by `Operation::IccTransform`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum IccProfile {
+    /// sRGB IEC61966-2.1
+    Srgb,
+    /// Display P3 (P3 primaries, D65 white, sRGB transfer curve)
+    DisplayP3,
+    /// ITU-R BT.2020 (BT.2020 primaries, D65 white, BT.709 transfer curve)
+    Bt2020,
+    /// Raw ICC profile bytes (an embedded or caller-supplied profile)
+    Bytes(Arc<Vec<u8>>),
+}
+
+impl FromStr for IccProfile {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let normalized = value.trim().to_ascii_lowercase();
+        match normalized.as_str() {
+            "srgb" => Ok(IccProfile::Srgb),
+            "p3" | "display-p3" | "displayp3" => Ok(IccProfile::DisplayP3),
+            "rec2020" | "bt2020" | "rec.2020" | "bt.2020" => Ok(IccProfile::Bt2020),
+            other => Err(format!(
+                "unknown colour profile '{other}'. Expected srgb, p3, rec2020, or ICC profile bytes"
+            )),
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum ResizeFit {
     /// Maintain aspect ratio while fitting inside the bounding box (default)
@@ -187,6 +529,10 @@ pub enum ResizeFit {
     Cover,
     /// Ignore aspect ratio and force exact dimensions
     Fill,
+    /// Fit inside the bounding box, then pad to its exact size (letterbox)
+    Contain { background: Color },
+    /// Maintain aspect ratio and cover the bounding box without cropping
+    Outside,
 }
 
 impl Default for ResizeFit {
@@ -204,8 +550,492 @@ impl FromStr for ResizeFit {
             "inside" => Ok(ResizeFit::Inside),
             "cover" => Ok(ResizeFit::Cover),
             "fill" => Ok(ResizeFit::Fill),
+            "contain" => Ok(ResizeFit::Contain {
+                background: Color::TRANSPARENT,
+            }),
+            "outside" => Ok(ResizeFit::Outside),
             other => Err(format!(
-                "unknown resize fit '{other}'. Expected inside, cover, or fill"
+                "unknown resize fit '{other}'. Expected inside, cover, fill, contain, or outside"
+            )),
+        }
+    }
+}
+
+/// Resampling kernel used by resize operations.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ResizeKernel {
+    /// Nearest neighbour (hard edges; pixel art, QR codes)
+    Nearest,
+    /// Box filter (area average when downscaling)
+    Box,
+    /// Bilinear (triangle) filter
+    Bilinear,
+    /// Catmull-Rom bicubic filter
+    CatmullRom,
+    /// Mitchell-Netravali bicubic filter (softer than Catmull-Rom)
+    Mitchell,
+    /// Lanczos with a 3-lobe window (default, sharpest)
+    #[default]
+    Lanczos3,
+}
+
+impl FromStr for ResizeKernel {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let normalized = value.trim().to_ascii_lowercase();
+        match normalized.as_str() {
+            "nearest" => Ok(ResizeKernel::Nearest),
+            "box" => Ok(ResizeKernel::Box),
+            "bilinear" => Ok(ResizeKernel::Bilinear),
+            "catmullrom" | "catmull-rom" => Ok(ResizeKernel::CatmullRom),
+            "mitchell" => Ok(ResizeKernel::Mitchell),
+            "lanczos3" => Ok(ResizeKernel::Lanczos3),
+            other => Err(format!(
+                "unknown resize kernel '{other}'. Expected nearest, box, bilinear, catmullrom, \
+                 mitchell, or lanczos3"
+            )),
+        }
+    }
+}
+
+/// How `Operation::Extend` fills the added border.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ExtendMode {
+    /// Solid background colour
+    #[default]
+    Background,
+    /// Repeat the nearest edge pixel
+    Copy,
+    /// Reflect the image across each edge
+    Mirror,
+}
+
+impl FromStr for ExtendMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let normalized = value.trim().to_ascii_lowercase();
+        match normalized.as_str() {
+            "background" => Ok(ExtendMode::Background),
+            "copy" => Ok(ExtendMode::Copy),
+            "mirror" => Ok(ExtendMode::Mirror),
+            other => Err(format!(
+                "unknown extend mode '{other}'. Expected background, copy, or mirror"
+            )),
+        }
+    }
+}
+
+/// Sampling filter for `Operation::RotateArbitrary` and `Operation::Affine`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum RotateInterpolation {
+    /// 2x2 linear interpolation
+    #[default]
+    Bilinear,
+    /// 4x4 Catmull-Rom (sharper, slightly slower)
+    Bicubic,
+}
+
+impl FromStr for RotateInterpolation {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let normalized = value.trim().to_ascii_lowercase();
+        match normalized.as_str() {
+            "bilinear" => Ok(RotateInterpolation::Bilinear),
+            "bicubic" => Ok(RotateInterpolation::Bicubic),
+            other => Err(format!(
+                "unknown interpolation '{other}'. Expected bilinear or bicubic"
+            )),
+        }
+    }
+}
+
+/// Channel selected by `Operation::ExtractChannel`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Channel {
+    Red,
+    Green,
+    Blue,
+    Alpha,
+}
+
+impl FromStr for Channel {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let normalized = value.trim().to_ascii_lowercase();
+        match normalized.as_str() {
+            "r" | "red" => Ok(Channel::Red),
+            "g" | "green" => Ok(Channel::Green),
+            "b" | "blue" => Ok(Channel::Blue),
+            "a" | "alpha" => Ok(Channel::Alpha),
+            other => Err(format!(
+                "unknown channel '{other}'. Expected r, g, b, or alpha"
+            )),
+        }
+    }
+}
+
+/// Parameters of `Operation::Levels`, all on the 0-255 scale except `gamma`:
+/// `[in_black, in_white]` is stretched to `[out_black, out_white]` (which may
+/// be reversed to invert) through the curve `in^(1 / gamma)`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Levels {
+    pub in_black: f64,
+    pub in_white: f64,
+    pub gamma: f64,
+    pub out_black: f64,
+    pub out_white: f64,
+}
+
+impl Default for Levels {
+    fn default() -> Self {
+        Self {
+            in_black: 0.0,
+            in_white: 255.0,
+            gamma: 1.0,
+            out_black: 0.0,
+            out_white: 255.0,
+        }
+    }
+}
+
+/// Parameters of `Operation::Sharpen`: every colour sample moves away from
+/// its Gaussian-blurred (`sigma` pixels) value by `amount` times the
+/// difference, unless the difference is below `threshold` (0-255 scale).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sharpen {
+    pub sigma: f64,
+    pub amount: f64,
+    pub threshold: u8,
+}
+
+impl Default for Sharpen {
+    fn default() -> Self {
+        Self {
+            sigma: 1.0,
+            amount: 1.0,
+            threshold: 0,
+        }
+    }
+}
+
+impl Sharpen {
+    /// Light mask for output downscaled by `ratio` (source / output size):
+    /// sigma 0.75 and threshold 2, with `amount` rising by 0.25 per halving
+    /// up to 1. `None` when the image was not reduced.
+    pub fn for_downscale(ratio: f64) -> Option<Self> {
+        if !ratio.is_finite() || ratio <= 1.0 {
+            return None;
+        }
+        Some(Self {
+            sigma: 0.75,
+            amount: (0.25 * ratio.log2()).min(1.0),
+            threshold: 2,
+        })
+    }
+}
+
+/// Adobe `.cube` 3D LUT: `size`^3 RGB outputs with red varying fastest,
+/// sampled over inputs from `domain_min` to `domain_max` per channel.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CubeLut {
+    pub size: usize,
+    pub domain_min: [f32; 3],
+    pub domain_max: [f32; 3],
+    pub table: Vec<[f32; 3]>,
+}
+
+/// One layer of `Operation::Composite`.
+#[derive(Clone, Debug)]
+pub struct Overlay {
+    /// Encoded image, decoded when the pipeline runs
+    pub input: Arc<Vec<u8>>,
+    pub placement: OverlayPlacement,
+    pub blend: BlendMode,
+    /// Multiplier on the overlay's alpha (0.0-1.0)
+    pub opacity: f64,
+    /// Repeat the overlay over the whole image, starting from its placement
+    pub tile: bool,
+}
+
+/// Where the top-left corner of an overlay lands on the image.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OverlayPlacement {
+    /// Compass gravity (`Center` and the eight edges/corners); an overlay
+    /// larger than the image is centred or anchored the same way
+    Gravity(ResizePosition),
+    /// Pixel offset from the image's top-left corner; may be negative
+    Offset { left: i32, top: i32 },
+}
+
+impl OverlayPlacement {
+    /// Offset of an `overlay` sized image placed on a `base` sized one.
+    pub fn origin(&self, base: (u32, u32), overlay: (u32, u32)) -> (i64, i64) {
+        match self {
+            OverlayPlacement::Offset { left, top } => (*left as i64, *top as i64),
+            OverlayPlacement::Gravity(position) => {
+                // At most one of the two offsets is non-zero on each axis.
+                let inside = position.crop_offset(base, overlay);
+                let outside = position.crop_offset(overlay, base);
+                (
+                    inside.0 as i64 - outside.0 as i64,
+                    inside.1 as i64 - outside.1 as i64,
+                )
+            }
+        }
+    }
+}
+
+/// Porter-Duff source-over with a separable blend function.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum BlendMode {
+    /// Plain alpha compositing (default)
+    #[default]
+    Over,
+    /// Darken: colours are multiplied
+    Multiply,
+    /// Lighten: inverted colours are multiplied
+    Screen,
+}
+
+impl FromStr for BlendMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let normalized = value.trim().to_ascii_lowercase();
+        match normalized.as_str() {
+            "over" => Ok(BlendMode::Over),
+            "multiply" => Ok(BlendMode::Multiply),
+            "screen" => Ok(BlendMode::Screen),
+            other => Err(format!(
+                "unknown blend mode '{other}'. Expected over, multiply, or screen"
+            )),
+        }
+    }
+}
+
+/// Per-pixel tonal step that `optimize_ops` can fold into an
+/// `Operation::PointLut`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PointOp {
+    Brightness(i32),
+    Contrast(i32),
+    Gamma(f64),
+    Negate,
+    /// Colour-channel threshold (0-255 scale); the greyscale variant of
+    /// `Operation::Threshold` changes the layout and is not a point op.
+    Threshold(u8),
+    Levels(Levels),
+}
+
+impl PointOp {
+    /// The fusible step `op` performs, if any.
+    pub fn from_operation(op: &Operation) -> Option<Self> {
+        match op {
+            Operation::Brightness { value } => Some(PointOp::Brightness(*value)),
+            Operation::Contrast { value } => Some(PointOp::Contrast(*value)),
+            Operation::Gamma { gamma } => Some(PointOp::Gamma(*gamma)),
+            Operation::Negate => Some(PointOp::Negate),
+            Operation::Threshold {
+                value,
+                grayscale: false,
+            } => Some(PointOp::Threshold(*value)),
+            Operation::Levels { levels } => Some(PointOp::Levels(*levels)),
+            _ => None,
+        }
+    }
+
+    /// The standalone operation this step stands for.
+    pub fn to_operation(self) -> Operation {
+        match self {
+            PointOp::Brightness(value) => Operation::Brightness { value },
+            PointOp::Contrast(value) => Operation::Contrast { value },
+            PointOp::Gamma(gamma) => Operation::Gamma { gamma },
+            PointOp::Negate => Operation::Negate,
+            PointOp::Threshold(value) => Operation::Threshold {
+                value,
+                grayscale: false,
+            },
+            PointOp::Levels(levels) => Operation::Levels { levels },
+        }
+    }
+}
+
+/// 8-bit RGBA colour used to fill canvas areas that have no source pixels
+/// (letterbox padding and similar).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl Color {
+    pub const TRANSPARENT: Color = Color::rgba(0, 0, 0, 0);
+    pub const BLACK: Color = Color::rgba(0, 0, 0, 255);
+    pub const WHITE: Color = Color::rgba(255, 255, 255, 255);
+
+    pub const fn rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self { r, g, b, a }
+    }
+
+    pub fn is_opaque(&self) -> bool {
+        self.a == u8::MAX
+    }
+
+    pub fn to_rgb(self) -> [u8; 3] {
+        [self.r, self.g, self.b]
+    }
+
+    pub fn to_rgba(self) -> [u8; 4] {
+        [self.r, self.g, self.b, self.a]
+    }
+}
+
+impl FromStr for Color {
+    type Err = String;
+
+    /// Parse `#rgb`, `#rgba`, `#rrggbb`, `#rrggbbaa` (the `#` is optional)
+    /// or one of `transparent`, `black`, `white`.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let normalized = value.trim().to_ascii_lowercase();
+        match normalized.as_str() {
+            "transparent" => return Ok(Color::TRANSPARENT),
+            "black" => return Ok(Color::BLACK),
+            "white" => return Ok(Color::WHITE),
+            _ => {}
+        }
+
+        let hex = normalized.strip_prefix('#').unwrap_or(&normalized);
+        let invalid =
+            || format!("invalid colour '{value}'. Expected #rgb, #rgba, #rrggbb or #rrggbbaa");
+        if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(invalid());
+        }
+        let digit = |i: usize| u8::from_str_radix(&hex[i..i + 1], 16).map(|v| v * 17);
+        let pair = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16);
+        let channels = match hex.len() {
+            3 | 4 => (0..hex.len()).map(digit).collect::<Result<Vec<_>, _>>(),
+            6 | 8 => (0..hex.len())
+                .step_by(2)
+                .map(pair)
+                .collect::<Result<Vec<_>, _>>(),
+            _ => return Err(invalid()),
+        }
+        .map_err(|_| invalid())?;
+        Ok(Color::rgba(
+            channels[0],
+            channels[1],
+            channels[2],
+            channels.get(3).copied().unwrap_or(u8::MAX),
+        ))
+    }
+}
+
+/// Which part of the image to keep when `ResizeFit::Cover` has to crop.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum ResizePosition {
+    /// Keep the centre of the image (default)
+    #[default]
+    Center,
+    North,
+    NorthEast,
+    East,
+    SouthEast,
+    South,
+    SouthWest,
+    West,
+    NorthWest,
+    /// Fractional focal point (0.0-1.0 of width/height) to keep as close
+    /// to the centre of the output as the crop allows.
+    Focal {
+        x: f64,
+        y: f64,
+    },
+    /// Content-aware: keep the window with the highest Shannon entropy
+    Entropy,
+    /// Content-aware: keep the window with the most detail, saturation and skin tones
+    Attention,
+}
+
+impl ResizePosition {
+    /// Create a focal point position, rejecting values outside 0.0-1.0.
+    pub fn focal(x: f64, y: f64) -> Result<Self, String> {
+        for (axis, value) in [("x", x), ("y", y)] {
+            if !value.is_finite() || !(0.0..=1.0).contains(&value) {
+                return Err(format!(
+                    "focal point {axis}={value} must be a number between 0.0 and 1.0"
+                ));
+            }
+        }
+        Ok(ResizePosition::Focal { x, y })
+    }
+
+    /// True for strategies that need pixel data to pick the crop window.
+    pub fn is_content_aware(&self) -> bool {
+        matches!(self, ResizePosition::Entropy | ResizePosition::Attention)
+    }
+
+    /// Offset of a `target` sized window inside a `resized` frame.
+    ///
+    /// Compass gravities anchor the window to the matching edge (centre is
+    /// `(resized - target) / 2`, identical to the historical behavior).
+    /// Focal points centre the window on the point, clamped to the frame.
+    /// Content-aware strategies cannot be resolved without pixels and fall
+    /// back to the centre here; the pipeline scores them separately.
+    pub fn crop_offset(&self, resized: (u32, u32), target: (u32, u32)) -> (u32, u32) {
+        let excess_x = resized.0.saturating_sub(target.0);
+        let excess_y = resized.1.saturating_sub(target.1);
+        let (anchor_x, anchor_y) = match self {
+            ResizePosition::Focal { x, y } => {
+                let place = |focal: f64, frame: u32, window: u32, excess: u32| -> u32 {
+                    let start = focal * frame as f64 - window as f64 / 2.0;
+                    (start.round().max(0.0) as u32).min(excess)
+                };
+                return (
+                    place(*x, resized.0, target.0, excess_x),
+                    place(*y, resized.1, target.1, excess_y),
+                );
+            }
+            ResizePosition::Center | ResizePosition::Entropy | ResizePosition::Attention => (1, 1),
+            ResizePosition::North => (1, 0),
+            ResizePosition::NorthEast => (2, 0),
+            ResizePosition::East => (2, 1),
+            ResizePosition::SouthEast => (2, 2),
+            ResizePosition::South => (1, 2),
+            ResizePosition::SouthWest => (0, 2),
+            ResizePosition::West => (0, 1),
+            ResizePosition::NorthWest => (0, 0),
+        };
+        // anchor is expressed in halves: 0 = start, 1 = middle, 2 = end
+        (excess_x * anchor_x / 2, excess_y * anchor_y / 2)
+    }
+}
+
+impl FromStr for ResizePosition {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let normalized = value.trim().to_ascii_lowercase();
+        match normalized.as_str() {
+            "center" | "centre" => Ok(ResizePosition::Center),
+            "north" | "top" => Ok(ResizePosition::North),
+            "northeast" | "right top" => Ok(ResizePosition::NorthEast),
+            "east" | "right" => Ok(ResizePosition::East),
+            "southeast" | "right bottom" => Ok(ResizePosition::SouthEast),
+            "south" | "bottom" => Ok(ResizePosition::South),
+            "southwest" | "left bottom" => Ok(ResizePosition::SouthWest),
+            "west" | "left" => Ok(ResizePosition::West),
+            "northwest" | "left top" => Ok(ResizePosition::NorthWest),
+            "entropy" => Ok(ResizePosition::Entropy),
+            "attention" => Ok(ResizePosition::Attention),
+            other => Err(format!(
+                "unknown resize position '{other}'. Expected centre, north, northeast, east, \
+                 southeast, south, southwest, west, northwest, entropy, or attention"
             )),
         }
     }
@@ -274,6 +1104,62 @@ impl OutputFormat {
             OutputFormat::Avif { .. } => "avif",
         }
     }
+
+    /// Whether the format can store an alpha channel.
+    pub fn supports_alpha(&self) -> bool {
+        !matches!(self, OutputFormat::Jpeg { .. })
+    }
+
+    /// Contract the encoder for this format places on the pipeline output.
+    pub fn encode_contract(&self) -> OperationContract {
+        if self.supports_alpha() {
+            OperationContract::ENCODE
+        } else {
+            OperationContract::ENCODE_OPAQUE
+        }
+    }
+
+    /// Bits per sample the encoder writes for a `requested` depth.
+    ///
+    /// JPEG and WebP are always 8-bit. PNG stores 8 or 16 bits; `Auto` keeps a
+    /// 16-bit pipeline at 16. AVIF stores 8, 10 or 12 bits; `Auto` is 8 and
+    /// 16 is capped at 12.
+    pub fn bit_depth(&self, requested: OutputDepth, sixteen_bit_pipeline: bool) -> u8 {
+        match (self, requested) {
+            (OutputFormat::Jpeg { .. } | OutputFormat::WebP { .. }, _) => 8,
+            (OutputFormat::Png, OutputDepth::Auto) if sixteen_bit_pipeline => 16,
+            (OutputFormat::Png, OutputDepth::Auto | OutputDepth::Eight) => 8,
+            (OutputFormat::Png, _) => 16,
+            (OutputFormat::Avif { .. }, OutputDepth::Auto | OutputDepth::Eight) => 8,
+            (OutputFormat::Avif { .. }, OutputDepth::Ten) => 10,
+            (OutputFormat::Avif { .. }, OutputDepth::Twelve | OutputDepth::Sixteen) => 12,
+        }
+    }
+}
+
+/// Requested output bit depth (`bitDepth()`); see `OutputFormat::bit_depth`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputDepth {
+    /// Follow the pipeline: 16-bit PNG for 16-bit sources, 8-bit otherwise
+    #[default]
+    Auto,
+    Eight,
+    Ten,
+    Twelve,
+    Sixteen,
+}
+
+impl OutputDepth {
+    /// Depth for a bit count; `None` unless it is 8, 10, 12 or 16.
+    pub fn from_bits(bits: u32) -> Option<Self> {
+        match bits {
+            8 => Some(Self::Eight),
+            10 => Some(Self::Ten),
+            12 => Some(Self::Twelve),
+            16 => Some(Self::Sixteen),
+            _ => None,
+        }
+    }
 }
 
 // =============================================================================
@@ -423,6 +1309,25 @@ mod tests {
             assert!(matches!(format, OutputFormat::Png));
         }
 
+        #[test]
+        fn test_bit_depth_per_format() {
+            let png = OutputFormat::Png;
+            let avif = OutputFormat::Avif { quality: 60 };
+            let jpeg = OutputFormat::Jpeg {
+                quality: 85,
+                fast_mode: false,
+            };
+            assert_eq!(png.bit_depth(OutputDepth::Auto, false), 8);
+            assert_eq!(png.bit_depth(OutputDepth::Auto, true), 16);
+            assert_eq!(png.bit_depth(OutputDepth::Eight, true), 8);
+            assert_eq!(png.bit_depth(OutputDepth::Ten, false), 16);
+            assert_eq!(avif.bit_depth(OutputDepth::Auto, true), 8);
+            assert_eq!(avif.bit_depth(OutputDepth::Ten, false), 10);
+            assert_eq!(avif.bit_depth(OutputDepth::Sixteen, true), 12);
+            assert_eq!(jpeg.bit_depth(OutputDepth::Sixteen, true), 8);
+            assert_eq!(OutputDepth::from_bits(9), None);
+        }
+
         #[test]
         fn test_case_insensitive_jpeg() {
             assert!(OutputFormat::from_str("JPEG", None).is_ok());
@@ -487,6 +1392,216 @@ mod tests {
         }
     }
 
+    mod resize_position_tests {
+        use super::*;
+
+        #[test]
+        fn test_parse_compass_and_aliases() {
+            assert_eq!(
+                "centre".parse::<ResizePosition>().unwrap(),
+                ResizePosition::Center
+            );
+            assert_eq!(
+                "Center".parse::<ResizePosition>().unwrap(),
+                ResizePosition::Center
+            );
+            assert_eq!(
+                "north".parse::<ResizePosition>().unwrap(),
+                ResizePosition::North
+            );
+            assert_eq!(
+                "top".parse::<ResizePosition>().unwrap(),
+                ResizePosition::North
+            );
+            assert_eq!(
+                "SouthEast".parse::<ResizePosition>().unwrap(),
+                ResizePosition::SouthEast
+            );
+        }
+
+        #[test]
+        fn test_parse_content_aware_strategies() {
+            let entropy = "entropy".parse::<ResizePosition>().unwrap();
+            let attention = "ATTENTION".parse::<ResizePosition>().unwrap();
+            assert_eq!(entropy, ResizePosition::Entropy);
+            assert_eq!(attention, ResizePosition::Attention);
+            assert!(entropy.is_content_aware());
+            assert!(!ResizePosition::North.is_content_aware());
+        }
+
+        #[test]
+        fn test_parse_unknown_position() {
+            let err = "middle".parse::<ResizePosition>().unwrap_err();
+            assert!(err.contains("unknown resize position"));
+        }
+
+        #[test]
+        fn test_focal_rejects_out_of_range() {
+            assert!(ResizePosition::focal(0.25, 1.0).is_ok());
+            assert!(ResizePosition::focal(-0.1, 0.5).is_err());
+            assert!(ResizePosition::focal(0.5, 1.5).is_err());
+            assert!(ResizePosition::focal(f64::NAN, 0.5).is_err());
+        }
+
+        #[test]
+        fn test_compass_offsets() {
+            let frame = (200, 100);
+            let target = (100, 100);
+            assert_eq!(ResizePosition::Center.crop_offset(frame, target), (50, 0));
+            assert_eq!(ResizePosition::West.crop_offset(frame, target), (0, 0));
+            assert_eq!(ResizePosition::East.crop_offset(frame, target), (100, 0));
+
+            let frame = (100, 201);
+            assert_eq!(ResizePosition::Center.crop_offset(frame, target), (0, 50));
+            assert_eq!(ResizePosition::North.crop_offset(frame, target), (0, 0));
+            assert_eq!(ResizePosition::South.crop_offset(frame, target), (0, 101));
+        }
+
+        #[test]
+        fn test_focal_offsets_are_clamped() {
+            let frame = (400, 100);
+            let target = (100, 100);
+            let focal = ResizePosition::focal(0.5, 0.5).unwrap();
+            assert_eq!(focal.crop_offset(frame, target), (150, 0));
+            let focal = ResizePosition::focal(0.25, 0.5).unwrap();
+            assert_eq!(focal.crop_offset(frame, target), (50, 0));
+            let focal = ResizePosition::focal(0.0, 0.0).unwrap();
+            assert_eq!(focal.crop_offset(frame, target), (0, 0));
+            let focal = ResizePosition::focal(1.0, 1.0).unwrap();
+            assert_eq!(focal.crop_offset(frame, target), (300, 0));
+        }
+
+        #[test]
+        fn test_overlay_origin_handles_larger_overlays() {
+            let base = (100, 50);
+            let centre = OverlayPlacement::Gravity(ResizePosition::Center);
+            assert_eq!(centre.origin(base, (20, 10)), (40, 20));
+            assert_eq!(centre.origin(base, (120, 10)), (-10, 20));
+            let corner = OverlayPlacement::Gravity(ResizePosition::SouthEast);
+            assert_eq!(corner.origin(base, (20, 70)), (80, -20));
+            let offset = OverlayPlacement::Offset { left: -5, top: 7 };
+            assert_eq!(offset.origin(base, (20, 10)), (-5, 7));
+        }
+    }
+
+    mod resize_fit_tests {
+        use super::*;
+
+        #[test]
+        fn test_parse_fit_modes() {
+            assert_eq!(ResizeFit::from_str("inside").unwrap(), ResizeFit::Inside);
+            assert_eq!(
+                ResizeFit::from_str(" Outside ").unwrap(),
+                ResizeFit::Outside
+            );
+            assert_eq!(
+                ResizeFit::from_str("contain").unwrap(),
+                ResizeFit::Contain {
+                    background: Color::TRANSPARENT
+                }
+            );
+            let err = ResizeFit::from_str("letterbox").unwrap_err();
+            assert!(err.contains("contain"));
+        }
+
+        #[test]
+        fn test_parse_kernels() {
+            assert_eq!(ResizeKernel::default(), ResizeKernel::Lanczos3);
+            assert_eq!(
+                ResizeKernel::from_str("Nearest").unwrap(),
+                ResizeKernel::Nearest
+            );
+            assert_eq!(
+                ResizeKernel::from_str("catmull-rom").unwrap(),
+                ResizeKernel::CatmullRom
+            );
+            assert_eq!(
+                ResizeKernel::from_str("mitchell").unwrap(),
+                ResizeKernel::Mitchell
+            );
+            assert!(ResizeKernel::from_str("hermite")
+                .unwrap_err()
+                .contains("lanczos3"));
+        }
+
+        #[test]
+        fn test_parse_extend_modes() {
+            assert_eq!(ExtendMode::default(), ExtendMode::Background);
+            assert_eq!(ExtendMode::from_str("Copy").unwrap(), ExtendMode::Copy);
+            assert_eq!(ExtendMode::from_str("mirror").unwrap(), ExtendMode::Mirror);
+            assert!(ExtendMode::from_str("repeat")
+                .unwrap_err()
+                .contains("mirror"));
+        }
+
+        #[test]
+        fn test_parse_rotate_interpolation() {
+            assert_eq!(
+                RotateInterpolation::default(),
+                RotateInterpolation::Bilinear
+            );
+            assert_eq!(
+                RotateInterpolation::from_str(" Bicubic ").unwrap(),
+                RotateInterpolation::Bicubic
+            );
+            assert!(RotateInterpolation::from_str("nearest")
+                .unwrap_err()
+                .contains("bicubic"));
+        }
+
+        #[test]
+        fn test_parse_icc_profiles() {
+            assert_eq!(IccProfile::from_str("sRGB").unwrap(), IccProfile::Srgb);
+            assert_eq!(
+                IccProfile::from_str(" Display-P3 ").unwrap(),
+                IccProfile::DisplayP3
+            );
+            assert!(IccProfile::from_str("adobergb").unwrap_err().contains("p3"));
+        }
+
+        #[test]
+        fn test_parse_channels() {
+            assert_eq!(Channel::from_str("r").unwrap(), Channel::Red);
+            assert_eq!(Channel::from_str("Green").unwrap(), Channel::Green);
+            assert_eq!(Channel::from_str(" ALPHA ").unwrap(), Channel::Alpha);
+            assert!(Channel::from_str("cyan").unwrap_err().contains("alpha"));
+        }
+
+        #[test]
+        fn test_downscale_sharpen_strength() {
+            assert_eq!(Sharpen::for_downscale(1.0), None);
+            assert_eq!(Sharpen::for_downscale(0.5), None);
+            assert_eq!(Sharpen::for_downscale(f64::NAN), None);
+            assert_eq!(Sharpen::for_downscale(4.0).unwrap().amount, 0.5);
+            assert_eq!(Sharpen::for_downscale(64.0).unwrap().amount, 1.0);
+        }
+
+        #[test]
+        fn test_parse_hex_colors() {
+            assert_eq!(Color::from_str("#fff").unwrap(), Color::WHITE);
+            assert_eq!(
+                Color::from_str("#1238").unwrap(),
+                Color::rgba(0x11, 0x22, 0x33, 0x88)
+            );
+            assert_eq!(
+                Color::from_str("102030").unwrap(),
+                Color::rgba(0x10, 0x20, 0x30, 255)
+            );
+            assert_eq!(
+                Color::from_str("#FF000080").unwrap(),
+                Color::rgba(255, 0, 0, 0x80)
+            );
+            assert_eq!(Color::from_str("Transparent").unwrap(), Color::TRANSPARENT);
+        }
+
+        #[test]
+        fn test_parse_invalid_colors() {
+            for value in ["", "#", "#12", "#12345", "#gggggg", "red", "#ff00ff00ff"] {
+                assert!(Color::from_str(value).is_err(), "{value}");
+            }
+        }
+    }
+
     mod preset_config_tests {
         use super::*;
 
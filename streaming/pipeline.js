@@ -37,10 +37,48 @@ function createStreamingPipeline(options) {
             for (const op of ops) {
                 switch (op.op) {
                     case 'resize':
-                        engine = engine.resize(op.width ?? null, op.height ?? null, op.fit ?? null);
+                        engine = engine.resize(
+                            op.width ?? null,
+                            op.height ?? null,
+                            op.fit ?? null,
+                            op.position ?? null,
+                            {
+                                background: op.background ?? undefined,
+                                withoutEnlargement: op.withoutEnlargement ?? undefined,
+                                withoutReduction: op.withoutReduction ?? undefined,
+                                kernel: op.kernel ?? undefined,
+                                linear: op.linear ?? undefined,
+                                sharpen: op.sharpen ?? undefined,
+                            },
+                        );
                         break;
                     case 'rotate':
-                        engine = engine.rotate(op.degrees);
+                        engine = engine.rotate(op.degrees, {
+                            background: op.background ?? undefined,
+                            interpolation: op.interpolation ?? undefined,
+                        });
+                        break;
+                    case 'affine':
+                        engine = engine.affine(op.matrix, {
+                            translate: op.translate ?? undefined,
+                            interpolator: op.interpolator ?? undefined,
+                            background: op.background ?? undefined,
+                        });
+                        break;
+                    case 'flatten':
+                        engine = engine.flatten({ background: op.background ?? undefined });
+                        break;
+                    case 'ensureAlpha':
+                        engine = engine.ensureAlpha(op.alpha ?? undefined);
+                        break;
+                    case 'removeAlpha':
+                        engine = engine.removeAlpha();
+                        break;
+                    case 'extractChannel':
+                        engine = engine.extractChannel(op.channel);
+                        break;
+                    case 'joinChannel':
+                        engine = engine.joinChannel(op.mask);
                         break;
                     case 'flipH':
                         engine = engine.flipH();
@@ -51,6 +89,72 @@ function createStreamingPipeline(options) {
                     case 'grayscale':
                         engine = engine.grayscale();
                         break;
+                    case 'modulate':
+                        engine = engine.modulate({
+                            brightness: op.brightness ?? undefined,
+                            saturation: op.saturation ?? undefined,
+                            hue: op.hue ?? undefined,
+                            lightness: op.lightness ?? undefined,
+                        });
+                        break;
+                    case 'gamma':
+                        engine = engine.gamma(op.gamma);
+                        break;
+                    case 'negate':
+                        engine = engine.negate();
+                        break;
+                    case 'threshold':
+                        engine = engine.threshold(op.threshold ?? undefined, {
+                            grayscale: op.grayscale ?? undefined,
+                        });
+                        break;
+                    case 'levels':
+                        engine = engine.levels({
+                            inBlack: op.inBlack ?? undefined,
+                            inWhite: op.inWhite ?? undefined,
+                            gamma: op.gamma ?? undefined,
+                            outBlack: op.outBlack ?? undefined,
+                            outWhite: op.outWhite ?? undefined,
+                        });
+                        break;
+                    case 'normalize':
+                        engine = engine.normalize({
+                            lower: op.lower ?? undefined,
+                            upper: op.upper ?? undefined,
+                        });
+                        break;
+                    case 'clahe':
+                        engine = engine.clahe({
+                            width: op.width,
+                            height: op.height,
+                            maxSlope: op.maxSlope ?? undefined,
+                        });
+                        break;
+                    case 'sharpen':
+                        engine = engine.sharpen({
+                            sigma: op.sigma ?? undefined,
+                            amount: op.amount ?? undefined,
+                            threshold: op.threshold ?? undefined,
+                        });
+                        break;
+                    case 'recomb':
+                        engine = engine.recomb(op.matrix);
+                        break;
+                    case 'applyLut':
+                        engine = engine.applyLut(op.cube);
+                        break;
+                    case 'blur':
+                        engine = engine.blur(op.sigma, op.regions ?? undefined);
+                        break;
+                    case 'pixelate':
+                        engine = engine.pixelate(op.blockSize, op.regions ?? undefined);
+                        break;
+                    case 'composite':
+                        engine = engine.composite(op.layers);
+                        break;
+                    case 'toColorProfile':
+                        engine = engine.toColorProfile(op.profile);
+                        break;
                     case 'autoOrient':
                         engine = engine.autoOrient(op.enabled !== false);
                         break;
@@ -95,6 +95,200 @@ async function runTests() {
         assert(result.length > 0, 'output should have content');
     });
 
+    await asyncTest('rotate(45) expands the canvas', async () => {
+        const result = await ImageEngine.from(buffer)
+            .resize(100, 50, 'fill')
+            .rotate(45, { background: '#fff', interpolation: 'bicubic' })
+            .toBuffer('png');
+        const meta = inspect(result);
+        assert.strictEqual(meta.width, 107);
+        assert.strictEqual(meta.height, 107);
+    });
+
+    await asyncTest('affine() sizes the canvas from the transformed bounds', async () => {
+        const result = await ImageEngine.from(buffer)
+            .resize(100, 50, 'fill')
+            .affine([[1, 0.5], [0, 1]], { translate: [10, -5], interpolator: 'bicubic', background: '#fff' })
+            .toBuffer('png');
+        const meta = inspect(result);
+        assert.strictEqual(meta.width, 135);
+        assert.strictEqual(meta.height, 45);
+    });
+
+    await asyncTest('flatten() drops the alpha channel', async () => {
+        const transparent = await ImageEngine.from(buffer)
+            .resize(40)
+            .extend({ top: 10, background: 'transparent' })
+            .toBuffer('png');
+        // Bit 4 of the IHDR colour type marks an alpha channel
+        assert.strictEqual(transparent[25] & 4, 4);
+        const flattened = await ImageEngine.from(transparent).flatten({ background: '#fff' }).toBuffer('png');
+        assert.strictEqual(flattened[25] & 4, 0);
+
+        const jpeg = await ImageEngine.from(transparent).flattenBackground('#00f').toBuffer('jpeg', 80);
+        assert.strictEqual(inspect(jpeg).format, 'jpeg');
+    });
+
+    await asyncTest('bitDepth(16) writes and keeps 16-bit PNGs', async () => {
+        // IHDR bit depth is byte 24
+        const png8 = await ImageEngine.from(buffer).resize(40).toBuffer('png');
+        assert.strictEqual(png8[24], 8);
+        const png16 = await ImageEngine.from(buffer).resize(40).bitDepth(16).toBuffer('png');
+        assert.strictEqual(png16[24], 16);
+
+        // 16-bit input stays 16-bit through resize unless 8 bits are requested
+        const kept = await ImageEngine.from(png16).resize(20).brightness(10).toBuffer('png');
+        assert.strictEqual(kept[24], 16);
+        const reduced = await ImageEngine.from(png16).resize(20).bitDepth(8).toBuffer('png');
+        assert.strictEqual(reduced[24], 8);
+        const jpeg = await ImageEngine.from(png16).toBuffer('jpeg', 80);
+        assert.strictEqual(inspect(jpeg).format, 'jpeg');
+    });
+
+    await asyncTest('channel ops change the PNG colour type', async () => {
+        // IHDR colour type 0 = grey; alpha is either colour type 4/6 or a tRNS chunk
+        const hasAlpha = (png) => (png[25] & 4) === 4 || png.includes('tRNS');
+        const rgb = await ImageEngine.from(buffer).resize(40, 40, 'fill').toBuffer('png');
+        const red = await ImageEngine.from(rgb).extractChannel('r').toBuffer('png');
+        assert.strictEqual(red[25], 0);
+
+        const mask = await ImageEngine.from(rgb).ensureAlpha(0.5).extractChannel('alpha').toBuffer('png');
+        const cutout = await ImageEngine.from(rgb).joinChannel(mask).toBuffer('png');
+        assert(hasAlpha(cutout), 'joinChannel should add alpha');
+        const opaque = await ImageEngine.from(cutout).removeAlpha().toBuffer('png');
+        assert(!hasAlpha(opaque), 'removeAlpha should drop alpha');
+
+        await assert.rejects(
+            ImageEngine.from(rgb).resize(20).joinChannel(mask).toBuffer('png'),
+            (err) => err.errorCode === 'E400',
+        );
+    });
+
+    await asyncTest('modulate() and gamma() work', async () => {
+        const result = await ImageEngine.from(buffer)
+            .resize(100)
+            .modulate({ brightness: 1.1, saturation: 0.5, hue: 90, lightness: -5 })
+            .gamma(2.2)
+            .toBuffer('png');
+        assert.strictEqual(inspect(result).width, 100);
+        // Zero saturation leaves a greyscale PNG (IHDR colour type 0)
+        const grey = await ImageEngine.from(buffer).resize(40).modulate({ saturation: 0 }).toBuffer('png');
+        assert.strictEqual(grey[25] & 2, 0);
+    });
+
+    await asyncTest('negate(), threshold() and levels() work', async () => {
+        const adjusted = await ImageEngine.from(buffer)
+            .resize(100)
+            .levels({ inBlack: 20, inWhite: 230, gamma: 1.2 })
+            .negate()
+            .threshold(100, { grayscale: false })
+            .toBuffer('png');
+        assert.strictEqual(inspect(adjusted).width, 100);
+        // Greyscale threshold writes a greyscale PNG (IHDR colour type 0)
+        const binary = await ImageEngine.from(buffer).resize(40).threshold().toBuffer('png');
+        assert.strictEqual(binary[25], 0);
+    });
+
+    await asyncTest('normalize() and clahe() work', async () => {
+        const result = await ImageEngine.from(buffer)
+            .resize(100)
+            .normalize({ lower: 2, upper: 98 })
+            .clahe({ width: 32, height: 32, maxSlope: 3 })
+            .toBuffer('png');
+        assert.strictEqual(inspect(result).width, 100);
+    });
+
+    await asyncTest('sharpen() and resize sharpen: auto work', async () => {
+        const sharpened = await ImageEngine.from(buffer)
+            .resize(100)
+            .sharpen({ sigma: 0.8, amount: 1.5, threshold: 2 })
+            .toBuffer('png');
+        assert.strictEqual(inspect(sharpened).width, 100);
+        const auto = await ImageEngine.from(buffer)
+            .resize(100, null, null, null, { sharpen: 'auto' })
+            .toBuffer('png');
+        assert.strictEqual(inspect(auto).width, 100);
+    });
+
+    await asyncTest('recomb() and applyLut() work', async () => {
+        const sepia = await ImageEngine.from(buffer)
+            .resize(100)
+            .recomb([
+                [0.393, 0.769, 0.189],
+                [0.349, 0.686, 0.168],
+                [0.272, 0.534, 0.131],
+            ])
+            .toBuffer('png');
+        assert.strictEqual(inspect(sepia).width, 100);
+        let cube = 'TITLE "swap red and blue"\nLUT_3D_SIZE 2\n';
+        for (let b = 0; b < 2; b++) {
+            for (let g = 0; g < 2; g++) {
+                for (let r = 0; r < 2; r++) {
+                    cube += `${b} ${g} ${r}\n`;
+                }
+            }
+        }
+        const graded = await ImageEngine.from(buffer)
+            .resize(100)
+            .applyLut(Buffer.from(cube))
+            .toBuffer('png');
+        assert.strictEqual(inspect(graded).width, 100);
+    });
+
+    await asyncTest('blur() and pixelate() work on the whole image and on regions', async () => {
+        const blurred = await ImageEngine.from(buffer).resize(100).blur(2).toBuffer('png');
+        assert.strictEqual(inspect(blurred).width, 100);
+        const redacted = await ImageEngine.from(buffer)
+            .resize(100)
+            .blur(6, [{ left: 10, top: 10, width: 30, height: 20 }])
+            .pixelate(8, [{ left: 50, top: 0, width: 500, height: 25 }])
+            .toBuffer('png');
+        assert.strictEqual(inspect(redacted).width, 100);
+    });
+
+    await asyncTest('composite() places, blends and tiles overlays', async () => {
+        const logo = await ImageEngine.from(buffer).resize(20).toBuffer('png');
+        const result = await ImageEngine.from(buffer)
+            .resize(100)
+            .composite([
+                { input: logo, gravity: 'southeast', opacity: 0.5 },
+                { input: logo, left: -5, top: 10, blend: 'multiply' },
+                { input: TEST_IMAGE, gravity: 'north', blend: 'screen' },
+                { input: logo, tile: true, opacity: 0.1 },
+            ])
+            .toBuffer('png');
+        assert.strictEqual(inspect(result).width, 100);
+    });
+
+    await asyncTest('toColorProfile() converts and tags non-sRGB targets', async () => {
+        const p3 = await ImageEngine.from(buffer).resize(60).toColorProfile('p3').toBuffer('png');
+        assert(ImageEngine.from(p3).hasIccProfile() > 0, 'Display P3 profile should be embedded');
+
+        // Converting back from the embedded P3 profile; sRGB is only tagged when ICC is kept
+        const srgb = await ImageEngine.from(p3).toColorProfile('srgb').toBuffer('png');
+        assert.strictEqual(ImageEngine.from(srgb).hasIccProfile(), null);
+        const tagged = await ImageEngine.from(p3)
+            .keepMetadata({ icc: true })
+            .toColorProfile('srgb')
+            .toBuffer('png');
+        assert(ImageEngine.from(tagged).hasIccProfile() > 0, 'kept sRGB profile should be embedded');
+
+        // Stripping the P3 profile converts to sRGB instead of dropping it silently
+        const stripped = await ImageEngine.from(p3).toBuffer('png');
+        assert.strictEqual(ImageEngine.from(stripped).hasIccProfile(), null);
+        assert.strictEqual(inspect(stripped).width, 60);
+    });
+
+    await asyncTest('toColorProfile() signals the gamut in PNG chunks', async () => {
+        const p3 = await ImageEngine.from(buffer).resize(40).toColorProfile('p3').toBuffer('png');
+        assert(p3.includes('cICP'), 'Display P3 output should carry a cICP chunk');
+        const rec2020 = await ImageEngine.from(buffer).resize(40).toColorProfile('rec2020').toBuffer('png');
+        assert(rec2020.includes('cICP'), 'BT.2020 output should carry a cICP chunk');
+
+        const srgb = await ImageEngine.from(buffer).resize(40).toBuffer('png');
+        assert(srgb.includes('sRGB') && !srgb.includes('cICP'), 'untagged sRGB output should carry an sRGB chunk');
+    });
+
     await asyncTest('grayscale() works', async () => {
         const result = await ImageEngine.from(buffer).resize(100).grayscale().toBuffer('jpeg', 80);
         assert(result.length > 0, 'output should have content');
@@ -253,7 +447,7 @@ async function runTests() {
     await asyncTest('invalid rotation angle throws error', async () => {
         let threw = false;
         try {
-            await ImageEngine.from(buffer).rotate(45).toBuffer('jpeg', 80);
+            await ImageEngine.from(buffer).rotate(NaN).toBuffer('jpeg', 80);
         } catch (e) {
             threw = true;
             assert(e.message.includes('rotation') || e.message.includes('angle'), 'error message should mention rotation');
@@ -324,7 +518,7 @@ async function runTests() {
     // Currently, not all error sites use this function, so some tests may fail until all error sites are updated.
     await asyncTest('error category: UserError for invalid rotation', async () => {
         try {
-            await ImageEngine.from(buffer).rotate(45).toBuffer('jpeg', 80);
+            await ImageEngine.from(buffer).rotate(Infinity).toBuffer('jpeg', 80);
             assert.fail('should have thrown an error');
         } catch (e) {
             const category = getErrorCategory(e);
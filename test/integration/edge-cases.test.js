@@ -249,24 +249,13 @@ async function runTests() {
         assert(result.length > 0, 'should handle negative rotation');
     });
     
-    await asyncTest('rejects rotation 360 (invalid angle)', async () => {
-        // 360 degrees is invalid; should error
-        // Implementation only supports 0, 90, 180, 270, -90, -180, -270
-        let threw = false;
-        try {
-            await ImageEngine.from(buffer).rotate(360).toBuffer('jpeg', 80);
-        } catch (e) {
-            threw = true;
-            assert(
-                e.message.includes('rotation') || 
-                e.message.includes('angle') || 
-                e.message.includes('360') ||
-                e.message.includes('Unsupported'),
-                `error should mention rotation/angle: ${e.message}`
-            );
-        }
-        // Verify that error was thrown for invalid rotation angle
-        assert(threw, 'should throw error for invalid rotation angle 360');
+    await asyncTest('treats rotation 360 as a no-op', async () => {
+        // Angles are normalized, so full turns keep the original size
+        const original = inspect(buffer);
+        const result = await ImageEngine.from(buffer).rotate(360).toBuffer('png');
+        const meta = inspect(result);
+        assert.strictEqual(meta.width, original.width);
+        assert.strictEqual(meta.height, original.height);
     });
     
     // ========================================================================
@@ -207,6 +207,36 @@ async function runTests() {
         }
     });
 
+    await asyncTest('sanitize() limits apply to composite() overlays', async () => {
+        const oversized = createGrayscalePng(10_000, 1);
+        try {
+            await ImageEngine.from(smallBuffer)
+                .composite([{ input: oversized }])
+                .sanitize({ policy: 'strict' })
+                .limits({ maxPixels: 1_000 })
+                .toBuffer('jpeg', 80);
+            assert.fail('should have thrown an error');
+        } catch (e) {
+            assert(e.message.includes('Firewall'), `error should mention Firewall: ${e.message}`);
+            assert(e.message.includes('10000x1'), `error should name the overlay size: ${e.message}`);
+        }
+    });
+
+    await asyncTest('sanitize() limits apply to joinChannel() masks', async () => {
+        const oversized = createGrayscalePng(10_000, 1);
+        try {
+            await ImageEngine.from(smallBuffer)
+                .joinChannel(oversized)
+                .sanitize({ policy: 'strict' })
+                .limits({ maxPixels: 1_000 })
+                .toBuffer('png');
+            assert.fail('should have thrown an error');
+        } catch (e) {
+            assert(e.message.includes('Firewall'), `error should mention Firewall: ${e.message}`);
+            assert(e.message.includes('10000x1'), `error should name the mask size: ${e.message}`);
+        }
+    });
+
     await asyncTest('invalid policy name throws error', async () => {
         try {
             await ImageEngine.from(buffer)
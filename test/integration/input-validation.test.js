@@ -48,6 +48,319 @@ async function asyncTest(name, fn) {
         assert(threw, 'negative resize width should throw synchronously');
     });
 
+    await asyncTest('resize rejects out-of-range focal point', async () => {
+        let threw = false;
+        try {
+            ImageEngine.from(BUFFER).resize(100, 100, 'cover', { x: 1.5, y: 0.5 });
+        } catch (e) {
+            threw = true;
+            assert.strictEqual(e.errorCode, 'E400', 'invalid_argument should map to E400');
+            assert(e.message.toLowerCase().includes('position'), 'message should mention position');
+        }
+        assert(threw, 'focal point outside 0..1 should throw synchronously');
+    });
+
+    await asyncTest('resize rejects unparsable contain background', async () => {
+        let threw = false;
+        try {
+            ImageEngine.from(BUFFER).resize(100, 100, 'contain', null, { background: '#12345' });
+        } catch (e) {
+            threw = true;
+            assert.strictEqual(e.errorCode, 'E400', 'invalid_argument should map to E400');
+            assert(e.message.toLowerCase().includes('background'), 'message should mention background');
+        }
+        assert(threw, 'invalid background colour should throw synchronously');
+    });
+
+    await asyncTest('resize rejects unknown kernel', async () => {
+        let threw = false;
+        try {
+            ImageEngine.from(BUFFER).resize(100, null, null, null, { kernel: 'bicubic' });
+        } catch (e) {
+            threw = true;
+            assert.strictEqual(e.errorCode, 'E400', 'invalid_argument should map to E400');
+            assert(e.message.toLowerCase().includes('kernel'), 'message should mention kernel');
+        }
+        assert(threw, 'unknown kernel should throw synchronously');
+    });
+
+    await asyncTest('extend rejects negative edges and unknown modes', async () => {
+        for (const options of [{ top: -1 }, { left: 1.5 }, { right: 10, extendWith: 'repeat' }]) {
+            let threw = false;
+            try {
+                ImageEngine.from(BUFFER).extend(options);
+            } catch (e) {
+                threw = true;
+                assert.strictEqual(e.errorCode, 'E400', 'invalid_argument should map to E400');
+            }
+            assert(threw, `extend(${JSON.stringify(options)}) should throw synchronously`);
+        }
+    });
+
+    await asyncTest('trim rejects out-of-range thresholds and bad colours', async () => {
+        for (const options of [{ threshold: -1 }, { threshold: 256 }, { threshold: 2.5 }, { background: 'nope' }]) {
+            let threw = false;
+            try {
+                ImageEngine.from(BUFFER).trim(options);
+            } catch (e) {
+                threw = true;
+                assert.strictEqual(e.errorCode, 'E400', 'invalid_argument should map to E400');
+            }
+            assert(threw, `trim(${JSON.stringify(options)}) should throw synchronously`);
+        }
+    });
+
+    await asyncTest('rotate rejects non-finite angles and unknown options', async () => {
+        const cases = [[NaN], [Infinity], [10, { interpolation: 'nearest' }], [10, { background: 'nope' }]];
+        for (const args of cases) {
+            let threw = false;
+            try {
+                ImageEngine.from(BUFFER).rotate(...args);
+            } catch (e) {
+                threw = true;
+                assert.strictEqual(e.errorCode, 'E400', 'invalid_argument should map to E400');
+            }
+            assert(threw, `rotate(${args.map(String).join(', ')}) should throw synchronously`);
+        }
+    });
+
+    await asyncTest('affine rejects malformed or singular matrices and bad options', async () => {
+        const cases = [
+            [[[1, 0, 0], [0, 1, 0]]],
+            [[[1, 0]]],
+            [[[1, NaN], [0, 1]]],
+            [[[1, 2], [2, 4]]],
+            [[[1, 0], [0, 1]], { translate: [1] }],
+            [[[1, 0], [0, 1]], { translate: [Infinity, 0] }],
+            [[[1, 0], [0, 1]], { interpolator: 'nearest' }],
+            [[[1, 0], [0, 1]], { background: 'nope' }],
+        ];
+        for (const args of cases) {
+            let threw = false;
+            try {
+                ImageEngine.from(BUFFER).affine(...args);
+            } catch (e) {
+                threw = true;
+                assert.strictEqual(e.errorCode, 'E400', 'invalid_argument should map to E400');
+            }
+            assert(threw, `affine(${JSON.stringify(args)}) should throw synchronously`);
+        }
+    });
+
+    await asyncTest('toColorProfile rejects unknown names and unusable profiles', async () => {
+        const cases = [
+            ['adobergb', 'E400'],
+            ['', 'E400'],
+            [Buffer.from('not an icc profile'), 'E210'],
+        ];
+        for (const [profile, code] of cases) {
+            let threw = false;
+            try {
+                ImageEngine.from(BUFFER).toColorProfile(profile);
+            } catch (e) {
+                threw = true;
+                assert.strictEqual(e.errorCode, code);
+            }
+            assert(threw, `toColorProfile(${String(profile)}) should throw synchronously`);
+        }
+    });
+
+    await asyncTest('flatten rejects unparsable colours', async () => {
+        const cases = [
+            (engine) => engine.flatten({ background: 'nope' }),
+            (engine) => engine.flattenBackground('#12'),
+        ];
+        for (const apply of cases) {
+            let threw = false;
+            try {
+                apply(ImageEngine.from(BUFFER));
+            } catch (e) {
+                threw = true;
+                assert.strictEqual(e.errorCode, 'E400', 'invalid_argument should map to E400');
+            }
+            assert(threw, `${apply} should throw synchronously`);
+        }
+    });
+
+    await asyncTest('bitDepth rejects unsupported depths', async () => {
+        for (const bits of [9, 0, 32, 10.5, NaN]) {
+            let threw = false;
+            try {
+                ImageEngine.from(BUFFER).bitDepth(bits);
+            } catch (e) {
+                threw = true;
+                assert.strictEqual(e.errorCode, 'E400', 'invalid_argument should map to E400');
+            }
+            assert(threw, `bitDepth(${bits}) should throw synchronously`);
+        }
+    });
+
+    await asyncTest('channel ops reject bad arguments', async () => {
+        const cases = [
+            (engine) => engine.ensureAlpha(1.5),
+            (engine) => engine.ensureAlpha(NaN),
+            (engine) => engine.extractChannel('cyan'),
+            (engine) => engine.joinChannel(Buffer.from('not an image')),
+        ];
+        for (const apply of cases) {
+            let threw = false;
+            try {
+                apply(ImageEngine.from(BUFFER));
+            } catch (e) {
+                threw = true;
+                assert.strictEqual(e.errorCode, 'E400', 'invalid_argument should map to E400');
+            }
+            assert(threw, `${apply} should throw synchronously`);
+        }
+    });
+
+    await asyncTest('modulate and gamma reject out-of-range values', async () => {
+        const cases = [
+            (engine) => engine.modulate({ brightness: -1 }),
+            (engine) => engine.modulate({ saturation: NaN }),
+            (engine) => engine.modulate({ hue: Infinity }),
+            (engine) => engine.modulate({ lightness: 101 }),
+            (engine) => engine.gamma(0),
+            (engine) => engine.gamma(NaN),
+        ];
+        for (const apply of cases) {
+            let threw = false;
+            try {
+                apply(ImageEngine.from(BUFFER));
+            } catch (e) {
+                threw = true;
+                assert.strictEqual(e.errorCode, 'E400', 'invalid_argument should map to E400');
+            }
+            assert(threw, `${apply} should throw synchronously`);
+        }
+    });
+
+    await asyncTest('threshold and levels reject out-of-range values', async () => {
+        const cases = [
+            (engine) => engine.threshold(256),
+            (engine) => engine.threshold(12.5),
+            (engine) => engine.levels({ inBlack: -1 }),
+            (engine) => engine.levels({ inBlack: 200, inWhite: 100 }),
+            (engine) => engine.levels({ outWhite: NaN }),
+            (engine) => engine.levels({ gamma: 0 }),
+        ];
+        for (const apply of cases) {
+            let threw = false;
+            try {
+                apply(ImageEngine.from(BUFFER));
+            } catch (e) {
+                threw = true;
+                assert.strictEqual(e.errorCode, 'E400', 'invalid_argument should map to E400');
+            }
+            assert(threw, `${apply} should throw synchronously`);
+        }
+    });
+
+    await asyncTest('normalize and clahe reject out-of-range values', async () => {
+        const cases = [
+            (engine) => engine.normalize({ lower: -1 }),
+            (engine) => engine.normalize({ lower: 60, upper: 40 }),
+            (engine) => engine.normalize({ upper: NaN }),
+            (engine) => engine.clahe({ width: 0, height: 8 }),
+            (engine) => engine.clahe({ width: 8.5, height: 8 }),
+            (engine) => engine.clahe({ width: 8, height: 8, maxSlope: 101 }),
+        ];
+        for (const apply of cases) {
+            let threw = false;
+            try {
+                apply(ImageEngine.from(BUFFER));
+            } catch (e) {
+                threw = true;
+                assert.strictEqual(e.errorCode, 'E400', 'invalid_argument should map to E400');
+            }
+            assert(threw, `${apply} should throw synchronously`);
+        }
+    });
+
+    await asyncTest('sharpen rejects out-of-range values', async () => {
+        const cases = [
+            (engine) => engine.sharpen({ sigma: 0 }),
+            (engine) => engine.sharpen({ amount: -1 }),
+            (engine) => engine.sharpen({ threshold: 300 }),
+            (engine) => engine.resize(100, null, null, null, { sharpen: 'strong' }),
+            (engine) => engine.resize(100, null, null, null, { sharpen: true }),
+        ];
+        for (const apply of cases) {
+            let threw = false;
+            try {
+                apply(ImageEngine.from(BUFFER));
+            } catch (e) {
+                threw = true;
+                assert.strictEqual(e.errorCode, 'E400', 'invalid_argument should map to E400');
+            }
+            assert(threw, `${apply} should throw synchronously`);
+        }
+    });
+
+    await asyncTest('recomb and applyLut reject invalid input', async () => {
+        const cases = [
+            [(engine) => engine.recomb([[1, 0], [0, 1]]), 'E400'],
+            [(engine) => engine.recomb([[1, 0, 0], [0, 1, 0], [0, 0]]), 'E400'],
+            [(engine) => engine.recomb([[1, 0, 0], [0, NaN, 0], [0, 0, 1]]), 'E400'],
+            [(engine) => engine.applyLut(Buffer.from('LUT_3D_SIZE 2\n0 0 0\n')), 'E220'],
+            [(engine) => engine.applyLut(Buffer.from('LUT_1D_SIZE 2\n0 0 0\n1 1 1\n')), 'E220'],
+            [(engine) => engine.applyLut(Buffer.from('LUT_3D_SIZE 128\n')), 'E221'],
+        ];
+        for (const [apply, code] of cases) {
+            let threw = false;
+            try {
+                apply(ImageEngine.from(BUFFER));
+            } catch (e) {
+                threw = true;
+                assert.strictEqual(e.errorCode, code, `${apply} should map to ${code}`);
+            }
+            assert(threw, `${apply} should throw synchronously`);
+        }
+    });
+
+    await asyncTest('blur and pixelate reject invalid sizes and regions', async () => {
+        const cases = [
+            (engine) => engine.blur(0.1),
+            (engine) => engine.blur(NaN),
+            (engine) => engine.pixelate(0),
+            (engine) => engine.pixelate(2.5),
+            (engine) => engine.blur(2, [{ left: -1, top: 0, width: 10, height: 10 }]),
+            (engine) => engine.pixelate(4, [{ left: 0, top: 0, width: 0, height: 10 }]),
+        ];
+        for (const apply of cases) {
+            let threw = false;
+            try {
+                apply(ImageEngine.from(BUFFER));
+            } catch (e) {
+                threw = true;
+                assert.strictEqual(e.errorCode, 'E400', 'invalid_argument should map to E400');
+            }
+            assert(threw, `${apply} should throw synchronously`);
+        }
+    });
+
+    await asyncTest('composite rejects invalid layers', async () => {
+        const cases = [
+            [(engine) => engine.composite([{ input: Buffer.from('not an image') }]), 'E400'],
+            [(engine) => engine.composite([{ input: BUFFER, blend: 'overlay' }]), 'E400'],
+            [(engine) => engine.composite([{ input: BUFFER, gravity: 'entropy' }]), 'E400'],
+            [(engine) => engine.composite([{ input: BUFFER, left: 10 }]), 'E400'],
+            [(engine) => engine.composite([{ input: BUFFER, gravity: 'north', left: 0, top: 0 }]), 'E400'],
+            [(engine) => engine.composite([{ input: BUFFER, opacity: 1.5 }]), 'E400'],
+            [(engine) => engine.composite([{ input: '/nonexistent/overlay.png' }]), 'E100'],
+        ];
+        for (const [apply, code] of cases) {
+            let threw = false;
+            try {
+                apply(ImageEngine.from(BUFFER));
+            } catch (e) {
+                threw = true;
+                assert.strictEqual(e.errorCode, code, `${apply} should map to ${code}`);
+            }
+            assert(threw, `${apply} should throw synchronously`);
+        }
+    });
+
     await asyncTest('toBuffer rejects NaN quality', async () => {
         let threw = false;
         try {
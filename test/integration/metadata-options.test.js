@@ -54,7 +54,24 @@ async function main() {
     .toBuffer('jpeg', 80);
   assert(bufferFirewallStrict.length > 0, 'firewall strict should override keepMetadata');
 
-  console.log('metadata-options.test.js passed - all 7 tests');
+  // Test 8: metadata() reads EXIF from the headers, GPS only on request
+  console.log('Test 8: metadata() parses EXIF without decoding');
+  const exifEngine = ImageEngine.fromPath(resolveFixture('test_with_exif.jpg'));
+  const parsed = exifEngine.metadata();
+  assert.strictEqual(parsed.orientation, 6, 'should read EXIF Orientation');
+  assert.strictEqual(parsed.gps, undefined, 'GPS should be opt-in');
+  assert.deepStrictEqual(parsed.iptcKeywords, []);
+  const rotated = await exifEngine.toBuffer('jpeg', 80);
+  assert(rotated.length > 0, 'engine should still process after metadata()');
+
+  // Test 9: ICC description of a converted output
+  console.log('Test 9: metadata() reports the ICC description');
+  const p3 = await ImageEngine.fromPath(input).resize(32).toColorProfile('p3').toBuffer('png');
+  const p3Metadata = ImageEngine.from(p3).metadata({ gps: true });
+  assert(p3Metadata.iccDescription, 'embedded Display P3 profile should have a description');
+  assert.strictEqual(p3Metadata.make, undefined);
+
+  console.log('metadata-options.test.js passed - all 9 tests');
 }
 
 main().catch((err) => {
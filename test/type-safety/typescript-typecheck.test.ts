@@ -7,7 +7,7 @@ import { ImageEngine, ImageMetadata, PresetResult } from '../../index';
 
 // Local type aliases for API string literals (not exported from index.d.ts)
 type OutputFormat = 'jpeg' | 'jpg' | 'png' | 'webp' | 'avif';
-type ResizeFit = 'inside' | 'cover' | 'fill';
+type ResizeFit = 'inside' | 'cover' | 'fill' | 'contain' | 'outside';
 type PresetName = 'thumbnail' | 'avatar' | 'hero' | 'social';
 
 async function testTypeSafety() {
@@ -16,7 +16,7 @@ async function testTypeSafety() {
     // Type-safe OutputFormat usage
     const validFormats: OutputFormat[] = ['jpeg', 'jpg', 'png', 'webp', 'avif'];
     
-    const fitModes: ResizeFit[] = ['inside', 'cover', 'fill'];
+    const fitModes: ResizeFit[] = ['inside', 'cover', 'fill', 'contain', 'outside'];
     for (let index = 0; index < validFormats.length; index++) {
         const format = validFormats[index];
         console.log(`Testing format: ${format}`);
@@ -32,6 +32,12 @@ async function testTypeSafety() {
     const coverFit: ResizeFit = 'cover';
     await ImageEngine.fromPath(imagePath).resize(300, 300, coverFit).toBuffer('jpeg', 75);
 
+    // Cover positioning: compass gravity or fractional focal point
+    await ImageEngine.fromPath(imagePath).resize(300, 300, 'cover', 'north').toBuffer('jpeg', 75);
+    await ImageEngine.fromPath(imagePath)
+        .resize(300, 300, 'cover', { x: 0.3, y: 0.2 })
+        .toBuffer('jpeg', 75);
+
     // Uppercase format is also accepted
     await ImageEngine.fromPath(imagePath).toBuffer('JPEG', 80);
     
@@ -48,7 +48,7 @@ fn create_valid_jpeg(width: u32, height: u32) -> Vec<u8> {
 
 mod minimal_image_tests {
     use super::*;
-    use lazy_image::ops::{Operation, ResizeFit};
+    use lazy_image::ops::{Operation, ResizeFit, ResizeKernel, ResizePosition};
 
     #[test]
     fn test_1x1_resize() {
@@ -57,6 +57,12 @@ mod minimal_image_tests {
             width: Some(100),
             height: Some(100),
             fit: ResizeFit::Inside,
+            position: ResizePosition::Center,
+            without_enlargement: false,
+            without_reduction: false,
+            kernel: ResizeKernel::Lanczos3,
+            linear: false,
+            sharpen: false,
         }];
         let result = apply_ops(Cow::Owned(img), &ops);
         assert!(result.is_ok());
@@ -334,7 +340,7 @@ mod quality_boundary_tests {
 
 mod zero_dimension_tests {
     use super::*;
-    use lazy_image::ops::{Operation, ResizeFit};
+    use lazy_image::ops::{Operation, ResizeFit, ResizeKernel, ResizePosition};
 
     #[test]
     fn test_resize_to_zero_width() {
@@ -343,6 +349,12 @@ mod zero_dimension_tests {
             width: Some(0),
             height: Some(50),
             fit: ResizeFit::Inside,
+            position: ResizePosition::Center,
+            without_enlargement: false,
+            without_reduction: false,
+            kernel: ResizeKernel::Lanczos3,
+            linear: false,
+            sharpen: false,
         }];
         let result = apply_ops(Cow::Owned(img), &ops);
         assert!(matches!(
@@ -358,6 +370,12 @@ mod zero_dimension_tests {
             width: Some(50),
             height: Some(0),
             fit: ResizeFit::Inside,
+            position: ResizePosition::Center,
+            without_enlargement: false,
+            without_reduction: false,
+            kernel: ResizeKernel::Lanczos3,
+            linear: false,
+            sharpen: false,
         }];
         let result = apply_ops(Cow::Owned(img), &ops);
         assert!(matches!(
@@ -412,7 +430,7 @@ mod zero_dimension_tests {
 
 mod extreme_aspect_ratio_tests {
     use super::*;
-    use lazy_image::ops::{Operation, ResizeFit};
+    use lazy_image::ops::{Operation, ResizeFit, ResizeKernel, ResizePosition};
 
     #[test]
     fn test_resize_extreme_wide() {
@@ -424,6 +442,12 @@ mod extreme_aspect_ratio_tests {
             width: Some(100),
             height: None,
             fit: ResizeFit::Inside,
+            position: ResizePosition::Center,
+            without_enlargement: false,
+            without_reduction: false,
+            kernel: ResizeKernel::Lanczos3,
+            linear: false,
+            sharpen: false,
         }];
         let result = apply_ops(Cow::Owned(img), &ops);
         assert!(
@@ -442,6 +466,12 @@ mod extreme_aspect_ratio_tests {
             width: None,
             height: Some(100),
             fit: ResizeFit::Inside,
+            position: ResizePosition::Center,
+            without_enlargement: false,
+            without_reduction: false,
+            kernel: ResizeKernel::Lanczos3,
+            linear: false,
+            sharpen: false,
         }];
         let result = apply_ops(Cow::Owned(img), &ops);
         assert!(
@@ -1,6 +1,6 @@
 use image::{DynamicImage, GenericImageView, RgbImage};
 use lazy_image::engine::{apply_ops, calc_resize_dimensions};
-use lazy_image::ops::{Operation, ResizeFit};
+use lazy_image::ops::{Operation, ResizeFit, ResizeKernel, ResizePosition};
 use proptest::prelude::*;
 use std::borrow::Cow;
 
@@ -93,6 +93,12 @@ proptest! {
             width: Some(target_w),
             height: Some(target_h),
             fit: ResizeFit::Inside,
+            position: ResizePosition::Center,
+            without_enlargement: false,
+            without_reduction: false,
+            kernel: ResizeKernel::Lanczos3,
+            linear: false,
+            sharpen: false,
         }];
         let result = apply_ops(Cow::Owned(img), &ops);
 
@@ -118,6 +124,12 @@ proptest! {
             width: Some(target_w),
             height: None,
             fit: ResizeFit::Inside,
+            position: ResizePosition::Center,
+            without_enlargement: false,
+            without_reduction: false,
+            kernel: ResizeKernel::Lanczos3,
+            linear: false,
+            sharpen: false,
         }];
         let result = apply_ops(Cow::Owned(img), &ops);
 
@@ -142,6 +154,12 @@ proptest! {
             width: None,
             height: Some(target_h),
             fit: ResizeFit::Inside,
+            position: ResizePosition::Center,
+            without_enlargement: false,
+            without_reduction: false,
+            kernel: ResizeKernel::Lanczos3,
+            linear: false,
+            sharpen: false,
         }];
         let result = apply_ops(Cow::Owned(img), &ops);
 
@@ -232,6 +250,12 @@ proptest! {
                 width: Some(target_w),
                 height: Some(target_h),
                 fit: ResizeFit::Fill,
+                position: ResizePosition::Center,
+                without_enlargement: false,
+                without_reduction: false,
+                kernel: ResizeKernel::Lanczos3,
+                linear: false,
+                sharpen: false,
             },
         ];
         let ops_b = vec![
@@ -239,6 +263,12 @@ proptest! {
                 width: Some(target_w),
                 height: Some(target_h),
                 fit: ResizeFit::Fill,
+                position: ResizePosition::Center,
+                without_enlargement: false,
+                without_reduction: false,
+                kernel: ResizeKernel::Lanczos3,
+                linear: false,
+                sharpen: false,
             },
             Operation::Rotate { degrees: 180 },
         ];